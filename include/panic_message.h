@@ -0,0 +1,34 @@
+/*
+ * The C API of the panic-message crate's `ffi` feature: why the last call into Rust made
+ * on this thread failed, for functions that catch their panics with `ffi::catch`.
+ */
+#ifndef PANIC_MESSAGE_H
+#define PANIC_MESSAGE_H
+
+#include <stddef.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+/*
+ * The message of the last panic caught on the calling thread, NUL-terminated, or NULL if
+ * there is none. It is valid until the next panic on the thread is caught or
+ * panic_message_clear is called.
+ */
+const char *panic_message_last(void);
+
+/*
+ * The length in bytes of the message panic_message_last returns, without the NUL, or 0 if
+ * there is none.
+ */
+size_t panic_message_last_len(void);
+
+/* Forget the message of the last panic caught on the calling thread. */
+void panic_message_clear(void);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif /* PANIC_MESSAGE_H */
@@ -0,0 +1,326 @@
+//! Derive and attribute macros for [`panic-message`](https://docs.rs/panic-message), enabled
+//! by its `macros` feature. See the re-exports there for documentation.
+//!
+//! The macros only use `proc_macro`, and parse just as much of their input as they need.
+extern crate proc_macro;
+
+use proc_macro::{Delimiter, Group, Ident, Literal, Punct, Spacing, Span, TokenStream, TokenTree};
+
+/// Implement `panic_message::PanicMessageProvider`, from the field marked
+/// `#[panic_message]` or, without one, from the type's `Display` impl, and register it
+/// when the program starts with `panic_message::submit_extractor!`.
+#[proc_macro_derive(PanicMessage, attributes(panic_message))]
+pub fn derive_panic_message(input: TokenStream) -> TokenStream {
+    match derive(input) {
+        Ok(output) => output,
+        Err((message, span)) => compile_error(message, span),
+    }
+}
+
+/// Catch panics in the function, returning them as `panic_message::CaughtPanic`s.
+///
+/// A function returning `T` is rewritten to return `Result<T, CaughtPanic>`. A function that
+/// already returns a `Result` keeps its signature, and a panic becomes its error through
+/// `From<CaughtPanic>`.
+#[proc_macro_attribute]
+pub fn catch_panic(args: TokenStream, item: TokenStream) -> TokenStream {
+    if let Some(arg) = args.into_iter().next() {
+        return compile_error("`#[catch_panic]` takes no arguments", arg.span());
+    }
+    match catch(item) {
+        Ok(output) => output,
+        Err((message, span)) => compile_error(message, span),
+    }
+}
+
+type Error = (&'static str, Span);
+
+fn catch(item: TokenStream) -> Result<TokenStream, Error> {
+    let mut tokens: Vec<TokenTree> = item.into_iter().collect();
+    let body = match tokens.pop() {
+        Some(TokenTree::Group(body)) if body.delimiter() == Delimiter::Brace => body,
+        _ => {
+            return Err((
+                "`#[catch_panic]` only applies to functions",
+                Span::call_site(),
+            ))
+        }
+    };
+    let fn_index = tokens
+        .iter()
+        .position(|token| is_ident(token, "fn"))
+        .ok_or((
+            "`#[catch_panic]` only applies to functions",
+            Span::call_site(),
+        ))?;
+    if let Some(token) = tokens[..fn_index]
+        .iter()
+        .find(|token| is_ident(token, "async"))
+    {
+        return Err((
+            "`#[catch_panic]` doesn't support async functions",
+            token.span(),
+        ));
+    }
+    let params = params_index(&tokens, fn_index).ok_or((
+        "`#[catch_panic]` only applies to functions",
+        Span::call_site(),
+    ))?;
+    // the return type runs from `->` after the parameters to a `where` clause or the body
+    let arrow = tokens[params..]
+        .windows(2)
+        .position(|pair| is_arrow(&pair[0], &pair[1]));
+    let (signature, return_type, where_clause) = match arrow {
+        Some(arrow) => {
+            let arrow = params + arrow;
+            let end = tokens[arrow..]
+                .iter()
+                .position(|token| is_ident(token, "where"))
+                .map_or(tokens.len(), |end| arrow + end);
+            let where_clause = tokens.split_off(end);
+            let return_type = tokens.split_off(arrow + 2);
+            tokens.truncate(arrow);
+            (tokens, return_type, where_clause)
+        }
+        None => {
+            let end = tokens[params..]
+                .iter()
+                .position(|token| is_ident(token, "where"))
+                .map_or(tokens.len(), |end| params + end);
+            let where_clause = tokens.split_off(end);
+            let unit = Group::new(Delimiter::Parenthesis, TokenStream::new());
+            (tokens, vec![TokenTree::Group(unit)], where_clause)
+        }
+    };
+
+    let return_type: TokenStream = return_type.into_iter().collect();
+    // the body is moved in as it is, so it keeps its spans
+    let mut closure = code("move || ->");
+    closure.extend(return_type.clone());
+    closure.extend([TokenTree::Group(body)]);
+    let mut caught = code("::std::panic::catch_unwind");
+    caught.extend([group(Delimiter::Parenthesis, {
+        let mut unwind_safe = code("::std::panic::AssertUnwindSafe");
+        unwind_safe.extend([group(Delimiter::Parenthesis, closure)]);
+        unwind_safe
+    })]);
+
+    let mut output: TokenStream = signature.into_iter().collect();
+    output.extend(code("->"));
+    let body = if returns_result(&return_type) {
+        output.extend(return_type);
+        let mut body = code("match");
+        body.extend(caught);
+        body.extend([group(
+            Delimiter::Brace,
+            code(
+                "::std::result::Result::Ok(result) => result,
+                ::std::result::Result::Err(payload) => ::std::result::Result::Err(
+                    ::std::convert::From::from(::panic_message::CaughtPanic::new(payload)),
+                ),",
+            ),
+        )]);
+        body
+    } else {
+        output.extend(code("::std::result::Result<"));
+        output.extend(return_type);
+        output.extend(code(", ::panic_message::CaughtPanic>"));
+        caught.extend(code(".map_err(::panic_message::CaughtPanic::new)"));
+        caught
+    };
+    output.extend(where_clause);
+    output.extend([group(Delimiter::Brace, body)]);
+    Ok(output)
+}
+
+/// The index of the parameter list of the function whose `fn` is at `fn_index`: the first
+/// parenthesized group outside the generics, where `Fn() -> T` bounds have groups too.
+fn params_index(tokens: &[TokenTree], fn_index: usize) -> Option<usize> {
+    let mut depth = 0_usize;
+    let mut previous: Option<&TokenTree> = None;
+    for (index, token) in tokens.iter().enumerate().skip(fn_index + 1) {
+        match token {
+            TokenTree::Punct(punct) if punct.as_char() == '<' => depth += 1,
+            TokenTree::Punct(punct)
+                if punct.as_char() == '>' && !previous.is_some_and(|p| is_arrow(p, token)) =>
+            {
+                depth = depth.saturating_sub(1)
+            }
+            TokenTree::Group(group)
+                if group.delimiter() == Delimiter::Parenthesis && depth == 0 =>
+            {
+                return Some(index)
+            }
+            _ => {}
+        }
+        previous = Some(token);
+    }
+    None
+}
+
+fn is_arrow(first: &TokenTree, second: &TokenTree) -> bool {
+    matches!(first, TokenTree::Punct(p) if p.as_char() == '-' && p.spacing() == Spacing::Joint)
+        && matches!(second, TokenTree::Punct(p) if p.as_char() == '>')
+}
+
+/// Tokens of code the macro adds, which has no spans of the input to keep.
+fn code(code: &str) -> TokenStream {
+    code.parse().expect("generated code parses")
+}
+
+fn group(delimiter: Delimiter, stream: TokenStream) -> TokenTree {
+    TokenTree::Group(Group::new(delimiter, stream))
+}
+
+/// Whether the type is a path to something named `Result`, like `Result<T, E>` or
+/// `std::io::Result<T>`.
+fn returns_result(return_type: &TokenStream) -> bool {
+    let mut last = None;
+    for token in return_type.clone() {
+        match token {
+            TokenTree::Ident(ident) => last = Some(ident.to_string()),
+            TokenTree::Punct(punct) if punct.as_char() == ':' => {}
+            TokenTree::Punct(punct) if punct.as_char() == '<' => break,
+            _ => return false,
+        }
+    }
+    last.as_deref() == Some("Result")
+}
+
+fn is_ident(token: &TokenTree, word: &str) -> bool {
+    matches!(token, TokenTree::Ident(ident) if ident.to_string() == word)
+}
+
+fn derive(input: TokenStream) -> Result<TokenStream, Error> {
+    let mut tokens = input.into_iter().peekable();
+    let mut kind = None;
+    // attributes and visibility come before `struct` or `enum`
+    for token in &mut tokens {
+        if let TokenTree::Ident(ident) = &token {
+            let word = ident.to_string();
+            if word == "struct" || word == "enum" || word == "union" {
+                kind = Some(word);
+                break;
+            }
+        }
+    }
+    let kind = kind.ok_or(("expected a struct or enum", Span::call_site()))?;
+    let name = match tokens.next() {
+        Some(TokenTree::Ident(name)) => name,
+        _ => return Err(("expected a type name", Span::call_site())),
+    };
+    if let Some(TokenTree::Punct(punct)) = tokens.peek() {
+        if punct.as_char() == '<' {
+            return Err(("generic panic payloads are not supported", punct.span()));
+        }
+    }
+    let field = match (kind.as_str(), tokens.next()) {
+        ("struct", Some(TokenTree::Group(fields))) => marked_field(&fields)?,
+        (_, Some(TokenTree::Group(fields))) => {
+            if marked_field(&fields)?.is_some() {
+                return Err((
+                    "`#[panic_message]` is only supported on structs",
+                    name.span(),
+                ));
+            }
+            None
+        }
+        _ => None,
+    };
+
+    let body = match field {
+        // AsRef, so `String`, `&'static str`, `Box<str>` and the like all work
+        Some(field) => format!(
+            "::std::borrow::Cow::Borrowed(::std::convert::AsRef::<str>::as_ref(&self.{}))",
+            field
+        ),
+        None => "::std::borrow::Cow::Owned(::std::string::ToString::to_string(self))".to_string(),
+    };
+    let output = format!(
+        "impl ::panic_message::PanicMessageProvider for {0} {{
+            fn panic_message(&self) -> ::std::borrow::Cow<'_, str> {{
+                {1}
+            }}
+        }}
+        ::panic_message::submit_extractor!({0});",
+        name, body
+    );
+    Ok(output.parse().expect("generated impl parses"))
+}
+
+/// The name (or index) of the field marked `#[panic_message]`, if any.
+fn marked_field(fields: &Group) -> Result<Option<String>, Error> {
+    let tuple = fields.delimiter() == Delimiter::Parenthesis;
+    let mut marked = None;
+    let mut index = 0;
+    let mut is_marked = false;
+    let mut name = None;
+    // angle brackets aren't groups, so commas inside generic arguments need skipping
+    let mut depth = 0_usize;
+    let mut tokens = fields.stream().into_iter().peekable();
+    while let Some(token) = tokens.next() {
+        match &token {
+            TokenTree::Punct(punct) if punct.as_char() == '#' && depth == 0 => {
+                if let Some(TokenTree::Group(attr)) = tokens.next() {
+                    let mut attr = attr.stream().into_iter();
+                    if let (Some(TokenTree::Ident(ident)), None) = (attr.next(), attr.next()) {
+                        if ident.to_string() == "panic_message" {
+                            if marked.is_some() || is_marked {
+                                return Err((
+                                    "only one field can be marked `#[panic_message]`",
+                                    ident.span(),
+                                ));
+                            }
+                            is_marked = true;
+                        }
+                    }
+                }
+            }
+            TokenTree::Punct(punct) if punct.as_char() == '<' => depth += 1,
+            // `->` in `fn() -> T` field types doesn't close a bracket
+            TokenTree::Punct(punct) if punct.as_char() == '>' && depth > 0 => depth -= 1,
+            TokenTree::Punct(punct) if punct.as_char() == ',' && depth == 0 => {
+                if is_marked {
+                    marked = field_name(tuple, index, name.take());
+                    is_marked = false;
+                }
+                index += 1;
+                name = None;
+            }
+            TokenTree::Ident(ident) if !tuple && name.is_none() && depth == 0 => {
+                if let Some(TokenTree::Punct(colon)) = tokens.peek() {
+                    if colon.as_char() == ':' && colon.spacing() == Spacing::Alone {
+                        name = Some(ident.to_string());
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    if is_marked {
+        marked = field_name(tuple, index, name);
+    }
+    Ok(marked)
+}
+
+fn field_name(tuple: bool, index: usize, name: Option<String>) -> Option<String> {
+    if tuple {
+        Some(index.to_string())
+    } else {
+        name
+    }
+}
+
+fn compile_error(message: &str, span: Span) -> TokenStream {
+    let mut tokens = vec![
+        TokenTree::Ident(Ident::new("compile_error", span)),
+        TokenTree::Punct(Punct::new('!', Spacing::Alone)),
+    ];
+    let mut literal = Literal::string(message);
+    literal.set_span(span);
+    let mut group = Group::new(Delimiter::Parenthesis, TokenTree::Literal(literal).into());
+    group.set_span(span);
+    tokens.push(TokenTree::Group(group));
+    tokens.push(TokenTree::Punct(Punct::new(';', Spacing::Alone)));
+    tokens.into_iter().collect()
+}
@@ -0,0 +1,87 @@
+//! A per-thread trail of recent events, to explain what a thread was doing when it crashed.
+use std::{cell::RefCell, collections::VecDeque};
+
+/// How many breadcrumbs each thread keeps.
+const CAPACITY: usize = 32;
+
+thread_local! {
+    static TRAIL: RefCell<VecDeque<String>> = const { RefCell::new(VecDeque::new()) };
+}
+
+/// Record a breadcrumb on the current thread, dropping the oldest one once the
+/// thread has 32.
+///
+/// ## Examples
+/// ```
+/// panic_message::breadcrumb("loading config");
+/// panic_message::breadcrumb("connecting");
+///
+/// assert_eq!(vec!["loading config", "connecting"], panic_message::breadcrumbs());
+/// ```
+pub fn breadcrumb(message: impl Into<String>) {
+    let message = message.into();
+    TRAIL.with(|trail| {
+        let mut trail = trail.borrow_mut();
+        if trail.len() == CAPACITY {
+            trail.pop_front();
+        }
+        trail.push_back(message);
+        #[cfg(all(any(unix, windows), feature = "signals"))]
+        if crate::signals::armed() {
+            crate::signals::render_breadcrumbs(&trail);
+        }
+    });
+}
+
+/// The current thread's breadcrumbs, oldest first.
+pub fn breadcrumbs() -> Vec<String> {
+    TRAIL.with(|trail| trail.borrow().iter().cloned().collect())
+}
+
+/// Forget the current thread's breadcrumbs.
+pub fn clear_breadcrumbs() {
+    TRAIL.with(|trail| {
+        let mut trail = trail.borrow_mut();
+        trail.clear();
+        #[cfg(all(any(unix, windows), feature = "signals"))]
+        if crate::signals::armed() {
+            crate::signals::render_breadcrumbs(&trail);
+        }
+    });
+}
+
+/// Render the current thread's breadcrumbs for the signal handlers, as they are only kept
+/// rendered once one is installed.
+#[cfg(all(any(unix, windows), feature = "signals"))]
+pub(crate) fn render_for_signals() {
+    let _ = TRAIL.try_with(|trail| crate::signals::render_breadcrumbs(&trail.borrow()));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bounded() {
+        for i in 0..40 {
+            breadcrumb(i.to_string());
+        }
+
+        let trail = breadcrumbs();
+        assert_eq!(CAPACITY, trail.len());
+        assert_eq!("8", trail[0]);
+        assert_eq!("39", trail[CAPACITY - 1]);
+
+        clear_breadcrumbs();
+        assert!(breadcrumbs().is_empty());
+    }
+
+    #[test]
+    fn per_thread() {
+        breadcrumb("here");
+
+        std::thread::spawn(|| assert!(breadcrumbs().is_empty()))
+            .join()
+            .unwrap();
+    }
+}
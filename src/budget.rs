@@ -0,0 +1,255 @@
+//! Keeping reports under a size limit, for sinks whose intake rejects large payloads.
+use crate::{PanicDetails, SectionContent};
+
+/// How the budget is shared: message, context, breadcrumbs, backtrace and other sections.
+const SHARES: [usize; 5] = [1, 1, 1, 4, 1];
+
+impl PanicDetails {
+    /// Shorten the report so its text takes at most `bytes` bytes, marking each cut with
+    /// `…[truncated N bytes]` where there is room for the marker.
+    ///
+    /// The budget is shared between the message, the context [fields][PanicDetails::fields],
+    /// the `breadcrumbs` [section][PanicDetails::sections], the backtrace and the other
+    /// sections: the backtrace gets half of it, the others an eighth each, and a part that
+    /// needs less than its share leaves the rest to the others. Field values are cut from
+    /// the last field, breadcrumbs from the oldest and the backtrace from its outermost frames.
+    ///
+    /// Only text counts: field keys, section names and the overhead a format adds, like
+    /// JSON quoting and the location, don't, so leave room for them below an intake limit.
+    ///
+    /// ## Examples
+    /// ```
+    /// use panic_message::PanicDetails;
+    ///
+    /// let details = PanicDetails::new("gus ".repeat(100))
+    ///     .with_field("request", "GET /")
+    ///     .with_size_budget(80);
+    ///
+    /// assert_eq!(
+    ///     "gus gus gus gus gus gus gus gus gus gus gus gus gus…[truncated 349 bytes]",
+    ///     details.message()
+    /// );
+    /// assert_eq!("GET /", details.fields()[0].1);
+    /// ```
+    pub fn with_size_budget(mut self, bytes: usize) -> Self {
+        let breadcrumbs = self.sections.iter().position(|section| {
+            section.name == "breadcrumbs" && matches!(section.content, SectionContent::List(_))
+        });
+        let mut backtrace = self.backtrace().map(str::to_string);
+
+        let mut message = [&mut self.message];
+        let mut fields: Vec<_> = self.fields.iter_mut().map(|(_, value)| value).collect();
+        let mut crumbs = Vec::new();
+        let mut others = Vec::new();
+        for (i, section) in self.sections.iter_mut().enumerate() {
+            let items = match &mut section.content {
+                SectionContent::Text(text) => vec![text],
+                SectionContent::List(items) => items.iter_mut().collect(),
+                SectionContent::Fields(fields) => {
+                    fields.iter_mut().map(|(_, value)| value).collect()
+                }
+            };
+            if Some(i) == breadcrumbs {
+                // the newest breadcrumbs are the ones worth keeping
+                crumbs.extend(items.into_iter().rev());
+            } else {
+                others.extend(items);
+            }
+        }
+        let mut trace: Vec<_> = backtrace.iter_mut().collect();
+
+        let mut parts = [
+            &mut message[..],
+            &mut fields[..],
+            &mut crumbs[..],
+            &mut trace[..],
+            &mut others[..],
+        ];
+        let sizes = parts
+            .each_ref()
+            .map(|part| part.iter().map(|item| item.len()).sum());
+        let caps = caps(bytes, sizes);
+        let [_, fields_kept, crumbs_kept, _, _] = [0, 1, 2, 3, 4].map(|i| fit(parts[i], caps[i]));
+        let trace_truncated = caps[3] < sizes[3];
+
+        self.fields.truncate(fields_kept);
+        if let Some(i) = breadcrumbs {
+            if let SectionContent::List(items) = &mut self.sections[i].content {
+                items.drain(..items.len() - crumbs_kept);
+            }
+        }
+        if trace_truncated {
+            self.backtrace = backtrace.map(Into::into);
+        }
+        self
+    }
+}
+
+/// Share `bytes` between parts of the given sizes, in proportion to [`SHARES`], giving what
+/// the parts that fit in their share don't use to the others.
+fn caps(bytes: usize, sizes: [usize; 5]) -> [usize; 5] {
+    let mut caps = [None; 5];
+    let mut remaining = bytes;
+    loop {
+        let shares: usize = (0..5)
+            .filter(|&i| caps[i].is_none())
+            .map(|i| SHARES[i])
+            .sum();
+        if shares == 0 {
+            break;
+        }
+        let mut settled = false;
+        for i in 0..5 {
+            if caps[i].is_none() && sizes[i] <= remaining * SHARES[i] / shares {
+                caps[i] = Some(sizes[i]);
+                settled = true;
+            }
+        }
+        if !settled {
+            for i in 0..5 {
+                if caps[i].is_none() {
+                    caps[i] = Some(remaining * SHARES[i] / shares);
+                }
+            }
+            break;
+        }
+        remaining = bytes - caps.iter().flatten().sum::<usize>();
+    }
+    caps.map(|cap| cap.unwrap_or(0))
+}
+
+/// Shorten `items` to at most `cap` bytes in total, keeping them in order: the first one
+/// that doesn't fit is cut, with a marker counting every byte dropped, and the ones after
+/// it are cleared. Returns how many items are left.
+fn fit(items: &mut [&mut String], cap: usize) -> usize {
+    let total: usize = items.iter().map(|item| item.len()).sum();
+    if total <= cap {
+        return items.len();
+    }
+    let mut used = 0;
+    for i in 0..items.len() {
+        if used + items[i].len() <= cap {
+            used += items[i].len();
+            continue;
+        }
+        let after = total - used - items[i].len();
+        let cut = truncate(items[i], cap - used, after);
+        for item in &mut items[i + 1..] {
+            item.clear();
+        }
+        return if cut { i + 1 } else { i };
+    }
+    unreachable!("the items didn't fit, so one of them can't")
+}
+
+/// Cut `text` so it and the marker take at most `cap` bytes, or clear it if the marker
+/// doesn't fit, returning whether anything is left. `after` more bytes were dropped after
+/// `text`, and are counted in the marker.
+fn truncate(text: &mut String, cap: usize, after: usize) -> bool {
+    let mut keep = cap;
+    // the marker shrinks as `keep` grows, so this settles within a few rounds
+    for _ in 0..3 {
+        keep = floor_char_boundary(
+            text,
+            cap.saturating_sub(marker(text.len() - keep + after).len()),
+        );
+    }
+    let marker = marker(text.len() - keep + after);
+    text.truncate(keep);
+    if keep + marker.len() > cap {
+        text.clear();
+        return false;
+    }
+    text.push_str(&marker);
+    true
+}
+
+fn marker(dropped: usize) -> String {
+    format!("…[truncated {} bytes]", dropped)
+}
+
+fn floor_char_boundary(text: &str, index: usize) -> usize {
+    let mut index = index.min(text.len());
+    while !text.is_char_boundary(index) {
+        index -= 1;
+    }
+    index
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Section;
+
+    #[test]
+    fn small_reports_are_untouched() {
+        let details = PanicDetails::new("gus")
+            .with_field("port", "8080")
+            .with_backtrace("0: main");
+
+        assert_eq!(details.clone(), details.clone().with_size_budget(15));
+    }
+
+    #[test]
+    fn shares_what_is_left() {
+        let details = PanicDetails::new("gus")
+            .with_field("port", "8080")
+            .with_backtrace("frame\n".repeat(100))
+            .with_section(Section::list(
+                "breadcrumbs",
+                (0..10).map(|i| format!("crumb {}", i)).collect(),
+            ))
+            .with_size_budget(200);
+
+        assert_eq!("gus", details.message());
+        assert_eq!("8080", details.fields()[0].1);
+        // the message and field leave 193 bytes: a fifth for the breadcrumbs, with no room
+        // for a marker after the fifth newest, and four fifths for the backtrace
+        assert_eq!(
+            &SectionContent::List((5..10).map(|i| format!("crumb {}", i)).collect()),
+            details.sections()[0].content()
+        );
+        let backtrace = details.backtrace().unwrap();
+        assert_eq!(193 * 4 / 5, backtrace.len());
+        assert!(backtrace.starts_with("frame\nframe\n"));
+        assert!(backtrace.ends_with("…[truncated 470 bytes]"));
+    }
+
+    #[test]
+    fn later_fields_are_dropped() {
+        let details = PanicDetails::new("gus")
+            .with_field("a", "x".repeat(50))
+            .with_field("b", "y".repeat(50))
+            .with_field("c", "z".repeat(50))
+            .with_size_budget(100);
+
+        // the message needs 3 bytes of its share, so the fields get the other 97
+        assert_eq!(
+            vec![
+                ("a".to_string(), "x".repeat(50)),
+                (
+                    "b".to_string(),
+                    format!("{}…[truncated 76 bytes]", "y".repeat(24))
+                )
+            ],
+            details.fields()
+        );
+    }
+
+    #[test]
+    fn no_room_for_the_marker() {
+        let mut text = "gus".repeat(10);
+
+        assert!(!truncate(&mut text, 10, 0));
+        assert_eq!("", text);
+    }
+
+    #[test]
+    fn cuts_on_char_boundaries() {
+        let mut text = "é".repeat(20);
+        truncate(&mut text, 30, 0);
+
+        assert_eq!(format!("{}…[truncated 34 bytes]", "ééé"), text);
+        assert!(text.len() <= 30);
+    }
+}
@@ -0,0 +1,199 @@
+//! Catching panics and extracting what they left behind in one call.
+use std::{
+    any::{Any, TypeId},
+    cell::{Cell, RefCell},
+    panic::{catch_unwind, UnwindSafe},
+    sync::Once,
+};
+
+use crate::{BacktraceMode, Location, PanicDetails};
+
+static INSTALL: Once = Once::new();
+
+thread_local! {
+    /// How many `catch_unwind_report` calls the thread is in, so the hook only pays for a
+    /// backtrace when one of them wants it.
+    static DEPTH: Cell<usize> = const { Cell::new(0) };
+    /// When the innermost of those calls wants a backtrace.
+    static MODE: Cell<BacktraceMode> = const { Cell::new(BacktraceMode::Env) };
+    /// How many panics the hook has seen on the thread while it was in one.
+    static SEQUENCE: Cell<u64> = const { Cell::new(0) };
+    /// What the hook saw of the latest of those panics.
+    static STASHED: RefCell<Option<Stashed>> = const { RefCell::new(None) };
+}
+
+/// What the hook keeps of a panic for the `catch_unwind_report` call that catches it. The
+/// message is rendered after the catch, as formatters may panic, which would abort in the
+/// hook.
+struct Stashed {
+    sequence: u64,
+    /// The payload's type and its message, if it is a string, to tell whether the payload that
+    /// was caught is this panic's.
+    payload: (TypeId, Option<String>),
+    /// The location, thread, time and backtrace, without a message.
+    details: PanicDetails,
+}
+
+fn identify(payload: &(dyn Any + Send)) -> (TypeId, Option<String>) {
+    (
+        payload.type_id(),
+        crate::imp::get_known_message(payload).map(str::to_string),
+    )
+}
+
+/// Run `f`, catching a panic and replacing its payload with the message, including one
+/// from a [registered formatter][crate::register_panic_formatter].
+///
+/// ## Examples
+/// ```
+/// use panic_message::catch_unwind_message;
+///
+/// #[derive(Debug)]
+/// enum Error {
+///     Panicked(String),
+/// }
+///
+/// impl From<String> for Error {
+///     fn from(message: String) -> Self {
+///         Error::Panicked(message)
+///     }
+/// }
+///
+/// fn parse(input: &str) -> Result<u32, Error> {
+///     let value = catch_unwind_message(|| input.parse::<u32>().unwrap())?;
+///     Ok(value)
+/// }
+///
+/// assert_eq!(3, parse("3").unwrap());
+/// assert!(matches!(parse("gus"), Err(Error::Panicked(message)) if message.contains("ParseIntError")));
+/// ```
+pub fn catch_unwind_message<F, T>(f: F) -> Result<T, String>
+where
+    F: FnOnce() -> T + UnwindSafe,
+{
+    catch_unwind(f).map_err(|payload| crate::registry::owned_message(&*payload))
+}
+
+/// Run `f`, catching a panic and reporting it with the location, thread and backtrace the
+/// payload alone doesn't carry.
+///
+/// The first call wraps the current panic hook in one that, while a thread is in
+/// `catch_unwind_report`, stashes each panic's details on that thread before calling the
+/// hook it wrapped. The caught payload is matched with the latest details stashed since `f`
+/// started, by its type and string message; without a match, as after another hook replaced this one, the
+/// report has only what [`PanicDetails::from_payload`] finds. So install other hooks before
+/// the first call.
+///
+/// ## Examples
+/// ```
+/// use panic_message::catch_unwind_report;
+///
+/// let line = line!() + 1;
+/// let report = catch_unwind_report(|| panic!("gus")).unwrap_err();
+///
+/// assert_eq!("gus", report.message());
+/// assert_eq!(line, report.location().unwrap().line());
+/// ```
+///
+/// The backtrace is captured when `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` asks for one,
+/// see [`catch_unwind_report_with`] to choose.
+// the report is as large as a panic's details, and only returned when there was a panic
+#[allow(clippy::result_large_err)]
+pub fn catch_unwind_report<F, T>(f: F) -> Result<T, PanicDetails>
+where
+    F: FnOnce() -> T + UnwindSafe,
+{
+    catch_unwind_report_with(BacktraceMode::Env, f)
+}
+
+/// Like [`catch_unwind_report`], but capture the backtrace as `backtrace` says, for
+/// example [always][BacktraceMode::Forced] to log caught panics with their stack traces
+/// whatever the environment asks for.
+///
+/// ## Examples
+/// ```
+/// use panic_message::{catch_unwind_report_with, BacktraceMode};
+///
+/// let report = catch_unwind_report_with(BacktraceMode::Forced, || panic!("gus")).unwrap_err();
+///
+/// assert!(report.std_backtrace().is_some());
+/// ```
+#[allow(clippy::result_large_err)]
+pub fn catch_unwind_report_with<F, T>(backtrace: BacktraceMode, f: F) -> Result<T, PanicDetails>
+where
+    F: FnOnce() -> T + UnwindSafe,
+{
+    INSTALL.call_once(install_hook);
+    let start = SEQUENCE.with(Cell::get);
+    DEPTH.with(|depth| depth.set(depth.get() + 1));
+    let outer = MODE.with(|mode| mode.replace(backtrace));
+    let result = catch_unwind(f);
+    MODE.with(|mode| mode.set(outer));
+    DEPTH.with(|depth| depth.set(depth.get() - 1));
+    result.map_err(|payload| {
+        let mut details = PanicDetails::from_payload(&payload);
+        match STASHED.with(|stashed| stashed.borrow_mut().take()) {
+            Some(stashed) if stashed.sequence > start && stashed.payload == identify(&*payload) => {
+                details.location = stashed.details.location;
+                details.thread = stashed.details.thread;
+                details.thread_id = stashed.details.thread_id;
+                details.time = stashed.details.time;
+                details.backtrace = stashed.details.backtrace;
+                details
+            }
+            _ => details,
+        }
+    })
+}
+
+fn install_hook() {
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |panic_info| {
+        if DEPTH.try_with(Cell::get).unwrap_or(0) > 0 {
+            let sequence = SEQUENCE.with(|sequence| {
+                sequence.set(sequence.get() + 1);
+                sequence.get()
+            });
+            let backtrace = MODE.with(Cell::get).capture();
+            let mut details = PanicDetails::new("");
+            details.thread_id = crate::details::current_thread_id();
+            if crate::time::HAS_CLOCK {
+                details = details.with_time(crate::time::now());
+            }
+            details.location = panic_info.location().map(Location::from);
+            details.thread = std::thread::current().name().map(str::to_string);
+            details.backtrace = backtrace;
+            let payload = identify(panic_info.payload());
+            STASHED.with(|stashed| {
+                *stashed.borrow_mut() = Some(Stashed {
+                    sequence,
+                    payload,
+                    details,
+                })
+            });
+        }
+        previous(panic_info)
+    }));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::panic_any;
+
+    #[test]
+    fn message() {
+        struct Fatal(u32);
+        crate::register_panic_formatter(|fatal: &Fatal| format!("fatal {}", fatal.0));
+
+        assert_eq!(Ok(3), catch_unwind_message(|| 3));
+        assert_eq!(
+            Err("gus".to_string()),
+            catch_unwind_message(|| panic!("gus"))
+        );
+        assert_eq!(
+            Err("fatal 3".to_string()),
+            catch_unwind_message(|| panic_any(Fatal(3)))
+        );
+    }
+}
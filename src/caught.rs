@@ -0,0 +1,274 @@
+//! An owned, caught panic payload.
+use std::{any::Any, backtrace::Backtrace, error::Error, fmt, io};
+
+use crate::{Location, PanicError, PanicKind};
+
+/// A panic payload caught with [`std::panic::catch_unwind`] or returned by
+/// [`std::thread::JoinHandle::join`], with its message at hand.
+///
+/// It displays as the panic message, and can be returned as an [`Error`]. With the `nightly`
+/// feature, the error provides its [backtrace][CaughtPanic::with_backtrace], if it has one.
+///
+/// ## Examples
+/// ```
+/// use std::panic::catch_unwind;
+/// use panic_message::{PanicKind, PanicPayloadExt};
+///
+/// let caught = catch_unwind(|| {
+///     panic!("gus");
+/// }).unwrap_err().into_caught_panic();
+///
+/// assert_eq!("gus", caught.message());
+/// assert_eq!(PanicKind::Explicit, caught.kind());
+/// assert_eq!("gus", caught.to_string());
+/// ```
+pub struct CaughtPanic {
+    payload: Box<dyn Any + Send>,
+    location: Option<Location>,
+    backtrace: Option<Backtrace>,
+}
+
+impl CaughtPanic {
+    /// Wrap a payload.
+    pub fn new(payload: Box<dyn Any + Send>) -> Self {
+        CaughtPanic {
+            payload,
+            location: None,
+            backtrace: None,
+        }
+    }
+
+    /// Attach where the panic happened, for example as recorded by a panic hook.
+    pub fn with_location(mut self, location: Location) -> Self {
+        self.location = Some(location);
+        self
+    }
+
+    /// Attach the backtrace captured when the panic happened, for example by a panic hook.
+    pub fn with_backtrace(mut self, backtrace: Backtrace) -> Self {
+        self.backtrace = Some(backtrace);
+        self
+    }
+
+    /// Where the panic happened, if it was attached.
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+
+    /// The backtrace of the panic, if one was attached.
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.backtrace.as_ref()
+    }
+
+    /// Replace the payload, which need not be `Sync`, with its owned message, keeping the
+    /// kind, location and backtrace.
+    pub fn into_sendable(self) -> PanicError {
+        let message = crate::registry::owned_message(&*self.payload);
+        PanicError {
+            kind: PanicKind::classify(&message),
+            message,
+            location: self.location,
+            backtrace: self.backtrace,
+            #[cfg(feature = "miette")]
+            source: Default::default(),
+        }
+    }
+
+    /// Turn the panic into an [`io::Error`] of kind [`Other`][io::ErrorKind::Other]
+    /// carrying its [sendable][CaughtPanic::into_sendable] form, for APIs that report
+    /// failures as `io::Error`s.
+    ///
+    /// ## Examples
+    /// ```
+    /// use std::{io, panic::catch_unwind};
+    /// use panic_message::PanicPayloadExt;
+    ///
+    /// fn read_plugin() -> io::Result<u32> {
+    ///     catch_unwind(|| -> u32 { panic!("gus") })
+    ///         .map_err(|payload| payload.into_caught_panic().into_io_error())
+    /// }
+    ///
+    /// let error = read_plugin().unwrap_err();
+    /// assert_eq!(io::ErrorKind::Other, error.kind());
+    /// assert_eq!("gus", error.to_string());
+    /// ```
+    pub fn into_io_error(self) -> io::Error {
+        io::Error::other(self.into_sendable())
+    }
+
+    /// Box the [sendable][CaughtPanic::into_sendable] form of the panic, for APIs that
+    /// return `Box<dyn Error + Send + Sync>`.
+    ///
+    /// There is no `From` impl doing this, because it would overlap with the standard
+    /// library's impl for every `Error + Send + Sync` type; `?` converts a [`PanicError`]
+    /// directly.
+    pub fn into_boxed_error(self) -> Box<dyn Error + Send + Sync> {
+        Box::new(self.into_sendable())
+    }
+
+    /// The panic message, `"Box<dyn Any>"` if the payload isn't a string.
+    pub fn message(&self) -> &str {
+        crate::panic_message(&self.payload)
+    }
+
+    /// The panic message, if the payload is a string.
+    pub fn get_message(&self) -> Option<&str> {
+        crate::get_panic_message(&self.payload)
+    }
+
+    /// What kind of failure the message describes, see [`PanicKind::classify`], rendering
+    /// messages the payload doesn't hold like [`Display`][fmt::Display] does.
+    pub fn kind(&self) -> PanicKind {
+        PanicKind::classify(&crate::registry::owned_message(&*self.payload))
+    }
+
+    /// The payload itself.
+    pub fn payload(&self) -> &(dyn Any + Send) {
+        &*self.payload
+    }
+
+    /// Take the payload back, for example to pass it to [`std::panic::resume_unwind`].
+    pub fn into_payload(self) -> Box<dyn Any + Send> {
+        self.payload
+    }
+
+    /// Continue unwinding with the original payload.
+    pub fn resume(self) -> ! {
+        std::panic::resume_unwind(self.payload)
+    }
+}
+
+impl From<Box<dyn Any + Send>> for CaughtPanic {
+    fn from(payload: Box<dyn Any + Send>) -> Self {
+        CaughtPanic::new(payload)
+    }
+}
+
+impl From<CaughtPanic> for io::Error {
+    /// See [`CaughtPanic::into_io_error`].
+    fn from(caught: CaughtPanic) -> Self {
+        caught.into_io_error()
+    }
+}
+
+impl fmt::Debug for CaughtPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("CaughtPanic")
+            .field("message", &crate::registry::owned_message(&*self.payload))
+            .finish()
+    }
+}
+
+impl fmt::Display for CaughtPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::write_panic_message(f, &self.payload)
+    }
+}
+
+impl Error for CaughtPanic {
+    #[cfg(feature = "nightly")]
+    fn provide<'a>(&'a self, request: &mut std::error::Request<'a>) {
+        if let Some(backtrace) = &self.backtrace {
+            request.provide_ref::<Backtrace>(backtrace);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{catch_unwind, panic_any, AssertUnwindSafe};
+
+    #[test]
+    fn non_string() {
+        let caught = CaughtPanic::new(catch_unwind(|| panic_any(1)).unwrap_err());
+
+        assert_eq!("Box<dyn Any>", caught.message());
+        assert_eq!(None, caught.get_message());
+        assert_eq!(PanicKind::NonString, caught.kind());
+        assert_eq!(Some(&1), caught.payload().downcast_ref::<i32>());
+        assert_eq!(
+            r#"CaughtPanic { message: "Box<dyn Any>" }"#,
+            format!("{:?}", caught)
+        );
+    }
+
+    #[test]
+    fn accessors_agree_for_boxed_errors() {
+        use crate::{PanicDetails, PanicEvent, PanicPayloadExt, PanicResultExt, ShowPanic};
+
+        fn boxed_error() -> Box<dyn Any + Send> {
+            let error: Box<dyn Error + Send + Sync> = io::Error::other("no such file").into();
+            Box::new(error)
+        }
+        let expected = "no such file";
+
+        let payload = boxed_error();
+        assert_eq!(expected, crate::panic_message_cow(&payload));
+        assert_eq!(expected, ShowPanic(&payload).to_string());
+        assert_eq!(expected, PanicDetails::from_payload(&payload).message());
+        assert_eq!(expected, payload.message_owned());
+        assert_eq!(PanicKind::Explicit, payload.kind());
+        let event = PanicEvent::from(&payload);
+        assert_eq!(expected, event.message());
+        assert_eq!(expected, event.to_string());
+        assert_eq!(PanicKind::Explicit, event.kind());
+        let caught = CaughtPanic::new(payload);
+        assert_eq!(expected, caught.to_string());
+        assert_eq!(PanicKind::Explicit, caught.kind());
+        let error = caught.into_sendable();
+        assert_eq!(expected, error.to_string());
+        assert_eq!(PanicKind::Explicit, error.kind());
+        assert_eq!(
+            Err(expected.to_string()),
+            Err::<(), _>(boxed_error()).message_err()
+        );
+        assert_eq!(
+            Err(expected.to_string()),
+            crate::catch_unwind_message(|| std::panic::resume_unwind(boxed_error()))
+        );
+    }
+
+    #[test]
+    fn resume() {
+        let caught = CaughtPanic::new(catch_unwind(|| panic!("gus")).unwrap_err());
+
+        let payload = catch_unwind(AssertUnwindSafe(move || caught.resume())).unwrap_err();
+
+        assert_eq!("gus", crate::panic_message(&payload));
+    }
+
+    #[test]
+    fn into_boxed_error() {
+        fn run() -> Result<(), Box<dyn Error + Send + Sync>> {
+            catch_unwind(|| panic!("gus"))
+                .map_err(|payload| CaughtPanic::new(payload).into_sendable())?;
+            Ok(())
+        }
+
+        let error = run().unwrap_err();
+        assert_eq!("gus", error.to_string());
+        let error = CaughtPanic::new(Box::new("gus")).into_boxed_error();
+        assert_eq!("gus", error.to_string());
+        assert_eq!(
+            PanicKind::Explicit,
+            error.downcast_ref::<PanicError>().unwrap().kind()
+        );
+
+        let error = io::Error::from(CaughtPanic::new(Box::new("gus")));
+        assert_eq!(io::ErrorKind::Other, error.kind());
+        assert!(error.get_ref().unwrap().is::<PanicError>());
+    }
+
+    #[test]
+    fn backtrace() {
+        let caught = CaughtPanic::new(Box::new("gus"));
+        assert!(caught.backtrace().is_none());
+
+        let caught = caught.with_backtrace(Backtrace::force_capture());
+        assert!(caught.backtrace().is_some());
+
+        #[cfg(feature = "nightly")]
+        assert!(std::error::request_ref::<Backtrace>(&caught).is_some());
+    }
+}
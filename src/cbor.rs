@@ -0,0 +1,591 @@
+//! [CBOR](https://www.rfc-editor.org/rfc/rfc8949) encoding of [`PanicDetails`].
+//!
+//! A report is a single map keyed by text strings, with absent fields omitted:
+//! ```text
+//! {
+//!     "message": text,
+//!     "location": { "file": text, "line": uint, "column": uint },
+//!     "thread": text,
+//!     "thread_id": uint,
+//!     "backtrace": text,
+//!     "resources": { "rss_bytes": uint, "user_time_us": uint, ... },
+//!     "uptime_us": uint,
+//!     "since_previous_panic_us": uint,
+//!     "time_us": uint, (since the Unix epoch)
+//!     "abandoned_cleanups": uint, (omitted when zero)
+//!     "fields": { text: text, ... }, (omitted when empty)
+//!     "raw_frames": [ { "address": uint, "module": text, "offset": uint }, ... ], (omitted when empty)
+//!     "sections": { text: text / [ text, ... ] / { text: text, ... }, ... }, (omitted when empty)
+//! }
+//! ```
+//! Decoding ignores keys it does not know, so reports from newer producers still decode.
+use std::{convert::TryFrom, error::Error, fmt, io, time::Duration};
+
+use crate::{Location, PanicDetails, RawFrame, ResourceUsage, Section, SectionContent};
+
+const MAJOR_UINT: u8 = 0;
+const MAJOR_TEXT: u8 = 3;
+const MAJOR_ARRAY: u8 = 4;
+const MAJOR_MAP: u8 = 5;
+const MAJOR_TAG: u8 = 6;
+const MAJOR_SIMPLE: u8 = 7;
+
+/// How deeply unknown values may nest before decoding gives up.
+const MAX_DEPTH: usize = 32;
+
+/// The longest frame [`PanicDetails::read_framed`] accepts, so a corrupt length can't make
+/// it allocate without bound.
+const MAX_FRAME_LEN: usize = 16 << 20;
+
+/// Why a CBOR report failed to decode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CborError {
+    reason: &'static str,
+    offset: usize,
+}
+
+impl fmt::Display for CborError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid CBOR panic report at byte {}: {}",
+            self.offset, self.reason
+        )
+    }
+}
+
+impl Error for CborError {}
+
+impl From<CborError> for io::Error {
+    fn from(e: CborError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+impl PanicDetails {
+    /// Encode these details as CBOR.
+    ///
+    /// ## Examples
+    /// ```
+    /// use panic_message::PanicDetails;
+    ///
+    /// let details = PanicDetails::new("gus").with_thread("main");
+    /// let bytes = details.to_cbor();
+    /// assert_eq!(details, PanicDetails::from_cbor(&bytes).unwrap());
+    /// ```
+    pub fn to_cbor(&self) -> Vec<u8> {
+        let mut out = Vec::new();
+        let len = 1
+            + self.location.is_some() as u64
+            + self.thread.is_some() as u64
+            + self.thread_id.is_some() as u64
+            + self.backtrace.is_some() as u64
+            + self.resources.is_some() as u64
+            + self.uptime.is_some() as u64
+            + self.since_previous_panic.is_some() as u64
+            + self.time.is_some() as u64
+            + (self.abandoned_cleanups > 0) as u64
+            + !self.fields.is_empty() as u64
+            + !self.raw_frames.is_empty() as u64
+            + !self.sections.is_empty() as u64;
+        write_head(&mut out, MAJOR_MAP, len);
+        write_text(&mut out, "message");
+        write_text(&mut out, &self.message);
+        if let Some(location) = &self.location {
+            write_text(&mut out, "location");
+            write_head(&mut out, MAJOR_MAP, 3);
+            write_text(&mut out, "file");
+            write_text(&mut out, &location.file);
+            write_text(&mut out, "line");
+            write_head(&mut out, MAJOR_UINT, location.line.into());
+            write_text(&mut out, "column");
+            write_head(&mut out, MAJOR_UINT, location.column.into());
+        }
+        if let Some(thread) = &self.thread {
+            write_text(&mut out, "thread");
+            write_text(&mut out, thread);
+        }
+        if let Some(thread_id) = self.thread_id {
+            write_text(&mut out, "thread_id");
+            write_head(&mut out, MAJOR_UINT, thread_id);
+        }
+        if let Some(backtrace) = &self.backtrace {
+            write_text(&mut out, "backtrace");
+            write_text(&mut out, backtrace.as_str());
+        }
+        if let Some(resources) = &self.resources {
+            let fields = resources.fields();
+            write_text(&mut out, "resources");
+            let len = fields.iter().filter(|(_, value)| value.is_some()).count();
+            write_head(&mut out, MAJOR_MAP, len as u64);
+            for (key, value) in fields {
+                if let Some(value) = value {
+                    write_text(&mut out, key);
+                    write_head(&mut out, MAJOR_UINT, value);
+                }
+            }
+        }
+        if let Some(uptime) = self.uptime {
+            write_text(&mut out, "uptime_us");
+            write_head(&mut out, MAJOR_UINT, uptime.as_micros() as u64);
+        }
+        if let Some(since_previous_panic) = self.since_previous_panic {
+            write_text(&mut out, "since_previous_panic_us");
+            write_head(
+                &mut out,
+                MAJOR_UINT,
+                since_previous_panic.as_micros() as u64,
+            );
+        }
+        if let Some(time) = self.time {
+            write_text(&mut out, "time_us");
+            write_head(&mut out, MAJOR_UINT, crate::details::unix_micros(time));
+        }
+        if self.abandoned_cleanups > 0 {
+            write_text(&mut out, "abandoned_cleanups");
+            write_head(&mut out, MAJOR_UINT, self.abandoned_cleanups.into());
+        }
+        if !self.fields.is_empty() {
+            write_text(&mut out, "fields");
+            write_head(&mut out, MAJOR_MAP, self.fields.len() as u64);
+            for (key, value) in &self.fields {
+                write_text(&mut out, key);
+                write_text(&mut out, value);
+            }
+        }
+        if !self.raw_frames.is_empty() {
+            write_text(&mut out, "raw_frames");
+            write_head(&mut out, MAJOR_ARRAY, self.raw_frames.len() as u64);
+            for frame in &self.raw_frames {
+                write_head(&mut out, MAJOR_MAP, 2 + frame.module.is_some() as u64);
+                write_text(&mut out, "address");
+                write_head(&mut out, MAJOR_UINT, frame.address);
+                if let Some(module) = &frame.module {
+                    write_text(&mut out, "module");
+                    write_text(&mut out, module);
+                }
+                write_text(&mut out, "offset");
+                write_head(&mut out, MAJOR_UINT, frame.offset);
+            }
+        }
+        if !self.sections.is_empty() {
+            write_text(&mut out, "sections");
+            write_head(&mut out, MAJOR_MAP, self.sections.len() as u64);
+            for section in &self.sections {
+                write_text(&mut out, &section.name);
+                write_section_content(&mut out, &section.content);
+            }
+        }
+        out
+    }
+
+    /// Write these details to `out` as one frame: the length of their CBOR encoding, as a
+    /// big-endian `u32`, followed by the encoding. Frames are written with a single write,
+    /// so a process can send the reports of several threads down one pipe.
+    ///
+    /// This is how a subprocess hands its panics to its parent, see [`sink::Pipe`][crate::sink::Pipe].
+    pub fn write_framed(&self, mut out: impl io::Write) -> io::Result<()> {
+        let encoded = self.to_cbor();
+        let len = u32::try_from(encoded.len())
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "report is too long"))?;
+        let mut frame = Vec::with_capacity(4 + encoded.len());
+        frame.extend_from_slice(&len.to_be_bytes());
+        frame.extend_from_slice(&encoded);
+        out.write_all(&frame)
+    }
+
+    /// Read one frame written by [`write_framed`][PanicDetails::write_framed], or `None` if
+    /// `input` ends before the frame starts.
+    ///
+    /// ## Examples
+    /// ```
+    /// use panic_message::PanicDetails;
+    ///
+    /// let mut pipe = Vec::new();
+    /// PanicDetails::new("gus").write_framed(&mut pipe).unwrap();
+    ///
+    /// let mut input = pipe.as_slice();
+    /// let details = PanicDetails::read_framed(&mut input).unwrap().unwrap();
+    /// assert_eq!("gus", details.message());
+    /// assert!(PanicDetails::read_framed(&mut input).unwrap().is_none());
+    /// ```
+    pub fn read_framed(mut input: impl io::Read) -> io::Result<Option<Self>> {
+        let mut len = [0; 4];
+        let mut read = 0;
+        while read < len.len() {
+            match input.read(&mut len[read..]) {
+                Ok(0) if read == 0 => return Ok(None),
+                Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+                Ok(n) => read += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+        let len = u32::from_be_bytes(len) as usize;
+        if len > MAX_FRAME_LEN {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "panic report frame is too long",
+            ));
+        }
+        let mut encoded = vec![0; len];
+        input.read_exact(&mut encoded)?;
+        Ok(Some(PanicDetails::from_cbor(&encoded)?))
+    }
+
+    /// Decode details previously encoded with [`to_cbor`][PanicDetails::to_cbor].
+    pub fn from_cbor(bytes: &[u8]) -> Result<Self, CborError> {
+        let mut decoder = Decoder { bytes, pos: 0 };
+        let mut message = None;
+        let mut details = PanicDetails::new(String::new());
+        for _ in 0..decoder.map_len()? {
+            match decoder.text()? {
+                "message" => message = Some(decoder.text()?.to_string()),
+                "location" => details.location = Some(decoder.location()?),
+                "thread" => details.thread = Some(decoder.text()?.to_string()),
+                "thread_id" => details.thread_id = Some(decoder.u64()?),
+                "backtrace" => details.backtrace = Some(decoder.text()?.to_string().into()),
+                "resources" => details.resources = Some(decoder.resources()?),
+                "uptime_us" => details.uptime = Some(Duration::from_micros(decoder.u64()?)),
+                "since_previous_panic_us" => {
+                    details.since_previous_panic = Some(Duration::from_micros(decoder.u64()?))
+                }
+                "time_us" => {
+                    let micros = decoder.u64()?;
+                    details.time = Some(
+                        crate::details::from_unix_micros(micros)
+                            .ok_or_else(|| decoder.error("time out of range"))?,
+                    )
+                }
+                "abandoned_cleanups" => details.abandoned_cleanups = decoder.uint()?,
+                "fields" => {
+                    for _ in 0..decoder.map_len()? {
+                        let key = decoder.text()?.to_string();
+                        details.fields.push((key, decoder.text()?.to_string()));
+                    }
+                }
+                "sections" => {
+                    for _ in 0..decoder.map_len()? {
+                        let name = decoder.text()?.to_string();
+                        let content = decoder.section_content()?;
+                        details.sections.push(Section { name, content });
+                    }
+                }
+                "raw_frames" => {
+                    for _ in 0..decoder.array_len()? {
+                        details.raw_frames.push(decoder.raw_frame()?);
+                    }
+                }
+                _ => decoder.skip(0)?,
+            }
+        }
+        if decoder.pos != bytes.len() {
+            return Err(decoder.error("trailing bytes"));
+        }
+        details.message = message.ok_or_else(|| decoder.error("missing message"))?;
+        Ok(details)
+    }
+}
+
+fn write_head(out: &mut Vec<u8>, major: u8, arg: u64) {
+    let major = major << 5;
+    if arg < 24 {
+        out.push(major | arg as u8);
+    } else if arg <= u8::MAX.into() {
+        out.extend_from_slice(&[major | 24, arg as u8]);
+    } else if arg <= u16::MAX.into() {
+        out.push(major | 25);
+        out.extend_from_slice(&(arg as u16).to_be_bytes());
+    } else if arg <= u32::MAX.into() {
+        out.push(major | 26);
+        out.extend_from_slice(&(arg as u32).to_be_bytes());
+    } else {
+        out.push(major | 27);
+        out.extend_from_slice(&arg.to_be_bytes());
+    }
+}
+
+fn write_text(out: &mut Vec<u8>, text: &str) {
+    write_head(out, MAJOR_TEXT, text.len() as u64);
+    out.extend_from_slice(text.as_bytes());
+}
+
+fn write_section_content(out: &mut Vec<u8>, content: &SectionContent) {
+    match content {
+        SectionContent::Text(text) => write_text(out, text),
+        SectionContent::List(items) => {
+            write_head(out, MAJOR_ARRAY, items.len() as u64);
+            for item in items {
+                write_text(out, item);
+            }
+        }
+        SectionContent::Fields(fields) => {
+            write_head(out, MAJOR_MAP, fields.len() as u64);
+            for (key, value) in fields {
+                write_text(out, key);
+                write_text(out, value);
+            }
+        }
+    }
+}
+
+struct Decoder<'a> {
+    bytes: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    fn error(&self, reason: &'static str) -> CborError {
+        CborError {
+            reason,
+            offset: self.pos,
+        }
+    }
+
+    fn take(&mut self, n: usize) -> Result<&'a [u8], CborError> {
+        match self.bytes.get(self.pos..).and_then(|rest| rest.get(..n)) {
+            Some(taken) => {
+                self.pos += n;
+                Ok(taken)
+            }
+            None => Err(self.error("unexpected end of input")),
+        }
+    }
+
+    fn head(&mut self) -> Result<(u8, u64), CborError> {
+        let initial = self.take(1)?[0];
+        let arg = match initial & 0x1f {
+            info @ 0..=23 => info.into(),
+            24 => self.take(1)?[0].into(),
+            25 => {
+                let mut buf = [0; 2];
+                buf.copy_from_slice(self.take(2)?);
+                u16::from_be_bytes(buf).into()
+            }
+            26 => {
+                let mut buf = [0; 4];
+                buf.copy_from_slice(self.take(4)?);
+                u32::from_be_bytes(buf).into()
+            }
+            27 => {
+                let mut buf = [0; 8];
+                buf.copy_from_slice(self.take(8)?);
+                u64::from_be_bytes(buf)
+            }
+            _ => return Err(self.error("indefinite lengths are not supported")),
+        };
+        Ok((initial >> 5, arg))
+    }
+
+    fn expect(&mut self, major: u8, reason: &'static str) -> Result<u64, CborError> {
+        let start = self.pos;
+        match self.head()? {
+            (found, arg) if found == major => Ok(arg),
+            _ => {
+                self.pos = start;
+                Err(self.error(reason))
+            }
+        }
+    }
+
+    fn map_len(&mut self) -> Result<u64, CborError> {
+        self.expect(MAJOR_MAP, "expected a map")
+    }
+
+    fn array_len(&mut self) -> Result<u64, CborError> {
+        self.expect(MAJOR_ARRAY, "expected an array")
+    }
+
+    fn u64(&mut self) -> Result<u64, CborError> {
+        self.expect(MAJOR_UINT, "expected an unsigned integer")
+    }
+
+    fn uint(&mut self) -> Result<u32, CborError> {
+        let value = self.u64()?;
+        u32::try_from(value).map_err(|_| self.error("integer out of range"))
+    }
+
+    fn text(&mut self) -> Result<&'a str, CborError> {
+        let len = self.expect(MAJOR_TEXT, "expected a text string")?;
+        let len = usize::try_from(len).map_err(|_| self.error("length out of range"))?;
+        let bytes = self.take(len)?;
+        std::str::from_utf8(bytes).map_err(|_| self.error("text is not valid UTF-8"))
+    }
+
+    fn location(&mut self) -> Result<Location, CborError> {
+        let mut location = Location::new(String::new(), 0, 0);
+        for _ in 0..self.map_len()? {
+            match self.text()? {
+                "file" => location.file = self.text()?.to_string(),
+                "line" => location.line = self.uint()?,
+                "column" => location.column = self.uint()?,
+                _ => self.skip(0)?,
+            }
+        }
+        Ok(location)
+    }
+
+    fn section_content(&mut self) -> Result<SectionContent, CborError> {
+        match self.bytes.get(self.pos).map(|initial| initial >> 5) {
+            Some(MAJOR_ARRAY) => {
+                let len = self.array_len()?;
+                let items = (0..len)
+                    .map(|_| self.text().map(str::to_string))
+                    .collect::<Result<_, _>>()?;
+                Ok(SectionContent::List(items))
+            }
+            Some(MAJOR_MAP) => {
+                let len = self.map_len()?;
+                let mut fields = Vec::new();
+                for _ in 0..len {
+                    let key = self.text()?.to_string();
+                    fields.push((key, self.text()?.to_string()));
+                }
+                Ok(SectionContent::Fields(fields))
+            }
+            _ => Ok(SectionContent::Text(self.text()?.to_string())),
+        }
+    }
+
+    fn raw_frame(&mut self) -> Result<RawFrame, CborError> {
+        let mut frame = RawFrame::default();
+        for _ in 0..self.map_len()? {
+            match self.text()? {
+                "address" => frame.address = self.u64()?,
+                "module" => frame.module = Some(self.text()?.to_string()),
+                "offset" => frame.offset = self.u64()?,
+                _ => self.skip(0)?,
+            }
+        }
+        Ok(frame)
+    }
+
+    fn resources(&mut self) -> Result<ResourceUsage, CborError> {
+        let mut resources = ResourceUsage::default();
+        for _ in 0..self.map_len()? {
+            let key = self.text()?;
+            // peek, so unknown keys with non-integer values can still be skipped
+            match self.bytes.get(self.pos) {
+                Some(initial) if initial >> 5 == MAJOR_UINT => {
+                    let value = self.u64()?;
+                    resources.set_field(key, value);
+                }
+                _ => self.skip(0)?,
+            }
+        }
+        Ok(resources)
+    }
+
+    /// Skip over one complete data item of any type.
+    fn skip(&mut self, depth: usize) -> Result<(), CborError> {
+        if depth > MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        let (major, arg) = self.head()?;
+        match major {
+            MAJOR_UINT | 1 | MAJOR_SIMPLE => {}
+            2 | MAJOR_TEXT => {
+                let len = usize::try_from(arg).map_err(|_| self.error("length out of range"))?;
+                self.take(len)?;
+            }
+            MAJOR_ARRAY => {
+                for _ in 0..arg {
+                    self.skip(depth + 1)?;
+                }
+            }
+            MAJOR_MAP => {
+                for _ in 0..arg {
+                    self.skip(depth + 1)?;
+                    self.skip(depth + 1)?;
+                }
+            }
+            MAJOR_TAG => self.skip(depth + 1)?,
+            _ => unreachable!("major types are 3 bits"),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        let details = PanicDetails::new("gus".repeat(100))
+            .with_location(Location::new("src/lib.rs", 70_000, 12))
+            .with_thread("main")
+            .with_thread_id(u64::MAX)
+            .with_backtrace("0: main")
+            .with_resources(ResourceUsage::capture())
+            .with_uptime(Duration::from_micros(1_500_000))
+            .with_since_previous_panic(Duration::from_micros(7))
+            .with_time(std::time::SystemTime::now())
+            .with_abandoned_cleanups(1)
+            .with_field("port", "8080")
+            .with_field("port", "8081")
+            .with_raw_frames(vec![
+                RawFrame {
+                    address: u64::MAX,
+                    module: Some("libgus.so".to_string()),
+                    offset: 0x1000,
+                },
+                RawFrame::default(),
+            ])
+            .with_section(Section::text("config", "a1b2"))
+            .with_section(Section::list("peers", vec!["10.0.0.1".to_string()]))
+            .with_section(Section::fields(
+                "limits",
+                vec![("fds".to_string(), "1024".to_string())],
+            ));
+
+        assert_eq!(
+            details,
+            PanicDetails::from_cbor(&details.to_cbor()).unwrap()
+        );
+    }
+
+    #[test]
+    fn minimal_encoding() {
+        let details = PanicDetails::new("gus");
+
+        // {"message": "gus"}
+        assert_eq!(
+            b"\xa1\x67message\x63gus".as_ref(),
+            details.to_cbor().as_slice()
+        );
+    }
+
+    #[test]
+    fn unknown_keys_are_skipped() {
+        // {"message": "gus", "extra": [1, {"a": null}]}
+        let bytes = b"\xa2\x67message\x63gus\x65extra\x82\x01\xa1\x61a\xf6";
+
+        assert_eq!(
+            PanicDetails::new("gus"),
+            PanicDetails::from_cbor(bytes).unwrap()
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert!(PanicDetails::from_cbor(b"").is_err());
+        // {} has no message
+        assert!(PanicDetails::from_cbor(b"\xa0").is_err());
+        // truncated text
+        assert!(PanicDetails::from_cbor(b"\xa1\x67message\x63gu").is_err());
+    }
+
+    #[test]
+    fn framing_errors() {
+        let mut frame = Vec::new();
+        PanicDetails::new("gus").write_framed(&mut frame).unwrap();
+
+        let truncated = PanicDetails::read_framed(&frame[..frame.len() - 1]).unwrap_err();
+        assert_eq!(io::ErrorKind::UnexpectedEof, truncated.kind());
+        let short_length = PanicDetails::read_framed(&frame[..2]).unwrap_err();
+        assert_eq!(io::ErrorKind::UnexpectedEof, short_length.kind());
+        let too_long = PanicDetails::read_framed(&b"\xff\xff\xff\xff"[..]).unwrap_err();
+        assert_eq!(io::ErrorKind::InvalidData, too_long.kind());
+    }
+}
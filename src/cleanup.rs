@@ -0,0 +1,196 @@
+//! Best-effort release of critical resources when the process panics.
+use std::{
+    cell::Cell,
+    panic::{catch_unwind, AssertUnwindSafe},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, RecvTimeoutError},
+        Arc, Mutex, MutexGuard,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+type Cleanup = Arc<dyn Fn() + Send + Sync + 'static>;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+static CLEANUPS: Mutex<Vec<(u64, Cleanup)>> = Mutex::new(Vec::new());
+
+thread_local! {
+    static ON_CLEANUP_THREAD: Cell<bool> = const { Cell::new(false) };
+}
+
+/// A registered cleanup callback, see [`register_cleanup`].
+///
+/// Dropping the handle leaves the callback registered.
+#[derive(Debug)]
+pub struct CleanupHandle {
+    id: u64,
+}
+
+impl CleanupHandle {
+    /// Unregister the callback, for when the resource was released normally. Returns
+    /// whether it was still registered.
+    pub fn cancel(self) -> bool {
+        let mut cleanups = lock();
+        let before = cleanups.len();
+        cleanups.retain(|(id, _)| *id != self.id);
+        cleanups.len() != before
+    }
+}
+
+/// Register `f` to release a critical resource (a lock file, an external lease, hardware
+/// state) when the process panics.
+///
+/// Callbacks run in reverse order of registration, from the hook
+/// [`PanicHookBuilder`][crate::PanicHookBuilder] installs, before the panic unwinds or
+/// aborts. Other hooks can run them with [`run_cleanups`]. The hook can't tell whether the
+/// panic will be caught, so a callback stays registered until it is
+/// [cancelled][CleanupHandle::cancel] and runs again for the next panic; it should be fine
+/// to run more than once.
+///
+/// ## Examples
+/// ```
+/// let lock_file = std::env::temp_dir().join("gus.lock");
+/// let path = lock_file.clone();
+/// let cleanup = panic_message::register_cleanup(move || {
+///     let _ = std::fs::remove_file(&path);
+/// });
+///
+/// // ... no panic happened, so release it normally
+/// cleanup.cancel();
+/// # let _ = std::fs::remove_file(lock_file);
+/// ```
+pub fn register_cleanup(f: impl Fn() + Send + Sync + 'static) -> CleanupHandle {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    lock().push((id, Arc::new(f)));
+    CleanupHandle { id }
+}
+
+/// Run every callback registered with [`register_cleanup`], newest first, waiting for as
+/// long as they take. The callbacks stay registered.
+///
+/// A panic inside a panic hook aborts the process, so each callback runs on a helper
+/// thread of its own; a callback that panics is reported on stderr and the rest still run.
+/// Panics on those helper threads are not reported by `PanicHookBuilder` hooks. Where no
+/// helper thread can be started, the callback runs on the current thread instead.
+pub fn run_cleanups() {
+    run(None);
+}
+
+/// Like [`run_cleanups`], but gives up once `timeout` has passed in total. The callback
+/// that is running then is abandoned on its helper thread, and the ones after it are
+/// skipped. Returns how many callbacks didn't finish. Callbacks run on the current thread,
+/// where no helper thread can be started, can't be abandoned.
+pub fn run_cleanups_within(timeout: Duration) -> usize {
+    // a deadline too far away to represent is no deadline
+    run(Instant::now().checked_add(timeout))
+}
+
+fn run(deadline: Option<Instant>) -> usize {
+    // copied out, so callbacks can register or cancel others
+    let cleanups: Vec<Cleanup> = lock()
+        .iter()
+        .map(|(_, cleanup)| Arc::clone(cleanup))
+        .collect();
+    let total = cleanups.len();
+    for (finished, cleanup) in cleanups.into_iter().rev().enumerate() {
+        let (sender, receiver) = mpsc::channel();
+        let run = {
+            let cleanup = Arc::clone(&cleanup);
+            move || {
+                ON_CLEANUP_THREAD.with(|flag| flag.set(true));
+                let _ = sender.send(catch_unwind(AssertUnwindSafe(|| cleanup())));
+            }
+        };
+        let helper = thread::Builder::new()
+            .name("panic-message-cleanup".to_string())
+            .spawn(run);
+        if helper.is_err() {
+            let on_cleanup_thread = ON_CLEANUP_THREAD.with(|flag| flag.replace(true));
+            let result = catch_unwind(AssertUnwindSafe(|| cleanup()));
+            ON_CLEANUP_THREAD.with(|flag| flag.set(on_cleanup_thread));
+            if let Err(payload) = result {
+                eprintln!(
+                    "panic-message: cleanup callback panicked: {}",
+                    crate::panic_message(&payload)
+                );
+            }
+            continue;
+        }
+        let result = match deadline {
+            Some(deadline) => {
+                receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+            }
+            None => receiver.recv().map_err(|_| RecvTimeoutError::Disconnected),
+        };
+        match result {
+            Ok(Ok(())) | Err(RecvTimeoutError::Disconnected) => {}
+            Ok(Err(payload)) => eprintln!(
+                "panic-message: cleanup callback panicked: {}",
+                crate::panic_message(&payload)
+            ),
+            Err(RecvTimeoutError::Timeout) => {
+                let abandoned = total - finished;
+                eprintln!(
+                    "panic-message: cleanup deadline passed, abandoning {} callback(s)",
+                    abandoned
+                );
+                return abandoned;
+            }
+        }
+    }
+    0
+}
+
+/// Whether the current thread is running a cleanup callback.
+pub(crate) fn on_cleanup_thread() -> bool {
+    ON_CLEANUP_THREAD.try_with(Cell::get).unwrap_or(false)
+}
+
+fn lock() -> MutexGuard<'static, Vec<(u64, Cleanup)>> {
+    CLEANUPS.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+
+    // the registry is process-global, so everything that touches it is in one test
+    #[test]
+    fn register_run_and_cancel() {
+        let order = Arc::new(Mutex::new(Vec::new()));
+        let first = Arc::clone(&order);
+        let first = register_cleanup(move || first.lock().unwrap().push(1));
+        let panicking = register_cleanup(|| panic!("gus"));
+        let cancelled = register_cleanup(|| unreachable!());
+        let last = Arc::clone(&order);
+        let last = register_cleanup(move || {
+            assert!(on_cleanup_thread());
+            last.lock().unwrap().push(2)
+        });
+
+        assert!(cancelled.cancel());
+        run_cleanups();
+        // still registered for the next panic
+        run_cleanups();
+
+        assert_eq!(vec![2, 1, 2, 1], *order.lock().unwrap());
+        assert!(!on_cleanup_thread());
+        for handle in [first, panicking, last] {
+            assert!(handle.cancel());
+        }
+
+        let skipped = register_cleanup(|| unreachable!("skipped after the deadline"));
+        let hung = register_cleanup(|| thread::sleep(Duration::from_secs(60)));
+        let finished = Arc::clone(&order);
+        let finished = register_cleanup(move || finished.lock().unwrap().push(3));
+
+        assert_eq!(2, run_cleanups_within(Duration::from_millis(50)));
+        assert_eq!(vec![2, 1, 2, 1, 3], *order.lock().unwrap());
+        for handle in [skipped, hung, finished] {
+            assert!(handle.cancel());
+        }
+    }
+}
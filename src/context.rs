@@ -0,0 +1,123 @@
+//! Per-thread context for reports, and what the hook takes from the panicking thread to
+//! the helper thread that runs the application's code.
+use std::{
+    cell::RefCell,
+    marker::PhantomData,
+    sync::atomic::{AtomicU64, Ordering},
+};
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+
+thread_local! {
+    static CONTEXT: RefCell<Vec<(u64, String, String)>> = const { RefCell::new(Vec::new()) };
+}
+
+/// Add `key` with `value` to the [fields][crate::PanicDetails::fields] of the reports of
+/// panics on the current thread, until the returned guard is dropped, like the id of the
+/// request the thread is handling.
+///
+/// The hook [`PanicHookBuilder`][crate::PanicHookBuilder] installs runs
+/// [section providers][crate::register_section_provider] and sinks on a helper thread,
+/// where the panicking thread's thread-locals can't be read, so context they need goes
+/// here: the hook copies it before leaving the panicking thread.
+///
+/// ## Examples
+/// ```
+/// use panic_message::{current_thread_context, thread_context};
+///
+/// {
+///     let _request = thread_context("request_id", "7");
+///     assert_eq!(
+///         vec![("request_id".to_string(), "7".to_string())],
+///         current_thread_context()
+///     );
+/// }
+/// assert!(current_thread_context().is_empty());
+/// ```
+pub fn thread_context(key: impl Into<String>, value: impl Into<String>) -> ContextGuard {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    CONTEXT.with(|context| context.borrow_mut().push((id, key.into(), value.into())));
+    ContextGuard {
+        id,
+        not_send: PhantomData,
+    }
+}
+
+/// The current thread's [context][thread_context], oldest first.
+pub fn current_thread_context() -> Vec<(String, String)> {
+    CONTEXT
+        .try_with(|context| {
+            context
+                .borrow()
+                .iter()
+                .map(|(_, key, value)| (key.clone(), value.clone()))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Removes a [`thread_context`] entry when dropped.
+#[derive(Debug)]
+#[must_use = "the context is removed when the guard is dropped"]
+pub struct ContextGuard {
+    id: u64,
+    // the entry belongs to the thread that added it
+    not_send: PhantomData<*const ()>,
+}
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        let _ = CONTEXT.try_with(|context| context.borrow_mut().retain(|(id, ..)| *id != self.id));
+    }
+}
+
+/// What code moved off the panicking thread needs of it, besides the report: the `tracing`
+/// span the panic happened in, with the `tracing` feature.
+pub(crate) struct Captured {
+    #[cfg(feature = "tracing")]
+    dispatch: tracing::Dispatch,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl Captured {
+    /// Capture the current thread's state.
+    pub(crate) fn capture() -> Self {
+        Captured {
+            #[cfg(feature = "tracing")]
+            dispatch: tracing::dispatcher::get_default(tracing::Dispatch::clone),
+            #[cfg(feature = "tracing")]
+            span: tracing::Span::current(),
+        }
+    }
+
+    /// Run `f` with the captured state, on any thread.
+    pub(crate) fn enter<T>(&self, f: impl FnOnce() -> T) -> T {
+        #[cfg(feature = "tracing")]
+        return tracing::dispatcher::with_default(&self.dispatch, || self.span.in_scope(f));
+        #[cfg(not(feature = "tracing"))]
+        f()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn nested() {
+        let outer = thread_context("request_id", "7");
+        let inner = thread_context("user", "gus");
+        drop(outer);
+
+        assert_eq!(
+            vec![("user".to_string(), "gus".to_string())],
+            current_thread_context()
+        );
+        std::thread::spawn(|| assert!(current_thread_context().is_empty()))
+            .join()
+            .unwrap();
+        drop(inner);
+        assert!(current_thread_context().is_empty());
+    }
+}
@@ -0,0 +1,98 @@
+//! Keeping a panic storm, like a retry loop hitting the same bug, out of the logs.
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// How many fingerprints are remembered before those that can't suppress anything
+/// anymore are forgotten.
+const MAX_REMEMBERED: usize = 1024;
+
+/// The fingerprints reported recently, and how many panics with each were suppressed since.
+pub(crate) struct Dedup {
+    window: Duration,
+    seen: Mutex<HashMap<u64, Seen>>,
+}
+
+struct Seen {
+    reported: Instant,
+    suppressed: u64,
+}
+
+impl Dedup {
+    pub(crate) fn new(window: Duration) -> Self {
+        Dedup {
+            window,
+            seen: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether to report a panic with `fingerprint` happening at `now`: `None` if one was
+    /// reported less than the window ago, otherwise how many were suppressed since the
+    /// last one was reported.
+    pub(crate) fn check(&self, fingerprint: u64, now: Instant) -> Option<u64> {
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(entry) = seen.get_mut(&fingerprint) {
+            if now.saturating_duration_since(entry.reported) < self.window {
+                entry.suppressed += 1;
+                return None;
+            }
+            let suppressed = entry.suppressed;
+            *entry = Seen {
+                reported: now,
+                suppressed: 0,
+            };
+            return Some(suppressed);
+        }
+        if seen.len() >= MAX_REMEMBERED {
+            let window = self.window;
+            seen.retain(|_, entry| {
+                entry.suppressed > 0 || now.saturating_duration_since(entry.reported) < window
+            });
+        }
+        seen.insert(
+            fingerprint,
+            Seen {
+                reported: now,
+                suppressed: 0,
+            },
+        );
+        Some(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn suppresses_within_the_window() {
+        let dedup = Dedup::new(Duration::from_secs(10));
+        let start = Instant::now();
+
+        assert_eq!(Some(0), dedup.check(1, start));
+        assert_eq!(None, dedup.check(1, start + Duration::from_secs(1)));
+        assert_eq!(None, dedup.check(1, start + Duration::from_secs(9)));
+        assert_eq!(Some(0), dedup.check(2, start + Duration::from_secs(9)));
+        assert_eq!(Some(2), dedup.check(1, start + Duration::from_secs(10)));
+        assert_eq!(None, dedup.check(1, start + Duration::from_secs(11)));
+        assert_eq!(Some(1), dedup.check(1, start + Duration::from_secs(30)));
+    }
+
+    #[test]
+    fn forgets_what_cannot_suppress() {
+        let dedup = Dedup::new(Duration::from_secs(1));
+        let start = Instant::now();
+        for fingerprint in 0..MAX_REMEMBERED as u64 {
+            dedup.check(fingerprint, start);
+        }
+        dedup.check(0, start);
+
+        dedup.check(u64::MAX, start + Duration::from_secs(2));
+
+        let seen = dedup.seen.lock().unwrap();
+        assert_eq!(2, seen.len());
+        assert_eq!(1, seen[&0].suppressed);
+    }
+}
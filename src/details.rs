@@ -0,0 +1,1020 @@
+//! Owned details about a panic, detached from the lifetime of the payload or
+//! [`PanicInfo`][std::panic::PanicInfo] they were obtained from.
+use std::{
+    cell::Cell,
+    convert::TryFrom,
+    fmt,
+    panic::PanicHookInfo as PanicInfo,
+    sync::atomic::{AtomicU64, Ordering},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use crate::{
+    json::{self, JsonError, Parser},
+    trace::{BacktraceMode, Trace},
+    AsPanicPayload, PanicKind, RawFrame, ResourceUsage, Section,
+};
+
+/// An owned snapshot of a panic: its message and, when it was obtained from a
+/// [`PanicInfo`][std::panic::PanicInfo], where and on which thread it happened.
+///
+/// It is the report every part of the crate hands out, like
+/// [`catch_unwind_report`][crate::catch_unwind_report], [`last_panic`][crate::last_panic]
+/// and [`Reporter`][crate::Reporter]s, and can be sent across threads, logged and held in
+/// error types.
+///
+/// ## Examples
+/// ```
+/// use std::panic::catch_unwind;
+/// use panic_message::PanicDetails;
+///
+/// let payload = catch_unwind(|| {
+///     panic!("gus");
+/// }).unwrap_err();
+///
+/// let details = PanicDetails::from_payload(&payload);
+/// assert_eq!("gus", details.message());
+/// assert_eq!(None, details.location());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(
+    feature = "schemars",
+    derive(schemars::JsonSchema),
+    schemars(description = "A panic report, as `PanicDetails::to_json` writes it.")
+)]
+pub struct PanicDetails {
+    /// The panic message.
+    pub(crate) message: String,
+    /// Where the panic happened.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub(crate) location: Option<Location>,
+    /// The name of the thread that panicked.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub(crate) thread: Option<String>,
+    /// The id of the thread that panicked.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub(crate) thread_id: Option<u64>,
+    /// The rendered backtrace.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "crate::serde_impl::backtrace"
+        )
+    )]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<String>"))]
+    pub(crate) backtrace: Option<Trace>,
+    /// The process's resource usage when it panicked.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub(crate) resources: Option<ResourceUsage>,
+    /// How long the process had been running, in microseconds when serialized.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "uptime_us",
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "crate::serde_impl::micros"
+        )
+    )]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<u64>"))]
+    pub(crate) uptime: Option<Duration>,
+    /// How long before this panic the previous one happened, in microseconds when serialized.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "since_previous_panic_us",
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "crate::serde_impl::micros"
+        )
+    )]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<u64>"))]
+    pub(crate) since_previous_panic: Option<Duration>,
+    /// When the panic happened, in microseconds since the Unix epoch when serialized.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "time_us",
+            default,
+            skip_serializing_if = "Option::is_none",
+            with = "crate::serde_impl::unix_micros"
+        )
+    )]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<u64>"))]
+    pub(crate) time: Option<SystemTime>,
+    /// How many cleanup callbacks didn't finish before the deadline.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "crate::serde_impl::is_zero")
+    )]
+    pub(crate) abandoned_cleanups: u32,
+    /// The key-value fields of a structured panic.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Vec::is_empty",
+            with = "crate::serde_impl::fields"
+        )
+    )]
+    #[cfg_attr(
+        feature = "schemars",
+        schemars(with = "std::collections::BTreeMap<String, String>")
+    )]
+    pub(crate) fields: Vec<(String, String)>,
+    /// The unresolved stack frames, to symbolize later.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Vec::is_empty")
+    )]
+    pub(crate) raw_frames: Vec<RawFrame>,
+    /// Extra report sections, keyed by name when serialized.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            default,
+            skip_serializing_if = "Vec::is_empty",
+            with = "crate::serde_impl::sections"
+        )
+    )]
+    #[cfg_attr(
+        feature = "schemars",
+        schemars(with = "std::collections::BTreeMap<String, crate::SectionContent>")
+    )]
+    pub(crate) sections: Vec<Section>,
+}
+
+/// The id [`current_thread_id`] gives the next thread that asks for one.
+static NEXT_THREAD_ID: AtomicU64 = AtomicU64::new(1);
+
+thread_local! {
+    static THREAD_ID: Cell<u64> = const { Cell::new(0) };
+}
+
+/// The id of the current thread, assigned by this crate the first time the thread asks,
+/// counting up from 1. `None` while the thread's thread-locals are being destroyed.
+pub(crate) fn current_thread_id() -> Option<u64> {
+    THREAD_ID
+        .try_with(|id| {
+            if id.get() == 0 {
+                id.set(NEXT_THREAD_ID.fetch_add(1, Ordering::Relaxed));
+            }
+            id.get()
+        })
+        .ok()
+}
+
+/// Round down to whole microseconds, the resolution of the serialized formats, so
+/// reports survive a round trip through them unchanged.
+fn truncate(time: SystemTime) -> SystemTime {
+    from_unix_micros(unix_micros(time)).unwrap_or(time)
+}
+
+/// The time `micros` microseconds after the Unix epoch, if `SystemTime` can represent it.
+pub(crate) fn from_unix_micros(micros: u64) -> Option<SystemTime> {
+    UNIX_EPOCH.checked_add(Duration::from_micros(micros))
+}
+
+/// Microseconds since the Unix epoch, clamped to it.
+pub(crate) fn unix_micros(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_micros() as u64
+}
+
+/// The owned source location of a panic.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct Location {
+    pub(crate) file: String,
+    pub(crate) line: u32,
+    pub(crate) column: u32,
+}
+
+impl PanicDetails {
+    /// Create details with only a message. Use the `with_*` methods to fill in the rest.
+    pub fn new(message: impl Into<String>) -> Self {
+        PanicDetails {
+            message: message.into(),
+            location: None,
+            thread: None,
+            thread_id: None,
+            backtrace: None,
+            resources: None,
+            uptime: None,
+            since_previous_panic: None,
+            time: None,
+            abandoned_cleanups: 0,
+            fields: Vec::new(),
+            raw_frames: Vec::new(),
+            sections: Vec::new(),
+        }
+    }
+
+    /// Collect details from a [`std::panic::PanicInfo`], as passed to a
+    /// [`std::panic::set_hook`] handler.
+    ///
+    /// The thread name is taken from the current thread, and a backtrace is captured
+    /// with [`Backtrace::capture`][std::backtrace::Backtrace::capture], so it honors
+    /// `RUST_BACKTRACE`. The backtrace is only symbolized when it is first rendered, see
+    /// [`resolve_backtrace`][PanicDetails::resolve_backtrace].
+    ///
+    /// The thread's id and the current time are recorded too, as is the process's uptime,
+    /// along with the time since the last call to this function, which in a panic hook is
+    /// the time since the previous panic. The thread's [context][crate::thread_context]
+    /// follows the payload's fields.
+    pub fn from_panic_info(panic_info: &PanicInfo<'_>) -> Self {
+        let mut details = PanicDetails::capture(panic_info, BacktraceMode::Env);
+        details.message = crate::registry::owned_message(panic_info.payload());
+        details
+    }
+
+    /// [`from_panic_info`][PanicDetails::from_panic_info], capturing the backtrace as
+    /// `backtrace` says, without rendering the message, which runs the application's
+    /// formatters and extractors: the message is that of the payload types this crate
+    /// knows, or `"Box<dyn Any>"`.
+    pub(crate) fn capture(panic_info: &PanicInfo<'_>, backtrace: BacktraceMode) -> Self {
+        let mut fields = crate::structured::fields(panic_info.payload());
+        fields.extend(crate::context::current_thread_context());
+        PanicDetails {
+            message: crate::imp::get_known_message(panic_info.payload())
+                .unwrap_or("Box<dyn Any>")
+                .to_string(),
+            location: panic_info.location().map(Location::from),
+            thread: std::thread::current().name().map(str::to_string),
+            thread_id: current_thread_id(),
+            backtrace: backtrace.capture(),
+            resources: None,
+            uptime: crate::uptime::uptime(),
+            since_previous_panic: None,
+            time: crate::time::HAS_CLOCK.then(|| truncate(crate::time::now())),
+            abandoned_cleanups: 0,
+            fields,
+            raw_frames: Vec::new(),
+            sections: Vec::new(),
+        }
+    }
+
+    /// Collect details from a [`std::panic::catch_unwind`] payload. The payload
+    /// alone carries no location, thread or backtrace, unless it is a
+    /// [replayed][PanicDetails::replay] panic, whose original details are returned.
+    pub fn from_payload(payload: impl AsPanicPayload) -> Self {
+        let payload = payload.as_panic_payload();
+        if let Some(details) = crate::replay::details(payload) {
+            return details.clone();
+        }
+        PanicDetails {
+            fields: crate::structured::fields(payload),
+            ..PanicDetails::new(crate::registry::owned_message(payload))
+        }
+    }
+
+    /// Set the location.
+    pub fn with_location(mut self, location: Location) -> Self {
+        self.location = Some(location);
+        self
+    }
+
+    /// Set the thread name.
+    pub fn with_thread(mut self, thread: impl Into<String>) -> Self {
+        self.thread = Some(thread.into());
+        self
+    }
+
+    /// Set the id of the thread that panicked.
+    pub fn with_thread_id(mut self, thread_id: u64) -> Self {
+        self.thread_id = Some(thread_id);
+        self
+    }
+
+    /// Set when the panic happened, rounded down to whole microseconds.
+    pub fn with_time(mut self, time: SystemTime) -> Self {
+        self.time = Some(truncate(time));
+        self
+    }
+
+    /// Set the rendered backtrace.
+    pub fn with_backtrace(mut self, backtrace: impl Into<String>) -> Self {
+        self.backtrace = Some(Trace::from(backtrace.into()));
+        self
+    }
+
+    /// Attach the process's resource usage.
+    pub fn with_resources(mut self, resources: ResourceUsage) -> Self {
+        self.resources = Some(resources);
+        self
+    }
+
+    /// Set how long the process had been running.
+    pub fn with_uptime(mut self, uptime: Duration) -> Self {
+        self.uptime = Some(uptime);
+        self
+    }
+
+    /// Set how long ago the previous panic happened.
+    pub fn with_since_previous_panic(mut self, since_previous_panic: Duration) -> Self {
+        self.since_previous_panic = Some(since_previous_panic);
+        self
+    }
+
+    /// Set how many [cleanup callbacks][crate::register_cleanup] were abandoned because
+    /// they didn't finish in time.
+    pub fn with_abandoned_cleanups(mut self, abandoned_cleanups: u32) -> Self {
+        self.abandoned_cleanups = abandoned_cleanups;
+        self
+    }
+
+    /// Add a key-value field, like the ones
+    /// [`panic_with_context!`][crate::panic_with_context] records.
+    pub fn with_field(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.fields.push((key.into(), value.into()));
+        self
+    }
+
+    /// Add a [`Section`], which every format renders after the data it knows about.
+    pub fn with_section(mut self, section: Section) -> Self {
+        self.sections.push(section);
+        self
+    }
+
+    /// Set the unresolved frames, to [symbolize][crate::resolve_report] later.
+    pub fn with_raw_frames(mut self, raw_frames: Vec<RawFrame>) -> Self {
+        self.raw_frames = raw_frames;
+        self
+    }
+
+    /// The panic message, rendered by a [registered formatter][crate::register_panic_formatter]
+    /// if the payload isn't a string, or `"Box<dyn Any>"` if none could be extracted.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// Where the panic happened, if known.
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+
+    /// The name of the thread that panicked, if known and named.
+    pub fn thread(&self) -> Option<&str> {
+        self.thread.as_deref()
+    }
+
+    /// The id of the thread that panicked, if known: a number this crate gives each thread
+    /// that panics, counting up from 1 in the order they first panicked. It is unrelated to
+    /// the thread's [`ThreadId`][std::thread::ThreadId], which std doesn't expose as a number.
+    pub fn thread_id(&self) -> Option<u64> {
+        self.thread_id
+    }
+
+    /// When the panic happened, to the microsecond, if known.
+    pub fn time(&self) -> Option<SystemTime> {
+        self.time
+    }
+
+    /// The rendered backtrace, if one was captured. Rendering symbolizes a backtrace that
+    /// hasn't been yet, which is slow.
+    pub fn backtrace(&self) -> Option<&str> {
+        self.backtrace.as_ref().map(Trace::as_str)
+    }
+
+    /// The captured [`Backtrace`][std::backtrace::Backtrace] itself, for loggers and error
+    /// types that take one. `None` when there is no backtrace, or only its rendered text,
+    /// as in details [decoded][PanicDetails::from_json] from a report.
+    pub fn std_backtrace(&self) -> Option<&std::backtrace::Backtrace> {
+        self.backtrace.as_ref().and_then(Trace::backtrace)
+    }
+
+    /// Symbolize and render the backtrace now, if it hasn't been yet, rather than the first
+    /// time it is needed. Returns whether there is a backtrace.
+    ///
+    /// Details collected with [`from_panic_info`][PanicDetails::from_panic_info] hold a
+    /// captured but unresolved backtrace, so panics whose report is never rendered don't
+    /// pay for symbolication; sinks that render on another thread, like
+    /// [`Background`][crate::sink::Background], move the cost off the panicking thread.
+    pub fn resolve_backtrace(&self) -> bool {
+        self.backtrace().is_some()
+    }
+
+    /// Whether the backtrace has been symbolized, `false` if there is none.
+    pub fn is_backtrace_resolved(&self) -> bool {
+        self.backtrace.as_ref().is_some_and(Trace::is_resolved)
+    }
+
+    /// The process's resource usage when it panicked, if it was captured.
+    pub fn resources(&self) -> Option<&ResourceUsage> {
+        self.resources.as_ref()
+    }
+
+    /// How long the process had been running when it panicked, if known.
+    pub fn uptime(&self) -> Option<Duration> {
+        self.uptime
+    }
+
+    /// How long before this panic the process panicked the previous time, as the hook
+    /// [`PanicHookBuilder`][crate::PanicHookBuilder] installs counts the panics it reports;
+    /// `None` for the first, and for details collected another way.
+    pub fn since_previous_panic(&self) -> Option<Duration> {
+        self.since_previous_panic
+    }
+
+    /// How many [cleanup callbacks][crate::register_cleanup] were abandoned because they
+    /// didn't finish before the hook's deadline.
+    pub fn abandoned_cleanups(&self) -> u32 {
+        self.abandoned_cleanups
+    }
+
+    /// The key-value fields of a [`panic_with_context!`][crate::panic_with_context] panic,
+    /// in order.
+    pub fn fields(&self) -> &[(String, String)] {
+        &self.fields
+    }
+
+    /// The extra sections, in order.
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// The unresolved frames, if they were [captured][crate::capture_raw_frames].
+    pub fn raw_frames(&self) -> &[RawFrame] {
+        &self.raw_frames
+    }
+
+    /// What kind of failure the message describes, see [`PanicKind::classify`].
+    pub fn kind(&self) -> PanicKind {
+        PanicKind::classify(&self.message)
+    }
+
+    /// Render these details as a single-line JSON object, omitting absent fields.
+    ///
+    /// ## Examples
+    /// ```
+    /// use panic_message::PanicDetails;
+    ///
+    /// let details = PanicDetails::new("gus").with_thread("main");
+    /// assert_eq!(r#"{"message":"gus","thread":"main"}"#, details.to_json());
+    /// ```
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(&mut out)
+            .expect("writing to a String can't fail");
+        out
+    }
+
+    /// Read details back from the output of [`to_json`][PanicDetails::to_json], for example
+    /// from a log pipeline or a crash service. Members it doesn't know are skipped.
+    ///
+    /// ## Examples
+    /// ```
+    /// use panic_message::{Location, PanicDetails};
+    ///
+    /// let details = PanicDetails::new("gus")
+    ///     .with_location(Location::new("src/main.rs", 3, 5))
+    ///     .with_field("port", "8080");
+    ///
+    /// assert_eq!(details, PanicDetails::from_json(&details.to_json()).unwrap());
+    /// ```
+    pub fn from_json(json: &str) -> Result<Self, JsonError> {
+        let mut parser = Parser::new(json);
+        let mut message = None;
+        let mut details = PanicDetails::new(String::new());
+        parser.object(|parser, key| {
+            match key.as_str() {
+                "message" => message = Some(parser.string()?),
+                "location" => details.location = Some(read_location(parser)?),
+                "thread" => details.thread = Some(parser.string()?),
+                "thread_id" => details.thread_id = Some(parser.u64()?),
+                "backtrace" => details.backtrace = Some(parser.string()?.into()),
+                "resources" => {
+                    let mut resources = ResourceUsage::default();
+                    parser.object(|parser, key| {
+                        match parser.peek() {
+                            Some(b'0'..=b'9') => {
+                                resources.set_field(&key, parser.u64()?);
+                            }
+                            _ => parser.skip()?,
+                        }
+                        Ok(())
+                    })?;
+                    details.resources = Some(resources);
+                }
+                "uptime_us" => details.uptime = Some(Duration::from_micros(parser.u64()?)),
+                "since_previous_panic_us" => {
+                    details.since_previous_panic = Some(Duration::from_micros(parser.u64()?))
+                }
+                "time_us" => {
+                    let micros = parser.u64()?;
+                    details.time = Some(
+                        from_unix_micros(micros)
+                            .ok_or_else(|| parser.error("time_us out of range"))?,
+                    )
+                }
+                "abandoned_cleanups" => {
+                    details.abandoned_cleanups = u32::try_from(parser.u64()?)
+                        .map_err(|_| parser.error("abandoned_cleanups out of range"))?
+                }
+                "fields" => parser.object(|parser, key| {
+                    details.fields.push((key, parser.string()?));
+                    Ok(())
+                })?,
+                "raw_frames" => parser.array(|parser| {
+                    let mut frame = RawFrame::default();
+                    parser.object(|parser, key| {
+                        match key.as_str() {
+                            "address" => frame.address = read_hex(parser)?,
+                            "module" => frame.module = Some(parser.string()?),
+                            "offset" => frame.offset = read_hex(parser)?,
+                            _ => parser.skip()?,
+                        }
+                        Ok(())
+                    })?;
+                    details.raw_frames.push(frame);
+                    Ok(())
+                })?,
+                "sections" => parser.object(|parser, name| {
+                    let section = match parser.peek() {
+                        Some(b'"') => Section::text(name, parser.string()?),
+                        Some(b'[') => {
+                            let mut items = Vec::new();
+                            parser.array(|parser| {
+                                items.push(parser.string()?);
+                                Ok(())
+                            })?;
+                            Section::list(name, items)
+                        }
+                        _ => {
+                            let mut fields = Vec::new();
+                            parser.object(|parser, key| {
+                                fields.push((key, parser.string()?));
+                                Ok(())
+                            })?;
+                            Section::fields(name, fields)
+                        }
+                    };
+                    details.sections.push(section);
+                    Ok(())
+                })?,
+                _ => parser.skip()?,
+            }
+            Ok(())
+        })?;
+        parser.finish()?;
+        details.message = message.ok_or_else(|| parser.error("missing message"))?;
+        Ok(details)
+    }
+
+    pub(crate) fn write_json(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        self.write_json_at(self.time, out)
+    }
+
+    /// Write the JSON object with `time` in place of the details' own.
+    pub(crate) fn write_json_at(
+        &self,
+        time: Option<SystemTime>,
+        out: &mut dyn fmt::Write,
+    ) -> fmt::Result {
+        let mut object = json::Object::new(out)?;
+        object.str("message", &self.message)?;
+        if let Some(location) = &self.location {
+            object.object("location", |object| {
+                object.str("file", &location.file)?;
+                object.u64("line", location.line.into())?;
+                object.u64("column", location.column.into())
+            })?;
+        }
+        object.opt_str("thread", self.thread())?;
+        if let Some(thread_id) = self.thread_id {
+            object.u64("thread_id", thread_id)?;
+        }
+        object.opt_str("backtrace", self.backtrace())?;
+        if let Some(resources) = &self.resources {
+            object.object("resources", |object| resources.write_json(object))?;
+        }
+        if let Some(uptime) = self.uptime {
+            object.u64("uptime_us", uptime.as_micros() as u64)?;
+        }
+        if let Some(since_previous_panic) = self.since_previous_panic {
+            object.u64(
+                "since_previous_panic_us",
+                since_previous_panic.as_micros() as u64,
+            )?;
+        }
+        if let Some(time) = time {
+            object.u64("time_us", unix_micros(time))?;
+        }
+        if self.abandoned_cleanups > 0 {
+            object.u64("abandoned_cleanups", self.abandoned_cleanups.into())?;
+        }
+        if !self.fields.is_empty() {
+            object.object("fields", |object| self.write_fields(object))?;
+        }
+        if !self.raw_frames.is_empty() {
+            object.array("raw_frames", |array| {
+                for frame in &self.raw_frames {
+                    array.object(|object| {
+                        // hex strings, because JSON numbers can't hold every address
+                        object.str("address", &format!("{:#x}", frame.address))?;
+                        object.opt_str("module", frame.module.as_deref())?;
+                        object.str("offset", &format!("{:#x}", frame.offset))
+                    })?;
+                }
+                Ok(())
+            })?;
+        }
+        if !self.sections.is_empty() {
+            object.object("sections", |object| self.write_sections(object))?;
+        }
+        object.finish()
+    }
+
+    pub(crate) fn write_sections(&self, object: &mut json::Object<'_>) -> fmt::Result {
+        for section in &self.sections {
+            section.write_json(object)?;
+        }
+        Ok(())
+    }
+
+    pub(crate) fn write_fields(&self, object: &mut json::Object<'_>) -> fmt::Result {
+        for (key, value) in &self.fields {
+            object.str(key, value)?;
+        }
+        Ok(())
+    }
+
+    /// Write the first line of the report, naming the thread and the location, without the
+    /// colon before the message.
+    pub(crate) fn write_heading(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        let labels = crate::labels();
+        let thread = self.thread().unwrap_or(&labels.unnamed_thread);
+        match &self.location {
+            Some(location) => write!(
+                out,
+                "{} '{}' {} {}",
+                labels.thread, thread, labels.panicked_at, location
+            ),
+            None => write!(out, "{} '{}' {}", labels.thread, thread, labels.panicked),
+        }
+    }
+}
+
+fn read_location(parser: &mut Parser<'_>) -> Result<Location, JsonError> {
+    let (mut file, mut line, mut column) = (None, None, None);
+    parser.object(|parser, key| {
+        match key.as_str() {
+            "file" => file = Some(parser.string()?),
+            "line" => line = Some(read_u32(parser)?),
+            "column" => column = Some(read_u32(parser)?),
+            _ => parser.skip()?,
+        }
+        Ok(())
+    })?;
+    match (file, line, column) {
+        (Some(file), Some(line), Some(column)) => Ok(Location { file, line, column }),
+        _ => Err(parser.error("incomplete location")),
+    }
+}
+
+fn read_u32(parser: &mut Parser<'_>) -> Result<u32, JsonError> {
+    let value = parser.u64()?;
+    u32::try_from(value).map_err(|_| parser.error("integer out of range"))
+}
+
+/// A `0x`-prefixed hex string, which is how addresses are written.
+fn read_hex(parser: &mut Parser<'_>) -> Result<u64, JsonError> {
+    let text = parser.string()?;
+    text.strip_prefix("0x")
+        .and_then(|digits| u64::from_str_radix(digits, 16).ok())
+        .ok_or_else(|| parser.error("expected a hex address"))
+}
+
+impl fmt::Display for PanicDetails {
+    /// Formats like the first line of rustc's default panic handler, with the
+    /// [labels][crate::set_labels] currently set.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.write_heading(f)?;
+        let labels = crate::labels();
+        let message = match self.kind() {
+            PanicKind::NonString => &labels.fallback_message,
+            _ => self.message(),
+        };
+        write!(f, ":\n{}", message)?;
+        for section in &self.sections {
+            write!(f, "\n{}", section)?;
+        }
+        Ok(())
+    }
+}
+
+impl Location {
+    /// Create a location.
+    pub fn new(file: impl Into<String>, line: u32, column: u32) -> Self {
+        Location {
+            file: file.into(),
+            line,
+            column,
+        }
+    }
+
+    /// The source file.
+    pub fn file(&self) -> &str {
+        &self.file
+    }
+
+    /// The 1-based line.
+    pub fn line(&self) -> u32 {
+        self.line
+    }
+
+    /// The 1-based column.
+    pub fn column(&self) -> u32 {
+        self.column
+    }
+
+    /// The byte offset of the location in `source`, the contents of its [file][Location::file],
+    /// or `None` if `source` is too short to have it.
+    ///
+    /// This is what diagnostic renderers need to point at the panic, since they take spans
+    /// as byte offsets. The `miette` feature's `Diagnostic` for
+    /// [`PanicError`][crate::PanicError] labels its location with it.
+    ///
+    /// ## Examples
+    /// ```
+    /// use panic_message::Location;
+    ///
+    /// let source = "fn main() {\n    panic!(\"gus\");\n}\n";
+    ///
+    /// let offset = Location::new("src/main.rs", 2, 5).byte_offset(source).unwrap();
+    /// assert!(source[offset..].starts_with("panic!"));
+    /// assert_eq!(None, Location::new("src/main.rs", 5, 1).byte_offset(source));
+    /// ```
+    pub fn byte_offset(&self, source: &str) -> Option<usize> {
+        let line_index = usize::try_from(self.line).ok()?.checked_sub(1)?;
+        let column_index = usize::try_from(self.column).ok()?.checked_sub(1)?;
+        let mut start = 0;
+        for _ in 0..line_index {
+            start += source[start..].find('\n')? + 1;
+        }
+        let line = source[start..].split('\n').next()?;
+        // columns count chars, and the one past the end is still a position
+        let column = line
+            .char_indices()
+            .map(|(i, _)| i)
+            .chain(std::iter::once(line.len()))
+            .nth(column_index)?;
+        Some(start + column)
+    }
+}
+
+impl From<&std::panic::Location<'_>> for Location {
+    fn from(location: &std::panic::Location<'_>) -> Self {
+        Location::new(location.file(), location.line(), location.column())
+    }
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}:{}", self.file, self.line, self.column)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::catch_unwind;
+
+    #[test]
+    fn from_payload() {
+        let payload = catch_unwind(|| panic!("gus")).unwrap_err();
+
+        let details = PanicDetails::from_payload(&payload);
+
+        assert_eq!("gus", details.message());
+        assert_eq!(None, details.location());
+        assert_eq!(None, details.thread());
+    }
+
+    #[test]
+    fn backtrace_resolves_on_first_use() {
+        let mut details = PanicDetails::new("gus");
+        details.backtrace = Some(Trace::captured(std::backtrace::Backtrace::force_capture()));
+        let copy = details.clone();
+
+        assert!(!details.is_backtrace_resolved());
+        assert!(details.resolve_backtrace());
+        assert!(details.is_backtrace_resolved());
+        assert!(!copy.is_backtrace_resolved());
+        assert_eq!(details.backtrace(), copy.backtrace());
+
+        let rendered = PanicDetails::new("gus").with_backtrace("frames");
+        assert!(rendered.is_backtrace_resolved());
+        assert!(!PanicDetails::new("gus").resolve_backtrace());
+    }
+
+    #[test]
+    fn display() {
+        let details = PanicDetails::new("gus")
+            .with_location(Location::new("src/main.rs", 1, 2))
+            .with_thread("main");
+
+        assert_eq!(
+            "thread 'main' panicked at src/main.rs:1:2:\ngus",
+            details.to_string()
+        );
+    }
+
+    #[test]
+    fn display_sections() {
+        let details = PanicDetails::new("gus")
+            .with_thread("main")
+            .with_section(Section::list("breadcrumbs", vec!["connecting".to_string()]));
+
+        assert_eq!(
+            "thread 'main' panicked:\ngus\nbreadcrumbs:\n  - connecting",
+            details.to_string()
+        );
+    }
+
+    #[test]
+    fn json() {
+        let details = PanicDetails::new("gus \"quoted\"")
+            .with_location(Location::new("src/main.rs", 1, 2))
+            .with_backtrace("0: main\n");
+
+        assert_eq!(
+            r#"{"message":"gus \"quoted\"","location":{"file":"src/main.rs","line":1,"column":2},"backtrace":"0: main\n"}"#,
+            details.to_json()
+        );
+    }
+
+    #[test]
+    fn json_resources() {
+        let resources = ResourceUsage {
+            open_fds: Some(3),
+            user_time: Some(std::time::Duration::from_millis(2)),
+            ..ResourceUsage::default()
+        };
+        let details = PanicDetails::new("gus").with_resources(resources);
+
+        assert_eq!(
+            r#"{"message":"gus","resources":{"open_fds":3,"user_time_us":2000}}"#,
+            details.to_json()
+        );
+    }
+
+    #[test]
+    fn thread_ids() {
+        let id = std::thread::spawn(current_thread_id).join().unwrap();
+
+        assert!(id.is_some());
+        assert_ne!(id, current_thread_id());
+        assert_eq!(current_thread_id(), current_thread_id());
+    }
+
+    #[test]
+    fn json_timing() {
+        let details = PanicDetails::new("gus")
+            .with_uptime(Duration::from_millis(1500))
+            .with_since_previous_panic(Duration::from_micros(7))
+            .with_time(UNIX_EPOCH + Duration::from_nanos(3_000_999))
+            .with_abandoned_cleanups(2);
+
+        assert_eq!(
+            r#"{"message":"gus","uptime_us":1500000,"since_previous_panic_us":7,"time_us":3000,"abandoned_cleanups":2}"#,
+            details.to_json()
+        );
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let details = PanicDetails::new("gus \"quoted\"\n😀")
+            .with_location(Location::new("src/lib.rs", 70_000, 12))
+            .with_thread("main")
+            .with_thread_id(u64::MAX)
+            .with_backtrace("0: main\n1: start")
+            .with_resources(ResourceUsage::capture())
+            .with_uptime(Duration::from_micros(1_500_000))
+            .with_since_previous_panic(Duration::from_micros(7))
+            .with_time(SystemTime::now())
+            .with_abandoned_cleanups(1)
+            .with_field("port", "8080")
+            .with_field("port", "8081")
+            .with_raw_frames(vec![
+                RawFrame {
+                    address: u64::MAX,
+                    module: Some("libgus.so".to_string()),
+                    offset: 0x1000,
+                },
+                RawFrame::default(),
+            ])
+            .with_section(Section::text("config", "a1b2"))
+            .with_section(Section::list("peers", vec!["10.0.0.1".to_string()]))
+            .with_section(Section::fields(
+                "limits",
+                vec![("fds".to_string(), "1024".to_string())],
+            ));
+
+        assert_eq!(
+            details,
+            PanicDetails::from_json(&details.to_json()).unwrap()
+        );
+    }
+
+    #[test]
+    fn from_json_skips_unknown_members() {
+        let details =
+            PanicDetails::from_json(r#"{"extra":[1,{"a":null}],"message":"gus","level":-1.5}"#);
+
+        assert_eq!(PanicDetails::new("gus"), details.unwrap());
+    }
+
+    #[test]
+    fn from_json_errors() {
+        let error = |json: &str| PanicDetails::from_json(json).unwrap_err().to_string();
+
+        assert_eq!(
+            "invalid JSON panic report at byte 17: missing message",
+            error(r#"{"thread":"main"}"#)
+        );
+        assert_eq!(
+            "invalid JSON panic report at byte 29: expected a hex address",
+            error(r#"{"raw_frames":[{"address":"5"}]}"#)
+        );
+        assert_eq!(
+            "invalid JSON panic report at byte 0: expected an object",
+            error(r#""gus""#)
+        );
+    }
+
+    #[cfg(feature = "schemars")]
+    #[test]
+    fn json_schema_covers_every_field() {
+        let details = PanicDetails::new("gus")
+            .with_location(Location::new("src/main.rs", 1, 2))
+            .with_thread("main")
+            .with_thread_id(1)
+            .with_backtrace("0: main")
+            .with_resources(ResourceUsage::capture())
+            .with_uptime(std::time::Duration::from_secs(1))
+            .with_time(SystemTime::now())
+            .with_since_previous_panic(std::time::Duration::from_secs(1))
+            .with_abandoned_cleanups(1)
+            .with_field("port", "8080")
+            .with_raw_frames(vec![RawFrame {
+                address: 0x5000,
+                module: Some("gus".to_string()),
+                offset: 0x1000,
+            }])
+            .with_section(Section::text("config", "a1b2"));
+        let schema = serde_json::to_value(schemars::schema_for!(PanicDetails)).unwrap();
+        let json: serde_json::Value = serde_json::from_str(&details.to_json()).unwrap();
+        let keys = |object: &serde_json::Value| {
+            let mut keys: Vec<_> = object.as_object().unwrap().keys().cloned().collect();
+            keys.sort();
+            keys
+        };
+
+        assert_eq!(keys(&json), keys(&schema["properties"]));
+        assert_eq!(serde_json::json!(["message"]), schema["required"]);
+        for (member, definition) in [
+            ("location", "Location"),
+            ("resources", "ResourceUsage"),
+            ("raw_frames", "RawFrame"),
+        ] {
+            let value = match &json[member] {
+                serde_json::Value::Array(items) => &items[0],
+                value => value,
+            };
+            let mut expected = keys(value);
+            if member == "resources" {
+                // only the numbers the platform provides are written
+                expected = ResourceUsage::default()
+                    .fields()
+                    .iter()
+                    .map(|(key, _)| key.to_string())
+                    .collect();
+                expected.sort();
+            }
+            assert_eq!(
+                expected,
+                keys(&schema["$defs"][definition]["properties"]),
+                "{}",
+                member
+            );
+        }
+    }
+}
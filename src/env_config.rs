@@ -0,0 +1,89 @@
+//! Configuring the installed hook with environment variables, like `RUST_BACKTRACE`.
+use std::fmt;
+
+use crate::{
+    format::{Format, Json},
+    sink::Stream,
+    BacktraceMode, PanicDetails,
+};
+
+/// Picks the format of the stderr sink.
+pub(crate) const FORMAT_VAR: &str = "PANIC_MESSAGE_FORMAT";
+/// Picks when to capture a backtrace.
+pub(crate) const BACKTRACE_VAR: &str = "PANIC_MESSAGE_BACKTRACE";
+
+/// The backtrace mode a `PANIC_MESSAGE_BACKTRACE` value asks for.
+pub(crate) fn backtrace_mode(value: &str) -> Option<BacktraceMode> {
+    match value {
+        "0" => Some(BacktraceMode::Disabled),
+        "1" | "full" => Some(BacktraceMode::Forced),
+        _ => None,
+    }
+}
+
+/// A stderr sink in the format a `PANIC_MESSAGE_FORMAT` value asks for.
+pub(crate) fn stderr_sink(value: &str) -> Option<Stream> {
+    match value {
+        "json" => Some(Stream::stderr().with_format(Json)),
+        "pretty" => Some(Stream::stderr()),
+        "compact" => Some(Stream::stderr().with_format(Compact)),
+        _ => None,
+    }
+}
+
+/// Reports on one line: `thread 'main' panicked at src/main.rs:3:5: message`, with the
+/// message's lines joined by spaces and without sections.
+struct Compact;
+
+impl Format for Compact {
+    fn format(&self, details: &PanicDetails, out: &mut dyn fmt::Write) -> fmt::Result {
+        let labels = crate::labels();
+        let thread = details.thread().unwrap_or(&labels.unnamed_thread);
+        match details.location() {
+            Some(location) => write!(
+                out,
+                "{} '{}' {} {}: ",
+                labels.thread, thread, labels.panicked_at, location
+            )?,
+            None => write!(out, "{} '{}' {}: ", labels.thread, thread, labels.panicked)?,
+        }
+        for (i, line) in details.message().lines().enumerate() {
+            if i > 0 {
+                out.write_str(" ")?;
+            }
+            out.write_str(line)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Location;
+
+    #[test]
+    fn values() {
+        assert_eq!(Some(BacktraceMode::Disabled), backtrace_mode("0"));
+        assert_eq!(Some(BacktraceMode::Forced), backtrace_mode("full"));
+        assert_eq!(None, backtrace_mode("yes"));
+        assert!(stderr_sink("json").is_some());
+        assert!(stderr_sink("xml").is_none());
+    }
+
+    #[test]
+    fn compact() {
+        let details = PanicDetails::new("gus\nwynn")
+            .with_thread("main")
+            .with_location(Location::new("src/main.rs", 3, 5));
+
+        assert_eq!(
+            "thread 'main' panicked at src/main.rs:3:5: gus wynn",
+            Compact.render(&details)
+        );
+        assert_eq!(
+            "thread '<unnamed>' panicked: gus",
+            Compact.render(&PanicDetails::new("gus"))
+        );
+    }
+}
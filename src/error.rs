@@ -0,0 +1,142 @@
+//! A caught panic as a `Send + Sync` error.
+use std::{any::Any, backtrace::Backtrace, error::Error, fmt, io};
+
+use crate::{CaughtPanic, Location, PanicKind};
+
+/// A caught panic reduced to owned data, so unlike [`CaughtPanic`][crate::CaughtPanic] it
+/// is `Send + Sync` and can be shared in an `Arc` or sent across async executors.
+///
+/// It displays as the panic message. Create one with
+/// [`CaughtPanic::into_sendable`][crate::CaughtPanic::into_sendable], or with `From` from a
+/// payload, so `?` turns the result of [`std::panic::catch_unwind`] into an error.
+///
+/// ## Examples
+/// ```
+/// use std::panic::catch_unwind;
+/// use std::sync::Arc;
+/// use panic_message::{PanicError, PanicPayloadExt};
+///
+/// let payload = catch_unwind(|| panic!("gus")).unwrap_err();
+/// let error: Arc<PanicError> = Arc::new(payload.into_caught_panic().into_sendable());
+///
+/// std::thread::spawn(move || assert_eq!("gus", error.message())).join().unwrap();
+/// ```
+///
+/// ```
+/// use std::{error::Error, panic::catch_unwind};
+/// use panic_message::PanicError;
+///
+/// fn parse(input: &str) -> Result<u32, PanicError> {
+///     let number = catch_unwind(|| input.parse::<u32>().unwrap())?;
+///     Ok(number)
+/// }
+///
+/// fn run() -> Result<u32, Box<dyn Error + Send + Sync>> {
+///     Ok(parse("gus")?)
+/// }
+///
+/// assert!(run().unwrap_err().is::<PanicError>());
+/// ```
+#[derive(Debug)]
+pub struct PanicError {
+    pub(crate) message: String,
+    pub(crate) kind: PanicKind,
+    pub(crate) location: Option<Location>,
+    pub(crate) backtrace: Option<Backtrace>,
+    /// The location's file, read the first time a miette report asks for it.
+    #[cfg(feature = "miette")]
+    pub(crate) source: std::sync::OnceLock<Option<miette::NamedSource<String>>>,
+}
+
+impl PanicError {
+    /// The panic message, rendered by a [registered formatter][crate::register_panic_formatter]
+    /// if the payload wasn't a string, or `"Box<dyn Any>"` if none could be extracted.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+
+    /// What kind of failure the panic was, see [`PanicKind::classify`].
+    pub fn kind(&self) -> PanicKind {
+        self.kind
+    }
+
+    /// Where the panic happened, if known.
+    pub fn location(&self) -> Option<&Location> {
+        self.location.as_ref()
+    }
+
+    /// The backtrace of the panic, if one was attached.
+    pub fn backtrace(&self) -> Option<&Backtrace> {
+        self.backtrace.as_ref()
+    }
+}
+
+impl fmt::Display for PanicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+impl From<Box<dyn Any + Send>> for PanicError {
+    /// The payload's owned message, with no location or backtrace.
+    fn from(payload: Box<dyn Any + Send>) -> Self {
+        CaughtPanic::new(payload).into_sendable()
+    }
+}
+
+impl From<CaughtPanic> for PanicError {
+    /// See [`CaughtPanic::into_sendable`].
+    fn from(caught: CaughtPanic) -> Self {
+        caught.into_sendable()
+    }
+}
+
+impl From<PanicError> for io::Error {
+    /// An error of kind [`Other`][io::ErrorKind::Other] carrying the panic.
+    fn from(error: PanicError) -> Self {
+        io::Error::other(error)
+    }
+}
+
+impl Error for PanicError {
+    #[cfg(feature = "nightly")]
+    fn provide<'a>(&'a self, request: &mut std::error::Request<'a>) {
+        if let Some(backtrace) = &self.backtrace {
+            request.provide_ref::<Backtrace>(backtrace);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::CaughtPanic;
+
+    fn assert_send_sync<T: Send + Sync + 'static>() {}
+
+    #[test]
+    fn from_caught_panic() {
+        assert_send_sync::<PanicError>();
+
+        let error = CaughtPanic::new(Box::new(1_u8))
+            .with_location(Location::new("src/main.rs", 1, 2))
+            .with_backtrace(Backtrace::force_capture())
+            .into_sendable();
+
+        assert_eq!("Box<dyn Any>", error.to_string());
+        assert_eq!(PanicKind::NonString, error.kind());
+        assert_eq!(Some(&Location::new("src/main.rs", 1, 2)), error.location());
+        assert!(error.backtrace().is_some());
+    }
+
+    #[test]
+    fn from_payload() {
+        let payload = std::panic::catch_unwind(|| panic!("gus {}", 1)).unwrap_err();
+
+        let error = PanicError::from(payload);
+
+        assert_eq!("gus 1", error.message());
+        assert_eq!(PanicKind::Explicit, error.kind());
+        assert_eq!(None, error.location());
+    }
+}
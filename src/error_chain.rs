@@ -0,0 +1,174 @@
+//! Messages of boxed error payloads, with their sources.
+use std::{
+    any::Any,
+    borrow::Cow,
+    error::Error,
+    fmt::{self, Write},
+    sync::RwLock,
+};
+
+static SEPARATOR: RwLock<Cow<'static, str>> = RwLock::new(Cow::Borrowed(": "));
+
+/// Set what goes between an error and its [source][Error::source] in the message of a
+/// `Box<dyn Error + Send>` or `Box<dyn Error + Send + Sync>` payload. The default is `": "`.
+///
+/// Such payloads have no string to borrow, so like a
+/// [formatter's][crate::register_panic_formatter], their message is only used where
+/// messages are owned, like [`PanicDetails`][crate::PanicDetails] and
+/// [`panic_message_cow`][crate::panic_message_cow].
+///
+/// ## Examples
+/// ```
+/// use std::{error::Error, fmt, io, panic::{catch_unwind, panic_any}};
+/// use panic_message::{set_error_chain_separator, PanicDetails};
+///
+/// #[derive(Debug)]
+/// struct ConfigError(io::Error);
+///
+/// impl fmt::Display for ConfigError {
+///     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+///         f.write_str("reading the config failed")
+///     }
+/// }
+///
+/// impl Error for ConfigError {
+///     fn source(&self) -> Option<&(dyn Error + 'static)> {
+///         Some(&self.0)
+///     }
+/// }
+///
+/// let payload = catch_unwind(|| {
+///     let error = ConfigError(io::Error::new(io::ErrorKind::NotFound, "no such file"));
+///     panic_any(Box::new(error) as Box<dyn Error + Send + Sync>)
+/// })
+/// .unwrap_err();
+///
+/// let details = PanicDetails::from_payload(&payload);
+/// assert_eq!("reading the config failed: no such file", details.message());
+///
+/// set_error_chain_separator("\ncaused by: ");
+/// let details = PanicDetails::from_payload(&payload);
+/// assert_eq!("reading the config failed\ncaused by: no such file", details.message());
+/// ```
+pub fn set_error_chain_separator(separator: impl Into<Cow<'static, str>>) {
+    *SEPARATOR.write().unwrap_or_else(|e| e.into_inner()) = separator.into();
+}
+
+/// Whether the payload is a boxed error, or an `anyhow::Error` with the `anyhow` feature,
+/// which has a message.
+pub(crate) fn is_error(payload: &(dyn Any + Send)) -> bool {
+    as_error(payload).is_some()
+}
+
+/// The message of a boxed error payload: the error followed by each of its sources.
+pub(crate) fn error_message(payload: &(dyn Any + Send)) -> Option<String> {
+    let error = as_error(payload)?;
+    let separator = SEPARATOR.read().unwrap_or_else(|e| e.into_inner()).clone();
+
+    let mut message = String::new();
+    let _ = write_chain(&mut message, error, &separator);
+    Some(message)
+}
+
+/// Write the message of a boxed error payload into `out` without allocating, or return
+/// `None` if the payload isn't one.
+pub(crate) fn write_error_message(
+    out: &mut dyn Write,
+    payload: &(dyn Any + Send),
+) -> Option<fmt::Result> {
+    let error = as_error(payload)?;
+    // held while writing, as cloning an owned separator would allocate
+    let separator = SEPARATOR.read().unwrap_or_else(|e| e.into_inner());
+    Some(write_chain(out, error, &separator))
+}
+
+fn as_error(payload: &(dyn Any + Send)) -> Option<&(dyn Error + 'static)> {
+    if let Some(error) = payload.downcast_ref::<Box<dyn Error + Send + Sync>>() {
+        Some(&**error)
+    } else if let Some(error) = payload.downcast_ref::<Box<dyn Error + Send>>() {
+        Some(&**error)
+    } else {
+        as_anyhow(payload)
+    }
+}
+
+/// An `anyhow::Error`'s sources are its context chain, so its message is what `{:#}`
+/// renders, with the configured separator.
+#[cfg(feature = "anyhow")]
+fn as_anyhow(payload: &(dyn Any + Send)) -> Option<&(dyn Error + 'static)> {
+    let error = payload.downcast_ref::<anyhow::Error>()?;
+    Some(&**error)
+}
+
+#[cfg(not(feature = "anyhow"))]
+fn as_anyhow(_: &(dyn Any + Send)) -> Option<&(dyn Error + 'static)> {
+    None
+}
+
+fn write_chain(out: &mut dyn Write, error: &(dyn Error + 'static), separator: &str) -> fmt::Result {
+    write!(out, "{}", error)?;
+    let mut source = error.source();
+    while let Some(error) = source {
+        write!(out, "{}{}", separator, error)?;
+        source = error.source();
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fmt, io};
+
+    #[derive(Debug)]
+    struct Wrapped(io::Error);
+
+    impl fmt::Display for Wrapped {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            f.write_str("reading the config failed")
+        }
+    }
+
+    impl Error for Wrapped {
+        fn source(&self) -> Option<&(dyn Error + 'static)> {
+            Some(&self.0)
+        }
+    }
+
+    #[test]
+    fn renders_the_sources() {
+        let error = Wrapped(io::Error::new(io::ErrorKind::NotFound, "no such file"));
+        let sync: Box<dyn Any + Send> = Box::new(Box::new(error) as Box<dyn Error + Send + Sync>);
+        let send: Box<dyn Any + Send> =
+            Box::new(Box::new(Wrapped(io::ErrorKind::Other.into())) as Box<dyn Error + Send>);
+
+        assert_eq!(
+            Some("reading the config failed: no such file".to_string()),
+            error_message(&*sync)
+        );
+        assert_eq!(
+            Some("reading the config failed: other error".to_string()),
+            error_message(&*send)
+        );
+        assert_eq!(None, error_message(&"gus"));
+        assert_eq!(
+            "reading the config failed: no such file",
+            crate::registry::owned_message(&*sync)
+        );
+    }
+
+    #[cfg(feature = "anyhow")]
+    #[test]
+    fn renders_anyhow_context() {
+        let error = anyhow::anyhow!("no such file").context("reading the config failed");
+        let expected = format!("{:#}", error);
+        let payload: Box<dyn Any + Send> = Box::new(error);
+
+        assert!(is_error(&*payload));
+        assert_eq!(Some(expected), error_message(&*payload));
+        assert_eq!(
+            "reading the config failed: no such file",
+            crate::registry::owned_message(&*payload)
+        );
+    }
+}
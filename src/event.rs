@@ -0,0 +1,210 @@
+//! One type for a panic, whether it came from a hook or was caught.
+use std::{any::Any, borrow::Cow, fmt, panic::PanicHookInfo as PanicInfo};
+
+use crate::{AsPanicPayload, CaughtPanic, Location, PanicDetails, PanicKind};
+
+/// A panic as a panic hook sees it or as [`std::panic::catch_unwind`] returns it, for
+/// libraries that accept either.
+///
+/// It is created with `From` from a [`PanicInfo`][std::panic::PanicInfo], a borrowed or
+/// owned payload, or a [`CaughtPanic`]. The location is known for the `PanicInfo` and for
+/// a `CaughtPanic` that has one; the payload can only be taken back from an owned one.
+///
+/// ## Examples
+/// ```
+/// use std::panic::catch_unwind;
+/// use panic_message::PanicEvent;
+///
+/// fn report(event: PanicEvent<'_>) -> String {
+///     match event.location() {
+///         Some(location) => format!("{} at {}", event.message(), location),
+///         None => event.message().to_string(),
+///     }
+/// }
+///
+/// std::panic::set_hook(Box::new(|pi| {
+///     eprintln!("{}", report(PanicEvent::from(pi)));
+/// }));
+/// # let _ = std::panic::take_hook();
+///
+/// let payload = catch_unwind(|| panic!("gus")).unwrap_err();
+/// assert_eq!("gus", report(PanicEvent::from(&payload)));
+/// ```
+pub struct PanicEvent<'a> {
+    source: Source<'a>,
+}
+
+enum Source<'a> {
+    Info(&'a PanicInfo<'a>),
+    Borrowed(&'a (dyn Any + Send)),
+    Owned(Box<dyn Any + Send>, Option<Location>),
+}
+
+impl PanicEvent<'_> {
+    /// The payload, owned or not.
+    pub fn payload(&self) -> &(dyn Any + Send) {
+        match &self.source {
+            Source::Info(info) => info.payload(),
+            Source::Borrowed(payload) => *payload,
+            Source::Owned(payload, _) => &**payload,
+        }
+    }
+
+    /// The panic message, borrowed if the payload is a string and otherwise rendered from a
+    /// boxed error or by a [registered formatter][crate::register_panic_formatter],
+    /// `"Box<dyn Any>"` if it has none.
+    pub fn message(&self) -> Cow<'_, str> {
+        match self.get_message() {
+            Some(message) => Cow::Borrowed(message),
+            None => Cow::Owned(crate::registry::owned_message(self.payload())),
+        }
+    }
+
+    /// The panic message, if the payload is a string.
+    pub fn get_message(&self) -> Option<&str> {
+        crate::imp::get_panic_message(self.payload())
+    }
+
+    /// What kind of failure the message describes, see [`PanicKind::classify`].
+    pub fn kind(&self) -> PanicKind {
+        PanicKind::classify(&self.message())
+    }
+
+    /// Where the panic happened, if it is known.
+    pub fn location(&self) -> Option<Location> {
+        match &self.source {
+            Source::Info(info) => info.location().map(Location::from),
+            Source::Borrowed(_) => None,
+            Source::Owned(_, location) => location.clone(),
+        }
+    }
+
+    /// Whether the payload is owned, so [`into_payload`][PanicEvent::into_payload] returns it.
+    pub fn is_owned(&self) -> bool {
+        matches!(self.source, Source::Owned(..))
+    }
+
+    /// Take the payload, if it is owned.
+    pub fn into_payload(self) -> Option<Box<dyn Any + Send>> {
+        match self.source {
+            Source::Owned(payload, _) => Some(payload),
+            _ => None,
+        }
+    }
+
+    /// Collect owned details: with [`PanicDetails::from_panic_info`] from a `PanicInfo`,
+    /// otherwise with [`PanicDetails::from_payload`] and the location, if known.
+    pub fn details(&self) -> PanicDetails {
+        match &self.source {
+            Source::Info(info) => PanicDetails::from_panic_info(info),
+            _ => {
+                let details = PanicDetails::from_payload(self.payload());
+                match self.location() {
+                    Some(location) => details.with_location(location),
+                    None => details,
+                }
+            }
+        }
+    }
+}
+
+impl<'a> From<&'a PanicInfo<'a>> for PanicEvent<'a> {
+    fn from(info: &'a PanicInfo<'a>) -> Self {
+        PanicEvent {
+            source: Source::Info(info),
+        }
+    }
+}
+
+impl<'a> From<&'a Box<dyn Any + Send>> for PanicEvent<'a> {
+    fn from(payload: &'a Box<dyn Any + Send>) -> Self {
+        PanicEvent {
+            source: Source::Borrowed(payload.as_panic_payload()),
+        }
+    }
+}
+
+impl<'a> From<&'a (dyn Any + Send)> for PanicEvent<'a> {
+    fn from(payload: &'a (dyn Any + Send)) -> Self {
+        PanicEvent {
+            source: Source::Borrowed(payload),
+        }
+    }
+}
+
+impl From<Box<dyn Any + Send>> for PanicEvent<'static> {
+    fn from(payload: Box<dyn Any + Send>) -> Self {
+        PanicEvent {
+            source: Source::Owned(payload, None),
+        }
+    }
+}
+
+impl From<CaughtPanic> for PanicEvent<'static> {
+    fn from(caught: CaughtPanic) -> Self {
+        let location = caught.location().cloned();
+        PanicEvent {
+            source: Source::Owned(caught.into_payload(), location),
+        }
+    }
+}
+
+impl fmt::Debug for PanicEvent<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PanicEvent")
+            .field("message", &self.message())
+            .field("location", &self.location())
+            .finish()
+    }
+}
+
+impl fmt::Display for PanicEvent<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        crate::imp::write_message(f, self.payload())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{catch_unwind, panic_any};
+
+    #[test]
+    fn payloads() {
+        let payload = catch_unwind(|| panic!("gus")).unwrap_err();
+
+        let borrowed = PanicEvent::from(&payload);
+        assert_eq!("gus", borrowed.message());
+        assert_eq!(None, borrowed.location());
+        assert!(!borrowed.is_owned());
+        assert!(borrowed.into_payload().is_none());
+
+        let owned = PanicEvent::from(payload);
+        assert_eq!(PanicKind::Explicit, owned.kind());
+        assert_eq!("gus", owned.details().message());
+        assert!(owned.into_payload().unwrap().is::<&str>());
+
+        let payload = catch_unwind(|| panic_any(1)).unwrap_err();
+        let event = PanicEvent::from(&*payload);
+        assert_eq!(None, event.get_message());
+        assert_eq!(Some(&1), event.payload().downcast_ref::<i32>());
+    }
+
+    #[test]
+    fn caught_panic_keeps_its_location() {
+        let caught =
+            CaughtPanic::new(Box::new("gus")).with_location(Location::new("src/main.rs", 3, 5));
+
+        let event = PanicEvent::from(caught);
+
+        assert_eq!(Some(Location::new("src/main.rs", 3, 5)), event.location());
+        assert_eq!(
+            Some(&Location::new("src/main.rs", 3, 5)),
+            event.details().location()
+        );
+        assert_eq!(
+            r#"PanicEvent { message: "gus", location: Some(Location { file: "src/main.rs", line: 3, column: 5 }) }"#,
+            format!("{:?}", event)
+        );
+    }
+}
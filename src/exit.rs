@@ -0,0 +1,98 @@
+//! Exiting the process from the panic hook, with an exit code that says what went wrong.
+use std::{collections::HashMap, time::Duration};
+
+use crate::PanicKind;
+
+/// The exit code Rust uses when the main thread panics.
+const DEFAULT_CODE: i32 = 101;
+/// How long sinks get to deliver the report before the process exits.
+const DEFAULT_FLUSH_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// Which exit code the process exits with after a panic, by [`PanicKind`], for a hook set
+/// up with [`PanicHookBuilder::with_exit_policy`][crate::PanicHookBuilder::with_exit_policy].
+///
+/// That hook reports the panic to every sink, gives them a chance to
+/// [flush][crate::sink::Sink::flush], then exits, so supervisors and CI can tell a failed
+/// assertion from an overflow from an explicit `panic!` by the exit code alone. Kinds
+/// without a code of their own exit with 101, like an unhandled panic on the main thread.
+///
+/// ## Examples
+/// ```no_run
+/// use panic_message::{ExitPolicy, PanicHookBuilder, PanicKind};
+///
+/// PanicHookBuilder::new()
+///     .with_exit_policy(
+///         ExitPolicy::new()
+///             .with_code(PanicKind::Assertion, 3)
+///             .with_code(PanicKind::Overflow, 4),
+///     )
+///     .install();
+/// ```
+#[derive(Debug, Clone)]
+pub struct ExitPolicy {
+    codes: HashMap<PanicKind, i32>,
+    default_code: i32,
+    flush_timeout: Duration,
+}
+
+impl Default for ExitPolicy {
+    fn default() -> Self {
+        ExitPolicy {
+            codes: HashMap::new(),
+            default_code: DEFAULT_CODE,
+            flush_timeout: DEFAULT_FLUSH_TIMEOUT,
+        }
+    }
+}
+
+impl ExitPolicy {
+    /// A policy that exits with 101 for every kind of panic.
+    pub fn new() -> Self {
+        ExitPolicy::default()
+    }
+
+    /// Exit with `code` after panics of `kind`.
+    pub fn with_code(mut self, kind: PanicKind, code: i32) -> Self {
+        self.codes.insert(kind, code);
+        self
+    }
+
+    /// Exit with `code` after panics of kinds without a code of their own, instead of 101.
+    pub fn with_default_code(mut self, code: i32) -> Self {
+        self.default_code = code;
+        self
+    }
+
+    /// How long to wait for sinks to flush before exiting, 1 second by default.
+    pub fn with_flush_timeout(mut self, timeout: Duration) -> Self {
+        self.flush_timeout = timeout;
+        self
+    }
+
+    /// The exit code for panics of `kind`.
+    pub fn code(&self, kind: PanicKind) -> i32 {
+        self.codes.get(&kind).copied().unwrap_or(self.default_code)
+    }
+
+    /// How long to wait for sinks to flush before exiting.
+    pub fn flush_timeout(&self) -> Duration {
+        self.flush_timeout
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn codes() {
+        let policy = ExitPolicy::new()
+            .with_code(PanicKind::Assertion, 3)
+            .with_code(PanicKind::Overflow, 4);
+
+        assert_eq!(3, policy.code(PanicKind::Assertion));
+        assert_eq!(4, policy.code(PanicKind::Overflow));
+        assert_eq!(101, policy.code(PanicKind::Explicit));
+        assert_eq!(1, policy.with_default_code(1).code(PanicKind::Unwrap));
+    }
+}
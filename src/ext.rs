@@ -0,0 +1,366 @@
+//! Extension traits, for calling this crate's functions as methods.
+use std::{any::Any, borrow::Cow, panic::PanicHookInfo as PanicInfo};
+
+use crate::{CaughtPanic, PanicDetails, PanicKind};
+
+mod sealed {
+    use std::any::Any;
+
+    pub trait Sealed {}
+
+    impl Sealed for std::panic::PanicHookInfo<'_> {}
+    impl Sealed for Box<dyn Any + Send> {}
+    impl<T> Sealed for Result<T, Box<dyn Any + Send>> {}
+    impl<T> Sealed for std::thread::JoinHandle<T> {}
+    impl<T> Sealed for std::thread::ScopedJoinHandle<'_, T> {}
+    #[cfg(feature = "tokio")]
+    impl Sealed for tokio::task::JoinError {}
+}
+
+/// Methods for the [`PanicInfo`][std::panic::PanicInfo] a [`std::panic::set_hook`] hook
+/// receives.
+///
+/// ## Examples
+/// ```
+/// use panic_message::PanicInfoExt;
+///
+/// std::panic::set_hook(Box::new(|pi| {
+///     eprintln!("{}", pi.message_or_default());
+///     let details = pi.details();
+/// }));
+/// # let _ = std::panic::take_hook();
+/// ```
+pub trait PanicInfoExt: sealed::Sealed {
+    /// The panic message, if it is a string. See [`get_panic_info_message`][crate::get_panic_info_message].
+    fn message_str(&self) -> Option<&str>;
+
+    /// The panic message, `"Box<dyn Any>"` if it isn't a string. See
+    /// [`panic_info_message`][crate::panic_info_message].
+    fn message_or_default(&self) -> &str;
+
+    /// Collect owned details about the panic, see [`PanicDetails::from_panic_info`].
+    fn details(&self) -> PanicDetails;
+}
+
+impl PanicInfoExt for PanicInfo<'_> {
+    fn message_str(&self) -> Option<&str> {
+        crate::get_panic_info_message(self)
+    }
+
+    fn message_or_default(&self) -> &str {
+        crate::panic_info_message(self)
+    }
+
+    fn details(&self) -> PanicDetails {
+        PanicDetails::from_panic_info(self)
+    }
+}
+
+/// Methods for the `Box<dyn Any + Send>` payload [`std::panic::catch_unwind`] and
+/// [`std::thread::JoinHandle::join`] return.
+///
+/// ## Examples
+/// ```
+/// use std::panic::catch_unwind;
+/// use panic_message::{PanicKind, PanicPayloadExt};
+///
+/// let payload = catch_unwind(|| {
+///     let v: Vec<u32> = Vec::new();
+///     v[0]
+/// }).unwrap_err();
+///
+/// assert_eq!(PanicKind::IndexOutOfBounds, payload.kind());
+/// assert!(payload.message().starts_with("index out of bounds"));
+/// assert!(payload.get_message().is_some());
+/// assert_eq!(payload.message(), payload.panic_message());
+/// ```
+///
+/// It is only implemented for the box, so the payload can't be mistaken for the
+/// `dyn Any` it holds, or the other way around:
+/// ```compile_fail
+/// use panic_message::PanicPayloadExt;
+///
+/// let payload: Box<dyn std::any::Any + Send> = Box::new("gus");
+/// let inner: &(dyn std::any::Any + Send) = &*payload;
+/// inner.message();
+/// ```
+pub trait PanicPayloadExt: sealed::Sealed {
+    /// The panic message, `"Box<dyn Any>"` if it isn't a string. See
+    /// [`panic_message`][crate::panic_message].
+    fn message(&self) -> &str;
+
+    /// The panic message, if it is a string. See
+    /// [`get_panic_message`][crate::get_panic_message].
+    fn get_message(&self) -> Option<&str>;
+
+    /// [`message`][PanicPayloadExt::message], named like the function it mirrors.
+    fn panic_message(&self) -> &str {
+        self.message()
+    }
+
+    /// [`get_message`][PanicPayloadExt::get_message], named like the function it mirrors.
+    fn get_panic_message(&self) -> Option<&str> {
+        self.get_message()
+    }
+
+    /// The panic message, borrowed if it is a `&'static str` and copied otherwise, so it
+    /// can outlive the payload. See [`panic_message_cow`][crate::panic_message_cow].
+    fn message_cow(&self) -> Cow<'static, str>;
+
+    /// The panic message as an owned `String`, rendered from a boxed error or by a
+    /// [registered formatter][crate::register_panic_formatter] if the payload isn't a
+    /// string.
+    fn message_owned(&self) -> String;
+
+    /// What kind of failure the [owned message][PanicPayloadExt::message_owned] describes,
+    /// see [`PanicKind::classify`].
+    fn kind(&self) -> PanicKind;
+
+    /// Wrap the payload in a [`CaughtPanic`].
+    fn into_caught_panic(self) -> CaughtPanic;
+}
+
+impl PanicPayloadExt for Box<dyn Any + Send> {
+    fn message(&self) -> &str {
+        crate::panic_message(self)
+    }
+
+    fn get_message(&self) -> Option<&str> {
+        crate::get_panic_message(self)
+    }
+
+    fn message_cow(&self) -> Cow<'static, str> {
+        crate::panic_message_cow(self)
+    }
+
+    fn message_owned(&self) -> String {
+        crate::registry::owned_message(&**self)
+    }
+
+    fn kind(&self) -> PanicKind {
+        PanicKind::classify(&self.message_owned())
+    }
+
+    fn into_caught_panic(self) -> CaughtPanic {
+        CaughtPanic::new(self)
+    }
+}
+
+/// Methods for the `Result` [`std::panic::catch_unwind`] and
+/// [`std::thread::JoinHandle::join`] return.
+///
+/// ## Examples
+/// ```
+/// use std::panic::catch_unwind;
+/// use panic_message::PanicResultExt;
+///
+/// let result = catch_unwind(|| -> u32 { panic!("gus") });
+/// assert_eq!(Err("gus".to_string()), result.message_err());
+///
+/// let result = std::thread::spawn(|| 3).join();
+/// assert_eq!(3, result.caught().unwrap());
+/// ```
+pub trait PanicResultExt<T>: sealed::Sealed {
+    /// Wrap the payload of a panic in a [`CaughtPanic`].
+    fn caught(self) -> Result<T, CaughtPanic>;
+
+    /// Replace the payload of a panic with its message, `"Box<dyn Any>"` if it isn't a
+    /// string.
+    fn message_err(self) -> Result<T, String>;
+
+    /// The value, or else continue unwinding with the original payload.
+    fn unwrap_or_resume(self) -> T;
+
+    /// The value, or else panic anew with the message of the panic, for example to fail a
+    /// test or a supervisor with the message of the thread it joined. Unlike
+    /// [`unwrap_or_resume`][PanicResultExt::unwrap_or_resume], the new panic runs the
+    /// panic hook and is reported where this is called.
+    #[track_caller]
+    fn unwrap_or_panic_message(self) -> T;
+}
+
+impl<T> PanicResultExt<T> for Result<T, Box<dyn Any + Send>> {
+    fn caught(self) -> Result<T, CaughtPanic> {
+        self.map_err(CaughtPanic::new)
+    }
+
+    fn message_err(self) -> Result<T, String> {
+        self.map_err(|payload| payload.message_owned())
+    }
+
+    fn unwrap_or_resume(self) -> T {
+        match self {
+            Ok(value) => value,
+            Err(payload) => std::panic::resume_unwind(payload),
+        }
+    }
+
+    #[track_caller]
+    fn unwrap_or_panic_message(self) -> T {
+        match self {
+            Ok(value) => value,
+            Err(payload) => panic!("{}", crate::ShowPanic(&payload)),
+        }
+    }
+}
+
+/// Methods for joining a [`std::thread::JoinHandle`] or [`std::thread::ScopedJoinHandle`]
+/// and getting the message of its panic in one call.
+///
+/// ## Examples
+/// ```
+/// use panic_message::JoinHandleExt;
+///
+/// let handle = std::thread::spawn(|| -> u32 { panic!("gus") });
+/// assert_eq!(Err("gus".to_string()), handle.join_message());
+///
+/// let handle = std::thread::spawn(|| 3);
+/// assert_eq!(3, handle.join_caught().unwrap());
+/// ```
+pub trait JoinHandleExt<T>: sealed::Sealed {
+    /// Join the thread, replacing the payload of its panic with its message. See
+    /// [`PanicResultExt::message_err`].
+    fn join_message(self) -> Result<T, String>;
+
+    /// Join the thread, wrapping the payload of its panic in a [`CaughtPanic`].
+    fn join_caught(self) -> Result<T, CaughtPanic>;
+}
+
+impl<T> JoinHandleExt<T> for std::thread::JoinHandle<T> {
+    fn join_message(self) -> Result<T, String> {
+        self.join().message_err()
+    }
+
+    fn join_caught(self) -> Result<T, CaughtPanic> {
+        self.join().caught()
+    }
+}
+
+impl<T> JoinHandleExt<T> for std::thread::ScopedJoinHandle<'_, T> {
+    fn join_message(self) -> Result<T, String> {
+        self.join().message_err()
+    }
+
+    fn join_caught(self) -> Result<T, CaughtPanic> {
+        self.join().caught()
+    }
+}
+
+/// The message of the panic of the task `error` was returned for, or `None` if the task was
+/// cancelled. See [`JoinErrorExt`].
+///
+/// `JoinError` only gives up the payload of the panic by value, so this takes the error.
+#[cfg(feature = "tokio")]
+pub fn join_error_message(error: tokio::task::JoinError) -> Option<String> {
+    error.into_panic_message().ok()
+}
+
+/// Methods for the [`JoinError`](https://docs.rs/tokio/1/tokio/task/struct.JoinError.html)
+/// of a tokio task, which holds the payload of its panic unless it was cancelled.
+///
+/// ## Examples
+/// ```
+/// use panic_message::JoinErrorExt;
+///
+/// let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+/// runtime.block_on(async {
+///     let error = tokio::spawn(async { panic!("gus") }).await.unwrap_err();
+///     assert_eq!("gus", error.into_panic_message().unwrap());
+///
+///     let task = tokio::spawn(std::future::pending::<()>());
+///     task.abort();
+///     let error = task.await.unwrap_err();
+///     assert!(error.into_caught_panic().unwrap_err().is_cancelled());
+/// });
+/// ```
+#[cfg(feature = "tokio")]
+pub trait JoinErrorExt: Sized + sealed::Sealed {
+    /// The message of the task's panic, see [`PanicPayloadExt::message_cow`], or the error
+    /// back if the task was cancelled.
+    fn into_panic_message(self) -> Result<String, Self>;
+
+    /// Wrap the payload of the task's panic in a [`CaughtPanic`], or give the error back if
+    /// the task was cancelled.
+    fn into_caught_panic(self) -> Result<CaughtPanic, Self>;
+}
+
+#[cfg(feature = "tokio")]
+impl JoinErrorExt for tokio::task::JoinError {
+    fn into_panic_message(self) -> Result<String, Self> {
+        self.try_into_panic()
+            .map(|payload| payload.message_cow().into_owned())
+    }
+
+    fn into_caught_panic(self) -> Result<CaughtPanic, Self> {
+        self.try_into_panic().map(CaughtPanic::new)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::{catch_unwind, panic_any};
+
+    #[test]
+    fn payload_ext() {
+        let payload = catch_unwind(|| panic_any("gus".to_string())).unwrap_err();
+
+        assert_eq!("gus", payload.message());
+        assert_eq!(Some("gus"), payload.get_message());
+        assert_eq!("gus", payload.message_cow());
+        assert_eq!("gus".to_string(), payload.message_owned());
+        assert_eq!(PanicKind::Explicit, payload.kind());
+        assert_eq!("gus", payload.into_caught_panic().message());
+    }
+
+    #[test]
+    fn result_ext() {
+        let ok: Result<u32, Box<dyn Any + Send>> = Ok(1);
+        let err = catch_unwind(|| -> u32 { panic_any(2) });
+
+        assert_eq!(1, ok.unwrap_or_resume());
+        assert_eq!(Err("Box<dyn Any>".to_string()), err.message_err());
+
+        let resumed = catch_unwind(|| catch_unwind(|| -> u32 { panic!("gus") }).unwrap_or_resume());
+        assert_eq!("gus", resumed.caught().unwrap_err().message());
+
+        let name = "gus";
+        let repanicked = catch_unwind(|| {
+            catch_unwind(|| -> u32 { panic_any(name.to_string()) }).unwrap_or_panic_message()
+        });
+        assert_eq!(Err("gus".to_string()), repanicked.message_err());
+    }
+
+    #[test]
+    fn join_handle_ext() {
+        let scoped = std::thread::scope(|scope| {
+            scope
+                .spawn(|| -> u32 { panic!("gus") })
+                .join_caught()
+                .unwrap_err()
+        });
+        assert_eq!(PanicKind::Explicit, scoped.kind());
+        assert_eq!(Ok(3), std::thread::spawn(|| 3).join_message());
+    }
+
+    #[cfg(feature = "tokio")]
+    #[test]
+    fn join_error() {
+        let runtime = tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap();
+        runtime.block_on(async {
+            let error = tokio::spawn(async { panic_any(3_u32) }).await.unwrap_err();
+            assert_eq!(Some("Box<dyn Any>".to_string()), join_error_message(error));
+
+            let error = tokio::spawn(async { panic!("gus {}", 1) })
+                .await
+                .unwrap_err();
+            assert_eq!("gus 1", error.into_caught_panic().unwrap().message());
+
+            let task = tokio::spawn(std::future::pending::<()>());
+            task.abort();
+            assert_eq!(None, join_error_message(task.await.unwrap_err()));
+        });
+    }
+}
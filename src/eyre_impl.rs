@@ -0,0 +1,70 @@
+//! [eyre](https://docs.rs/eyre) support for [`PanicDetails`][crate::PanicDetails].
+use crate::{AsPanicPayload, PanicDetails, PanicError};
+
+impl From<PanicDetails> for eyre::Report {
+    /// A report whose error is a [`PanicError`] with the message and location, wrapped in
+    /// the details' first line, which names the thread and the location. So the location
+    /// stays in the report when it's rendered, and `downcast_ref::<PanicError>` finds the
+    /// panic.
+    fn from(details: PanicDetails) -> Self {
+        let mut heading = String::new();
+        let _ = details.write_heading(&mut heading);
+        let error = PanicError {
+            message: details.message().to_string(),
+            kind: details.kind(),
+            location: details.location().cloned(),
+            backtrace: None,
+            #[cfg(feature = "miette")]
+            source: Default::default(),
+        };
+        eyre::Report::new(error).wrap_err(heading)
+    }
+}
+
+/// Turn a [`std::panic::catch_unwind`] payload into an
+/// [`eyre::Report`](https://docs.rs/eyre/0.6/eyre/struct.Report.html), through
+/// [`PanicDetails::from_payload`].
+///
+/// ## Examples
+/// ```
+/// use std::panic::catch_unwind;
+/// use panic_message::{eyre_report, PanicError};
+///
+/// let payload = catch_unwind(|| panic!("gus")).unwrap_err();
+/// let report = eyre_report(&payload);
+///
+/// assert_eq!("thread '<unnamed>' panicked: gus", format!("{:#}", report));
+/// assert_eq!("gus", report.downcast_ref::<PanicError>().unwrap().message());
+/// ```
+pub fn eyre_report(payload: impl AsPanicPayload) -> eyre::Report {
+    PanicDetails::from_payload(payload).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Location;
+
+    #[test]
+    fn keeps_the_location() {
+        fn run() -> eyre::Result<()> {
+            Err(PanicDetails::new("gus")
+                .with_thread("main")
+                .with_location(Location::new("src/main.rs", 3, 5)))?;
+            Ok(())
+        }
+
+        let report = run().unwrap_err();
+
+        assert_eq!(
+            "thread 'main' panicked at src/main.rs:3:5",
+            report.to_string()
+        );
+        assert_eq!(
+            vec!["thread 'main' panicked at src/main.rs:3:5", "gus"],
+            report.chain().map(|e| e.to_string()).collect::<Vec<_>>()
+        );
+        let error = report.downcast_ref::<PanicError>().unwrap();
+        assert_eq!(Some(&Location::new("src/main.rs", 3, 5)), error.location());
+    }
+}
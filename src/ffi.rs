@@ -0,0 +1,144 @@
+//! A C API for finding out why a call into Rust failed, for libraries with a C interface.
+//!
+//! A panic must not unwind out of an `extern "C"` function, so exported functions wrap
+//! their body in [`catch`], which returns an error value instead and keeps the panic
+//! message in a per-thread slot. The C caller then reads it with the functions declared in
+//! `include/panic_message.h`:
+//!
+//! - `panic_message_last()`: the message, NUL-terminated, or `NULL` if there is none
+//! - `panic_message_last_len()`: its length in bytes, without the NUL
+//! - `panic_message_clear()`: forget it
+//!
+//! Like `errno`, the slot is only written when a call fails, so check it after a call
+//! returned its error value. The pointer stays valid until the next panic on the thread
+//! is caught or the message is cleared.
+//!
+//! ## Examples
+//! ```
+//! use std::os::raw::c_int;
+//! use panic_message::ffi;
+//!
+//! #[no_mangle]
+//! pub extern "C" fn parse_port(text: *const u8, len: usize) -> c_int {
+//!     ffi::catch(-1, || {
+//!         let text = unsafe { std::slice::from_raw_parts(text, len) };
+//!         std::str::from_utf8(text).unwrap().parse::<u16>().unwrap().into()
+//!     })
+//! }
+//!
+//! assert_eq!(8080, parse_port(b"8080".as_ptr(), 4));
+//! assert_eq!(-1, parse_port(b"gus".as_ptr(), 3));
+//! assert!(ffi::last_message().unwrap().contains("InvalidDigit"));
+//! ```
+//!
+//! And from C:
+//! ```c
+//! #include "panic_message.h"
+//!
+//! if (parse_port(text, len) < 0) {
+//!     const char *message = panic_message_last();
+//!     fprintf(stderr, "parse_port failed: %s\n", message ? message : "unknown error");
+//!     panic_message_clear();
+//! }
+//! ```
+use std::{cell::RefCell, ffi::CString, os::raw::c_char, panic::UnwindSafe, ptr};
+
+thread_local! {
+    static LAST: RefCell<Option<CString>> = const { RefCell::new(None) };
+}
+
+/// Run `f`, returning `on_panic` instead if it panics, after keeping the panic message for
+/// `panic_message_last`.
+pub fn catch<T>(on_panic: T, f: impl FnOnce() -> T + UnwindSafe) -> T {
+    match crate::catch_unwind_message(f) {
+        Ok(value) => value,
+        Err(message) => {
+            set_last_message(message);
+            on_panic
+        }
+    }
+}
+
+/// Keep `message` for `panic_message_last`, replacing the one kept before, for failures
+/// that aren't panics. NUL bytes in it are dropped.
+pub fn set_last_message(message: impl Into<String>) {
+    let mut message = message.into();
+    message.retain(|c| c != '\0');
+    let message = CString::new(message).expect("NUL bytes were removed");
+    // the slot is gone while the thread exits, and there's nobody left to read it then
+    let _ = LAST.try_with(|last| *last.borrow_mut() = Some(message));
+}
+
+/// The message kept on the current thread, if there is one.
+pub fn last_message() -> Option<String> {
+    LAST.try_with(|last| {
+        last.borrow()
+            .as_ref()
+            .map(|message| message.to_string_lossy().into_owned())
+    })
+    .ok()
+    .flatten()
+}
+
+/// The message of the last panic caught on the calling thread, NUL-terminated, or `NULL`
+/// if there is none. It is valid until the next panic on the thread is caught or
+/// `panic_message_clear` is called.
+#[no_mangle]
+pub extern "C" fn panic_message_last() -> *const c_char {
+    LAST.try_with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(ptr::null(), |message| message.as_ptr())
+    })
+    .unwrap_or(ptr::null())
+}
+
+/// The length in bytes of the message `panic_message_last` returns, without the NUL, or 0
+/// if there is none.
+#[no_mangle]
+pub extern "C" fn panic_message_last_len() -> usize {
+    LAST.try_with(|last| {
+        last.borrow()
+            .as_ref()
+            .map_or(0, |message| message.as_bytes().len())
+    })
+    .unwrap_or(0)
+}
+
+/// Forget the message of the last panic caught on the calling thread.
+#[no_mangle]
+pub extern "C" fn panic_message_clear() {
+    let _ = LAST.try_with(|last| last.borrow_mut().take());
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CStr;
+
+    #[test]
+    fn last_message_slot() {
+        assert!(panic_message_last().is_null());
+        assert_eq!(0, panic_message_last_len());
+
+        assert_eq!(3, catch(0, || 3));
+        assert!(panic_message_last().is_null());
+        assert_eq!(0, catch(0, || panic!("gus {}", 1)));
+
+        let message = panic_message_last();
+        // SAFETY: the message was just set, and stays until it is cleared.
+        assert_eq!(
+            "gus 1",
+            unsafe { CStr::from_ptr(message) }.to_str().unwrap()
+        );
+        assert_eq!(5, panic_message_last_len());
+        assert_eq!(None, std::thread::spawn(last_message).join().unwrap());
+
+        set_last_message("g\0us");
+        assert_eq!(Some("gus".to_string()), last_message());
+
+        panic_message_clear();
+        assert!(panic_message_last().is_null());
+        assert_eq!(None, last_message());
+    }
+}
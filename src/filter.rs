@@ -0,0 +1,133 @@
+//! Choosing which panics a hook reports by where they happened.
+use crate::Location;
+
+/// Which panic locations a [`PanicHookBuilder`][crate::PanicHookBuilder] hook reports, see
+/// [`include_path`][crate::PanicHookBuilder::include_path],
+/// [`exclude_path`][crate::PanicHookBuilder::exclude_path] and
+/// [`exclude_crate`][crate::PanicHookBuilder::exclude_crate].
+#[derive(Debug, Clone, Default)]
+pub(crate) struct LocationFilter {
+    pub(crate) include: Vec<String>,
+    pub(crate) exclude: Vec<String>,
+    pub(crate) exclude_crates: Vec<String>,
+}
+
+impl LocationFilter {
+    /// Whether a panic at `location` is reported. Panics without a location always are.
+    pub(crate) fn allows(&self, location: Option<&Location>) -> bool {
+        let location = match location {
+            Some(location) => location,
+            None => return true,
+        };
+        let path = location.file().replace('\\', "/");
+        if !self.include.is_empty() && !self.include.iter().any(|glob| matches(glob, &path)) {
+            return false;
+        }
+        if self.exclude.iter().any(|glob| matches(glob, &path)) {
+            return false;
+        }
+        match crate_name(&path) {
+            Some(name) => !self
+                .exclude_crates
+                .iter()
+                .any(|excluded| excluded.replace('-', "_") == name.replace('-', "_")),
+            None => true,
+        }
+    }
+}
+
+/// The crate a source path belongs to: the directory holding its last `src` directory,
+/// without the version cargo appends to registry checkouts, like `serde` for
+/// `.../serde-1.0.0/src/de.rs`. Relative paths of the crate being built have none.
+fn crate_name(path: &str) -> Option<&str> {
+    let components: Vec<&str> = path.split('/').collect();
+    let src = components
+        .iter()
+        .rposition(|component| *component == "src")?;
+    let dir = *components.get(src.checked_sub(1)?)?;
+    let name = match dir.rfind('-') {
+        Some(dash) if dir[dash + 1..].starts_with(|c: char| c.is_ascii_digit()) => &dir[..dash],
+        _ => dir,
+    };
+    Some(name).filter(|name| !name.is_empty())
+}
+
+/// Match `path` against a glob, where `*` matches within a path component, `**` matches
+/// any number of components, and `?` matches one character.
+fn matches(glob: &str, path: &str) -> bool {
+    fn go(glob: &[u8], path: &[u8]) -> bool {
+        match glob {
+            [] => path.is_empty(),
+            [b'*', b'*', b'/', rest @ ..] => {
+                // `**/` also matches no components at all
+                go(rest, path)
+                    || (0..path.len()).any(|i| path[i] == b'/' && go(rest, &path[i + 1..]))
+            }
+            [b'*', b'*', rest @ ..] => (0..=path.len()).any(|i| go(rest, &path[i..])),
+            [b'*', rest @ ..] => (0..=path.len())
+                .take_while(|&i| i == 0 || path[i - 1] != b'/')
+                .any(|i| go(rest, &path[i..])),
+            [b'?', rest @ ..] => matches!(path, [c, tail @ ..] if *c != b'/' && go(rest, tail)),
+            [c, rest @ ..] => matches!(path, [p, tail @ ..] if p == c && go(rest, tail)),
+        }
+    }
+    go(glob.as_bytes(), path.as_bytes())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn globs() {
+        assert!(matches("src/*.rs", "src/main.rs"));
+        assert!(!matches("src/*.rs", "src/bin/main.rs"));
+        assert!(matches("src/**/*.rs", "src/main.rs"));
+        assert!(matches("src/**/*.rs", "src/bin/main.rs"));
+        assert!(matches(
+            "**/vendor/**",
+            "/home/gus/app/vendor/dep/src/lib.rs"
+        ));
+        assert!(matches("src/ma?n.rs", "src/main.rs"));
+        assert!(!matches("src/main.rs", "src/main.rss"));
+    }
+
+    #[test]
+    fn crate_names() {
+        assert_eq!(
+            Some("serde_json"),
+            crate_name("/home/gus/.cargo/registry/src/index.crates.io-6f17d22bba15001f/serde_json-1.0.108/src/de.rs")
+        );
+        assert_eq!(
+            Some("core"),
+            crate_name(
+                "/rustc/90b35a6239c3d8bdabc530a6a0816f7ff89a0aaf/library/core/src/option.rs"
+            )
+        );
+        assert_eq!(Some("my-app"), crate_name("/build/my-app/src/main.rs"));
+        assert_eq!(None, crate_name("src/main.rs"));
+    }
+
+    #[test]
+    fn filter() {
+        let filter = LocationFilter {
+            include: vec![],
+            exclude: vec!["**/generated/**".to_string()],
+            exclude_crates: vec!["hyper-util".to_string()],
+        };
+        let at = |file: &str| filter.allows(Some(&Location::new(file, 1, 1)));
+
+        assert!(at("src/main.rs"));
+        assert!(!at("src/generated/api.rs"));
+        assert!(!at("/r/hyper_util-0.1.0/src/client.rs"));
+        assert!(at("/r/hyper-1.0.0/src/client.rs"));
+        assert!(filter.allows(None));
+
+        let filter = LocationFilter {
+            include: vec!["src/**".to_string()],
+            ..LocationFilter::default()
+        };
+        assert!(filter.allows(Some(&Location::new("src/main.rs", 1, 1))));
+        assert!(!filter.allows(Some(&Location::new("/r/hyper-1.0.0/src/lib.rs", 1, 1))));
+    }
+}
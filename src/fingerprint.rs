@@ -0,0 +1,73 @@
+//! Grouping panics by where they happened, for labels and deduplication.
+use crate::{Location, PanicDetails};
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+impl PanicDetails {
+    /// A hash identifying where the panic happened, the same for every panic at one
+    /// location, in every run and build of the program that doesn't move it. Panics
+    /// without a location are told apart by message instead.
+    ///
+    /// It is the 64-bit FNV-1a hash of `file:line:column`, so other tools can compute it
+    /// too, and is suited for metric labels, as it has at most one value per `panic!`. With
+    /// the `metrics` feature, the hook [`PanicHookBuilder`][crate::PanicHookBuilder] installs
+    /// labels the `panics_total` counter with it, in hex.
+    ///
+    /// ## Examples
+    /// ```
+    /// use panic_message::{Location, PanicDetails};
+    ///
+    /// let here = Location::new("src/main.rs", 3, 5);
+    /// let a = PanicDetails::new("gus 1").with_location(here.clone());
+    /// let b = PanicDetails::new("gus 2").with_location(here);
+    ///
+    /// assert_eq!(a.fingerprint(), b.fingerprint());
+    /// assert_eq!("08a06522a6d2af7a", format!("{:016x}", a.fingerprint()));
+    /// ```
+    pub fn fingerprint(&self) -> u64 {
+        fingerprint(self.location(), self.message())
+    }
+}
+
+/// The [fingerprint][PanicDetails::fingerprint] of a panic at `location` with `message`.
+pub(crate) fn fingerprint(location: Option<&Location>, message: &str) -> u64 {
+    let mut hash = FNV_OFFSET;
+    let mut write = |bytes: &[u8]| {
+        for &byte in bytes {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    };
+    match location {
+        Some(location) => {
+            write(location.file().as_bytes());
+            write(b":");
+            write(location.line().to_string().as_bytes());
+            write(b":");
+            write(location.column().to_string().as_bytes());
+        }
+        None => write(message.as_bytes()),
+    }
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Location;
+
+    #[test]
+    fn fnv1a() {
+        // the published FNV-1a test vector for "a"
+        assert_eq!(0xaf63_dc4c_8601_ec8c, PanicDetails::new("a").fingerprint());
+        assert_ne!(
+            PanicDetails::new("a")
+                .with_location(Location::new("a", 1, 1))
+                .fingerprint(),
+            PanicDetails::new("a")
+                .with_location(Location::new("a", 1, 2))
+                .fingerprint()
+        );
+    }
+}
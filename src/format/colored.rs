@@ -0,0 +1,153 @@
+use std::{
+    env, fmt,
+    io::{self, IsTerminal},
+};
+
+use crate::{format::Format, PanicDetails, PanicKind};
+
+const RED: &str = "\x1b[31m";
+const DIM: &str = "\x1b[2m";
+const RESET: &str = "\x1b[0m";
+
+/// Whether [`Colored`] writes colors.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ColorChoice {
+    /// Only when stderr is a terminal and the
+    /// [`NO_COLOR`](https://no-color.org) environment variable isn't set to a non-empty
+    /// value.
+    #[default]
+    Auto,
+    /// Always, for example when stderr is piped to a pager that shows colors.
+    Always,
+    /// Never.
+    Never,
+}
+
+/// The layout of the default panic hook, like the [`Display`][fmt::Display] impl of
+/// [`PanicDetails`], with the `thread 'main' panicked at` header in red and the location
+/// dimmed, for a [`Stream::stderr`][crate::sink::Stream::stderr] read by people.
+///
+/// With [`ColorChoice::Auto`], whether to color is decided when the format is created, as
+/// the hook is usually installed at startup; without colors it writes exactly what the
+/// `Display` impl does.
+///
+/// ## Examples
+/// ```
+/// use panic_message::{format::{ColorChoice, Colored, Format}, sink::Stream, PanicDetails, PanicHookBuilder};
+///
+/// PanicHookBuilder::new()
+///     .sink(Stream::stderr().with_format(Colored::new(ColorChoice::Auto)))
+///     .install();
+/// # let _ = std::panic::take_hook();
+///
+/// let details = PanicDetails::new("gus").with_thread("main");
+/// assert_eq!(
+///     "\x1b[31mthread 'main' panicked:\x1b[0m\ngus",
+///     Colored::new(ColorChoice::Always).render(&details)
+/// );
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Colored {
+    enabled: bool,
+}
+
+impl Colored {
+    /// A format that colors according to `choice`.
+    pub fn new(choice: ColorChoice) -> Self {
+        Colored {
+            enabled: enabled(choice, env::var_os("NO_COLOR").as_deref(), || {
+                io::stderr().is_terminal()
+            }),
+        }
+    }
+
+    /// Whether the format writes colors.
+    pub fn is_enabled(&self) -> bool {
+        self.enabled
+    }
+}
+
+impl Default for Colored {
+    /// A format that colors with [`ColorChoice::Auto`].
+    fn default() -> Self {
+        Colored::new(ColorChoice::Auto)
+    }
+}
+
+fn enabled(
+    choice: ColorChoice,
+    no_color: Option<&std::ffi::OsStr>,
+    terminal: impl FnOnce() -> bool,
+) -> bool {
+    match choice {
+        ColorChoice::Always => true,
+        ColorChoice::Never => false,
+        ColorChoice::Auto => no_color.map_or(true, |value| value.is_empty()) && terminal(),
+    }
+}
+
+impl Format for Colored {
+    fn format(&self, details: &PanicDetails, out: &mut dyn fmt::Write) -> fmt::Result {
+        if !self.enabled {
+            return write!(out, "{}", details);
+        }
+        let labels = crate::labels();
+        let thread = details.thread().unwrap_or(&labels.unnamed_thread);
+        match details.location() {
+            Some(location) => write!(
+                out,
+                "{}{} '{}' {}{} {}{}{}:",
+                RED, labels.thread, thread, labels.panicked_at, RESET, DIM, location, RESET
+            )?,
+            None => write!(
+                out,
+                "{}{} '{}' {}:{}",
+                RED, labels.thread, thread, labels.panicked, RESET
+            )?,
+        }
+        let message = match details.kind() {
+            PanicKind::NonString => &labels.fallback_message,
+            _ => details.message(),
+        };
+        write!(out, "\n{}", message)?;
+        for section in details.sections() {
+            write!(out, "\n{}", section)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Location;
+    use std::ffi::OsStr;
+
+    #[test]
+    fn auto_detection() {
+        assert!(enabled(ColorChoice::Auto, None, || true));
+        assert!(enabled(ColorChoice::Auto, Some(OsStr::new("")), || true));
+        assert!(!enabled(ColorChoice::Auto, Some(OsStr::new("1")), || true));
+        assert!(!enabled(ColorChoice::Auto, None, || false));
+        assert!(enabled(ColorChoice::Always, Some(OsStr::new("1")), || {
+            false
+        }));
+        assert!(!enabled(ColorChoice::Never, None, || true));
+    }
+
+    #[test]
+    fn colors() {
+        let details = PanicDetails::new("gus")
+            .with_thread("main")
+            .with_location(Location::new("src/main.rs", 3, 5));
+
+        assert_eq!(
+            "\x1b[31mthread 'main' panicked at\x1b[0m \x1b[2msrc/main.rs:3:5\x1b[0m:\ngus",
+            Colored::new(ColorChoice::Always).render(&details)
+        );
+        assert_eq!(
+            details.to_string(),
+            Colored::new(ColorChoice::Never).render(&details)
+        );
+    }
+}
@@ -0,0 +1,152 @@
+use std::{
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{format::Format, json, PanicDetails};
+
+/// A single-line JSON document using Datadog's
+/// [reserved and standard attributes](https://docs.datadoghq.com/logs/log_configuration/attributes_naming_convention/),
+/// so the log pipeline parses the panic without a custom processor and Error Tracking
+/// picks it up from `error.kind`, `error.message` and `error.stack`.
+///
+/// `error.kind` is the snake_case [`PanicKind`][crate::PanicKind] of the panic, and
+/// `timestamp` the [time of the panic][PanicDetails::time], or the time of formatting for
+/// details that don't have one.
+///
+/// ## Examples
+/// ```
+/// use panic_message::{format::{Datadog, Format}, PanicDetails};
+///
+/// let line = Datadog::new().with_service("api").render(&PanicDetails::new("gus"));
+/// assert!(line.contains(r#""status":"error""#));
+/// assert!(line.contains(r#""service":"api""#));
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Datadog {
+    service: Option<String>,
+}
+
+impl Datadog {
+    /// A Datadog format without a `service` attribute, leaving it to the agent.
+    pub fn new() -> Self {
+        Datadog::default()
+    }
+
+    /// Set the `service` reserved attribute.
+    pub fn with_service(mut self, service: impl Into<String>) -> Self {
+        self.service = Some(service.into());
+        self
+    }
+}
+
+impl Format for Datadog {
+    fn format(&self, details: &PanicDetails, out: &mut dyn fmt::Write) -> fmt::Result {
+        let time = details.time().unwrap_or_else(crate::time::now);
+        write_datadog(self, details, time, out)
+    }
+}
+
+fn write_datadog(
+    datadog: &Datadog,
+    details: &PanicDetails,
+    now: SystemTime,
+    out: &mut dyn fmt::Write,
+) -> fmt::Result {
+    let timestamp = now.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let mut document = json::Object::new(out)?;
+    document.u64("timestamp", timestamp.as_millis() as u64)?;
+    document.str("status", "error")?;
+    document.opt_str("service", datadog.service.as_deref())?;
+    document.str("ddsource", "rust")?;
+    document.str("message", details.message())?;
+    document.object("error", |error| {
+        error.str("kind", details.kind().as_str())?;
+        error.str("message", details.message())?;
+        error.opt_str("stack", details.backtrace())
+    })?;
+    document.object("logger", |logger| {
+        logger.str("name", "panic")?;
+        logger.opt_str("thread_name", details.thread())
+    })?;
+    if let Some(location) = details.location() {
+        document.object("code", |code| {
+            code.str("filepath", location.file())?;
+            code.u64("lineno", location.line().into())
+        })?;
+    }
+    if let Some(uptime) = details.uptime() {
+        document.u64("uptime_ms", uptime.as_millis() as u64)?;
+    }
+    if let Some(since) = details.since_previous_panic() {
+        document.u64("since_previous_panic_ms", since.as_millis() as u64)?;
+    }
+    if !details.fields().is_empty() {
+        document.object("fields", |fields| details.write_fields(fields))?;
+    }
+    if !details.sections().is_empty() {
+        document.object("sections", |sections| details.write_sections(sections))?;
+    }
+    document.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Location;
+
+    #[test]
+    fn full() {
+        let details = PanicDetails::new("attempt to add with overflow")
+            .with_location(Location::new("src/main.rs", 3, 5))
+            .with_thread("main")
+            .with_backtrace("0: main");
+        let mut out = String::new();
+
+        write_datadog(&Datadog::new(), &details, UNIX_EPOCH, &mut out).unwrap();
+
+        assert_eq!(
+            concat!(
+                r#"{"timestamp":0,"status":"error","ddsource":"rust","#,
+                r#""message":"attempt to add with overflow","#,
+                r#""error":{"kind":"overflow","message":"attempt to add with overflow","stack":"0: main"},"#,
+                r#""logger":{"name":"panic","thread_name":"main"},"#,
+                r#""code":{"filepath":"src/main.rs","lineno":3}}"#
+            ),
+            out
+        );
+    }
+
+    #[test]
+    fn fields() {
+        let details = PanicDetails::new("gus").with_field("port", "8080");
+
+        let out = Datadog::new().render(&details);
+
+        assert!(out.ends_with(r#""fields":{"port":"8080"}}"#), "{}", out);
+    }
+
+    #[test]
+    fn sections() {
+        let details = PanicDetails::new("gus").with_section(crate::Section::text("config", "a1b2"));
+
+        let out = Datadog::new().render(&details);
+
+        assert!(out.ends_with(r#""sections":{"config":"a1b2"}}"#), "{}", out);
+    }
+
+    #[test]
+    fn timing() {
+        let details = PanicDetails::new("gus")
+            .with_uptime(std::time::Duration::from_millis(1500))
+            .with_since_previous_panic(std::time::Duration::from_millis(250));
+
+        let out = Datadog::new().render(&details);
+
+        assert!(
+            out.ends_with(r#""uptime_ms":1500,"since_previous_panic_ms":250}"#),
+            "{}",
+            out
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use std::{fmt, time::SystemTime};
+
+use crate::{format::Format, json, time::Rfc3339, PanicDetails};
+
+/// The [Elastic Common Schema](https://www.elastic.co/guide/en/ecs/current/index.html)
+/// version the [`Ecs`] format targets.
+const ECS_VERSION: &str = "8.11.0";
+
+/// A single-line JSON document following the
+/// [Elastic Common Schema](https://www.elastic.co/guide/en/ecs/current/index.html),
+/// so panics shipped to Elasticsearch land in the `error.*`, `log.*` and `process.*`
+/// fields Kibana already knows how to display.
+///
+/// `@timestamp` is the [time of the panic][PanicDetails::time], or the time of formatting
+/// for details that don't have one.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Ecs;
+
+impl Format for Ecs {
+    fn format(&self, details: &PanicDetails, out: &mut dyn fmt::Write) -> fmt::Result {
+        write_ecs(
+            details,
+            details.time().unwrap_or_else(crate::time::now),
+            out,
+        )
+    }
+}
+
+fn write_ecs(details: &PanicDetails, now: SystemTime, out: &mut dyn fmt::Write) -> fmt::Result {
+    let mut document = json::Object::new(out)?;
+    document.str("@timestamp", &Rfc3339(now).to_string())?;
+    document.str("message", details.message())?;
+    document.object("ecs", |ecs| ecs.str("version", ECS_VERSION))?;
+    if !details.fields().is_empty() {
+        document.object("labels", |labels| details.write_fields(labels))?;
+    }
+    document.object("error", |error| {
+        error.str("message", details.message())?;
+        error.str("type", "panic")?;
+        error.opt_str("stack_trace", details.backtrace())
+    })?;
+    document.object("log", |log| {
+        log.str("level", "error")?;
+        match details.location() {
+            Some(location) => log.object("origin", |origin| {
+                origin.object("file", |file| {
+                    file.str("name", location.file())?;
+                    file.u64("line", location.line().into())
+                })
+            }),
+            None => Ok(()),
+        }
+    })?;
+    document.object("process", |process| {
+        process.u64("pid", std::process::id().into())?;
+        if let Some(uptime) = details.uptime() {
+            process.u64("uptime", uptime.as_secs())?;
+        }
+        match details.thread() {
+            Some(name) => process.object("thread", |thread| thread.str("name", name)),
+            None => Ok(()),
+        }
+    })?;
+    // not ECS fields, so they go in the custom `panic` namespace
+    if details.since_previous_panic().is_some() || !details.sections().is_empty() {
+        document.object("panic", |panic| {
+            if let Some(since) = details.since_previous_panic() {
+                panic.u64("since_previous_ms", since.as_millis() as u64)?;
+            }
+            if !details.sections().is_empty() {
+                panic.object("sections", |sections| details.write_sections(sections))?;
+            }
+            Ok(())
+        })?;
+    }
+    document.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Location;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn full() {
+        let details = PanicDetails::new("gus")
+            .with_location(Location::new("src/main.rs", 3, 5))
+            .with_thread("main")
+            .with_backtrace("0: main");
+        let mut out = String::new();
+
+        write_ecs(&details, UNIX_EPOCH, &mut out).unwrap();
+
+        assert_eq!(
+            format!(
+                concat!(
+                    r#"{{"@timestamp":"1970-01-01T00:00:00.000Z","message":"gus","#,
+                    r#""ecs":{{"version":"8.11.0"}},"#,
+                    r#""error":{{"message":"gus","type":"panic","stack_trace":"0: main"}},"#,
+                    r#""log":{{"level":"error","origin":{{"file":{{"name":"src/main.rs","line":3}}}}}},"#,
+                    r#""process":{{"pid":{},"thread":{{"name":"main"}}}}}}"#
+                ),
+                std::process::id()
+            ),
+            out
+        );
+    }
+
+    #[test]
+    fn minimal() {
+        let out = Ecs.render(&PanicDetails::new("gus"));
+
+        assert!(out.contains(r#""log":{"level":"error"}"#));
+        assert!(!out.contains("stack_trace"));
+        assert!(!out.contains("thread"));
+    }
+
+    #[test]
+    fn fields_are_labels() {
+        let out = Ecs.render(&PanicDetails::new("gus").with_field("port", "8080"));
+
+        assert!(
+            out.contains(r#""ecs":{"version":"8.11.0"},"labels":{"port":"8080"},"#),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn sections() {
+        let details = PanicDetails::new("gus").with_section(crate::Section::text("config", "a1b2"));
+
+        let out = Ecs.render(&details);
+
+        assert!(
+            out.ends_with(r#""panic":{"sections":{"config":"a1b2"}}}"#),
+            "{}",
+            out
+        );
+    }
+
+    #[test]
+    fn timing() {
+        let details = PanicDetails::new("gus")
+            .with_uptime(std::time::Duration::from_millis(90_500))
+            .with_since_previous_panic(std::time::Duration::from_millis(250));
+
+        let out = Ecs.render(&details);
+
+        assert!(out.contains(r#","uptime":90}"#), "{}", out);
+        assert!(
+            out.ends_with(r#""panic":{"since_previous_ms":250}}"#),
+            "{}",
+            out
+        );
+    }
+}
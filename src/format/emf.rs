@@ -0,0 +1,178 @@
+use std::{
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use crate::{format::Format, json, PanicDetails};
+
+/// A single-line JSON document in CloudWatch
+/// [Embedded Metric Format](https://docs.aws.amazon.com/AmazonCloudWatch/latest/monitoring/CloudWatch_Embedded_Metric_Format_Specification.html).
+///
+/// CloudWatch Logs stores the line as a structured log event carrying the panic details
+/// (`message`, `file`, `line`, `column`, `thread` and `backtrace`), and also extracts a
+/// `PanicCount` metric of `1` from it, so one log line gives both the event and a metric
+/// to alarm on. Its timestamp is the [time of the panic][PanicDetails::time], or the time
+/// of formatting for details that don't have one.
+///
+/// ## Examples
+/// ```
+/// use panic_message::{format::{CloudWatchEmf, Format}, PanicDetails};
+///
+/// let emf = CloudWatchEmf::new("MyService").with_dimension("Stage", "prod");
+/// let line = emf.render(&PanicDetails::new("gus"));
+/// assert!(line.contains(r#""Namespace":"MyService""#));
+/// assert!(line.contains(r#""PanicCount":1"#));
+/// ```
+#[derive(Debug, Clone)]
+pub struct CloudWatchEmf {
+    namespace: String,
+    dimensions: Vec<(String, String)>,
+}
+
+impl CloudWatchEmf {
+    /// Emit the `PanicCount` metric into the given CloudWatch namespace.
+    pub fn new(namespace: impl Into<String>) -> Self {
+        CloudWatchEmf {
+            namespace: namespace.into(),
+            dimensions: Vec::new(),
+        }
+    }
+
+    /// Add a dimension to the `PanicCount` metric, like the service or stage name.
+    pub fn with_dimension(mut self, name: impl Into<String>, value: impl Into<String>) -> Self {
+        self.dimensions.push((name.into(), value.into()));
+        self
+    }
+}
+
+impl Format for CloudWatchEmf {
+    fn format(&self, details: &PanicDetails, out: &mut dyn fmt::Write) -> fmt::Result {
+        let time = details.time().unwrap_or_else(crate::time::now);
+        write_emf(self, details, time, out)
+    }
+}
+
+fn write_emf(
+    emf: &CloudWatchEmf,
+    details: &PanicDetails,
+    now: SystemTime,
+    out: &mut dyn fmt::Write,
+) -> fmt::Result {
+    let timestamp = now.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let mut document = json::Object::new(out)?;
+    document.object("_aws", |aws| {
+        aws.u64("Timestamp", timestamp.as_millis() as u64)?;
+        aws.array("CloudWatchMetrics", |metrics| {
+            metrics.object(|directive| {
+                directive.str("Namespace", &emf.namespace)?;
+                directive.array("Dimensions", |sets| {
+                    sets.array(|set| {
+                        for (name, _) in &emf.dimensions {
+                            set.str(name)?;
+                        }
+                        Ok(())
+                    })
+                })?;
+                directive.array("Metrics", |metrics| {
+                    metrics.object(|metric| {
+                        metric.str("Name", "PanicCount")?;
+                        metric.str("Unit", "Count")
+                    })
+                })
+            })
+        })
+    })?;
+    for (name, value) in &emf.dimensions {
+        document.str(name, value)?;
+    }
+    document.u64("PanicCount", 1)?;
+    document.str("message", details.message())?;
+    if let Some(location) = details.location() {
+        document.str("file", location.file())?;
+        document.u64("line", location.line().into())?;
+        document.u64("column", location.column().into())?;
+    }
+    document.opt_str("thread", details.thread())?;
+    document.opt_str("backtrace", details.backtrace())?;
+    if let Some(uptime) = details.uptime() {
+        document.u64("uptime_ms", uptime.as_millis() as u64)?;
+    }
+    if let Some(since) = details.since_previous_panic() {
+        document.u64("since_previous_panic_ms", since.as_millis() as u64)?;
+    }
+    if !details.fields().is_empty() {
+        document.object("fields", |fields| details.write_fields(fields))?;
+    }
+    if !details.sections().is_empty() {
+        document.object("sections", |sections| details.write_sections(sections))?;
+    }
+    document.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Location;
+    use std::time::Duration;
+
+    #[test]
+    fn full() {
+        let emf = CloudWatchEmf::new("Svc").with_dimension("Stage", "prod");
+        let details = PanicDetails::new("gus")
+            .with_location(Location::new("src/main.rs", 3, 5))
+            .with_thread("main");
+        let mut out = String::new();
+
+        write_emf(
+            &emf,
+            &details,
+            UNIX_EPOCH + Duration::from_millis(1500),
+            &mut out,
+        )
+        .unwrap();
+
+        assert_eq!(
+            concat!(
+                r#"{"_aws":{"Timestamp":1500,"CloudWatchMetrics":[{"Namespace":"Svc","#,
+                r#""Dimensions":[["Stage"]],"Metrics":[{"Name":"PanicCount","Unit":"Count"}]}]},"#,
+                r#""Stage":"prod","PanicCount":1,"message":"gus","#,
+                r#""file":"src/main.rs","line":3,"column":5,"thread":"main"}"#
+            ),
+            out
+        );
+    }
+
+    #[test]
+    fn no_dimensions() {
+        let out = CloudWatchEmf::new("Svc").render(&PanicDetails::new("gus"));
+
+        assert!(out.contains(r#""Dimensions":[[]]"#));
+    }
+
+    #[test]
+    fn fields() {
+        let details = PanicDetails::new("gus").with_field("port", "8080");
+
+        let out = CloudWatchEmf::new("Svc").render(&details);
+
+        assert!(out.ends_with(r#""fields":{"port":"8080"}}"#), "{}", out);
+    }
+
+    #[test]
+    fn sections() {
+        let details = PanicDetails::new("gus").with_section(crate::Section::text("config", "a1b2"));
+
+        let out = CloudWatchEmf::new("Svc").render(&details);
+
+        assert!(out.ends_with(r#""sections":{"config":"a1b2"}}"#), "{}", out);
+    }
+
+    #[test]
+    fn timing() {
+        let details = PanicDetails::new("gus").with_uptime(Duration::from_millis(1500));
+
+        let out = CloudWatchEmf::new("Svc").render(&details);
+
+        assert!(out.ends_with(r#""uptime_ms":1500}"#), "{}", out);
+    }
+}
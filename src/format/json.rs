@@ -0,0 +1,55 @@
+use std::fmt;
+
+use crate::{format::Format, PanicDetails};
+
+/// The single-line JSON object of [`PanicDetails::to_json`], with the message, location,
+/// thread, backtrace, [raw frames][crate::capture_raw_frames] and every other field that
+/// is present, for log aggregators that take structured events.
+///
+/// `time_us` is the [time of the panic][PanicDetails::time], or the time of formatting for
+/// details that don't have one. [`PanicDetails::from_json`] reads the object back.
+///
+/// ## Examples
+/// ```
+/// use panic_message::{format::Json, sink::Stream, PanicHookBuilder};
+///
+/// PanicHookBuilder::new()
+///     .sink(Stream::stderr().with_format(Json))
+///     .install();
+/// # let _ = std::panic::take_hook();
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Json;
+
+impl Format for Json {
+    fn format(&self, details: &PanicDetails, out: &mut dyn fmt::Write) -> fmt::Result {
+        details.write_json_at(Some(details.time().unwrap_or_else(crate::time::now)), out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Location;
+    use std::time::SystemTime;
+
+    #[test]
+    fn round_trips() {
+        let details = PanicDetails::new("gus")
+            .with_location(Location::new("src/main.rs", 3, 5))
+            .with_thread("main")
+            .with_time(SystemTime::now());
+
+        let line = Json.render(&details);
+
+        assert_eq!(details.to_json(), line);
+        assert_eq!(details, PanicDetails::from_json(&line).unwrap());
+    }
+
+    #[test]
+    fn fills_in_the_time() {
+        let line = Json.render(&PanicDetails::new("gus"));
+
+        assert!(PanicDetails::from_json(&line).unwrap().time().is_some());
+    }
+}
@@ -0,0 +1,42 @@
+//! Formats that render [`PanicDetails`] for a particular log pipeline, as the crate's own
+//! [JSON][Json] object, as a line laid out by a [`Template`], or [colored][Colored] for a
+//! terminal.
+//!
+//! ## Examples
+//! ```
+//! use panic_message::{format::{Ecs, Format}, PanicDetails};
+//!
+//! let line = Ecs.render(&PanicDetails::new("gus"));
+//! assert!(line.contains(r#""error":{"message":"gus""#));
+//! ```
+use std::fmt;
+
+use crate::PanicDetails;
+
+mod colored;
+mod datadog;
+mod ecs;
+mod emf;
+mod json;
+mod template;
+
+pub use colored::{ColorChoice, Colored};
+pub use datadog::Datadog;
+pub use ecs::Ecs;
+pub use emf::CloudWatchEmf;
+pub use json::Json;
+pub use template::{Template, TemplateError};
+
+/// Renders [`PanicDetails`] as text.
+pub trait Format: Send + Sync {
+    /// Write `details` to `out`, without a trailing newline.
+    fn format(&self, details: &PanicDetails, out: &mut dyn fmt::Write) -> fmt::Result;
+
+    /// Render `details` into a new `String`.
+    fn render(&self, details: &PanicDetails) -> String {
+        let mut out = String::new();
+        self.format(details, &mut out)
+            .expect("writing to a String can't fail");
+        out
+    }
+}
@@ -0,0 +1,248 @@
+use std::{error::Error, fmt, io};
+
+use crate::{format::Format, time::Rfc3339, PanicDetails};
+
+/// A line of text laid out by a template like
+/// `"{timestamp} {thread} panicked at {location}: {message}"`, for hooks that only need to
+/// rearrange the usual parts of a report.
+///
+/// The template is parsed once, by [`Template::parse`], which rejects unknown placeholders.
+/// The placeholders are:
+///
+/// - `{message}`: the panic message
+/// - `{kind}`: the snake_case [`PanicKind`][crate::PanicKind]
+/// - `{location}`: `file:line:column`, and `{file}`, `{line}` and `{column}` on their own
+/// - `{thread}`: the thread name, `<unnamed>` for a thread without one, and `{thread_id}`
+/// - `{timestamp}`: the [time of the panic][PanicDetails::time] in RFC 3339, or the time of
+///   formatting for details that don't have one
+/// - `{pid}`: the process ID
+/// - `{fingerprint}`: the [fingerprint][PanicDetails::fingerprint], as 16 hex digits
+/// - `{backtrace}`: the backtrace, which usually spans several lines
+/// - `{field.<key>}`: the value of the [context field][PanicDetails::fields] `<key>`
+///
+/// A value the report doesn't have, like `{location}` for a panic without one, is left
+/// empty. `{{` and `}}` write a literal brace.
+///
+/// ## Examples
+/// ```
+/// use panic_message::{format::{Format, Template}, sink::Stream, Location, PanicDetails};
+///
+/// let template = Template::parse("{thread} panicked at {location}: {message} [{field.request}]")?;
+///
+/// let details = PanicDetails::new("gus")
+///     .with_thread("main")
+///     .with_location(Location::new("src/main.rs", 3, 5))
+///     .with_field("request", "GET /");
+/// assert_eq!("main panicked at src/main.rs:3:5: gus [GET /]", template.render(&details));
+///
+/// let stderr = Stream::stderr().with_format(template);
+/// # Ok::<(), panic_message::format::TemplateError>(())
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Literal(String),
+    Message,
+    Kind,
+    Location,
+    File,
+    Line,
+    Column,
+    Thread,
+    ThreadId,
+    Timestamp,
+    Pid,
+    Fingerprint,
+    Backtrace,
+    Field(String),
+}
+
+impl Template {
+    /// Parse a template, see [`Template`] for its placeholders.
+    pub fn parse(template: &str) -> Result<Self, TemplateError> {
+        let mut parts = Vec::new();
+        let mut literal = String::new();
+        let mut rest = template;
+        while let Some(c) = rest.chars().next() {
+            let offset = template.len() - rest.len();
+            if let Some(after) = rest.strip_prefix("{{") {
+                literal.push('{');
+                rest = after;
+            } else if let Some(after) = rest.strip_prefix("}}") {
+                literal.push('}');
+                rest = after;
+            } else if c == '{' {
+                let end = rest
+                    .find('}')
+                    .ok_or_else(|| TemplateError::new("unclosed placeholder", offset))?;
+                let part = placeholder(&rest[1..end])
+                    .ok_or_else(|| TemplateError::new("unknown placeholder", offset))?;
+                if !literal.is_empty() {
+                    parts.push(Part::Literal(std::mem::take(&mut literal)));
+                }
+                parts.push(part);
+                rest = &rest[end + 1..];
+            } else if c == '}' {
+                return Err(TemplateError::new("unmatched `}`", offset));
+            } else {
+                literal.push(c);
+                rest = &rest[c.len_utf8()..];
+            }
+        }
+        if !literal.is_empty() {
+            parts.push(Part::Literal(literal));
+        }
+        Ok(Template { parts })
+    }
+}
+
+fn placeholder(name: &str) -> Option<Part> {
+    Some(match name {
+        "message" => Part::Message,
+        "kind" => Part::Kind,
+        "location" => Part::Location,
+        "file" => Part::File,
+        "line" => Part::Line,
+        "column" => Part::Column,
+        "thread" => Part::Thread,
+        "thread_id" => Part::ThreadId,
+        "timestamp" => Part::Timestamp,
+        "pid" => Part::Pid,
+        "fingerprint" => Part::Fingerprint,
+        "backtrace" => Part::Backtrace,
+        _ => match name.strip_prefix("field.") {
+            Some(key) if !key.is_empty() => Part::Field(key.to_string()),
+            _ => return None,
+        },
+    })
+}
+
+impl Format for Template {
+    fn format(&self, details: &PanicDetails, out: &mut dyn fmt::Write) -> fmt::Result {
+        let location = details.location();
+        for part in &self.parts {
+            match part {
+                Part::Literal(text) => out.write_str(text)?,
+                Part::Message => out.write_str(details.message())?,
+                Part::Kind => out.write_str(details.kind().as_str())?,
+                Part::Location => {
+                    if let Some(location) = location {
+                        write!(out, "{}", location)?;
+                    }
+                }
+                Part::File => out.write_str(location.map_or("", |l| l.file()))?,
+                Part::Line => {
+                    if let Some(location) = location {
+                        write!(out, "{}", location.line())?;
+                    }
+                }
+                Part::Column => {
+                    if let Some(location) = location {
+                        write!(out, "{}", location.column())?;
+                    }
+                }
+                Part::Thread => out.write_str(details.thread().unwrap_or("<unnamed>"))?,
+                Part::ThreadId => {
+                    if let Some(id) = details.thread_id() {
+                        write!(out, "{}", id)?;
+                    }
+                }
+                Part::Timestamp => {
+                    let time = details.time().unwrap_or_else(crate::time::now);
+                    write!(out, "{}", Rfc3339(time))?;
+                }
+                Part::Pid => write!(out, "{}", std::process::id())?,
+                Part::Fingerprint => write!(out, "{:016x}", details.fingerprint())?,
+                Part::Backtrace => out.write_str(details.backtrace().unwrap_or(""))?,
+                Part::Field(key) => {
+                    if let Some((_, value)) = details.fields().iter().find(|(k, _)| k == key) {
+                        out.write_str(value)?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Why a [`Template`] failed to parse.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TemplateError {
+    reason: &'static str,
+    offset: usize,
+}
+
+impl TemplateError {
+    fn new(reason: &'static str, offset: usize) -> Self {
+        TemplateError { reason, offset }
+    }
+}
+
+impl fmt::Display for TemplateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid panic template at byte {}: {}",
+            self.offset, self.reason
+        )
+    }
+}
+
+impl Error for TemplateError {}
+
+impl From<TemplateError> for io::Error {
+    fn from(e: TemplateError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidInput, e)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn placeholders() {
+        let template =
+            Template::parse("{timestamp} {kind} {file}:{line}:{column} {{{thread}}} {message}")
+                .unwrap();
+        let details = PanicDetails::new("gus")
+            .with_time(UNIX_EPOCH + Duration::from_millis(1500))
+            .with_location(crate::Location::new("src/main.rs", 3, 5));
+
+        assert_eq!(
+            "1970-01-01T00:00:01.500Z explicit src/main.rs:3:5 {<unnamed>} gus",
+            template.render(&details)
+        );
+        assert_eq!(
+            "[]",
+            Template::parse("[{location}{field.request}{backtrace}]")
+                .unwrap()
+                .render(&PanicDetails::new("gus"))
+        );
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            "invalid panic template at byte 4: unknown placeholder",
+            Template::parse("gus {mesage}").unwrap_err().to_string()
+        );
+        assert_eq!(
+            TemplateError::new("unclosed placeholder", 0),
+            Template::parse("{message").unwrap_err()
+        );
+        assert_eq!(
+            TemplateError::new("unmatched `}`", 3),
+            Template::parse("gus}").unwrap_err()
+        );
+        assert_eq!(
+            TemplateError::new("unknown placeholder", 0),
+            Template::parse("{field.}").unwrap_err()
+        );
+    }
+}
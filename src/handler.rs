@@ -0,0 +1,144 @@
+//! Messages in `#[panic_handler]`s, which `no_std` programs panic through.
+use core::{
+    fmt::{self, Display, Write},
+    panic::{Location, PanicInfo},
+};
+
+/// The message a `#[panic_handler]` is given, if it is a string literal, like the message of
+/// `panic!("gus")`, which is the only kind of message that doesn't need formatting.
+///
+/// This is [`core::panic::PanicInfo`], the argument of a `no_std` program's panic handler,
+/// not the [`PanicHookInfo`][std::panic::PanicHookInfo] of
+/// [`panic_info_message`][crate::panic_info_message]. Messages with format arguments are
+/// `None`; a handler that can write them writes [`PanicInfo::message`] instead.
+///
+/// ## Examples
+/// ```ignore
+/// #![no_std]
+///
+/// #[panic_handler]
+/// fn panic(info: &core::panic::PanicInfo) -> ! {
+///     if let Some(message) = panic_message::get_panic_handler_message(info) {
+///         uart::write_str(message);
+///     }
+///     loop {}
+/// }
+/// ```
+pub fn get_panic_handler_message(panic_info: &PanicInfo<'_>) -> Option<&'static str> {
+    panic_info.message().as_str()
+}
+
+/// Write the panic as `panicked at src/main.rs:3:5: message`, or `panicked: message` without
+/// a location, on one line and without allocating, for `#[panic_handler]`s that write to a
+/// UART, RTT channel or other [`fmt::Write`] sink.
+///
+/// The message is written with its format arguments, unlike
+/// [`get_panic_handler_message`]'s.
+///
+/// ## Examples
+/// ```ignore
+/// #![no_std]
+///
+/// #[panic_handler]
+/// fn panic(info: &core::panic::PanicInfo) -> ! {
+///     let _ = panic_message::write_panic_info(&mut rtt::channel(0), info);
+///     loop {}
+/// }
+/// ```
+pub fn write_panic_info(out: &mut impl Write, panic_info: &PanicInfo<'_>) -> fmt::Result {
+    write!(out, "{}", display_panic_info(panic_info))
+}
+
+/// Display the panic like [`write_panic_info`] writes it, for loggers that take a
+/// `Display` value.
+///
+/// With the `defmt` feature, it is also a `defmt::Format`, see [`defmt_panic_info`].
+pub fn display_panic_info<'a>(panic_info: &'a PanicInfo<'a>) -> PanicInfoDisplay<'a> {
+    PanicInfoDisplay(panic_info)
+}
+
+/// The panic of a `#[panic_handler]`, displayed compactly, see [`display_panic_info`].
+#[derive(Debug, Clone, Copy)]
+pub struct PanicInfoDisplay<'a>(&'a PanicInfo<'a>);
+
+impl Display for PanicInfoDisplay<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_compact(f, self.0.location(), self.0.message())
+    }
+}
+
+/// Sends the file and line as defmt arguments, and a
+/// [string literal message][get_panic_handler_message] as an interned string, so only
+/// messages with format arguments are formatted on the device.
+#[cfg(feature = "defmt")]
+impl defmt::Format for PanicInfoDisplay<'_> {
+    fn format(&self, f: defmt::Formatter<'_>) {
+        let message = self.0.message();
+        match (message.as_str(), self.0.location()) {
+            (Some(message), Some(location)) => defmt::write!(
+                f,
+                "panicked at {=str}:{=u32}:{=u32}: {=str}",
+                location.file(),
+                location.line(),
+                location.column(),
+                message
+            ),
+            (None, Some(location)) => defmt::write!(
+                f,
+                "panicked at {=str}:{=u32}:{=u32}: {}",
+                location.file(),
+                location.line(),
+                location.column(),
+                defmt::Display2Format(&message)
+            ),
+            (Some(message), None) => defmt::write!(f, "panicked: {=str}", message),
+            (None, None) => defmt::write!(f, "panicked: {}", defmt::Display2Format(&message)),
+        }
+    }
+}
+
+/// Log the panic with `defmt::error!`, as `panicked at src/main.rs:3:5: message`, for
+/// `#[panic_handler]`s of programs that log with defmt.
+///
+/// ## Examples
+/// ```ignore
+/// #![no_std]
+///
+/// #[panic_handler]
+/// fn panic(info: &core::panic::PanicInfo) -> ! {
+///     panic_message::defmt_panic_info(info);
+///     cortex_m::asm::udf()
+/// }
+/// ```
+#[cfg(feature = "defmt")]
+pub fn defmt_panic_info(panic_info: &PanicInfo<'_>) {
+    defmt::error!("{}", display_panic_info(panic_info));
+}
+
+pub(crate) fn write_compact(
+    out: &mut impl Write,
+    location: Option<&Location<'_>>,
+    message: impl Display,
+) -> fmt::Result {
+    match location {
+        Some(location) => write!(out, "panicked at {}: {}", location, message),
+        None => write!(out, "panicked: {}", message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compact() {
+        let mut out = String::new();
+        let location = Location::caller();
+        write_compact(&mut out, Some(location), format_args!("gus {}", 1)).unwrap();
+        assert_eq!(format!("panicked at {}: gus 1", location), out);
+
+        let mut out = String::new();
+        write_compact(&mut out, None, "gus").unwrap();
+        assert_eq!("panicked: gus", out);
+    }
+}
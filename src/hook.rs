@@ -0,0 +1,581 @@
+//! Installing a panic hook that reports panics to [`Sink`]s.
+use std::{
+    any::Any,
+    convert::TryFrom,
+    panic::PanicHookInfo as PanicInfo,
+    time::{Duration, Instant},
+};
+
+use crate::{
+    context::Captured, dedup::Dedup, filter::LocationFilter, sink::Sink, BacktraceFilter,
+    BacktraceMode, CrashMetadata, ExitPolicy, Location, PanicDetails, ResourceUsage,
+};
+
+/// How long cleanup callbacks get, in total, by default.
+const DEFAULT_CLEANUP_DEADLINE: Duration = Duration::from_secs(5);
+
+/// The type of hook [`PanicHookBuilder::build`] returns, as accepted by [`std::panic::set_hook`].
+pub type PanicHook = Box<dyn Fn(&PanicInfo<'_>) + Send + Sync + 'static>;
+
+/// Builds a panic hook that collects [`PanicDetails`] for every panic and sends them
+/// to each configured [`Sink`], in order, and then to every registered
+/// [`Reporter`][crate::Reporter], whose docs say when to use which.
+///
+/// The hook replaces the default one, so nothing is printed unless a sink prints it.
+///
+/// The application's code the hook runs (sinks, [reporters][crate::Reporter],
+/// [section providers][crate::register_section_provider], and the
+/// [formatters][crate::register_panic_formatter] and [extractors][crate::register_extractor]
+/// of payloads) runs on a helper thread, so one that panics is reported on stderr and
+/// skipped instead of aborting the process, which is what a panic on the panicking thread
+/// does. The thread is only started when there is such code to run. The backtrace, the
+/// [breadcrumbs][crate::breadcrumb], the [thread context][crate::thread_context] and, with
+/// the `tracing` feature, the current span are taken from the panicking thread first, so
+/// the report and `tracing` events still describe it. Where no thread can be started, as on
+/// `wasm32-unknown-unknown`, the code runs on the panicking thread instead.
+///
+/// ## Examples
+/// ```
+/// use std::{io, sync::Arc};
+/// use panic_message::{sink::{Background, Sink}, PanicDetails, PanicHookBuilder};
+///
+/// struct Upload;
+///
+/// impl Sink for Upload {
+///     fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+///         Ok(())
+///     }
+/// }
+///
+/// PanicHookBuilder::new()
+///     .sink(Background::new(Upload))
+///     .install();
+/// # let _ = std::panic::take_hook();
+/// ```
+pub struct PanicHookBuilder {
+    sinks: Vec<Box<dyn Sink>>,
+    location: bool,
+    thread: bool,
+    backtrace: BacktraceMode,
+    backtrace_filter: Option<BacktraceFilter>,
+    resource_usage: bool,
+    exit_policy: Option<ExitPolicy>,
+    cleanup_deadline: Duration,
+    unknown_payload_warnings: bool,
+    raw_frames: bool,
+    breadcrumbs: bool,
+    metadata: Option<CrashMetadata>,
+    source_snippet: bool,
+    last_panic: bool,
+    size_budget: Option<usize>,
+    dedup: Option<Duration>,
+    filter: LocationFilter,
+    chain: Option<Chain>,
+}
+
+/// The payload of the panic being reported, lent to the hook's helper thread.
+struct Payload<'a>(&'a (dyn Any + Send));
+
+// SAFETY: the panicking thread, which owns the payload, waits in `isolated` while the helper
+// thread reads it, so it is never used by two threads at once.
+unsafe impl Send for Payload<'_> {}
+
+impl<'a> Payload<'a> {
+    fn get(self) -> &'a (dyn Any + Send) {
+        self.0
+    }
+}
+
+/// When the hook calls the one it replaced.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Chain {
+    Before,
+    After,
+}
+
+impl Default for PanicHookBuilder {
+    fn default() -> Self {
+        PanicHookBuilder {
+            sinks: Vec::new(),
+            location: true,
+            thread: true,
+            backtrace: BacktraceMode::Env,
+            backtrace_filter: None,
+            resource_usage: false,
+            exit_policy: None,
+            cleanup_deadline: DEFAULT_CLEANUP_DEADLINE,
+            unknown_payload_warnings: false,
+            raw_frames: false,
+            breadcrumbs: false,
+            metadata: None,
+            source_snippet: false,
+            last_panic: false,
+            size_budget: None,
+            dedup: None,
+            filter: LocationFilter::default(),
+            chain: None,
+        }
+    }
+}
+
+impl PanicHookBuilder {
+    /// A builder with no sinks.
+    pub fn new() -> Self {
+        PanicHookBuilder::default()
+    }
+
+    /// Add a sink. Sinks are called in the order they were added.
+    pub fn sink(mut self, sink: impl Sink) -> Self {
+        self.sinks.push(Box::new(sink));
+        self
+    }
+
+    /// Whether reports say where the panic happened, `true` by default. Locations are
+    /// still used to [filter][PanicHookBuilder::include_path] panics when they aren't
+    /// reported.
+    pub fn with_location(mut self, location: bool) -> Self {
+        self.location = location;
+        self
+    }
+
+    /// Whether reports name the panicking thread and give its id, `true` by default.
+    pub fn with_thread(mut self, thread: bool) -> Self {
+        self.thread = thread;
+        self
+    }
+
+    /// When to capture a backtrace, [`BacktraceMode::Env`] by default.
+    ///
+    /// ## Examples
+    /// ```
+    /// use panic_message::{BacktraceMode, PanicHookBuilder};
+    ///
+    /// PanicHookBuilder::new()
+    ///     .with_location(true)
+    ///     .with_thread(false)
+    ///     .with_backtrace(BacktraceMode::Forced)
+    ///     .install();
+    /// # let _ = std::panic::take_hook();
+    /// ```
+    pub fn with_backtrace(mut self, mode: BacktraceMode) -> Self {
+        self.backtrace = mode;
+        self
+    }
+
+    /// Hide the frames `filter` matches in every report's backtrace, like the panic
+    /// machinery above the code that panicked, see [`PanicDetails::with_backtrace_filter`].
+    /// Off by default, so backtraces have every frame.
+    ///
+    /// ## Examples
+    /// ```
+    /// use panic_message::{BacktraceFilter, PanicHookBuilder};
+    ///
+    /// PanicHookBuilder::new()
+    ///     .with_backtrace_filter(BacktraceFilter::new().hide_prefix("tokio::runtime::"))
+    ///     .install();
+    /// # let _ = std::panic::take_hook();
+    /// ```
+    pub fn with_backtrace_filter(mut self, filter: BacktraceFilter) -> Self {
+        self.backtrace_filter = Some(filter);
+        self
+    }
+
+    /// Attach the process's [`ResourceUsage`] to every report. Off by default, because
+    /// measuring it reads from `/proc` and makes a few system calls inside the hook.
+    pub fn with_resource_usage(mut self) -> Self {
+        self.resource_usage = true;
+        self
+    }
+
+    /// Exit the process after reporting each panic, with the exit code `policy` picks for
+    /// its [`PanicKind`][crate::PanicKind], once the sinks have flushed or the policy's
+    /// flush timeout has passed.
+    ///
+    /// This exits on every panic, on any thread, including panics that would have been
+    /// caught with [`catch_unwind`][std::panic::catch_unwind].
+    pub fn with_exit_policy(mut self, policy: ExitPolicy) -> Self {
+        self.exit_policy = Some(policy);
+        self
+    }
+
+    /// How long the [cleanup callbacks][crate::register_cleanup] may take in total,
+    /// 5 seconds by default. Callbacks still running then are abandoned, and counted in
+    /// [`PanicDetails::abandoned_cleanups`].
+    pub fn with_cleanup_deadline(mut self, deadline: Duration) -> Self {
+        self.cleanup_deadline = deadline;
+        self
+    }
+
+    /// The first time a panic's payload type has no message, add a `payload_warning` field
+    /// to its report, naming the type if it was registered with
+    /// [`register_payload_debug`][crate::register_payload_debug] and suggesting
+    /// [`register_panic_formatter`][crate::register_panic_formatter]. That way the sinks
+    /// show where `"Box<dyn Any>"` messages come from.
+    pub fn with_unknown_payload_warnings(mut self) -> Self {
+        self.unknown_payload_warnings = true;
+        self
+    }
+
+    /// Attach the [raw frames][crate::capture_raw_frames] of the panicking thread to every
+    /// report, so reports from stripped binaries can be [symbolized][crate::resolve_report]
+    /// later, where the symbol files are.
+    pub fn with_raw_frames(mut self) -> Self {
+        self.raw_frames = true;
+        self
+    }
+
+    /// Attach the panicking thread's [breadcrumbs][crate::breadcrumb] to every report, as
+    /// a `breadcrumbs` [section][crate::Section].
+    pub fn with_breadcrumbs(mut self) -> Self {
+        self.breadcrumbs = true;
+        self
+    }
+
+    /// Add `metadata` identifying the build to every report, as a `metadata`
+    /// [section][crate::Section], see [`CrashMetadata`].
+    pub fn with_metadata(mut self, metadata: CrashMetadata) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Add the source line the panic happened on to every report, with a caret under the
+    /// column, as a `source` section, see [`PanicDetails::with_source_snippet`]. Off by
+    /// default, as it reads the source file, which is only there where the code was built.
+    pub fn with_source_snippets(mut self) -> Self {
+        self.source_snippet = true;
+        self
+    }
+
+    /// Keep each report as the panicking thread's [last panic][crate::last_panic], to get the
+    /// whole report of a panic after catching it.
+    pub fn with_last_panic(mut self) -> Self {
+        self.last_panic = true;
+        self
+    }
+
+    /// Shorten every report to at most `bytes` bytes of text before sending it to the
+    /// sinks, for intakes that reject larger payloads. See
+    /// [`PanicDetails::with_size_budget`].
+    pub fn with_size_budget(mut self, bytes: usize) -> Self {
+        self.size_budget = Some(bytes);
+        self
+    }
+
+    /// Report a panic at most once per `window` for each
+    /// [fingerprint][PanicDetails::fingerprint], so a panic storm, like a retry loop hitting
+    /// the same bug, doesn't flood the logs. Off by default.
+    ///
+    /// Repeats within the window aren't sent to the sinks or
+    /// [reporters][crate::Reporter]. The next panic reported with that fingerprint has a
+    /// `suppressed_repeats` field counting them, like syslog's "last message repeated N
+    /// times". Cleanups and the [previous hook][PanicHookBuilder::chain_previous] still run
+    /// for repeats, and nothing is suppressed with an
+    /// [exit policy][PanicHookBuilder::with_exit_policy], as the panic ends the process.
+    ///
+    /// ## Examples
+    /// ```
+    /// use std::time::Duration;
+    /// use panic_message::{sink::Stream, PanicHookBuilder};
+    ///
+    /// PanicHookBuilder::new()
+    ///     .sink(Stream::stderr())
+    ///     .with_dedup_window(Duration::from_secs(60))
+    ///     .install();
+    /// # let _ = std::panic::take_hook();
+    /// ```
+    pub fn with_dedup_window(mut self, window: Duration) -> Self {
+        self.dedup = Some(window);
+        self
+    }
+
+    /// Only report panics whose source path matches `glob`, and any other `include_path`
+    /// glob. In globs, `*` matches within a path component, `**` matches any number of
+    /// components, and `?` matches one character; `\` in paths is matched as `/`.
+    ///
+    /// Panics that aren't reported are ignored entirely: no sinks, cleanups or exit. Panics
+    /// without a location are always reported.
+    ///
+    /// ## Examples
+    /// ```
+    /// use panic_message::PanicHookBuilder;
+    ///
+    /// PanicHookBuilder::new()
+    ///     // a vendored dependency that panics and recovers internally
+    ///     .exclude_path("**/vendor/**")
+    ///     .exclude_crate("flaky-parser")
+    ///     .install();
+    /// # let _ = std::panic::take_hook();
+    /// ```
+    pub fn include_path(mut self, glob: impl Into<String>) -> Self {
+        self.filter.include.push(glob.into());
+        self
+    }
+
+    /// Don't report panics whose source path matches `glob`, see
+    /// [`include_path`][PanicHookBuilder::include_path].
+    pub fn exclude_path(mut self, glob: impl Into<String>) -> Self {
+        self.filter.exclude.push(glob.into());
+        self
+    }
+
+    /// Don't report panics in the named crate, as recognized from the source path: the
+    /// directory its `src` directory is in, without the version cargo adds to registry
+    /// checkouts. `-` and `_` are interchangeable.
+    pub fn exclude_crate(mut self, name: impl Into<String>) -> Self {
+        self.filter.exclude_crates.push(name.into());
+        self
+    }
+
+    /// Call the hook that was installed before, like the default hook, a test harness's or
+    /// another library's, after reporting each panic and before any
+    /// [exit][PanicHookBuilder::with_exit_policy], instead of replacing it. Off by default.
+    ///
+    /// The previous hook is taken with [`std::panic::take_hook`] when the hook is
+    /// [built][PanicHookBuilder::build], which leaves the default hook installed until the
+    /// built hook is. It is called for every panic, including those the hook
+    /// [filters out][PanicHookBuilder::include_path], on the panicking thread.
+    ///
+    /// ## Examples
+    /// ```
+    /// use panic_message::{sink::Stream, PanicHookBuilder};
+    ///
+    /// PanicHookBuilder::new()
+    ///     .sink(Stream::stderr())
+    ///     .chain_previous(true)
+    ///     .install();
+    /// # let _ = std::panic::take_hook();
+    /// ```
+    pub fn chain_previous(mut self, chain: bool) -> Self {
+        self.chain = if chain { Some(Chain::After) } else { None };
+        self
+    }
+
+    /// Like [`chain_previous`][PanicHookBuilder::chain_previous], but call the previous
+    /// hook before reporting each panic, so whatever it prints comes first.
+    pub fn chain_previous_first(mut self, chain: bool) -> Self {
+        self.chain = if chain { Some(Chain::Before) } else { None };
+        self
+    }
+
+    /// Apply the configuration operators set in environment variables, read when this is
+    /// called, so they can change what panics print without recompiling. Values that
+    /// aren't recognized are reported on stderr and leave the builder's setting alone.
+    ///
+    /// - `PANIC_MESSAGE_BACKTRACE`: `0` to never capture a backtrace, `1` or `full` to
+    ///   always capture one, replacing [`with_backtrace`][PanicHookBuilder::with_backtrace].
+    ///   As with `RUST_BACKTRACE`, a captured backtrace lists every frame.
+    /// - `PANIC_MESSAGE_FORMAT`: add a [`Stream::stderr`][crate::sink::Stream::stderr] sink
+    ///   writing each report as `json` (the [`Json`][crate::format::Json] format), `pretty`
+    ///   (the default hook's layout) or `compact` (on one line, like
+    ///   `thread 'main' panicked at src/main.rs:3:5: message`), after the sinks added so far.
+    ///   Without it, no sink is added.
+    ///
+    /// ## Examples
+    /// ```
+    /// use panic_message::PanicHookBuilder;
+    ///
+    /// // PANIC_MESSAGE_FORMAT=json PANIC_MESSAGE_BACKTRACE=1 ./server
+    /// PanicHookBuilder::new().with_env_config().install();
+    /// # let _ = std::panic::take_hook();
+    /// ```
+    pub fn with_env_config(mut self) -> Self {
+        use crate::env_config::{backtrace_mode, stderr_sink, BACKTRACE_VAR, FORMAT_VAR};
+
+        if let Ok(value) = std::env::var(BACKTRACE_VAR) {
+            match backtrace_mode(&value) {
+                Some(mode) => self.backtrace = mode,
+                None => eprintln!(
+                    "panic-message: ignoring {}={:?}, expected 0, 1 or full",
+                    BACKTRACE_VAR, value
+                ),
+            }
+        }
+        if let Ok(value) = std::env::var(FORMAT_VAR) {
+            match stderr_sink(&value) {
+                Some(sink) => self.sinks.push(Box::new(sink)),
+                None => eprintln!(
+                    "panic-message: ignoring {}={:?}, expected json, pretty or compact",
+                    FORMAT_VAR, value
+                ),
+            }
+        }
+        self
+    }
+
+    /// Build the hook without installing it, to install it later or call it from another hook.
+    pub fn build(self) -> PanicHook {
+        crate::uptime::start();
+        let previous = self.chain.map(|chain| (chain, std::panic::take_hook()));
+        let dedup = match self.exit_policy {
+            None => self.dedup.map(Dedup::new),
+            Some(_) => None,
+        };
+        let call_previous = move |chain: Chain, panic_info: &PanicInfo<'_>| {
+            if let Some((when, previous)) = &previous {
+                if *when == chain {
+                    previous(panic_info);
+                }
+            }
+        };
+        Box::new(move |panic_info| {
+            if crate::cleanup::on_cleanup_thread() {
+                // reported by `run_cleanups`
+                return;
+            }
+            if crate::isolate::on_helper_thread() {
+                // reported on stderr by `isolated`
+                return;
+            }
+            call_previous(Chain::Before, panic_info);
+            let location = panic_info.location().map(Location::from);
+            if !self.filter.allows(location.as_ref()) {
+                call_previous(Chain::After, panic_info);
+                return;
+            }
+            let thread = std::thread::current().name().map(str::to_string);
+            crate::stats::record(thread.as_deref());
+            let abandoned = crate::run_cleanups_within(self.cleanup_deadline);
+            // everything taken from the panicking thread, before any of the application's
+            // code runs
+            let mut details = PanicDetails::capture(panic_info, self.backtrace)
+                .with_abandoned_cleanups(u32::try_from(abandoned).unwrap_or(u32::MAX));
+            details.since_previous_panic = crate::uptime::record_panic();
+            if !self.location {
+                details.location = None;
+            }
+            if !self.thread {
+                details.thread = None;
+                details.thread_id = None;
+            }
+            if self.resource_usage {
+                details = details.with_resources(ResourceUsage::capture());
+            }
+            if self.raw_frames {
+                details = details.with_raw_frames(crate::capture_raw_frames());
+            }
+            if self.breadcrumbs {
+                details =
+                    details.with_section(crate::Section::list("breadcrumbs", crate::breadcrumbs()));
+            }
+            if let Some(metadata) = &self.metadata {
+                details = details.with_metadata(metadata);
+            }
+            // what the exit code is chosen by if reporting fails
+            let fallback_kind = details.kind();
+            let isolate = self.runs_application_code(panic_info.payload());
+            let captured = Captured::capture();
+            let payload = Payload(panic_info.payload());
+            let this = &self;
+            let dedup = &dedup;
+            let report = move || {
+                let payload = payload.get();
+                captured.enter(|| this.report(details, payload, location, thread, dedup.as_ref()))
+            };
+            // A panic on this thread would abort the process, so the application's code, from
+            // payload formatters and section providers to sinks, runs on a helper thread,
+            // where it can be caught.
+            let reported = match isolate {
+                true => crate::isolate::isolated("panic hook", report),
+                false => Some(report()),
+            };
+            let kind = match reported {
+                Some(Some(details)) => {
+                    let kind = details.kind();
+                    if self.last_panic {
+                        crate::set_last_panic(details);
+                    }
+                    kind
+                }
+                // suppressed as a repeat
+                Some(None) => {
+                    call_previous(Chain::After, panic_info);
+                    return;
+                }
+                None => fallback_kind,
+            };
+            call_previous(Chain::After, panic_info);
+            if let Some(policy) = &self.exit_policy {
+                std::process::exit(policy.code(kind));
+            }
+        })
+    }
+
+    /// Whether reporting a panic with `payload` runs code of the application's, which needs
+    /// the helper thread: sinks, reporters, section providers, or the formatters, extractors
+    /// and `Display` impls a payload that isn't a string is rendered with.
+    fn runs_application_code(&self, payload: &(dyn Any + Send)) -> bool {
+        !self.sinks.is_empty()
+            || crate::reporter::has_reporters()
+            || crate::report::has_providers()
+            || crate::imp::get_known_message(payload).is_none()
+            // the recorder
+            || cfg!(feature = "metrics")
+    }
+
+    /// Finish the report of a panic with `payload` and send it to the sinks and reporters,
+    /// on the hook's helper thread. Returns `None` if it was suppressed as a repeat.
+    fn report(
+        &self,
+        mut details: PanicDetails,
+        payload: &(dyn Any + Send),
+        location: Option<Location>,
+        thread: Option<String>,
+        dedup: Option<&Dedup>,
+    ) -> Option<PanicDetails> {
+        if let Some(message) = crate::isolate::guarded("payload formatter", || {
+            crate::registry::owned_message(payload)
+        }) {
+            details.message = message;
+        }
+        let fingerprint = crate::fingerprint::fingerprint(location.as_ref(), details.message());
+        #[cfg(feature = "metrics")]
+        crate::isolate::guarded("metrics recorder", || {
+            crate::stats::record_metric(thread.as_deref(), fingerprint)
+        });
+        #[cfg(not(feature = "metrics"))]
+        let _ = thread;
+        if let Some(dedup) = dedup {
+            let suppressed = dedup.check(fingerprint, Instant::now())?;
+            if suppressed > 0 {
+                details = details.with_field("suppressed_repeats", suppressed.to_string());
+            }
+        }
+        if self.unknown_payload_warnings {
+            if let Some(warning) = crate::registry::unknown_payload_warning(payload) {
+                details = details.with_field("payload_warning", warning);
+            }
+        }
+        details = details.with_provided_sections();
+        if self.source_snippet {
+            details = details.with_source_snippet();
+        }
+        if let Some(filter) = &self.backtrace_filter {
+            details = details.with_backtrace_filter(filter);
+        }
+        if let Some(bytes) = self.size_budget {
+            details = details.with_size_budget(bytes);
+        }
+        #[cfg(all(any(unix, windows), feature = "signals"))]
+        crate::signals::record_last_panic(&details);
+        for sink in &self.sinks {
+            if let Some(Err(e)) = crate::isolate::guarded("sink", || sink.emit(&details)) {
+                eprintln!("panic-message: failed to report panic: {}", e);
+            }
+        }
+        crate::reporter::report(&details);
+        if let Some(policy) = &self.exit_policy {
+            let deadline = Instant::now() + policy.flush_timeout();
+            for sink in &self.sinks {
+                crate::isolate::guarded("sink", || {
+                    sink.flush(deadline.saturating_duration_since(Instant::now()))
+                });
+            }
+        }
+        Some(details)
+    }
+
+    /// Build the hook and install it with [`std::panic::set_hook`].
+    pub fn install(self) {
+        std::panic::set_hook(self.build());
+    }
+}
@@ -0,0 +1,88 @@
+//! Running user code from a panic hook without risking a second panic on the panicking
+//! thread, which aborts the process.
+use std::{
+    cell::Cell,
+    panic::{catch_unwind, AssertUnwindSafe},
+    thread,
+};
+
+thread_local! {
+    static ON_HELPER_THREAD: Cell<bool> = const { Cell::new(false) };
+}
+
+/// Run `f` on a helper thread and wait for it, returning `None` if it panicked. The panic
+/// is reported on stderr as coming from `what`.
+///
+/// Inside a panic hook, a panic on the panicking thread aborts even under `catch_unwind`;
+/// on a helper thread it unwinds as usual. If the thread can't be spawned, `f` runs on
+/// the current thread.
+pub(crate) fn isolated<T: Send>(what: &str, f: impl FnOnce() -> T + Send) -> Option<T> {
+    let mut f = Some(f);
+    let result = thread::scope(|scope| {
+        let helper = thread::Builder::new()
+            .name("panic-message-hook".to_string())
+            .spawn_scoped(scope, || {
+                mark_helper_thread();
+                let f = f.take().expect("only taken once");
+                catch_unwind(AssertUnwindSafe(f))
+            });
+        match helper {
+            Ok(helper) => Some(helper.join().unwrap_or_else(Err)),
+            Err(_) => None,
+        }
+    });
+    match result {
+        Some(Ok(value)) => Some(value),
+        Some(Err(payload)) => {
+            eprintln!(
+                "panic-message: {} panicked: {}",
+                what,
+                crate::panic_message(&payload)
+            );
+            None
+        }
+        // no helper thread, so `f` wasn't taken
+        None => f.map(|f| f()),
+    }
+}
+
+/// Run `f` on the current thread, reporting a panic on stderr as coming from `what` and
+/// returning `None` instead. Only for threads that aren't running a panic hook.
+pub(crate) fn guarded<T>(what: &str, f: impl FnOnce() -> T) -> Option<T> {
+    match catch_unwind(AssertUnwindSafe(f)) {
+        Ok(value) => Some(value),
+        Err(payload) => {
+            eprintln!(
+                "panic-message: {} panicked: {}",
+                what,
+                crate::panic_message(&payload)
+            );
+            None
+        }
+    }
+}
+
+/// Mark the current thread as running this crate's reporting, so the hook ignores panics
+/// on it instead of reporting them through the sinks that panicked.
+pub(crate) fn mark_helper_thread() {
+    ON_HELPER_THREAD.with(|flag| flag.set(true));
+}
+
+/// Whether the current thread was marked with [`mark_helper_thread`].
+pub(crate) fn on_helper_thread() -> bool {
+    ON_HELPER_THREAD.try_with(Cell::get).unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catches_panics_on_the_helper() {
+        assert_eq!(Some(3), isolated("test", || 3));
+        assert_eq!(None, isolated("test", || -> u32 { panic!("gus") }));
+        assert_eq!(None, guarded("test", || -> u32 { panic!("gus") }));
+        assert!(!on_helper_thread());
+        assert_eq!(Some(true), isolated("test", on_helper_thread));
+    }
+}
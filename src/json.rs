@@ -0,0 +1,477 @@
+//! Just enough JSON writing for the formats this crate emits, and reading for the reports
+//! it reads back.
+use std::{
+    error::Error,
+    fmt::{self, Write},
+    io,
+};
+
+/// How deeply values may nest before reading gives up, so hostile input can't overflow
+/// the stack.
+const MAX_DEPTH: usize = 64;
+
+/// Write `value` as a quoted, escaped JSON string.
+pub(crate) fn write_str(out: &mut dyn Write, value: &str) -> fmt::Result {
+    out.write_char('"')?;
+    let mut start = 0;
+    for (i, c) in value.char_indices() {
+        let escape = match c {
+            '"' => "\\\"",
+            '\\' => "\\\\",
+            '\n' => "\\n",
+            '\r' => "\\r",
+            '\t' => "\\t",
+            c if c.is_control() => "",
+            _ => continue,
+        };
+        out.write_str(&value[start..i])?;
+        if escape.is_empty() {
+            write!(out, "\\u{:04x}", c as u32)?;
+        } else {
+            out.write_str(escape)?;
+        }
+        start = i + c.len_utf8();
+    }
+    out.write_str(&value[start..])?;
+    out.write_char('"')
+}
+
+/// Writes the members of a JSON object, in order, as they are added.
+pub(crate) struct Object<'a> {
+    out: &'a mut dyn Write,
+    empty: bool,
+}
+
+impl<'a> Object<'a> {
+    pub(crate) fn new(out: &'a mut dyn Write) -> Result<Self, fmt::Error> {
+        out.write_char('{')?;
+        Ok(Object { out, empty: true })
+    }
+
+    fn key(&mut self, key: &str) -> fmt::Result {
+        if !self.empty {
+            self.out.write_char(',')?;
+        }
+        self.empty = false;
+        write_str(self.out, key)?;
+        self.out.write_char(':')
+    }
+
+    pub(crate) fn str(&mut self, key: &str, value: &str) -> fmt::Result {
+        self.key(key)?;
+        write_str(self.out, value)
+    }
+
+    /// Like [`str`][Object::str], but skips the member entirely for `None`.
+    pub(crate) fn opt_str(&mut self, key: &str, value: Option<&str>) -> fmt::Result {
+        match value {
+            Some(value) => self.str(key, value),
+            None => Ok(()),
+        }
+    }
+
+    /// Add a member whose value is already written JSON.
+    pub(crate) fn raw(&mut self, key: &str, json: &str) -> fmt::Result {
+        self.key(key)?;
+        self.out.write_str(json)
+    }
+
+    pub(crate) fn u64(&mut self, key: &str, value: u64) -> fmt::Result {
+        self.key(key)?;
+        write!(self.out, "{}", value)
+    }
+
+    pub(crate) fn object(
+        &mut self,
+        key: &str,
+        members: impl FnOnce(&mut Object<'_>) -> fmt::Result,
+    ) -> fmt::Result {
+        self.key(key)?;
+        let mut object = Object::new(&mut *self.out)?;
+        members(&mut object)?;
+        object.finish()
+    }
+
+    pub(crate) fn array(
+        &mut self,
+        key: &str,
+        elements: impl FnOnce(&mut Array<'_>) -> fmt::Result,
+    ) -> fmt::Result {
+        self.key(key)?;
+        let mut array = Array::new(&mut *self.out)?;
+        elements(&mut array)?;
+        array.finish()
+    }
+
+    pub(crate) fn finish(self) -> fmt::Result {
+        self.out.write_char('}')
+    }
+}
+
+/// Writes the elements of a JSON array, in order, as they are added.
+pub(crate) struct Array<'a> {
+    out: &'a mut dyn Write,
+    empty: bool,
+}
+
+impl<'a> Array<'a> {
+    pub(crate) fn new(out: &'a mut dyn Write) -> Result<Self, fmt::Error> {
+        out.write_char('[')?;
+        Ok(Array { out, empty: true })
+    }
+
+    fn separator(&mut self) -> fmt::Result {
+        if !self.empty {
+            self.out.write_char(',')?;
+        }
+        self.empty = false;
+        Ok(())
+    }
+
+    pub(crate) fn str(&mut self, value: &str) -> fmt::Result {
+        self.separator()?;
+        write_str(self.out, value)
+    }
+
+    pub(crate) fn object(
+        &mut self,
+        members: impl FnOnce(&mut Object<'_>) -> fmt::Result,
+    ) -> fmt::Result {
+        self.separator()?;
+        let mut object = Object::new(&mut *self.out)?;
+        members(&mut object)?;
+        object.finish()
+    }
+
+    pub(crate) fn array(
+        &mut self,
+        elements: impl FnOnce(&mut Array<'_>) -> fmt::Result,
+    ) -> fmt::Result {
+        self.separator()?;
+        let mut array = Array::new(&mut *self.out)?;
+        elements(&mut array)?;
+        array.finish()
+    }
+
+    pub(crate) fn finish(self) -> fmt::Result {
+        self.out.write_char(']')
+    }
+}
+
+/// Why a JSON panic report failed to decode.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+    reason: &'static str,
+    offset: usize,
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid JSON panic report at byte {}: {}",
+            self.offset, self.reason
+        )
+    }
+}
+
+impl Error for JsonError {}
+
+impl From<JsonError> for io::Error {
+    fn from(e: JsonError) -> Self {
+        io::Error::new(io::ErrorKind::InvalidData, e)
+    }
+}
+
+/// Reads JSON values one at a time, as the caller expects them.
+pub(crate) struct Parser<'a> {
+    input: &'a str,
+    pos: usize,
+    depth: usize,
+}
+
+impl<'a> Parser<'a> {
+    pub(crate) fn new(input: &'a str) -> Self {
+        Parser {
+            input,
+            pos: 0,
+            depth: 0,
+        }
+    }
+
+    pub(crate) fn error(&self, reason: &'static str) -> JsonError {
+        JsonError {
+            reason,
+            offset: self.pos,
+        }
+    }
+
+    /// The next byte that isn't whitespace, without consuming it.
+    pub(crate) fn peek(&mut self) -> Option<u8> {
+        let bytes = self.input.as_bytes();
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = bytes.get(self.pos) {
+            self.pos += 1;
+        }
+        bytes.get(self.pos).copied()
+    }
+
+    fn expect(&mut self, byte: u8, reason: &'static str) -> Result<(), JsonError> {
+        if self.peek() != Some(byte) {
+            return Err(self.error(reason));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    /// Read the end of the input, which may only be whitespace.
+    pub(crate) fn finish(&mut self) -> Result<(), JsonError> {
+        match self.peek() {
+            None => Ok(()),
+            Some(_) => Err(self.error("trailing data")),
+        }
+    }
+
+    /// Read an object, calling `member` with each key to read its value.
+    pub(crate) fn object(
+        &mut self,
+        mut member: impl FnMut(&mut Self, String) -> Result<(), JsonError>,
+    ) -> Result<(), JsonError> {
+        self.expect(b'{', "expected an object")?;
+        self.nest()?;
+        if self.peek() == Some(b'}') {
+            self.pos += 1;
+            self.depth -= 1;
+            return Ok(());
+        }
+        loop {
+            let key = self.string()?;
+            self.expect(b':', "expected `:`")?;
+            member(self, key)?;
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b'}') => break,
+                _ => return Err(self.error("expected `,` or `}`")),
+            }
+        }
+        self.pos += 1;
+        self.depth -= 1;
+        Ok(())
+    }
+
+    /// Read an array, calling `element` to read each element.
+    pub(crate) fn array(
+        &mut self,
+        mut element: impl FnMut(&mut Self) -> Result<(), JsonError>,
+    ) -> Result<(), JsonError> {
+        self.expect(b'[', "expected an array")?;
+        self.nest()?;
+        if self.peek() == Some(b']') {
+            self.pos += 1;
+            self.depth -= 1;
+            return Ok(());
+        }
+        loop {
+            element(self)?;
+            match self.peek() {
+                Some(b',') => self.pos += 1,
+                Some(b']') => break,
+                _ => return Err(self.error("expected `,` or `]`")),
+            }
+        }
+        self.pos += 1;
+        self.depth -= 1;
+        Ok(())
+    }
+
+    fn nest(&mut self) -> Result<(), JsonError> {
+        self.depth += 1;
+        if self.depth > MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        Ok(())
+    }
+
+    pub(crate) fn string(&mut self) -> Result<String, JsonError> {
+        self.expect(b'"', "expected a string")?;
+        let mut out = String::new();
+        loop {
+            let rest = &self.input[self.pos..];
+            let end = rest
+                .find(|c: char| c == '"' || c == '\\' || c.is_control())
+                .ok_or_else(|| self.error("unterminated string"))?;
+            out.push_str(&rest[..end]);
+            self.pos += end;
+            match self.input.as_bytes()[self.pos] {
+                b'"' => {
+                    self.pos += 1;
+                    return Ok(out);
+                }
+                b'\\' => {
+                    self.pos += 1;
+                    let escape = self.input.as_bytes().get(self.pos).copied();
+                    self.pos += 1;
+                    out.push(match escape {
+                        Some(b'"') => '"',
+                        Some(b'\\') => '\\',
+                        Some(b'/') => '/',
+                        Some(b'b') => '\u{8}',
+                        Some(b'f') => '\u{c}',
+                        Some(b'n') => '\n',
+                        Some(b'r') => '\r',
+                        Some(b't') => '\t',
+                        Some(b'u') => self.unicode_escape()?,
+                        _ => return Err(self.error("invalid escape")),
+                    });
+                }
+                _ => return Err(self.error("control character in string")),
+            }
+        }
+    }
+
+    /// The character of a `\\u` escape, after the `u`, joining surrogate pairs.
+    fn unicode_escape(&mut self) -> Result<char, JsonError> {
+        let high = self.hex4()?;
+        if !(0xd800..0xdc00).contains(&high) {
+            return char::from_u32(high).ok_or_else(|| self.error("invalid \\u escape"));
+        }
+        if !self.input[self.pos..].starts_with("\\u") {
+            return Err(self.error("unpaired surrogate"));
+        }
+        self.pos += 2;
+        let low = self.hex4()?;
+        if !(0xdc00..0xe000).contains(&low) {
+            return Err(self.error("unpaired surrogate"));
+        }
+        char::from_u32(0x10000 + ((high - 0xd800) << 10) + (low - 0xdc00))
+            .ok_or_else(|| self.error("invalid \\u escape"))
+    }
+
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let digits = self
+            .input
+            .get(self.pos..self.pos + 4)
+            .filter(|digits| digits.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("invalid \\u escape"))?;
+        self.pos += 4;
+        Ok(u32::from_str_radix(digits, 16).expect("checked the digits"))
+    }
+
+    pub(crate) fn u64(&mut self) -> Result<u64, JsonError> {
+        self.peek();
+        let rest = &self.input[self.pos..];
+        let len = rest.bytes().take_while(u8::is_ascii_digit).count();
+        let value = rest[..len]
+            .parse()
+            .map_err(|_| self.error("expected an unsigned integer"))?;
+        self.pos += len;
+        Ok(value)
+    }
+
+    /// Read and discard a value of any type.
+    pub(crate) fn skip(&mut self) -> Result<(), JsonError> {
+        match self.peek() {
+            Some(b'{') => self.object(|parser, _| parser.skip()),
+            Some(b'[') => self.array(Parser::skip),
+            Some(b'"') => self.string().map(drop),
+            Some(b't') => self.literal("true"),
+            Some(b'f') => self.literal("false"),
+            Some(b'n') => self.literal("null"),
+            Some(b'-' | b'0'..=b'9') => {
+                let len = self.input[self.pos..]
+                    .bytes()
+                    .take_while(|b| matches!(b, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'))
+                    .count();
+                self.pos += len;
+                Ok(())
+            }
+            _ => Err(self.error("expected a value")),
+        }
+    }
+
+    fn literal(&mut self, literal: &str) -> Result<(), JsonError> {
+        if !self.input[self.pos..].starts_with(literal) {
+            return Err(self.error("expected a value"));
+        }
+        self.pos += literal.len();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escaping() {
+        let mut out = String::new();
+        write_str(&mut out, "a\"b\\c\nd\u{1}é").unwrap();
+
+        assert_eq!(r#""a\"b\\c\nd\u0001é""#, out);
+    }
+
+    #[test]
+    fn nested() {
+        let mut out = String::new();
+        let mut object = Object::new(&mut out).unwrap();
+        object.str("a", "b").unwrap();
+        object.opt_str("skipped", None).unwrap();
+        object.object("c", |object| object.u64("d", 1)).unwrap();
+        object.finish().unwrap();
+
+        assert_eq!(r#"{"a":"b","c":{"d":1}}"#, out);
+    }
+
+    #[test]
+    fn arrays() {
+        let mut out = String::new();
+        let mut object = Object::new(&mut out).unwrap();
+        object
+            .array("a", |array| {
+                array.str("b")?;
+                array.array(|_| Ok(()))?;
+                array.object(|object| object.u64("c", 1))
+            })
+            .unwrap();
+        object.finish().unwrap();
+
+        assert_eq!(r#"{"a":["b",[],{"c":1}]}"#, out);
+    }
+
+    #[test]
+    fn parsing() {
+        let mut parser = Parser::new(
+            r#" {"a": "b\u00e9\ud83d\ude00\n", "skip": [1.5e3, true, null, {}], "c": 7} "#,
+        );
+        let mut seen = Vec::new();
+        parser
+            .object(|parser, key| {
+                match key.as_str() {
+                    "a" => seen.push(parser.string()?),
+                    "c" => seen.push(parser.u64()?.to_string()),
+                    _ => parser.skip()?,
+                }
+                Ok(())
+            })
+            .unwrap();
+        parser.finish().unwrap();
+
+        assert_eq!(vec!["bé😀\n".to_string(), "7".to_string()], seen);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |input: &str| {
+            let mut parser = Parser::new(input);
+            parser.skip().and_then(|()| parser.finish()).unwrap_err()
+        };
+
+        assert_eq!(
+            "invalid JSON panic report at byte 2: unterminated string",
+            error(r#"["a"#).to_string()
+        );
+        assert_eq!("trailing data", error("{} {}").reason);
+        assert_eq!("unpaired surrogate", error(r#""\ud83d""#).reason);
+        assert_eq!("nested too deeply", error(&"[".repeat(100)).reason);
+        assert_eq!("expected `,` or `}`", error(r#"{"a":1"#).reason);
+    }
+}
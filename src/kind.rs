@@ -0,0 +1,138 @@
+//! Classifying panics by what caused them.
+use std::fmt;
+
+/// What kind of failure a panic message describes, recognized from the messages the
+/// standard library and language emit.
+///
+/// ## Examples
+/// ```
+/// use panic_message::PanicKind;
+///
+/// assert_eq!(PanicKind::Overflow, PanicKind::classify("attempt to add with overflow"));
+/// assert_eq!(PanicKind::Explicit, PanicKind::classify("gus"));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PanicKind {
+    /// `panic!`, `expect`, and everything not recognized as one of the other kinds.
+    Explicit,
+    /// A failed `assert!`, `assert_eq!` or `assert_ne!`.
+    Assertion,
+    /// `unwrap()` on a `None` or `Err`.
+    Unwrap,
+    /// Arithmetic overflow, with overflow checks enabled.
+    Overflow,
+    /// Integer division or remainder by zero.
+    DivideByZero,
+    /// An out of bounds index or range into a slice or `str`.
+    IndexOutOfBounds,
+    /// The payload was not a string, so there is no message to classify.
+    NonString,
+}
+
+impl PanicKind {
+    /// Classify a panic message, as produced by [`panic_message`][crate::panic_message].
+    pub fn classify(message: &str) -> Self {
+        if message == "Box<dyn Any>" {
+            PanicKind::NonString
+        } else if message.starts_with("assertion failed")
+            || (message.starts_with("assertion `left ") && message.contains("` failed"))
+        {
+            PanicKind::Assertion
+        } else if message.starts_with("called `Option::unwrap()` on a `None` value")
+            || message.starts_with("called `Result::unwrap()` on an `Err` value")
+        {
+            PanicKind::Unwrap
+        } else if message.starts_with("attempt to ") && message.ends_with(" with overflow") {
+            PanicKind::Overflow
+        } else if message == "attempt to divide by zero"
+            || message == "attempt to calculate the remainder with a divisor of zero"
+        {
+            PanicKind::DivideByZero
+        } else if message.starts_with("index out of bounds")
+            || message.starts_with("range start index ")
+            || message.starts_with("range end index ")
+            || message.starts_with("slice index starts at ")
+            || message.starts_with("byte index ")
+            || message.starts_with("start byte index ")
+            || message.starts_with("end byte index ")
+            || message.starts_with("begin <= end ")
+        {
+            PanicKind::IndexOutOfBounds
+        } else {
+            PanicKind::Explicit
+        }
+    }
+
+    /// A stable `snake_case` name, for logs and metrics labels.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            PanicKind::Explicit => "explicit",
+            PanicKind::Assertion => "assertion",
+            PanicKind::Unwrap => "unwrap",
+            PanicKind::Overflow => "overflow",
+            PanicKind::DivideByZero => "divide_by_zero",
+            PanicKind::IndexOutOfBounds => "index_out_of_bounds",
+            PanicKind::NonString => "non_string",
+        }
+    }
+}
+
+impl fmt::Display for PanicKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::panic_message;
+    use std::panic::catch_unwind;
+
+    fn kind_of(f: impl FnOnce() + std::panic::UnwindSafe) -> PanicKind {
+        PanicKind::classify(panic_message(&catch_unwind(f).unwrap_err()))
+    }
+
+    #[test]
+    #[allow(clippy::unnecessary_literal_unwrap)]
+    fn std_messages() {
+        let zero = std::hint::black_box(0);
+        let max = std::hint::black_box(u8::MAX);
+        let v: Vec<u8> = Vec::new();
+
+        assert_eq!(PanicKind::Assertion, kind_of(|| assert!(zero == 1)));
+        assert_eq!(PanicKind::Assertion, kind_of(|| assert_eq!(zero, 1)));
+        assert_eq!(PanicKind::Assertion, kind_of(|| assert_ne!(zero, 0)));
+        assert_eq!(PanicKind::Unwrap, kind_of(|| None::<()>.unwrap()));
+        assert_eq!(PanicKind::Unwrap, kind_of(|| Err::<(), _>(()).unwrap()));
+        assert_eq!(
+            PanicKind::IndexOutOfBounds,
+            kind_of(|| {
+                std::hint::black_box(v[zero]);
+            })
+        );
+        assert_eq!(
+            PanicKind::IndexOutOfBounds,
+            kind_of(|| {
+                std::hint::black_box(&"gus"[..zero + 4]);
+            })
+        );
+        assert_eq!(
+            PanicKind::DivideByZero,
+            kind_of(|| {
+                std::hint::black_box(1 / zero);
+            })
+        );
+        assert_eq!(PanicKind::NonString, kind_of(|| std::panic::panic_any(1)));
+        assert_eq!(PanicKind::Explicit, kind_of(|| panic!("gus")));
+        if cfg!(debug_assertions) {
+            assert_eq!(
+                PanicKind::Overflow,
+                kind_of(|| {
+                    std::hint::black_box(max + 1);
+                })
+            );
+        }
+    }
+}
@@ -0,0 +1,77 @@
+//! The words reports are written with, for shipping them in the product's language.
+use std::{
+    borrow::Cow,
+    sync::{Arc, RwLock},
+};
+
+static LABELS: RwLock<Option<Arc<Labels>>> = RwLock::new(None);
+
+/// The text human-readable reports are written with, like the
+/// [`Display`][std::fmt::Display] impl of [`PanicDetails`][crate::PanicDetails] and the
+/// reports of [`PanicExit`][crate::PanicExit]. Machine-readable formats, like
+/// [`PanicDetails::to_json`][crate::PanicDetails::to_json], don't use them.
+///
+/// Start from the English defaults and replace what needs translating.
+///
+/// ## Examples
+/// ```
+/// use panic_message::{set_labels, Labels, Location, PanicDetails};
+///
+/// let mut labels = Labels::default();
+/// labels.thread = "Thread".into();
+/// labels.panicked_at = "ist abgestürzt bei".into();
+/// set_labels(labels);
+///
+/// let details = PanicDetails::new("gus")
+///     .with_thread("main")
+///     .with_location(Location::new("src/main.rs", 1, 2));
+/// assert_eq!("Thread 'main' ist abgestürzt bei src/main.rs:1:2:\ngus", details.to_string());
+/// # set_labels(Labels::default());
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub struct Labels {
+    /// Shown instead of a message that couldn't be extracted, `"Box<dyn Any>"`.
+    pub fallback_message: Cow<'static, str>,
+    /// `"thread"`, as in `thread 'main' panicked`.
+    pub thread: Cow<'static, str>,
+    /// The name of a thread without one, `"<unnamed>"`.
+    pub unnamed_thread: Cow<'static, str>,
+    /// `"panicked"`, for panics without a location.
+    pub panicked: Cow<'static, str>,
+    /// `"panicked at"`, followed by the location.
+    pub panicked_at: Cow<'static, str>,
+    /// `"the program panicked"`, what [`PanicExit`][crate::PanicExit] reports before the
+    /// message.
+    pub program_panicked: Cow<'static, str>,
+    /// `"A crash report was written to"`, followed by its path, for crash dialogs.
+    pub report_file_prompt: Cow<'static, str>,
+}
+
+impl Default for Labels {
+    fn default() -> Self {
+        Labels {
+            fallback_message: "Box<dyn Any>".into(),
+            thread: "thread".into(),
+            unnamed_thread: "<unnamed>".into(),
+            panicked: "panicked".into(),
+            panicked_at: "panicked at".into(),
+            program_panicked: "the program panicked".into(),
+            report_file_prompt: "A crash report was written to".into(),
+        }
+    }
+}
+
+/// Replace the labels reports are written with, for the whole process.
+pub fn set_labels(labels: Labels) {
+    *LABELS.write().unwrap_or_else(|e| e.into_inner()) = Some(Arc::new(labels));
+}
+
+/// The labels reports are currently written with.
+pub fn labels() -> Arc<Labels> {
+    let labels = LABELS.read().unwrap_or_else(|e| e.into_inner());
+    match &*labels {
+        Some(labels) => Arc::clone(labels),
+        None => Arc::new(Labels::default()),
+    }
+}
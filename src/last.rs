@@ -0,0 +1,64 @@
+//! The last panic each thread reported, for code that caught it and wants the whole report.
+use std::cell::RefCell;
+
+use crate::PanicDetails;
+
+thread_local! {
+    static LAST: RefCell<Option<PanicDetails>> = const { RefCell::new(None) };
+}
+
+/// Keep `report` as the current thread's [last panic][last_panic], replacing the one kept
+/// before. The hook [`PanicHookBuilder::with_last_panic`][crate::PanicHookBuilder::with_last_panic]
+/// installs calls this for every panic; other hooks can call it themselves.
+pub fn set_last_panic(report: PanicDetails) {
+    LAST.with(|last| *last.borrow_mut() = Some(report));
+}
+
+/// The report of the last panic on the current thread, if a hook
+/// [kept one][set_last_panic], including the location and backtrace that a
+/// [`catch_unwind`][std::panic::catch_unwind] payload lacks.
+///
+/// The report stays until the thread's next panic replaces it or
+/// [`take_last_panic`] takes it.
+///
+/// ## Examples
+/// ```
+/// use std::panic::catch_unwind;
+/// use panic_message::{last_panic, take_last_panic, PanicHookBuilder};
+///
+/// PanicHookBuilder::new().with_last_panic().install();
+///
+/// let line = line!() + 1;
+/// let _ = catch_unwind(|| panic!("gus"));
+/// # let _ = std::panic::take_hook();
+///
+/// let report = last_panic().unwrap();
+/// assert_eq!("gus", report.message());
+/// assert_eq!(line, report.location().unwrap().line());
+///
+/// assert!(take_last_panic().is_some());
+/// assert!(last_panic().is_none());
+/// ```
+pub fn last_panic() -> Option<PanicDetails> {
+    LAST.with(|last| last.borrow().clone())
+}
+
+/// Take the report of the last panic on the current thread, see [`last_panic`].
+pub fn take_last_panic() -> Option<PanicDetails> {
+    LAST.with(|last| last.borrow_mut().take())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn per_thread() {
+        set_last_panic(PanicDetails::new("gus"));
+
+        let other = std::thread::spawn(last_panic).join().unwrap();
+
+        assert_eq!(None, other);
+        assert_eq!(Some(PanicDetails::new("gus")), take_last_panic());
+    }
+}
@@ -8,6 +8,12 @@
 //! doing its best attempt to unpack a `&str` message from the payload, defaulting to the
 //! literal `"Box<dyn Any>"` in an attempt to recreate what rustc does.
 //!
+//! Besides the `&'static str` and `String` payloads `panic!` produces, it unpacks the
+//! `Box<str>`, `Arc<str>`, `Arc<String>` and `Cow<'static, str>` payloads `panic_any` is
+//! sometimes called with. Where messages are owned, a `Box<dyn Error + Send + Sync>` or
+//! `Box<dyn Error + Send>` payload renders as the error followed by its sources, see
+//! [`set_error_chain_separator`].
+//!
 //! ## Examples
 //! ```
 //! use std::panic::catch_unwind;
@@ -58,14 +64,105 @@
 //! assert_eq!(None, msg);
 //! ```
 //!
+//! # `panic_message_cow`
+//!
+//! [`panic_message_cow`][crate::panic_message_cow] and
+//! [`get_panic_message_cow`][crate::get_panic_message_cow] return a `Cow<'static, str>`
+//! instead, which can be stored after the payload is dropped. Literal messages are borrowed,
+//! so only formatted ones are copied.
+//!
+//! ## Examples
+//! ```
+//! use std::{borrow::Cow, panic::catch_unwind};
+//!
+//! let payload = catch_unwind(|| {
+//!     panic!("gus");
+//! }).unwrap_err();
+//!
+//! let msg = panic_message::panic_message_cow(&payload);
+//! drop(payload);
+//! assert_eq!(Cow::Borrowed("gus"), msg);
+//! ```
+//!
+//! # Methods
+//!
+//! [`PanicPayloadExt`][crate::PanicPayloadExt] offers the same as methods on the payload
+//! ([`PanicResultExt`][crate::PanicResultExt] on the `Result` around it, and
+//! [`JoinHandleExt`][crate::JoinHandleExt] on the thread handle it comes from),
+//! and turns it into a [`CaughtPanic`][crate::CaughtPanic], an owned payload that displays
+//! as its message and can be used as an error. That in turn converts into a `Send + Sync`
+//! [`PanicError`][crate::PanicError] for sharing across threads. `main` can return a
+//! [`PanicExit`][crate::PanicExit] to report a caught panic and exit with a failure code.
+//! [`catch_unwind_message`][crate::catch_unwind_message] catches a panic and turns it into
+//! its message in one step, and [`catch_unwind_report`][crate::catch_unwind_report] into a
+//! report with the location and backtrace too. A hook that
+//! [keeps the last panic][crate::PanicHookBuilder::with_last_panic] makes the report of a
+//! panic caught some other way available from [`last_panic`][crate::last_panic].
+//!
+//! The [`prelude`][crate::prelude] imports the extension traits, macros and `CaughtPanic`
+//! at once.
+//!
+//! # Async tasks
+//!
+//! Executors hand back the payload of a task that panicked, and the same methods apply to
+//! it. With the `tokio` feature, [`JoinErrorExt`][crate::JoinErrorExt] takes the message of
+//! a tokio task's panic out of its `JoinError`, or wraps it in a
+//! [`CaughtPanic`][crate::CaughtPanic], leaving cancelled tasks as errors.
+//!
+//! # Payload shapes
+//!
+//! Newer apis that take a payload accept any [`AsPanicPayload`][crate::AsPanicPayload]:
+//! a `&Box<dyn Any + Send>`, a `&(dyn Any + Send)`, or the `Box<dyn Any + Send>` itself.
+//! [`payload_kind`][crate::payload_kind] tells what a payload is, and
+//! [`try_downcast_payload`][crate::try_downcast_payload] downcasts it without the risk of
+//! downcasting the box instead. [`into_panic_message`][crate::into_panic_message] takes the
+//! message out of a payload, to keep it after the payload is gone, without copying a
+//! `String`.
+//!
+//! Payload types that aren't strings can be given a message with
+//! [`register_panic_formatter`][crate::register_panic_formatter], which
+//! [`PanicDetails`][crate::PanicDetails] then uses, or with
+//! [`register_extractor`][crate::register_extractor], whose messages can be borrowed from
+//! the payload, so `panic_message` and the other `&str` functions return them too;
+//! [`set_unknown_payload_policy`][crate::set_unknown_payload_policy] can make payloads
+//! without either a warning, an abort or a callback. Types registered with
+//! [`register_payload_debug`][crate::register_payload_debug] can be inspected with
+//! [`payload_debug`][crate::payload_debug]. Payload types can also implement
+//! [`PanicMessageProvider`][crate::PanicMessageProvider], or derive it with the `macros`
+//! feature. [`panic_any_display`][crate::panic_any_display] panics with any `Display` value,
+//! whose rendering is then the message, and [`panic_any_debug!`][crate::panic_any_debug] with
+//! any `Debug` one. [`submit_extractor!`][crate::submit_extractor] registers a payload type when the
+//! program starts, so a library's payloads have messages without the binary registering
+//! them.
+//!
+//! With the `anyhow` feature, an [`anyhow::Error`](https://docs.rs/anyhow/1/anyhow/struct.Error.html)
+//! panicked with directly is rendered like a boxed error, with its context chain, as `{:#}`
+//! does:
+//! ```
+//! # #[cfg(feature = "anyhow")] {
+//! use std::panic::{catch_unwind, panic_any};
+//! use panic_message::PanicDetails;
+//!
+//! let payload = catch_unwind(|| {
+//!     panic_any(anyhow::anyhow!("no such file").context("reading the config failed"))
+//! })
+//! .unwrap_err();
+//!
+//! assert_eq!(
+//!     "reading the config failed: no such file",
+//!     PanicDetails::from_payload(&payload).message()
+//! );
+//! # }
+//! ```
+//!
 //! # `PanicInfo`
 //!
 //! This library also offers apis for getting messages from [`PanicInfo`][std::panic::PanicInfo`]'s
 //! as returned by [`std::panic::set_hook`]:
 //! - [`panic_info_message`][crate::panic_info_message] is similar
-//! to [`panic_message`][crate::panic_message] and has a default string `"Box<dyn Any>"`
+//!   to [`panic_message`][crate::panic_message] and has a default string `"Box<dyn Any>"`
 //! - [`get_panic_info_message`][crate::get_panic_info_message] is similar
-//! to [`get_panic_message`][crate::get_panic_message] and returns an `Option<&str>`
+//!   to [`get_panic_message`][crate::get_panic_message] and returns an `Option<&str>`
 //!
 //! ## Example
 //!
@@ -76,21 +173,442 @@
 //! }));
 //! ```
 //!
+//! The same functions are available as methods through
+//! [`PanicInfoExt`][crate::PanicInfoExt].
+//!
+//! Libraries that take panics from either a hook or `catch_unwind` can accept a
+//! [`PanicEvent`][crate::PanicEvent], which is created from a `PanicInfo` or a payload and
+//! has the same accessors for both.
+//!
+//! # `PanicDetails`
+//!
+//! [`PanicDetails`][crate::PanicDetails] is an owned snapshot of a panic (message, location,
+//! thread and backtrace) that can outlive the payload or `PanicInfo` it was collected from.
+//! It can also carry the process's [`ResourceUsage`][crate::ResourceUsage] (memory, file
+//! descriptors and CPU time), which helps with panics caused by running out of either.
+//! Details collected in a panic hook also record the process's uptime and the time since
+//! the previous panic, to tell crash loops and warm-up failures apart.
+//!
+//! Backtraces are only symbolized when a report renders them. Stripped binaries can record
+//! [raw frames][crate::capture_raw_frames] instead, which
+//! [`resolve_report`][crate::resolve_report] symbolizes later, on a host with the symbol
+//! files.
+//!
+//! Other data, like the thread's [breadcrumbs][crate::breadcrumb] or anything an
+//! application wants to add, goes in named [sections][crate::Section], which every format
+//! renders without knowing what they hold. Applications can
+//! [register providers][crate::register_section_provider] that add sections to every report.
+//! A [`Report`][crate::Report] lays out all of a
+//! panic's data as sections, to render it as text or JSON.
+//!
+//! [`PanicDetails::to_json`][crate::PanicDetails::to_json] renders a report as a JSON
+//! object, which [`PanicDetails::from_json`][crate::PanicDetails::from_json] reads back, so
+//! reports can be shipped to log pipelines and crash services and decoded on the other side.
+//!
+//! [`PanicDetails::with_size_budget`][crate::PanicDetails::with_size_budget] shortens a
+//! report to fit an intake's size limit, sharing the budget between its parts and marking
+//! what was cut.
+//!
+//! [`PanicDetails::replay`][crate::PanicDetails::replay] resumes the panic that details
+//! describe, to carry a panic from a worker thread to the caller waiting on it without
+//! losing its message.
+//!
+//! With the `eyre` feature, `?` turns details into an `eyre::Report`, whose error is a
+//! [`PanicError`][crate::PanicError] wrapped in the line naming the thread and location.
+//!
+//! The words human-readable reports are written with can be replaced with
+//! [`set_labels`][crate::set_labels], to ship them in the product's language.
+//!
+//! # Formats
+//!
+//! The [`format`][crate::format] module renders `PanicDetails` for specific log pipelines,
+//! like the [Elastic Common Schema][crate::format::Ecs], CloudWatch's
+//! [Embedded Metric Format][crate::format::CloudWatchEmf] or [Datadog][crate::format::Datadog],
+//! or as the crate's own [JSON object][crate::format::Json].
+//!
+//! # Hooks and sinks
+//!
+//! [`PanicHookBuilder`][crate::PanicHookBuilder] installs a panic hook that sends the
+//! `PanicDetails` of every panic to the [sinks][crate::sink] it was given. Slow sinks can be
+//! moved off the panicking thread with [`sink::Background`][crate::sink::Background].
+//! [`sink::Stream`][crate::sink::Stream] prints reports to stderr, or to any file or file
+//! descriptor. With an [`ExitPolicy`][crate::ExitPolicy], the hook then exits the process
+//! with an exit code chosen by the kind of panic.
+//!
+//! On `wasm32-unknown-unknown`, which has no stderr, the `wasm` feature's
+//! `sink::install_console_hook` writes reports to the browser console with `console.error`
+//! instead. Reports there have no time or uptime, because the target has no clock.
+//!
+//! Before reporting, the hook runs the callbacks registered with
+//! [`register_cleanup`][crate::register_cleanup], so lock files, leases and hardware state
+//! can be released before the panic unwinds or aborts. Callbacks that don't finish before a
+//! deadline are abandoned and counted in the report.
+//!
+//! # Structured panics
+//!
+//! [`panic_with_context!`][crate::panic_with_context] panics with a message and key-value
+//! fields, which `PanicDetails` and the report formats keep alongside the message.
+//! [`get_structured_panic`][crate::get_structured_panic] recovers them from the payload, and
+//! [`ensure_or_panic!`][crate::ensure_or_panic] checks invariants with it.
+//!
+//! # Breadcrumbs
+//!
+//! [`breadcrumb`][crate::breadcrumb] records a short note on the current thread's trail of
+//! recent events, which crash reports can include to show what led up to a crash.
+//!
+//! # Crash loops
+//!
+//! [`CrashWatchdog`][crate::CrashWatchdog] keeps a marker file that counts how many runs in
+//! a row crashed before shutting down cleanly, so an application can start in a safe mode
+//! after repeated crashes. Within a run, [`panic_stats`][crate::panic_stats] counts the
+//! panics the hook reported, in total and for each thread name, for supervisors that
+//! restart workers after panics.
+//!
+//! # Features
+//!
+//! - `std` (default): everything but the functions below. Without it the crate is
+//!   `no_std`, for embedded and kernel code that reuses its message logic:
+//!   [`get_payload_message`], [`payload_message`] and [`write_panic_message`] take a
+//!   borrowed `dyn Any + Send` payload, for example from a custom unwinding system, and
+//!   [`get_panic_handler_message`] the `core::panic::PanicInfo` of a `#[panic_handler]`,
+//!   which [`write_panic_info`] writes to a `fmt::Write` sink without allocating, and
+//!   [`display_panic_info`] displays, for example for defmt's `Display2Format`.
+//! - `alloc` (enabled by `std`): [`panic_message`], [`panic_message_cow`] and the other
+//!   functions taking a boxed payload, [`ShowPanic`], and the messages of `String`, `Box<str>`, `Arc<str>`,
+//!   `Arc<String>` and `Cow<'static, str>` payloads, without `std`. The other features,
+//!   except `defmt` and `persist`, enable `std`.
+//! - `android` (Android only): [`sink::Logcat`][crate::sink::Logcat], a sink that writes
+//!   reports to logcat, with a configurable tag. Links against the NDK's `liblog`.
+//! - `anyhow`: messages for `anyhow::Error` payloads, with their context chain.
+//! - `cbor`: [CBOR](https://www.rfc-editor.org/rfc/rfc8949) encoding and decoding of
+//!   `PanicDetails`, see [`PanicDetails::to_cbor`], and the [`sink::Pipe`] sink, which
+//!   sends a subprocess's panics to its parent.
+//! - `defmt`: `defmt::Format` for [`PanicInfoDisplay`], and
+//!   [`defmt_panic_info`][crate::defmt_panic_info], which logs a `#[panic_handler]`'s panic
+//!   with `defmt::error!`.
+//! - `eyre`: `From<PanicDetails>` for `eyre::Report`, and [`eyre_report`][crate::eyre_report]
+//!   for payloads.
+//! - `ffi`: the [`ffi`][crate::ffi] module, which catches panics at `extern "C"` boundaries
+//!   and exports C functions for reading their messages, declared in
+//!   `include/panic_message.h`.
+//! - `journald` (Linux only): [`sink::Journald`][crate::sink::Journald], a sink that sends
+//!   reports to the systemd journal with structured fields, like `CODE_FILE`, `CODE_LINE`
+//!   and `THREAD_NAME`.
+//! - `kafka`: [`sink::Kafka`][crate::sink::Kafka], a sink that publishes reports to a Kafka
+//!   topic with rdkafka, meant to run in a [`sink::Background`][crate::sink::Background].
+//!   Builds librdkafka from source.
+//! - `log`: [`sink::Log`][crate::sink::Log], a sink that emits reports as `log` records,
+//!   and [`sink::install_log_hook`][crate::sink::install_log_hook], which installs a hook
+//!   with it.
+//! - `macros`: `#[derive(PanicMessage)]`, which implements
+//!   [`PanicMessageProvider`][crate::PanicMessageProvider] from a field marked
+//!   `#[panic_message]` or from the type's `Display` impl, and registers it at startup.
+//!   `#[catch_panic]` makes a function return its panics as
+//!   [`CaughtPanic`][crate::CaughtPanic]s.
+//! - `metrics`: the hook [`PanicHookBuilder`][crate::PanicHookBuilder] installs increments
+//!   a `panics_total` counter through the [`metrics`](https://docs.rs/metrics) facade, with
+//!   `thread` and `fingerprint` labels, the thread's name and the panic's
+//!   [fingerprint][crate::PanicDetails::fingerprint] in hex.
+//! - `miette`: `miette::Diagnostic` for [`PanicError`][crate::PanicError], which labels the
+//!   location in its source file.
+//! - `nightly` (nightly compilers only): [`CaughtPanic`][crate::CaughtPanic] provides its
+//!   backtrace through [`Error::provide`][std::error::Error::provide], for error reporters
+//!   that look for backtraces there.
+//! - `redis`: [`sink::RedisStream`][crate::sink::RedisStream], a sink that adds reports to a
+//!   Redis stream.
+//! - `schemars`: [`JsonSchema`](https://docs.rs/schemars/1/schemars/trait.JsonSchema.html)
+//!   for the types `serde` serializes, describing what [`PanicDetails::to_json`] writes, so
+//!   `schemars::schema_for!(PanicDetails)` generates the JSON Schema of reports. Enables
+//!   `serde`.
+//! - `serde`: `Serialize` and `Deserialize` for `PanicDetails`, [`Location`],
+//!   [`ResourceUsage`], [`RawFrame`] and [`SectionContent`], in the shape
+//!   [`PanicDetails::to_json`] writes.
+//! - `signals` (unix and Windows): the [`signals`][crate::signals] module, which prints the
+//!   last panic and the thread's breadcrumbs when the process aborts or crashes. On Windows
+//!   they are written to a report file by an unhandled-exception filter.
+//! - `sqlite`: [`sink::SqliteSink`][crate::sink::SqliteSink], a sink that keeps a queryable
+//!   crash history in a local SQLite database, built in with `rusqlite`.
+//! - `tokio`: [`JoinErrorExt`][crate::JoinErrorExt] and
+//!   [`join_error_message`][crate::join_error_message], for the `JoinError` of a tokio task
+//!   that panicked.
+//! - `tracing`: [`sink::Tracing`][crate::sink::Tracing], a sink that emits reports as
+//!   `tracing` events with structured fields, in the span the panic happened in, and
+//!   [`sink::install_tracing_hook`][crate::sink::install_tracing_hook], which installs a
+//!   hook with it.
+//! - `wasm` (`wasm32` only): `sink::Console`, a sink that writes reports with
+//!   `console.error`, and `sink::install_console_hook`, which installs a hook with it.
+//! - `persist`: the [`persist`][crate::persist] module, which keeps the last panic in RAM
+//!   across a reset, for post-mortem debugging on embedded devices. It doesn't need `std`.
+//! - `prost`: the [`proto`][crate::proto] module, with [prost](https://docs.rs/prost)
+//!   messages for the schema in `proto/panic_details.proto` and conversions from and to
+//!   `PanicDetails`.
+//!
 //! # Note
 //!
-//! This library has methods that take values that are returned by standard mechanisms to obtain
-//! panic payloads, as opposed to a single generic method that takes `&dyn Any`.
-//! This is to prevent misuse.
-//! For example, the reason to take `PanicInfo` and not the `&dyn Any` as returned by
-//! [`PanicInfo::payload`][std::panic::PanicInfo::payload] is because `Box<dyn Any>`
-//! can be coerced into `&dyn Any`, which would make a method that takes `&dyn Any` possible
-//! to misuse with a payload from [`std::panic::catch_unwind`].
+//! The methods that take a payload are generic over [`AsPanicPayload`], which is only
+//! implemented for the shapes the standard mechanisms return payloads in, as opposed to taking
+//! `&dyn Any` directly. This is to prevent misuse.
+//! A `&Box<dyn Any + Send>` from [`std::panic::catch_unwind`] can be coerced into
+//! `&dyn Any`, so a method that took `&dyn Any` would see the box instead of the payload in
+//! it, and find no message. Through [`AsPanicPayload`], the box is always looked through,
+//! whether the payload is passed as the box or as the `&(dyn Any + Send)` returned by
+//! [`PanicInfo::payload`][std::panic::PanicInfo::payload].
 //!
-use std::{any::Any, panic::PanicInfo};
+#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(feature = "nightly", feature(error_generic_member_access))]
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+use alloc::{borrow::Cow, boxed::Box};
+use core::{
+    any::Any,
+    fmt::{self, Write},
+};
+#[cfg(feature = "std")]
+use std::panic::PanicHookInfo as PanicInfo;
+
+#[cfg(feature = "std")]
+mod breadcrumbs;
+#[cfg(feature = "std")]
+mod budget;
+#[cfg(feature = "std")]
+mod catch;
+#[cfg(feature = "std")]
+mod caught;
+#[cfg(feature = "cbor")]
+mod cbor;
+#[cfg(feature = "std")]
+mod cleanup;
+#[cfg(feature = "std")]
+mod context;
+#[cfg(feature = "std")]
+mod dedup;
+#[cfg(feature = "std")]
+mod details;
+#[cfg(feature = "std")]
+mod env_config;
+#[cfg(feature = "std")]
+mod error;
+#[cfg(feature = "std")]
+mod error_chain;
+#[cfg(feature = "std")]
+mod event;
+#[cfg(feature = "std")]
+mod exit;
+#[cfg(feature = "std")]
+mod ext;
+#[cfg(feature = "eyre")]
+mod eyre_impl;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "std")]
+mod filter;
+#[cfg(feature = "std")]
+mod fingerprint;
+#[cfg(feature = "std")]
+pub mod format;
+mod handler;
+#[cfg(feature = "std")]
+mod hook;
+#[cfg(feature = "std")]
+mod isolate;
+#[cfg(feature = "std")]
+mod json;
+#[cfg(feature = "std")]
+mod kind;
+#[cfg(feature = "std")]
+mod labels;
+#[cfg(feature = "std")]
+mod last;
+#[cfg(feature = "std")]
+mod metadata;
+#[cfg(feature = "miette")]
+mod miette_impl;
+mod payload;
+#[cfg(feature = "persist")]
+pub mod persist;
+#[cfg(feature = "std")]
+pub mod prelude;
+#[cfg(feature = "prost")]
+pub mod proto;
+#[cfg(feature = "std")]
+mod provider;
+#[cfg(feature = "std")]
+mod registry;
+#[cfg(feature = "std")]
+mod replay;
+#[cfg(feature = "std")]
+mod report;
+#[cfg(feature = "std")]
+mod reporter;
+#[cfg(feature = "std")]
+mod resources;
+#[cfg(feature = "std")]
+mod rustc_style;
+#[cfg(feature = "serde")]
+mod serde_impl;
+#[cfg(all(any(unix, windows), feature = "signals"))]
+mod signal_safe;
+#[cfg(all(any(unix, windows), feature = "signals"))]
+pub mod signals;
+#[cfg(feature = "std")]
+pub mod sink;
+#[cfg(feature = "std")]
+mod snippet;
+#[cfg(feature = "std")]
+mod stats;
+#[cfg(feature = "std")]
+mod structured;
+#[cfg(feature = "std")]
+mod symbols;
+#[cfg(feature = "std")]
+mod termination;
+#[cfg(feature = "std")]
+mod time;
+#[cfg(feature = "std")]
+mod trace;
+#[cfg(feature = "std")]
+mod uptime;
+#[cfg(feature = "std")]
+mod watchdog;
+#[cfg(feature = "std")]
+mod wrap;
+
+#[cfg(feature = "std")]
+pub use breadcrumbs::{breadcrumb, breadcrumbs, clear_breadcrumbs};
+#[cfg(feature = "std")]
+pub use catch::{catch_unwind_message, catch_unwind_report, catch_unwind_report_with};
+#[cfg(feature = "std")]
+pub use caught::CaughtPanic;
+#[cfg(feature = "cbor")]
+pub use cbor::CborError;
+#[cfg(feature = "std")]
+pub use cleanup::{register_cleanup, run_cleanups, run_cleanups_within, CleanupHandle};
+#[cfg(feature = "std")]
+pub use context::{current_thread_context, thread_context, ContextGuard};
+#[cfg(feature = "std")]
+pub use details::{Location, PanicDetails};
+#[cfg(feature = "std")]
+pub use error::PanicError;
+#[cfg(feature = "std")]
+pub use error_chain::set_error_chain_separator;
+#[cfg(feature = "std")]
+pub use event::PanicEvent;
+#[cfg(feature = "std")]
+pub use exit::ExitPolicy;
+#[cfg(feature = "tokio")]
+pub use ext::{join_error_message, JoinErrorExt};
+#[cfg(feature = "std")]
+pub use ext::{JoinHandleExt, PanicInfoExt, PanicPayloadExt, PanicResultExt};
+#[cfg(feature = "eyre")]
+pub use eyre_impl::eyre_report;
+#[cfg(feature = "defmt")]
+pub use handler::defmt_panic_info;
+pub use handler::{
+    display_panic_info, get_panic_handler_message, write_panic_info, PanicInfoDisplay,
+};
+#[cfg(feature = "std")]
+pub use hook::{PanicHook, PanicHookBuilder};
+#[cfg(feature = "std")]
+pub use json::JsonError;
+#[cfg(feature = "std")]
+pub use kind::PanicKind;
+#[cfg(feature = "std")]
+pub use labels::{labels, set_labels, Labels};
+#[cfg(feature = "std")]
+pub use last::{last_panic, set_last_panic, take_last_panic};
+#[cfg(feature = "std")]
+pub use metadata::CrashMetadata;
+/// Catch panics in the function, for FFI and plugin entry points that must never unwind
+/// into their caller.
+///
+/// A function returning `T` is rewritten to return `Result<T, CaughtPanic>`. A function
+/// that already returns a `Result` keeps its signature, and a panic becomes its error
+/// through `From<CaughtPanic>`. The body is treated as [unwind safe][std::panic::AssertUnwindSafe],
+/// like at any boundary that reports the panic instead of resuming it.
+///
+/// ## Examples
+/// ```
+/// use panic_message::catch_panic;
+///
+/// #[catch_panic]
+/// fn parse(input: &str) -> u32 {
+///     input.parse().unwrap()
+/// }
+///
+/// assert_eq!(3, parse("3").unwrap());
+/// assert!(parse("gus").unwrap_err().message().contains("InvalidDigit"));
+/// ```
+#[cfg(feature = "macros")]
+pub use panic_message_macros::catch_panic;
+/// Derive [`PanicMessageProvider`], from the field marked `#[panic_message]` or, without
+/// one, from the type's `Display` impl. The field can be any `AsRef<str>`. The type is
+/// registered when the program starts, see [`submit_extractor!`].
+///
+/// ## Examples
+/// ```
+/// use panic_message::{PanicMessage, PanicMessageProvider};
+///
+/// #[derive(PanicMessage)]
+/// struct Fatal {
+///     code: u32,
+///     #[panic_message]
+///     reason: String,
+/// }
+///
+/// let fatal = Fatal { code: 3, reason: "gus".to_string() };
+/// assert_eq!("gus", fatal.panic_message());
+/// ```
+///
+/// Panic payloads are `'static`, and generic ones aren't supported:
+/// ```compile_fail
+/// #[derive(panic_message::PanicMessage)]
+/// struct Fatal<T>(#[panic_message] T);
+/// ```
+#[cfg(feature = "macros")]
+pub use panic_message_macros::PanicMessage;
+#[cfg(feature = "std")]
+pub use payload::{into_panic_message, payload_kind, PayloadKind};
+pub use payload::{try_downcast_payload, AsPanicPayload};
+#[cfg(feature = "alloc")]
+pub use payload::{try_downcast_payload_owned, try_into_string};
+#[cfg(feature = "std")]
+pub use provider::{register_provider, PanicMessageProvider};
+#[cfg(feature = "std")]
+pub use registry::{
+    payload_debug, register_extractor, register_panic_formatter, register_payload_debug,
+    set_unknown_payload_policy, UnknownPayloadPolicy,
+};
+#[cfg(feature = "std")]
+pub use replay::ReplayedPanic;
+#[cfg(feature = "std")]
+pub use report::{register_section_provider, Report, Section, SectionContent};
+#[cfg(feature = "std")]
+pub use reporter::{register_reporter, Reporter, ReporterHandle};
+#[cfg(feature = "std")]
+pub use resources::ResourceUsage;
+#[cfg(feature = "std")]
+pub use rustc_style::RustcStyle;
+#[cfg(feature = "std")]
+pub use stats::{panic_stats, PanicStats};
+#[cfg(feature = "std")]
+pub use structured::{get_structured_panic, StructuredPanic};
+#[cfg(feature = "std")]
+pub use symbols::{capture_raw_frames, resolve_report, RawFrame, SymbolFiles};
+#[cfg(feature = "std")]
+pub use termination::PanicExit;
+#[cfg(feature = "std")]
+pub use trace::{BacktraceFilter, BacktraceMode};
+#[cfg(feature = "std")]
+pub use watchdog::CrashWatchdog;
+#[cfg(feature = "std")]
+pub use wrap::{panic_any_display, DebugPanic, DisplayPanic};
 
 /// Attempt to produce a `&str` message (with a default)
 /// from a [`std::panic::catch_unwind`] payload.
 /// See [module docs][crate] for usage.
+#[cfg(feature = "alloc")]
 pub fn panic_message(payload: &Box<dyn Any + Send>) -> &str {
     imp::get_panic_message(payload.as_ref()).unwrap_or({
         // Copy what rustc does in the default panic handler
@@ -101,13 +619,128 @@ pub fn panic_message(payload: &Box<dyn Any + Send>) -> &str {
 /// Attempt to produce a `&str` message
 /// from a [`std::panic::catch_unwind`] payload.
 /// See [module docs][crate] for usage.
+#[cfg(feature = "alloc")]
 pub fn get_panic_message(payload: &Box<dyn Any + Send>) -> Option<&str> {
     imp::get_panic_message(payload.as_ref())
 }
 
+/// Like [`panic_message`], but the message can outlive the payload: a `&'static str`
+/// message is borrowed and any other is copied.
+/// See [module docs][crate] for usage.
+#[cfg(feature = "alloc")]
+pub fn panic_message_cow(payload: &Box<dyn Any + Send>) -> Cow<'static, str> {
+    get_panic_message_cow(payload).unwrap_or(Cow::Borrowed("Box<dyn Any>"))
+}
+
+/// Like [`get_panic_message`], but the message can outlive the payload: a `&'static str`
+/// message is borrowed and any other is copied.
+/// See [module docs][crate] for usage.
+#[cfg(feature = "alloc")]
+pub fn get_panic_message_cow(payload: &Box<dyn Any + Send>) -> Option<Cow<'static, str>> {
+    use alloc::string::ToString;
+
+    match payload.downcast_ref::<&'static str>() {
+        Some(msg) => Some(Cow::Borrowed(*msg)),
+        None => match payload.downcast_ref::<Cow<'static, str>>() {
+            Some(msg) => Some(msg.clone()),
+            None => imp::get_known_message(&**payload)
+                .map(|msg| Cow::Owned(msg.to_string()))
+                .or_else(|| imp::rendered_message(&**payload).map(Cow::Owned)),
+        },
+    }
+}
+
+/// Attempt to produce a `&str` message (with a default) from a payload in any of the
+/// [shapes][AsPanicPayload] payloads are held in, including a borrowed `dyn Any + Send`.
+///
+/// Unlike [`panic_message`], it is available without the `alloc` feature, for payloads of
+/// custom unwinding systems in `no_std` programs, where the payload is a `&'static str`.
+///
+/// ## Examples
+/// ```
+/// use std::any::Any;
+///
+/// let payload: &(dyn Any + Send) = &"gus";
+/// assert_eq!("gus", panic_message::payload_message(payload));
+/// ```
+pub fn payload_message<P: AsPanicPayload + ?Sized>(payload: &P) -> &str {
+    get_payload_message(payload).unwrap_or({
+        // Copy what rustc does in the default panic handler
+        "Box<dyn Any>"
+    })
+}
+
+/// Like [`payload_message`], but returns `None` when it can't unpack a message from the
+/// payload.
+pub fn get_payload_message<P: AsPanicPayload + ?Sized>(payload: &P) -> Option<&str> {
+    imp::get_panic_message(payload.as_panic_payload())
+}
+
+/// Write the message of a payload in any of the [shapes][AsPanicPayload] payloads are held
+/// in, `"Box<dyn Any>"` if it has none, without allocating, for hooks that may run because
+/// an allocation failed.
+///
+/// Unlike [`payload_message`], it also writes the message of a payload that has to be
+/// rendered, like a [boxed error][set_error_chain_separator]'s, straight into `out`. Only a
+/// [registered extractor][register_extractor] that returns an owned message allocates.
+///
+/// ## Examples
+/// ```
+/// use std::panic::catch_unwind;
+///
+/// let payload = catch_unwind(|| panic!("gus {}", 1)).unwrap_err();
+///
+/// let mut message = String::with_capacity(64);
+/// panic_message::write_panic_message(&mut message, &payload).unwrap();
+/// assert_eq!("gus 1", message);
+/// ```
+pub fn write_panic_message<P: AsPanicPayload + ?Sized>(
+    out: &mut impl Write,
+    payload: &P,
+) -> fmt::Result {
+    imp::write_message(out, payload.as_panic_payload())
+}
+
+/// A payload displayed as its message, like [`panic_message_cow`] returns it, for putting a
+/// payload straight into `format!` or a log macro.
+///
+/// It is written like [`write_panic_message`] writes it, so displaying it doesn't allocate
+/// either.
+///
+/// ## Examples
+/// ```
+/// use std::panic::catch_unwind;
+/// use panic_message::ShowPanic;
+///
+/// let payload = catch_unwind(|| panic!("gus")).unwrap_err();
+///
+/// assert_eq!("worker failed: gus", format!("worker failed: {}", ShowPanic(&payload)));
+/// assert_eq!(r#"ShowPanic("gus")"#, format!("{:?}", ShowPanic(&payload)));
+/// ```
+#[cfg(feature = "alloc")]
+#[derive(Clone, Copy)]
+pub struct ShowPanic<'a>(pub &'a Box<dyn Any + Send>);
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for ShowPanic<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_panic_message(f, self.0)
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Debug for ShowPanic<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("ShowPanic")
+            .field(&panic_message_cow(self.0))
+            .finish()
+    }
+}
+
 /// Attempt to produce a `&str` message (with a default)
 /// from a [`std::panic::PanicInfo`].
 /// See [module docs][crate] for usage.
+#[cfg(feature = "std")]
 pub fn panic_info_message<'pi>(panic_info: &'pi PanicInfo<'_>) -> &'pi str {
     imp::get_panic_message(panic_info.payload()).unwrap_or({
         // Copy what rustc does in the default panic handler
@@ -118,23 +751,144 @@ pub fn panic_info_message<'pi>(panic_info: &'pi PanicInfo<'_>) -> &'pi str {
 /// Attempt to produce a `&str` message (with a default)
 /// from a [`std::panic::PanicInfo`].
 /// See [module docs][crate] for usage.
+#[cfg(feature = "std")]
 pub fn get_panic_info_message<'pi>(panic_info: &'pi PanicInfo<'_>) -> Option<&'pi str> {
     imp::get_panic_message(panic_info.payload())
 }
 
+/// Write the panic of a [`std::panic::PanicInfo`] as `panicked at src/main.rs:3:5: message`
+/// on one line without allocating, with its message written like [`write_panic_message`].
+///
+/// This is the panic hook's counterpart of [`write_panic_info`], which takes the
+/// `core::panic::PanicInfo` of a `#[panic_handler]`.
+///
+/// ## Examples
+/// ```
+/// use std::io::Write;
+///
+/// std::panic::set_hook(Box::new(|pi| {
+///     let mut line = String::with_capacity(256);
+///     if panic_message::write_panic_hook_info(&mut line, pi).is_ok() {
+///         let _ = writeln!(std::io::stderr(), "{}", line);
+///     }
+/// }));
+/// # let _ = std::panic::take_hook();
+/// ```
+#[cfg(feature = "std")]
+pub fn write_panic_hook_info(out: &mut impl Write, panic_info: &PanicInfo<'_>) -> fmt::Result {
+    handler::write_compact(
+        out,
+        panic_info.location(),
+        imp::Message(panic_info.payload()),
+    )
+}
+
 mod imp {
     use super::*;
+
+    /// A payload's message, displayed with [`write_message`].
+    #[cfg(feature = "std")]
+    pub(crate) struct Message<'a>(pub(crate) &'a (dyn Any + Send));
+
+    #[cfg(feature = "std")]
+    impl fmt::Display for Message<'_> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            write_message(f, self.0)
+        }
+    }
+
+    /// Write the payload's message, rendering it into `out` if it has to be rendered.
+    pub(crate) fn write_message(out: &mut impl Write, payload: &(dyn Any + Send)) -> fmt::Result {
+        if let Some(msg) = get_known_message(payload) {
+            return out.write_str(msg);
+        }
+        #[cfg(feature = "std")]
+        if let Some(msg) = crate::registry::extract(payload) {
+            return out.write_str(&msg);
+        }
+        #[cfg(feature = "std")]
+        if let Some(result) = crate::error_chain::write_error_message(out, payload) {
+            return result;
+        }
+        #[cfg(feature = "std")]
+        if let Some(msg) = crate::registry::format(payload) {
+            return out.write_str(&msg);
+        }
+        // Copy what rustc does in the default panic handler
+        out.write_str("Box<dyn Any>")
+    }
     /// Attempt to produce a message from a borrowed `dyn Any`. Note that care must be taken
     /// when calling this to avoid a `Box<dyn Any>` being coerced to a `dyn Any` itself.
-    pub(super) fn get_panic_message(payload: &(dyn Any + Send)) -> Option<&str> {
+    #[cfg(feature = "std")]
+    pub(crate) fn get_panic_message(payload: &(dyn Any + Send)) -> Option<&str> {
+        get_known_message(payload).or_else(|| match crate::registry::extract(payload)? {
+            Cow::Borrowed(msg) => Some(msg),
+            Cow::Owned(_) => None,
+        })
+    }
+
+    /// Without `std` there are no registered extractors.
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn get_panic_message(payload: &(dyn Any + Send)) -> Option<&str> {
+        get_known_message(payload)
+    }
+
+    /// The message of the payload types this crate knows, without the registered extractors.
+    pub(crate) fn get_known_message(payload: &(dyn Any + Send)) -> Option<&str> {
         // taken from: https://github.com/rust-lang/rust/blob/4b9f4b221b92193c7e95b1beb502c6eb32c3b613/library/std/src/panicking.rs#L194-L200
-        match payload.downcast_ref::<&'static str>() {
-            Some(msg) => Some(*msg),
-            None => match payload.downcast_ref::<String>() {
-                Some(msg) => Some(msg.as_str()),
-                // Copy what rustc does in the default panic handler
-                None => None,
-            },
+        if let Some(msg) = payload.downcast_ref::<&'static str>() {
+            return Some(*msg);
+        }
+        #[cfg(feature = "alloc")]
+        if let Some(msg) = payload.downcast_ref::<alloc::string::String>() {
+            return Some(msg.as_str());
+        }
+        #[cfg(feature = "std")]
+        if let Some(structured) = payload.downcast_ref::<crate::StructuredPanic>() {
+            return Some(structured.message.as_str());
+        }
+        #[cfg(feature = "std")]
+        if let Some(replayed) = payload.downcast_ref::<crate::ReplayedPanic>() {
+            return replayed.message();
+        }
+        #[cfg(feature = "alloc")]
+        if let Some(msg) = string_container(payload) {
+            return Some(msg);
+        }
+        // Copy what rustc does in the default panic handler
+        None
+    }
+
+    /// The message of a payload that has to be rendered: a registered extractor's owned
+    /// message, a boxed error's, or a registered formatter's.
+    #[cfg(feature = "std")]
+    pub(crate) fn rendered_message(payload: &(dyn Any + Send)) -> Option<String> {
+        crate::registry::extract(payload)
+            .map(Cow::into_owned)
+            .or_else(|| crate::error_chain::error_message(payload))
+            .or_else(|| crate::registry::format(payload))
+    }
+
+    #[cfg(all(feature = "alloc", not(feature = "std")))]
+    pub(crate) fn rendered_message(_: &(dyn Any + Send)) -> Option<alloc::string::String> {
+        None
+    }
+
+    /// The string in the other containers `panic_any` is commonly called with.
+    #[cfg(feature = "alloc")]
+    pub(crate) fn string_container(payload: &(dyn Any + Send)) -> Option<&str> {
+        use alloc::{string::String, sync::Arc};
+
+        if let Some(msg) = payload.downcast_ref::<Box<str>>() {
+            Some(msg)
+        } else if let Some(msg) = payload.downcast_ref::<Arc<str>>() {
+            Some(msg)
+        } else if let Some(msg) = payload.downcast_ref::<Arc<String>>() {
+            Some(msg)
+        } else if let Some(msg) = payload.downcast_ref::<Cow<'static, str>>() {
+            Some(msg)
+        } else {
+            None
         }
     }
 }
@@ -163,6 +917,11 @@ mod tests {
     }
 
     #[test]
+    #[allow(
+        clippy::unnecessary_literal_unwrap,
+        clippy::expect_fun_call,
+        clippy::useless_format
+    )]
     fn expect() {
         let payload = catch_unwind(|| {
             // Note this is a reference to a local string
@@ -176,6 +935,74 @@ mod tests {
         assert_eq!("gus", msg);
     }
 
+    #[test]
+    fn cow() {
+        let payload = catch_unwind(|| panic!("gus")).unwrap_err();
+        assert!(matches!(panic_message_cow(&payload), Cow::Borrowed("gus")));
+
+        let name = "gus";
+        let payload = catch_unwind(|| panic!("{}", name)).unwrap_err();
+        assert!(matches!(get_panic_message_cow(&payload), Some(Cow::Owned(msg)) if msg == "gus"));
+
+        let payload = catch_unwind(|| std::panic::panic_any(1)).unwrap_err();
+        assert_eq!(None, get_panic_message_cow(&payload));
+        assert_eq!("Box<dyn Any>", panic_message_cow(&payload));
+    }
+
+    #[test]
+    fn string_containers() {
+        use std::{panic::panic_any, sync::Arc};
+
+        let payloads: Vec<Box<dyn Any + Send>> = vec![
+            catch_unwind(|| panic_any(Box::<str>::from("gus"))).unwrap_err(),
+            catch_unwind(|| panic_any(Arc::<str>::from("gus"))).unwrap_err(),
+            catch_unwind(|| panic_any(Arc::new("gus".to_string()))).unwrap_err(),
+            catch_unwind(|| panic_any(Cow::<'static, str>::Owned("gus".to_string()))).unwrap_err(),
+        ];
+
+        for payload in &payloads {
+            assert_eq!("gus", panic_message(payload));
+            assert_eq!(Some("gus"), get_panic_message(payload));
+        }
+        let payload = catch_unwind(|| panic_any(Cow::Borrowed("gus"))).unwrap_err();
+        assert!(matches!(panic_message_cow(&payload), Cow::Borrowed("gus")));
+    }
+
+    #[test]
+    fn write_without_allocating() {
+        use std::{error::Error, io, panic::panic_any};
+
+        let payload = catch_unwind(|| panic_any(1)).unwrap_err();
+        let mut out = String::new();
+        write_panic_message(&mut out, &payload).unwrap();
+        assert_eq!("Box<dyn Any>", out);
+
+        let payload: &(dyn Any + Send) =
+            &(Box::new(io::Error::other("no such file")) as Box<dyn Error + Send + Sync>);
+        let mut out = String::new();
+        write_panic_message(&mut out, payload).unwrap();
+        assert_eq!("no such file", out);
+
+        let location = core::panic::Location::caller();
+        let mut out = String::new();
+        handler::write_compact(&mut out, Some(location), imp::Message(&"gus")).unwrap();
+        assert_eq!(format!("panicked at {}: gus", location), out);
+    }
+
+    #[test]
+    fn show_panic() {
+        let name = "gus";
+        let payload = catch_unwind(|| panic!("{}", name)).unwrap_err();
+        assert_eq!("gus", ShowPanic(&payload).to_string());
+
+        let payload = catch_unwind(|| std::panic::panic_any(1)).unwrap_err();
+        assert_eq!("Box<dyn Any>", ShowPanic(&payload).to_string());
+        assert_eq!(
+            r#"ShowPanic("Box<dyn Any>")"#,
+            format!("{:?}", ShowPanic(&payload))
+        );
+    }
+
     #[test]
     fn something_else() {
         let payload = catch_unwind(|| {
@@ -0,0 +1,97 @@
+//! What identifies the build that panicked, for reports collected from the field.
+use crate::{PanicDetails, Section};
+
+/// The application's name, version, build and other static facts, added to every report
+/// as a `metadata` [section][crate::Section], so JSON reports, crash files and the text
+/// layout all say which build they came from.
+///
+/// [`crash_metadata!`][crate::crash_metadata] fills in the calling package's name and
+/// version; give it to [`PanicHookBuilder::with_metadata`][crate::PanicHookBuilder::with_metadata]
+/// when installing the hook.
+///
+/// ## Examples
+/// ```
+/// use panic_message::{crash_metadata, PanicDetails, PanicHookBuilder};
+///
+/// let metadata = crash_metadata!()
+///     .with_git_sha(option_env!("GIT_SHA").unwrap_or("unknown"))
+///     .with_field("channel", "beta");
+///
+/// let details = PanicDetails::new("gus").with_metadata(&metadata);
+/// assert!(details.to_json().contains(r#""metadata":{"name":"panic-message","#));
+///
+/// PanicHookBuilder::new().with_metadata(metadata).install();
+/// # let _ = std::panic::take_hook();
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CrashMetadata {
+    fields: Vec<(String, String)>,
+}
+
+impl CrashMetadata {
+    /// Metadata naming the application and its version.
+    pub fn new(name: impl Into<String>, version: impl Into<String>) -> Self {
+        CrashMetadata {
+            fields: vec![
+                ("name".to_string(), name.into()),
+                ("version".to_string(), version.into()),
+            ],
+        }
+    }
+
+    /// Add the commit the application was built from, as `git_sha`.
+    pub fn with_git_sha(self, sha: impl Into<String>) -> Self {
+        self.with_field("git_sha", sha)
+    }
+
+    /// Add a key-value pair, like the build profile or release channel.
+    pub fn with_field(mut self, key: impl Into<String>, value: impl Into<String>) -> Self {
+        self.fields.push((key.into(), value.into()));
+        self
+    }
+
+    /// The key-value pairs, in order, starting with `name` and `version`.
+    pub fn fields(&self) -> &[(String, String)] {
+        &self.fields
+    }
+}
+
+impl PanicDetails {
+    /// Add `metadata` as a `metadata` [section][PanicDetails::sections].
+    pub fn with_metadata(self, metadata: &CrashMetadata) -> Self {
+        self.with_section(Section::fields("metadata", metadata.fields.clone()))
+    }
+}
+
+/// [`CrashMetadata`] with the name and version of the package this is called from, as
+/// `CARGO_PKG_NAME` and `CARGO_PKG_VERSION` were at build time.
+///
+/// ## Examples
+/// ```
+/// let metadata = panic_message::crash_metadata!();
+///
+/// assert_eq!(("version".to_string(), env!("CARGO_PKG_VERSION").to_string()), metadata.fields()[1]);
+/// ```
+#[macro_export]
+macro_rules! crash_metadata {
+    () => {
+        $crate::CrashMetadata::new(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn section() {
+        let metadata = CrashMetadata::new("tool", "1.2.0").with_git_sha("a1b2");
+
+        let details = PanicDetails::new("gus").with_metadata(&metadata);
+
+        assert_eq!(
+            "thread '<unnamed>' panicked:\ngus\nmetadata:\n  name: tool\n  version: 1.2.0\n  git_sha: a1b2",
+            details.to_string()
+        );
+    }
+}
@@ -0,0 +1,85 @@
+//! [miette](https://docs.rs/miette) support for [`PanicError`][crate::PanicError].
+use std::iter;
+
+use miette::{Diagnostic, LabeledSpan, NamedSource, SourceCode};
+
+use crate::PanicError;
+
+impl PanicError {
+    /// The contents of the location's file, if it can be read and has the location.
+    fn source_file(&self) -> Option<&NamedSource<String>> {
+        self.source
+            .get_or_init(|| {
+                let location = self.location.as_ref()?;
+                let text = std::fs::read_to_string(location.file()).ok()?;
+                location.byte_offset(&text)?;
+                Some(NamedSource::new(location.file(), text))
+            })
+            .as_ref()
+    }
+}
+
+/// The location is a labeled span in its file, which is read relative to the current
+/// directory, where `cargo run` and `cargo test` start the program. Without a location or
+/// a readable file the diagnostic is just the message.
+///
+/// ## Examples
+/// ```
+/// use std::panic::catch_unwind;
+/// use panic_message::PanicError;
+///
+/// fn run() -> miette::Result<()> {
+///     catch_unwind(|| panic!("gus")).map_err(PanicError::from)?;
+///     Ok(())
+/// }
+///
+/// assert_eq!("gus", run().unwrap_err().to_string());
+/// ```
+impl Diagnostic for PanicError {
+    fn source_code(&self) -> Option<&dyn SourceCode> {
+        self.source_file().map(|source| source as &dyn SourceCode)
+    }
+
+    fn labels(&self) -> Option<Box<dyn Iterator<Item = LabeledSpan> + '_>> {
+        let offset = self
+            .location
+            .as_ref()?
+            .byte_offset(self.source_file()?.inner())?;
+        Some(Box::new(iter::once(LabeledSpan::at_offset(
+            offset,
+            "panicked here",
+        ))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{CaughtPanic, Location};
+
+    #[test]
+    fn labels_the_location() {
+        let line = line!();
+        let error = CaughtPanic::new(Box::new("gus"))
+            .with_location(Location::new(file!(), line, 9))
+            .into_sendable();
+
+        let labels: Vec<_> = error.labels().unwrap().collect();
+        assert_eq!(1, labels.len());
+        let text = std::fs::read_to_string(file!()).unwrap();
+        assert!(text[labels[0].offset()..].starts_with("let line = line!();"));
+        let source = error.source_code().unwrap();
+        let span = source.read_span(labels[0].inner(), 0, 0).unwrap();
+        assert_eq!(Some(file!()), span.name());
+    }
+
+    #[test]
+    fn without_the_file() {
+        let error = CaughtPanic::new(Box::new("gus"))
+            .with_location(Location::new("src/missing.rs", 1, 1))
+            .into_sendable();
+
+        assert!(error.source_code().is_none());
+        assert!(error.labels().is_none());
+    }
+}
@@ -0,0 +1,362 @@
+//! The payload shapes the payload APIs accept.
+#[cfg(feature = "std")]
+use alloc::{borrow::Cow, sync::Arc};
+#[cfg(feature = "alloc")]
+use alloc::{boxed::Box, string::String};
+use core::any::Any;
+#[cfg(feature = "std")]
+use core::any::TypeId;
+
+mod sealed {
+    #[cfg(feature = "alloc")]
+    use alloc::boxed::Box;
+    use core::any::Any;
+
+    pub trait Sealed {}
+
+    #[cfg(feature = "alloc")]
+    impl Sealed for &Box<dyn Any + Send> {}
+    impl Sealed for &(dyn Any + Send) {}
+    #[cfg(feature = "alloc")]
+    impl Sealed for Box<dyn Any + Send> {}
+    impl Sealed for dyn Any + Send {}
+}
+
+/// A panic payload, as returned by [`std::panic::catch_unwind`] or
+/// [`std::thread::JoinHandle::join`], in one of the shapes callers usually have it in:
+/// `&Box<dyn Any + Send>`, `&(dyn Any + Send)` or `Box<dyn Any + Send>`.
+///
+/// It is also implemented for `dyn Any + Send` itself, so functions that borrow the payload,
+/// like [`try_downcast_payload`], can take `&P` for any of these shapes. Without the
+/// `alloc` feature, only the borrowed `dyn Any + Send` shapes are.
+///
+/// The trait is sealed. Accepting only these shapes, instead of any `&dyn Any`, means a
+/// `&Box<dyn Any + Send>` can't be coerced into a `&dyn Any` whose concrete type is the box
+/// itself, which would make every downcast fail.
+///
+/// ## Examples
+/// ```
+/// use std::panic::catch_unwind;
+/// use panic_message::PanicDetails;
+///
+/// let payload = catch_unwind(|| panic!("gus")).unwrap_err();
+///
+/// assert_eq!("gus", PanicDetails::from_payload(&payload).message());
+/// assert_eq!("gus", PanicDetails::from_payload(&*payload).message());
+/// assert_eq!("gus", PanicDetails::from_payload(payload).message());
+/// ```
+///
+/// Payloads that aren't `Send` are rejected, because no panic payload can be one:
+/// ```compile_fail
+/// let payload: &dyn std::any::Any = &"gus";
+/// panic_message::PanicDetails::from_payload(payload);
+/// ```
+pub trait AsPanicPayload: sealed::Sealed {
+    /// The payload itself, with the box (if any) looked through.
+    fn as_panic_payload(&self) -> &(dyn Any + Send);
+}
+
+#[cfg(feature = "alloc")]
+impl AsPanicPayload for &Box<dyn Any + Send> {
+    fn as_panic_payload(&self) -> &(dyn Any + Send) {
+        &***self
+    }
+}
+
+impl AsPanicPayload for &(dyn Any + Send) {
+    fn as_panic_payload(&self) -> &(dyn Any + Send) {
+        *self
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl AsPanicPayload for Box<dyn Any + Send> {
+    fn as_panic_payload(&self) -> &(dyn Any + Send) {
+        &**self
+    }
+}
+
+impl AsPanicPayload for dyn Any + Send {
+    fn as_panic_payload(&self) -> &(dyn Any + Send) {
+        self
+    }
+}
+
+/// Borrow the payload as a `T`, if that is what it is.
+///
+/// Unlike calling `downcast_ref` yourself, this can't accidentally downcast the `Box`
+/// holding the payload instead of the payload.
+///
+/// ## Examples
+/// ```
+/// use std::panic::{catch_unwind, panic_any};
+/// use panic_message::try_downcast_payload;
+///
+/// struct Fatal(u32);
+///
+/// let payload = catch_unwind(|| panic_any(Fatal(3))).unwrap_err();
+///
+/// assert_eq!(3, try_downcast_payload::<Fatal, _>(&payload).unwrap().0);
+/// assert!(try_downcast_payload::<String, _>(&*payload).is_none());
+/// ```
+pub fn try_downcast_payload<T, P>(payload: &P) -> Option<&T>
+where
+    T: Any,
+    P: AsPanicPayload + ?Sized,
+{
+    payload.as_panic_payload().downcast_ref()
+}
+
+/// Take the payload as a `T`, if that is what it is, or give it back.
+///
+/// ## Examples
+/// ```
+/// use std::panic::{catch_unwind, panic_any};
+/// use panic_message::try_downcast_payload_owned;
+///
+/// let payload = catch_unwind(|| panic_any(3_u32)).unwrap_err();
+///
+/// let payload = try_downcast_payload_owned::<String>(payload).unwrap_err();
+/// assert_eq!(3, try_downcast_payload_owned::<u32>(payload).unwrap());
+/// ```
+#[cfg(feature = "alloc")]
+pub fn try_downcast_payload_owned<T: Any>(
+    payload: Box<dyn Any + Send>,
+) -> Result<T, Box<dyn Any + Send>> {
+    payload.downcast().map(|payload| *payload)
+}
+
+/// Move a `String` payload out of its box without copying it, or give the payload back if
+/// it is anything else, including a `&'static str`.
+///
+/// ## Examples
+/// ```
+/// use std::panic::catch_unwind;
+/// use panic_message::try_into_string;
+///
+/// let name = "gus";
+/// let payload = catch_unwind(|| panic!("{} failed", name)).unwrap_err();
+/// assert_eq!("gus failed", try_into_string(payload).unwrap());
+///
+/// let payload = catch_unwind(|| panic!("gus")).unwrap_err();
+/// assert!(try_into_string(payload).is_err());
+/// ```
+#[cfg(feature = "alloc")]
+pub fn try_into_string(payload: Box<dyn Any + Send>) -> Result<String, Box<dyn Any + Send>> {
+    try_downcast_payload_owned(payload)
+}
+
+/// Take the message out of the payload, to keep it after the payload is gone, or give the
+/// payload back if it has none, for example to [`resume_unwind`][std::panic::resume_unwind]
+/// it.
+///
+/// The payloads with a message are the strings [`panic_message`][crate::panic_message]
+/// knows, and the payloads with a [registered extractor][crate::register_extractor], whose
+/// message is copied out of the payload. A `String` or `Box<str>` message (or an
+/// `Arc<String>` nothing else holds), including that of a
+/// [structured][crate::panic_with_context] or [replayed][crate::PanicDetails::replay]
+/// panic, is moved out without copying it.
+///
+/// ## Examples
+/// ```
+/// use std::panic::{catch_unwind, panic_any};
+/// use panic_message::into_panic_message;
+///
+/// let payload = catch_unwind(|| panic!("gus")).unwrap_err();
+/// assert_eq!("gus", into_panic_message(payload).unwrap());
+///
+/// let payload = catch_unwind(|| panic_any(3_u32)).unwrap_err();
+/// let payload = into_panic_message(payload).unwrap_err();
+/// assert_eq!(Some(&3), payload.downcast_ref::<u32>());
+/// ```
+#[cfg(feature = "std")]
+pub fn into_panic_message(payload: Box<dyn Any + Send>) -> Result<String, Box<dyn Any + Send>> {
+    let payload = match try_into_string(payload) {
+        Ok(message) => return Ok(message),
+        Err(payload) => payload,
+    };
+    let payload = match try_downcast_payload_owned::<&'static str>(payload) {
+        Ok(message) => return Ok(message.to_string()),
+        Err(payload) => payload,
+    };
+    let payload = match try_downcast_payload_owned::<Box<str>>(payload) {
+        Ok(message) => return Ok(message.into_string()),
+        Err(payload) => payload,
+    };
+    let payload = match try_downcast_payload_owned::<Cow<'static, str>>(payload) {
+        Ok(message) => return Ok(message.into_owned()),
+        Err(payload) => payload,
+    };
+    let payload = match try_downcast_payload_owned::<Arc<String>>(payload) {
+        Ok(message) => return Ok(Arc::try_unwrap(message).unwrap_or_else(|m| m.to_string())),
+        Err(payload) => payload,
+    };
+    let payload = match try_downcast_payload_owned::<Arc<str>>(payload) {
+        Ok(message) => return Ok(message.to_string()),
+        Err(payload) => payload,
+    };
+    let payload = match try_downcast_payload_owned::<crate::StructuredPanic>(payload) {
+        Ok(structured) => return Ok(structured.message),
+        Err(payload) => payload,
+    };
+    let payload = match payload.downcast::<crate::ReplayedPanic>() {
+        Ok(replayed) if replayed.message().is_some() => return Ok(replayed.into_details().message),
+        Ok(replayed) => return Err(replayed),
+        Err(payload) => payload,
+    };
+    match crate::registry::extract(&*payload) {
+        Some(message) => Ok(message.into_owned()),
+        None => Err(payload),
+    }
+}
+
+/// What a panic payload is, see [`payload_kind`].
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum PayloadKind {
+    /// A `&'static str`, as `panic!` with a literal message produces.
+    StaticStr,
+    /// A `String`, as `panic!` with format arguments produces.
+    String,
+    /// A string in one of the other containers `panic_any` is commonly called with: a
+    /// `Box<str>`, `Arc<str>`, `Arc<String>` or `Cow<'static, str>`.
+    StringContainer,
+    /// The payload of [`panic_with_context!`][crate::panic_with_context].
+    Structured,
+    /// The payload of a [replayed][crate::PanicDetails::replay] panic.
+    Replayed,
+    /// A type with a formatter registered with
+    /// [`register_panic_formatter`][crate::register_panic_formatter].
+    Registered(TypeId),
+    /// Anything else.
+    Other(TypeId),
+}
+
+/// Find out what the payload is, to branch on it without extracting the message twice.
+///
+/// ## Examples
+/// ```
+/// use std::any::TypeId;
+/// use std::panic::{catch_unwind, panic_any};
+/// use panic_message::{payload_kind, PayloadKind};
+///
+/// let payload = catch_unwind(|| panic!("gus")).unwrap_err();
+/// assert_eq!(PayloadKind::StaticStr, payload_kind(&payload));
+///
+/// let payload = catch_unwind(|| panic_any(1_u32)).unwrap_err();
+/// assert_eq!(PayloadKind::Other(TypeId::of::<u32>()), payload_kind(&payload));
+/// ```
+#[cfg(feature = "std")]
+pub fn payload_kind<P: AsPanicPayload + ?Sized>(payload: &P) -> PayloadKind {
+    let payload = payload.as_panic_payload();
+    if payload.is::<&'static str>() {
+        PayloadKind::StaticStr
+    } else if payload.is::<String>() {
+        PayloadKind::String
+    } else if crate::imp::string_container(payload).is_some() {
+        PayloadKind::StringContainer
+    } else if payload.is::<crate::StructuredPanic>() {
+        PayloadKind::Structured
+    } else if payload.is::<crate::ReplayedPanic>() {
+        PayloadKind::Replayed
+    } else if crate::registry::is_registered(payload) {
+        PayloadKind::Registered(payload.type_id())
+    } else {
+        PayloadKind::Other(payload.type_id())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn is_str(payload: impl AsPanicPayload) -> bool {
+        payload.as_panic_payload().is::<&'static str>()
+    }
+
+    #[test]
+    fn every_shape_sees_through_the_box() {
+        let payload: Box<dyn Any + Send> = Box::new("gus");
+
+        assert!(is_str(&payload));
+        assert!(is_str(&*payload));
+        assert!(is_str(payload));
+    }
+
+    #[test]
+    fn downcast() {
+        let payload: Box<dyn Any + Send> = Box::new(3_u32);
+        let borrowed: &Box<dyn Any + Send> = &payload;
+
+        assert_eq!(Some(&3), try_downcast_payload::<u32, _>(&payload));
+        assert_eq!(Some(&3), try_downcast_payload::<u32, _>(&*payload));
+        assert_eq!(Some(&3), try_downcast_payload::<u32, _>(&borrowed));
+        assert!(try_downcast_payload::<Box<dyn Any + Send>, _>(&payload).is_none());
+        assert_eq!(3, try_downcast_payload_owned::<u32>(payload).unwrap());
+    }
+
+    #[test]
+    fn into_string_moves_the_string() {
+        let message = "gus".repeat(1000);
+        let ptr = message.as_ptr();
+        let payload: Box<dyn Any + Send> = Box::new(message);
+
+        let message = try_into_string(payload).unwrap();
+        assert_eq!(ptr, message.as_ptr());
+    }
+
+    #[test]
+    fn into_message_moves_the_message() {
+        let message = "gus".repeat(1000);
+        let ptr = message.as_ptr();
+        let payload = crate::PanicDetails::new(message).into_payload();
+
+        let message = into_panic_message(payload).unwrap();
+        assert_eq!(ptr, message.as_ptr());
+
+        let payload = crate::PanicDetails::from_payload(Box::new(3_u32) as Box<dyn Any + Send>)
+            .into_payload();
+        let payload = into_panic_message(payload).unwrap_err();
+        assert!(payload.is::<crate::ReplayedPanic>());
+
+        let message = Arc::new("gus".repeat(1000));
+        let ptr = message.as_ptr();
+        let message = into_panic_message(Box::new(message)).unwrap();
+        assert_eq!(ptr, message.as_ptr());
+        let payload: Box<dyn Any + Send> = Box::new(Arc::<str>::from("gus"));
+        assert_eq!("gus", into_panic_message(payload).unwrap());
+    }
+
+    #[test]
+    fn into_message_uses_extractors() {
+        struct Extracted(&'static str);
+        crate::register_extractor(|extracted: &Extracted| Cow::Borrowed(extracted.0));
+
+        let payload: Box<dyn Any + Send> = Box::new(Extracted("gus"));
+        assert_eq!("gus", into_panic_message(payload).unwrap());
+    }
+
+    #[test]
+    fn kind() {
+        struct Registered;
+        crate::register_panic_formatter(|_: &Registered| "registered".to_string());
+
+        let string: Box<dyn Any + Send> = Box::new("gus".to_string());
+        let registered: Box<dyn Any + Send> = Box::new(Registered);
+
+        assert_eq!(PayloadKind::String, payload_kind(&string));
+        for container in [
+            Box::new(Box::<str>::from("gus")) as Box<dyn Any + Send>,
+            Box::new(Arc::<str>::from("gus")),
+            Box::new(Arc::new("gus".to_string())),
+            Box::new(Cow::<'static, str>::Borrowed("gus")),
+        ] {
+            assert_eq!(PayloadKind::StringContainer, payload_kind(&container));
+        }
+        assert_eq!(
+            PayloadKind::Registered(TypeId::of::<Registered>()),
+            payload_kind(&registered)
+        );
+    }
+}
@@ -0,0 +1,234 @@
+//! Keeping the last panic in RAM across a reset, for post-mortem debugging on devices
+//! without storage or a debugger attached.
+//!
+//! A [`PersistBuffer`] is a `static` the application places in a RAM region its startup
+//! code doesn't zero, usually an `.uninit` section declared `NOLOAD` in the linker script.
+//! The panic handler writes the panic into it, the device resets, and the next boot reads
+//! the message back with [`get_persisted_panic`][PersistBuffer::get_persisted_panic], for
+//! example to print or upload it.
+//!
+//! ```ignore
+//! #![no_std]
+//! #![no_main]
+//!
+//! use panic_message::persist::PersistBuffer;
+//!
+//! #[link_section = ".uninit.PANIC"]
+//! static PANIC: PersistBuffer<1024> = PersistBuffer::new();
+//!
+//! #[panic_handler]
+//! fn panic(info: &core::panic::PanicInfo) -> ! {
+//!     PANIC.persist_panic(info);
+//!     cortex_m::peripheral::SCB::sys_reset()
+//! }
+//!
+//! #[cortex_m_rt::entry]
+//! fn main() -> ! {
+//!     if let Some(panic) = PANIC.get_persisted_panic() {
+//!         uart::write_str(panic.as_str());
+//!         PANIC.clear();
+//!     }
+//!     // ...
+//! }
+//! ```
+//!
+//! Every field of the buffer is an atomic integer, so whatever the RAM held at power-on is
+//! a valid value, and a buffer is only reported as persisted once a panic was written to it
+//! completely. Writing takes no locks and doesn't allocate, so it works in any
+//! `#[panic_handler]` or panic hook; two panics written at once can garble each other's
+//! message, but not anything else.
+use core::{
+    fmt::{self, Display, Write},
+    panic::PanicInfo,
+    sync::atomic::{AtomicU8, AtomicUsize, Ordering},
+};
+
+/// Marks a buffer holding a completely written panic; anything else in the RAM at boot is
+/// taken as no panic.
+const PERSISTED: usize = 0x7061_6e63;
+
+/// A fixed-size buffer the text of a panic is written to and read back from, after a
+/// reset, see the [module docs][self].
+pub struct PersistBuffer<const N: usize> {
+    state: AtomicUsize,
+    len: AtomicUsize,
+    bytes: [AtomicU8; N],
+}
+
+impl<const N: usize> PersistBuffer<N> {
+    /// An empty buffer. In a section the startup code doesn't initialize, the buffer keeps
+    /// what was in the RAM instead.
+    pub const fn new() -> Self {
+        PersistBuffer {
+            state: AtomicUsize::new(0),
+            len: AtomicUsize::new(0),
+            bytes: [const { AtomicU8::new(0) }; N],
+        }
+    }
+
+    /// Write the panic, formatted like [`write_panic_info`][crate::write_panic_info] and
+    /// truncated to `N` bytes, replacing the one persisted before.
+    pub fn persist_panic(&self, panic_info: &PanicInfo<'_>) {
+        self.persist_with(|out| crate::write_panic_info(out, panic_info));
+    }
+
+    /// Write `message`, truncated to `N` bytes, replacing the panic persisted before. This
+    /// is for panic hooks, which get a `PanicHookInfo` instead of a `PanicInfo`.
+    ///
+    /// ## Examples
+    /// ```
+    /// use panic_message::{persist::PersistBuffer, PanicInfoExt};
+    ///
+    /// static PANIC: PersistBuffer<256> = PersistBuffer::new();
+    ///
+    /// std::panic::set_hook(Box::new(|pi| PANIC.persist(pi.details())));
+    /// let _ = std::panic::catch_unwind(|| panic!("gus"));
+    /// # let _ = std::panic::take_hook();
+    ///
+    /// assert!(PANIC.get_persisted_panic().unwrap().as_str().ends_with(":\ngus"));
+    /// ```
+    pub fn persist(&self, message: impl Display) {
+        self.persist_with(|out| write!(out, "{}", message));
+    }
+
+    fn persist_with(&self, render: impl FnOnce(&mut Cursor<'_, N>) -> fmt::Result) {
+        self.state.store(0, Ordering::Relaxed);
+        let mut cursor = Cursor {
+            buffer: self,
+            len: 0,
+        };
+        // a fmt::Error here only means the output was truncated
+        let _ = render(&mut cursor);
+        self.len.store(cursor.len, Ordering::Relaxed);
+        self.state.store(PERSISTED, Ordering::Release);
+    }
+
+    /// A copy of the persisted panic, if one was written since the buffer was last
+    /// [cleared][PersistBuffer::clear].
+    pub fn get_persisted_panic(&self) -> Option<PersistedPanic<N>> {
+        if self.state.load(Ordering::Acquire) != PERSISTED {
+            return None;
+        }
+        let len = self.len.load(Ordering::Relaxed).min(N);
+        let mut bytes = [0; N];
+        for (byte, persisted) in bytes.iter_mut().zip(&self.bytes[..len]) {
+            *byte = persisted.load(Ordering::Relaxed);
+        }
+        Some(PersistedPanic { bytes, len })
+    }
+
+    /// Forget the persisted panic, once it has been reported.
+    pub fn clear(&self) {
+        self.state.store(0, Ordering::Relaxed);
+    }
+}
+
+impl<const N: usize> Default for PersistBuffer<N> {
+    fn default() -> Self {
+        PersistBuffer::new()
+    }
+}
+
+impl<const N: usize> fmt::Debug for PersistBuffer<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PersistBuffer")
+            .field("persisted", &self.get_persisted_panic())
+            .finish()
+    }
+}
+
+/// A panic read back from a [`PersistBuffer`].
+#[derive(Clone)]
+pub struct PersistedPanic<const N: usize> {
+    bytes: [u8; N],
+    len: usize,
+}
+
+impl<const N: usize> PersistedPanic<N> {
+    /// The panic's text. Text cut in the middle of a character by the size limit, or left
+    /// invalid by a failing RAM, ends before the first byte that isn't UTF-8.
+    pub fn as_str(&self) -> &str {
+        let bytes = &self.bytes[..self.len];
+        match core::str::from_utf8(bytes) {
+            Ok(text) => text,
+            Err(e) => core::str::from_utf8(&bytes[..e.valid_up_to()]).unwrap_or_default(),
+        }
+    }
+}
+
+impl<const N: usize> fmt::Debug for PersistedPanic<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("PersistedPanic")
+            .field(&self.as_str())
+            .finish()
+    }
+}
+
+impl<const N: usize> Display for PersistedPanic<N> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// A `fmt::Write` into a buffer, failing once it is full.
+struct Cursor<'a, const N: usize> {
+    buffer: &'a PersistBuffer<N>,
+    len: usize,
+}
+
+impl<const N: usize> Write for Cursor<'_, N> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let n = s.len().min(N - self.len);
+        for (persisted, &byte) in self.buffer.bytes[self.len..].iter().zip(&s.as_bytes()[..n]) {
+            persisted.store(byte, Ordering::Relaxed);
+        }
+        self.len += n;
+        if n < s.len() {
+            return Err(fmt::Error);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn persists_and_clears() {
+        let buffer = PersistBuffer::<16>::new();
+        assert!(buffer.get_persisted_panic().is_none());
+
+        buffer.persist("gus");
+        assert_eq!("gus", buffer.get_persisted_panic().unwrap().as_str());
+
+        buffer.persist(format_args!("{} and a longer message", "gus"));
+        assert_eq!(
+            "gus and a longer",
+            buffer.get_persisted_panic().unwrap().to_string()
+        );
+
+        buffer.clear();
+        assert!(buffer.get_persisted_panic().is_none());
+    }
+
+    #[test]
+    fn truncated_characters_are_dropped() {
+        let buffer = PersistBuffer::<4>::new();
+        buffer.persist("gusé");
+
+        assert_eq!("gus", buffer.get_persisted_panic().unwrap().as_str());
+    }
+
+    #[test]
+    fn garbage_is_no_panic() {
+        let buffer = PersistBuffer::<4>::new();
+        buffer.state.store(12345, Ordering::Relaxed);
+        buffer.len.store(usize::MAX, Ordering::Relaxed);
+        assert!(buffer.get_persisted_panic().is_none());
+
+        // whatever the length says, no more than the buffer is read
+        buffer.state.store(PERSISTED, Ordering::Relaxed);
+        assert_eq!(4, buffer.get_persisted_panic().unwrap().as_str().len());
+    }
+}
@@ -0,0 +1,14 @@
+//! The extension traits, macros and types most code using this crate needs.
+//!
+//! ```
+//! use panic_message::prelude::*;
+//!
+//! let result = std::panic::catch_unwind(|| ensure_or_panic!(1 > 2, "gus"));
+//! assert_eq!("gus", result.caught().unwrap_err().message());
+//! ```
+#[cfg(feature = "tokio")]
+pub use crate::JoinErrorExt;
+pub use crate::{
+    ensure_or_panic, panic_with_context, CaughtPanic, JoinHandleExt, PanicInfoExt, PanicPayloadExt,
+    PanicResultExt,
+};
@@ -0,0 +1,366 @@
+//! Protobuf messages for panic reports, matching `proto/panic_details.proto`
+//! (shipped with the crate, so services can generate their own bindings from it).
+//!
+//! The types are [`prost`](https://docs.rs/prost) messages, as `prost-build` generates them
+//! for that schema, so they encode and decode with [`prost::Message`] and can be embedded
+//! in a service's own prost types.
+//!
+//! ## Examples
+//! ```
+//! use panic_message::{proto, PanicDetails};
+//! use prost::Message;
+//!
+//! let details = PanicDetails::new("gus").with_thread("main");
+//! let bytes = proto::PanicReport::from(details.clone()).encode_to_vec();
+//!
+//! let decoded = proto::PanicReport::decode(bytes.as_slice()).unwrap();
+//! assert_eq!(details, PanicDetails::from(decoded));
+//! ```
+use std::time::Duration;
+
+use crate::PanicDetails;
+
+/// `panic_message.Location`
+#[derive(Clone, PartialEq, Eq, ::prost::Message)]
+pub struct Location {
+    #[prost(string, tag = "1")]
+    pub file: String,
+    #[prost(uint32, tag = "2")]
+    pub line: u32,
+    #[prost(uint32, tag = "3")]
+    pub column: u32,
+}
+
+/// `panic_message.ResourceUsage`
+#[derive(Clone, PartialEq, Eq, ::prost::Message)]
+pub struct ResourceUsage {
+    #[prost(uint64, optional, tag = "1")]
+    pub rss_bytes: Option<u64>,
+    #[prost(uint64, optional, tag = "2")]
+    pub max_rss_bytes: Option<u64>,
+    #[prost(uint64, optional, tag = "3")]
+    pub open_fds: Option<u64>,
+    #[prost(uint64, optional, tag = "4")]
+    pub user_time_us: Option<u64>,
+    #[prost(uint64, optional, tag = "5")]
+    pub system_time_us: Option<u64>,
+    #[prost(uint64, optional, tag = "6")]
+    pub minor_faults: Option<u64>,
+    #[prost(uint64, optional, tag = "7")]
+    pub major_faults: Option<u64>,
+    #[prost(uint64, optional, tag = "8")]
+    pub voluntary_context_switches: Option<u64>,
+    #[prost(uint64, optional, tag = "9")]
+    pub involuntary_context_switches: Option<u64>,
+}
+
+/// `panic_message.Field`
+#[derive(Clone, PartialEq, Eq, ::prost::Message)]
+pub struct Field {
+    #[prost(string, tag = "1")]
+    pub key: String,
+    #[prost(string, tag = "2")]
+    pub value: String,
+}
+
+/// `panic_message.RawFrame`
+#[derive(Clone, PartialEq, Eq, ::prost::Message)]
+pub struct RawFrame {
+    #[prost(uint64, tag = "1")]
+    pub address: u64,
+    #[prost(string, optional, tag = "2")]
+    pub module: Option<String>,
+    #[prost(uint64, tag = "3")]
+    pub offset: u64,
+}
+
+/// `panic_message.StringList`
+#[derive(Clone, PartialEq, Eq, ::prost::Message)]
+pub struct StringList {
+    #[prost(string, repeated, tag = "1")]
+    pub items: Vec<String>,
+}
+
+/// `panic_message.FieldList`
+#[derive(Clone, PartialEq, Eq, ::prost::Message)]
+pub struct FieldList {
+    #[prost(message, repeated, tag = "1")]
+    pub fields: Vec<Field>,
+}
+
+/// `panic_message.Section`
+#[derive(Clone, PartialEq, Eq, ::prost::Message)]
+pub struct Section {
+    #[prost(string, tag = "1")]
+    pub name: String,
+    #[prost(oneof = "section::Content", tags = "2, 3, 4")]
+    pub content: Option<section::Content>,
+}
+
+/// Nested types of `panic_message.Section`.
+pub mod section {
+    /// `panic_message.Section.content`
+    #[derive(Clone, PartialEq, Eq, ::prost::Oneof)]
+    pub enum Content {
+        #[prost(string, tag = "2")]
+        Text(String),
+        #[prost(message, tag = "3")]
+        List(super::StringList),
+        #[prost(message, tag = "4")]
+        Fields(super::FieldList),
+    }
+}
+
+/// `panic_message.PanicReport`
+#[derive(Clone, PartialEq, Eq, ::prost::Message)]
+pub struct PanicReport {
+    #[prost(string, tag = "1")]
+    pub message: String,
+    #[prost(message, optional, tag = "2")]
+    pub location: Option<Location>,
+    #[prost(string, optional, tag = "3")]
+    pub thread: Option<String>,
+    #[prost(string, optional, tag = "4")]
+    pub backtrace: Option<String>,
+    #[prost(message, optional, tag = "5")]
+    pub resources: Option<ResourceUsage>,
+    #[prost(uint64, optional, tag = "6")]
+    pub uptime_us: Option<u64>,
+    #[prost(uint64, optional, tag = "7")]
+    pub since_previous_panic_us: Option<u64>,
+    #[prost(uint32, tag = "8")]
+    pub abandoned_cleanups: u32,
+    #[prost(message, repeated, tag = "9")]
+    pub fields: Vec<Field>,
+    #[prost(message, repeated, tag = "10")]
+    pub raw_frames: Vec<RawFrame>,
+    #[prost(message, repeated, tag = "11")]
+    pub sections: Vec<Section>,
+    #[prost(uint64, optional, tag = "12")]
+    pub thread_id: Option<u64>,
+    /// Microseconds since the Unix epoch.
+    #[prost(uint64, optional, tag = "13")]
+    pub time_us: Option<u64>,
+}
+
+impl ResourceUsage {
+    /// The fields, in field number order.
+    fn fields(&self) -> [Option<u64>; 9] {
+        [
+            self.rss_bytes,
+            self.max_rss_bytes,
+            self.open_fds,
+            self.user_time_us,
+            self.system_time_us,
+            self.minor_faults,
+            self.major_faults,
+            self.voluntary_context_switches,
+            self.involuntary_context_switches,
+        ]
+    }
+
+    fn fields_mut(&mut self) -> [&mut Option<u64>; 9] {
+        [
+            &mut self.rss_bytes,
+            &mut self.max_rss_bytes,
+            &mut self.open_fds,
+            &mut self.user_time_us,
+            &mut self.system_time_us,
+            &mut self.minor_faults,
+            &mut self.major_faults,
+            &mut self.voluntary_context_switches,
+            &mut self.involuntary_context_switches,
+        ]
+    }
+}
+
+impl From<crate::Section> for Section {
+    fn from(section: crate::Section) -> Self {
+        let content = match section.content {
+            crate::SectionContent::Text(text) => section::Content::Text(text),
+            crate::SectionContent::List(items) => section::Content::List(StringList { items }),
+            crate::SectionContent::Fields(fields) => section::Content::Fields(FieldList {
+                fields: fields
+                    .into_iter()
+                    .map(|(key, value)| Field { key, value })
+                    .collect(),
+            }),
+        };
+        Section {
+            name: section.name,
+            content: Some(content),
+        }
+    }
+}
+
+impl From<PanicDetails> for PanicReport {
+    fn from(details: PanicDetails) -> Self {
+        let backtrace = details.backtrace().map(str::to_string);
+        PanicReport {
+            message: details.message,
+            location: details.location.map(|location| Location {
+                file: location.file,
+                line: location.line,
+                column: location.column,
+            }),
+            thread: details.thread,
+            backtrace,
+            resources: details.resources.map(|resources| {
+                // the fields are in the same order in both types
+                let mut usage = ResourceUsage::default();
+                for (field, (_, value)) in usage.fields_mut().iter_mut().zip(resources.fields()) {
+                    **field = value;
+                }
+                usage
+            }),
+            uptime_us: details.uptime.map(|uptime| uptime.as_micros() as u64),
+            since_previous_panic_us: details
+                .since_previous_panic
+                .map(|since| since.as_micros() as u64),
+            abandoned_cleanups: details.abandoned_cleanups,
+            fields: details
+                .fields
+                .into_iter()
+                .map(|(key, value)| Field { key, value })
+                .collect(),
+            raw_frames: details
+                .raw_frames
+                .into_iter()
+                .map(|frame| RawFrame {
+                    address: frame.address,
+                    module: frame.module,
+                    offset: frame.offset,
+                })
+                .collect(),
+            sections: details.sections.into_iter().map(Section::from).collect(),
+            thread_id: details.thread_id,
+            time_us: details.time.map(crate::details::unix_micros),
+        }
+    }
+}
+
+impl From<PanicReport> for PanicDetails {
+    fn from(report: PanicReport) -> Self {
+        PanicDetails {
+            message: report.message,
+            location: report.location.map(|location| {
+                crate::Location::new(location.file, location.line, location.column)
+            }),
+            thread: report.thread,
+            thread_id: report.thread_id,
+            backtrace: report.backtrace.map(crate::trace::Trace::from),
+            resources: report.resources.map(|resources| {
+                let mut usage = crate::ResourceUsage::default();
+                for ((key, _), value) in usage.fields().iter().zip(resources.fields()) {
+                    if let Some(value) = value {
+                        usage.set_field(key, value);
+                    }
+                }
+                usage
+            }),
+            uptime: report.uptime_us.map(Duration::from_micros),
+            since_previous_panic: report.since_previous_panic_us.map(Duration::from_micros),
+            // a time `SystemTime` can't represent is dropped, rather than panicking
+            time: report.time_us.and_then(crate::details::from_unix_micros),
+            abandoned_cleanups: report.abandoned_cleanups,
+            fields: report
+                .fields
+                .into_iter()
+                .map(|field| (field.key, field.value))
+                .collect(),
+            raw_frames: report
+                .raw_frames
+                .into_iter()
+                .map(|frame| crate::RawFrame {
+                    address: frame.address,
+                    module: frame.module,
+                    offset: frame.offset,
+                })
+                .collect(),
+            // a section without content holds a kind this version doesn't know
+            sections: report
+                .sections
+                .into_iter()
+                .filter_map(|section| {
+                    let content = match section.content? {
+                        section::Content::Text(text) => crate::SectionContent::Text(text),
+                        section::Content::List(list) => crate::SectionContent::List(list.items),
+                        section::Content::Fields(list) => crate::SectionContent::Fields(
+                            list.fields
+                                .into_iter()
+                                .map(|field| (field.key, field.value))
+                                .collect(),
+                        ),
+                    };
+                    Some(crate::Section {
+                        name: section.name,
+                        content,
+                    })
+                })
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use prost::Message;
+
+    #[test]
+    fn round_trip() {
+        let details = PanicDetails::new("gus")
+            .with_location(crate::Location::new("src/lib.rs", 300, 5))
+            .with_thread("main")
+            .with_thread_id(3)
+            .with_backtrace("0: main")
+            .with_resources(crate::ResourceUsage::capture())
+            .with_uptime(Duration::from_micros(1_500_000))
+            .with_since_previous_panic(Duration::from_micros(7))
+            .with_time(std::time::SystemTime::now())
+            .with_abandoned_cleanups(1)
+            .with_field("port", "8080")
+            .with_raw_frames(vec![crate::RawFrame {
+                address: u64::MAX,
+                module: Some("libgus.so".to_string()),
+                offset: 0x1000,
+            }])
+            .with_section(crate::Section::text("config", ""))
+            .with_section(crate::Section::list("peers", vec!["10.0.0.1".to_string()]))
+            .with_section(crate::Section::fields("limits", Vec::new()));
+
+        let bytes = PanicReport::from(details.clone()).encode_to_vec();
+
+        assert_eq!(
+            details,
+            PanicDetails::from(PanicReport::decode(bytes.as_slice()).unwrap())
+        );
+    }
+
+    #[test]
+    fn wire_format() {
+        let report = PanicReport {
+            message: "gus".to_string(),
+            location: Some(Location {
+                file: "a".to_string(),
+                line: 300,
+                column: 0,
+            }),
+            ..PanicReport::default()
+        };
+
+        assert_eq!(
+            b"\x0a\x03gus\x12\x06\x0a\x01a\x10\xac\x02".as_ref(),
+            report.encode_to_vec().as_slice()
+        );
+    }
+
+    #[test]
+    fn unknown_fields_are_skipped() {
+        // message = "gus", then field 15 as a varint and field 16 as a fixed32
+        let bytes = b"\x0a\x03gus\x78\x01\x85\x01\x00\x00\x00\x00";
+
+        assert_eq!("gus", PanicReport::decode(bytes.as_ref()).unwrap().message);
+        assert!(PanicReport::decode(b"\x0a\x05gus".as_ref()).is_err());
+    }
+}
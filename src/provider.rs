@@ -0,0 +1,166 @@
+//! Payload types that know their own panic message.
+use std::{any::Any, borrow::Cow};
+
+/// A panic payload type that can describe itself, usually implemented with
+/// `#[derive(PanicMessage)]` (the `macros` feature).
+///
+/// Panicking with [`panic`][PanicMessageProvider::panic] registers the type with
+/// [`register_extractor`][crate::register_extractor] first, so
+/// [`panic_message`][crate::panic_message], [`PanicDetails`][crate::PanicDetails] and the
+/// hooks built on them show the message.
+///
+/// ## Examples
+/// ```
+/// use std::borrow::Cow;
+/// use std::panic::catch_unwind;
+/// use panic_message::{PanicDetails, PanicMessageProvider};
+///
+/// struct Fatal {
+///     reason: String,
+/// }
+///
+/// impl PanicMessageProvider for Fatal {
+///     fn panic_message(&self) -> Cow<'_, str> {
+///         Cow::Borrowed(&self.reason)
+///     }
+/// }
+///
+/// let payload = catch_unwind(|| {
+///     Fatal { reason: "gus".to_string() }.panic()
+/// }).unwrap_err();
+/// assert_eq!("gus", PanicDetails::from_payload(payload).message());
+/// ```
+pub trait PanicMessageProvider: Any + Send {
+    /// The message to report when panicking with this value.
+    fn panic_message(&self) -> Cow<'_, str>;
+
+    /// Register this type, then panic with `self` as the payload.
+    #[track_caller]
+    fn panic(self) -> !
+    where
+        Self: Sized,
+    {
+        register_provider::<Self>();
+        std::panic::panic_any(self)
+    }
+}
+
+/// Register `T`'s [`PanicMessageProvider`] impl with
+/// [`register_extractor`][crate::register_extractor], for payloads that are
+/// panicked with without going through [`PanicMessageProvider::panic`].
+pub fn register_provider<T: PanicMessageProvider>() {
+    let payload_type = std::any::TypeId::of::<T>();
+    if !crate::registry::is_registered_type(payload_type) {
+        crate::register_extractor(|payload: &T| payload.panic_message());
+    }
+}
+
+/// Register an extractor for a payload type when the program starts, before `main`, so a
+/// library's payloads have their messages without the binary registering them.
+///
+/// `submit_extractor!(Type)` registers the [`PanicMessageProvider`] impl of `Type`, like
+/// [`register_provider`]; `#[derive(PanicMessage)]` does this itself.
+/// `submit_extractor!(Type, extractor)` registers `extractor` with
+/// [`register_extractor`][crate::register_extractor].
+///
+/// Registration uses the platform's static constructors: `.init_array` on Linux, Android
+/// and the BSDs, `__mod_init_func` on Apple platforms and `.CRT$XCU` on Windows. On other
+/// targets, like WebAssembly, nothing is registered, so call the registration functions at
+/// startup there; a [`PanicMessageProvider`] that panics with
+/// [`panic`][PanicMessageProvider::panic] is registered either way.
+///
+/// ## Examples
+/// ```
+/// use std::{borrow::Cow, panic::{catch_unwind, panic_any}};
+/// use panic_message::{panic_message, submit_extractor};
+///
+/// struct Fatal {
+///     reason: String,
+/// }
+///
+/// submit_extractor!(Fatal, |fatal: &Fatal| Cow::Borrowed(fatal.reason.as_str()));
+///
+/// let payload = catch_unwind(|| panic_any(Fatal { reason: "gus".to_string() })).unwrap_err();
+/// assert_eq!("gus", panic_message(&payload));
+/// ```
+#[macro_export]
+macro_rules! submit_extractor {
+    ($payload:ty) => {
+        $crate::__at_startup!($crate::register_provider::<$payload>());
+    };
+    ($payload:ty, $extractor:expr $(,)?) => {
+        $crate::__at_startup!($crate::register_extractor::<$payload>($extractor));
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __at_startup {
+    ($register:expr) => {
+        const _: () = {
+            #[used]
+            #[cfg_attr(
+                any(
+                    target_os = "linux",
+                    target_os = "android",
+                    target_os = "freebsd",
+                    target_os = "netbsd",
+                    target_os = "openbsd",
+                    target_os = "dragonfly",
+                    target_os = "illumos",
+                    target_os = "solaris",
+                ),
+                link_section = ".init_array"
+            )]
+            #[cfg_attr(target_vendor = "apple", link_section = "__DATA,__mod_init_func")]
+            #[cfg_attr(windows, link_section = ".CRT$XCU")]
+            static REGISTER: extern "C" fn() = {
+                extern "C" fn register() {
+                    $register;
+                }
+                register
+            };
+        };
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::catch_unwind;
+
+    struct Provided(&'static str);
+
+    impl PanicMessageProvider for Provided {
+        fn panic_message(&self) -> Cow<'_, str> {
+            Cow::Borrowed(self.0)
+        }
+    }
+
+    struct Submitted(&'static str);
+
+    impl PanicMessageProvider for Submitted {
+        fn panic_message(&self) -> Cow<'_, str> {
+            Cow::Borrowed(self.0)
+        }
+    }
+
+    crate::submit_extractor!(Submitted);
+
+    #[cfg(any(target_os = "linux", target_vendor = "apple", windows))]
+    #[test]
+    fn submitted_at_startup() {
+        let payload = catch_unwind(|| std::panic::panic_any(Submitted("gus"))).unwrap_err();
+
+        assert_eq!("gus", crate::panic_message(&payload));
+    }
+
+    #[test]
+    fn panic_registers() {
+        let payload = catch_unwind(|| Provided("gus").panic()).unwrap_err();
+
+        assert_eq!("gus", crate::registry::owned_message(&*payload));
+        assert_eq!("gus", crate::panic_message(&payload));
+        assert!(payload.is::<Provided>());
+    }
+}
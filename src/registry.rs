@@ -0,0 +1,414 @@
+//! Formatters for payload types that aren't strings, registered at runtime.
+use std::{
+    any::{Any, TypeId},
+    borrow::Cow,
+    collections::HashMap,
+    fmt::{self, Debug},
+    sync::{Arc, Mutex, OnceLock, RwLock},
+};
+
+use crate::AsPanicPayload;
+
+type Format = dyn Fn(&(dyn Any + Send)) -> String + Send + Sync;
+
+type Extract = dyn for<'a> Fn(&'a (dyn Any + Send)) -> Cow<'a, str> + Send + Sync;
+
+/// A callback and the name of the payload type it takes.
+type Entry<F> = (&'static str, Arc<F>);
+
+/// Callbacks by the payload type they take.
+struct Registry<F: ?Sized>(OnceLock<RwLock<HashMap<TypeId, Entry<F>>>>);
+
+static FORMATTERS: Registry<Format> = Registry::new();
+static EXTRACTORS: Registry<Extract> = Registry::new();
+static DEBUG_FORMATTERS: Registry<Format> = Registry::new();
+static UNKNOWN_PAYLOAD_POLICY: RwLock<UnknownPayloadPolicy> =
+    RwLock::new(UnknownPayloadPolicy::Allow);
+/// The payload types [`unknown_payload_warning`] has warned about.
+static WARNED: Mutex<Vec<TypeId>> = Mutex::new(Vec::new());
+
+/// What to do when a payload has neither a string message nor a
+/// [registered formatter][register_panic_formatter], see [`set_unknown_payload_policy`].
+#[derive(Clone)]
+#[non_exhaustive]
+pub enum UnknownPayloadPolicy {
+    /// Report the panic as `"Box<dyn Any>"`. The default.
+    Allow,
+    /// Also print a warning to stderr, with the payload's type name if it was registered
+    /// with [`register_payload_debug`], and its type id otherwise.
+    Warn,
+    /// Print the warning, then abort the process, for enforcing the policy in CI.
+    Abort,
+    /// Call a function with the payload's type id.
+    Callback(Arc<dyn Fn(TypeId) + Send + Sync>),
+}
+
+impl UnknownPayloadPolicy {
+    /// Call `f` with the type id of every unknown payload.
+    pub fn callback(f: impl Fn(TypeId) + Send + Sync + 'static) -> Self {
+        UnknownPayloadPolicy::Callback(Arc::new(f))
+    }
+}
+
+impl fmt::Debug for UnknownPayloadPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UnknownPayloadPolicy::Allow => f.write_str("Allow"),
+            UnknownPayloadPolicy::Warn => f.write_str("Warn"),
+            UnknownPayloadPolicy::Abort => f.write_str("Abort"),
+            UnknownPayloadPolicy::Callback(_) => f.write_str("Callback(..)"),
+        }
+    }
+}
+
+/// Opt in to a strict mode for payloads without a message, to enforce an "only string or
+/// [structured][crate::StructuredPanic] payloads" policy.
+///
+/// The policy applies whenever [`PanicDetails`][crate::PanicDetails] are collected, which
+/// includes the hooks [`PanicHookBuilder`][crate::PanicHookBuilder] installs, which apply it
+/// on a helper thread, where a callback that panics is reported and skipped.
+///
+/// ## Examples
+/// ```
+/// use panic_message::{set_unknown_payload_policy, UnknownPayloadPolicy};
+///
+/// if std::env::var_os("CI").is_some() {
+///     set_unknown_payload_policy(UnknownPayloadPolicy::Abort);
+/// }
+/// ```
+pub fn set_unknown_payload_policy(policy: UnknownPayloadPolicy) {
+    *UNKNOWN_PAYLOAD_POLICY
+        .write()
+        .unwrap_or_else(|e| e.into_inner()) = policy;
+}
+
+/// Register how to render panics whose payload is a `T`, for code that panics with
+/// [`std::panic::panic_any`] and a payload type of its own. Registering a type again
+/// replaces its formatter.
+///
+/// The formatter is used wherever the message is owned or written out, like
+/// [`PanicDetails`][crate::PanicDetails], [`panic_message_cow`][crate::panic_message_cow] and
+/// [`ShowPanic`][crate::ShowPanic]; the `&str` functions like
+/// [`panic_message`][crate::panic_message] can't return a rendered message, so they don't
+/// use it; [`register_extractor`] registers a
+/// message they can return. The hook [`PanicHookBuilder`][crate::PanicHookBuilder] installs
+/// runs formatters on a helper thread, where one that panics is reported and skipped.
+///
+/// ## Examples
+/// ```
+/// use std::panic::{catch_unwind, panic_any};
+/// use panic_message::{register_panic_formatter, PanicDetails};
+///
+/// struct Fatal {
+///     code: u32,
+/// }
+///
+/// register_panic_formatter(|fatal: &Fatal| format!("fatal error {}", fatal.code));
+///
+/// let payload = catch_unwind(|| panic_any(Fatal { code: 3 })).unwrap_err();
+/// assert_eq!("fatal error 3", PanicDetails::from_payload(payload).message());
+/// ```
+pub fn register_panic_formatter<T: Any + Send>(
+    format: impl Fn(&T) -> String + Send + Sync + 'static,
+) {
+    FORMATTERS.insert::<T>(formatter(format));
+}
+
+/// Register how to get the message of panics whose payload is a `T`, for payload types
+/// that hold or borrow their message. Registering a type again replaces its extractor.
+///
+/// Unlike a [formatter][register_panic_formatter], an extractor can return a message
+/// borrowed from the payload, which the `&str` functions like
+/// [`panic_message`][crate::panic_message] and
+/// [`panic_info_message`][crate::panic_info_message] then return, after trying the payload
+/// types they know. An owned message is only used where messages are owned, like
+/// [`PanicDetails`][crate::PanicDetails] and [`panic_message_cow`][crate::panic_message_cow].
+/// The hook [`PanicHookBuilder`][crate::PanicHookBuilder] installs runs extractors on a
+/// helper thread, where one that panics is reported and skipped.
+///
+/// ## Examples
+/// ```
+/// use std::{borrow::Cow, panic::{catch_unwind, panic_any}};
+/// use panic_message::{panic_message, register_extractor};
+///
+/// struct Fatal {
+///     reason: String,
+/// }
+///
+/// register_extractor(|fatal: &Fatal| Cow::Borrowed(fatal.reason.as_str()));
+///
+/// let payload = catch_unwind(|| panic_any(Fatal { reason: "gus".to_string() })).unwrap_err();
+/// assert_eq!("gus", panic_message(&payload));
+/// ```
+pub fn register_extractor<T: Any + Send>(
+    extract: impl for<'a> Fn(&'a T) -> Cow<'a, str> + Send + Sync + 'static,
+) {
+    EXTRACTORS.insert::<T>(Arc::new(move |payload: &(dyn Any + Send)| {
+        // only ever looked up by `T`'s id
+        extract(
+            payload
+                .downcast_ref()
+                .expect("payload of the registered type"),
+        )
+    }));
+}
+
+/// The message a [registered extractor][register_extractor] gets from the payload.
+pub(crate) fn extract(payload: &(dyn Any + Send)) -> Option<Cow<'_, str>> {
+    let extract = EXTRACTORS.lookup(payload)?;
+    Some(extract(payload))
+}
+
+/// Register `T`'s `Debug` impl for [`payload_debug`].
+///
+/// ## Examples
+/// ```
+/// use std::panic::{catch_unwind, panic_any};
+/// use panic_message::{payload_debug, register_payload_debug};
+///
+/// #[derive(Debug)]
+/// struct Fatal {
+///     code: u32,
+/// }
+///
+/// register_payload_debug::<Fatal>();
+///
+/// let payload = catch_unwind(|| panic_any(Fatal { code: 3 })).unwrap_err();
+/// assert_eq!(Some("Fatal { code: 3 }".to_string()), payload_debug(&payload));
+/// ```
+pub fn register_payload_debug<T: Any + Send + Debug>() {
+    DEBUG_FORMATTERS.insert::<T>(formatter(|payload: &T| format!("{:?}", payload)));
+}
+
+/// Render the payload with the `Debug` impl registered for its type with
+/// [`register_payload_debug`], or `None` if there is none.
+///
+/// This is for inspecting payloads that have no message; string payloads have their
+/// messages, see [`get_panic_message`][crate::get_panic_message].
+pub fn payload_debug<P: AsPanicPayload + ?Sized>(payload: &P) -> Option<String> {
+    let payload = payload.as_panic_payload();
+    DEBUG_FORMATTERS
+        .lookup(payload)
+        .map(|format| format(payload))
+}
+
+/// Whether a formatter or extractor is registered for the payload's type.
+pub(crate) fn is_registered(payload: &(dyn Any + Send)) -> bool {
+    is_registered_type(payload.type_id())
+}
+
+pub(crate) fn is_registered_type(payload_type: TypeId) -> bool {
+    FORMATTERS.get(payload_type).is_some() || EXTRACTORS.get(payload_type).is_some()
+}
+
+/// The payload's message: the string itself, or else taken by a registered extractor,
+/// rendered from a boxed error or by a registered formatter, or else `"Box<dyn Any>"`.
+pub(crate) fn owned_message(payload: &(dyn Any + Send)) -> String {
+    if let Some(message) = crate::imp::get_known_message(payload) {
+        return message.to_string();
+    }
+    if let Some(message) = extract(payload) {
+        return message.into_owned();
+    }
+    if let Some(message) = crate::error_chain::error_message(payload) {
+        return message;
+    }
+    format(payload).unwrap_or_else(|| {
+        unknown_payload(payload.type_id());
+        "Box<dyn Any>".to_string()
+    })
+}
+
+/// The payload's message rendered by its registered formatter, if it has one.
+pub(crate) fn format(payload: &(dyn Any + Send)) -> Option<String> {
+    FORMATTERS.lookup(payload).map(|format| format(payload))
+}
+
+/// A warning about the payload having no message, the first time a payload of its type
+/// is seen; `None` if it has one or its type was warned about before.
+pub(crate) fn unknown_payload_warning(payload: &(dyn Any + Send)) -> Option<String> {
+    if crate::imp::get_panic_message(payload).is_some()
+        || is_registered(payload)
+        || crate::error_chain::is_error(payload)
+    {
+        return None;
+    }
+    let payload_type = payload.type_id();
+    let mut warned = WARNED.lock().unwrap_or_else(|e| e.into_inner());
+    if warned.contains(&payload_type) {
+        return None;
+    }
+    warned.push(payload_type);
+    Some(warning(payload_type))
+}
+
+/// The warning about a payload of `payload_type` having no message, with the type's name if
+/// it was registered.
+fn warning(payload_type: TypeId) -> String {
+    match type_name(payload_type) {
+        Some(name) => format!(
+            "panic payload of type `{0}` has no message; \
+             call `register_panic_formatter` for `{0}` to report one",
+            name
+        ),
+        None => format!(
+            "panic payload of an unregistered type ({:?}) has no message; \
+             call `register_panic_formatter` for it to report one",
+            payload_type
+        ),
+    }
+}
+
+/// The name of a payload type registered with any of the registries.
+fn type_name(payload_type: TypeId) -> Option<&'static str> {
+    FORMATTERS
+        .type_name(payload_type)
+        .or_else(|| EXTRACTORS.type_name(payload_type))
+        .or_else(|| DEBUG_FORMATTERS.type_name(payload_type))
+}
+
+fn unknown_payload(payload_type: TypeId) {
+    let policy = UNKNOWN_PAYLOAD_POLICY
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .clone();
+    match policy {
+        UnknownPayloadPolicy::Allow => {}
+        UnknownPayloadPolicy::Warn | UnknownPayloadPolicy::Abort => {
+            eprintln!("panic-message: {}", warning(payload_type));
+            if let UnknownPayloadPolicy::Abort = policy {
+                std::process::abort();
+            }
+        }
+        UnknownPayloadPolicy::Callback(callback) => callback(payload_type),
+    }
+}
+
+/// Wrap `format` to take the payload it is registered for.
+fn formatter<T: Any + Send>(format: impl Fn(&T) -> String + Send + Sync + 'static) -> Arc<Format> {
+    Arc::new(move |payload: &(dyn Any + Send)| {
+        // only ever looked up by `T`'s id
+        format(
+            payload
+                .downcast_ref()
+                .expect("payload of the registered type"),
+        )
+    })
+}
+
+impl<F: ?Sized> Registry<F> {
+    const fn new() -> Self {
+        Registry(OnceLock::new())
+    }
+
+    /// Register `callback` for payloads of type `T`, replacing the one registered before.
+    fn insert<T: Any>(&self, callback: Arc<F>) {
+        self.map()
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(TypeId::of::<T>(), (std::any::type_name::<T>(), callback));
+    }
+
+    fn lookup(&self, payload: &(dyn Any + Send)) -> Option<Arc<F>> {
+        self.get(payload.type_id())
+    }
+
+    fn get(&self, payload_type: TypeId) -> Option<Arc<F>> {
+        // cloned out, so a callback that registers another doesn't deadlock
+        self.map()
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&payload_type)
+            .map(|(_, callback)| Arc::clone(callback))
+    }
+
+    fn type_name(&self, payload_type: TypeId) -> Option<&'static str> {
+        self.map()
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&payload_type)
+            .map(|(name, _)| *name)
+    }
+
+    fn map(&self) -> &RwLock<HashMap<TypeId, Entry<F>>> {
+        self.0.get_or_init(Default::default)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Registered(u32);
+    struct Unregistered;
+
+    #[test]
+    fn formats_registered_types() {
+        register_panic_formatter(|r: &Registered| format!("registered {}", r.0));
+
+        let registered: Box<dyn Any + Send> = Box::new(Registered(1));
+        let unregistered: Box<dyn Any + Send> = Box::new(Unregistered);
+
+        assert!(is_registered(&*registered));
+        assert!(!is_registered(&*unregistered));
+        assert_eq!("registered 1", owned_message(&*registered));
+        assert_eq!("Box<dyn Any>", owned_message(&*unregistered));
+        assert_eq!("gus", owned_message(&"gus"));
+    }
+
+    #[test]
+    fn extracts_registered_types() {
+        struct Borrowed(String);
+        struct Owned(u32);
+        register_extractor(|b: &Borrowed| Cow::Borrowed(b.0.as_str()));
+        register_extractor(|o: &Owned| Cow::Owned(format!("owned {}", o.0)));
+
+        let borrowed: Box<dyn Any + Send> = Box::new(Borrowed("gus".to_string()));
+        let owned: Box<dyn Any + Send> = Box::new(Owned(1));
+
+        assert!(is_registered(&*owned));
+        assert_eq!(Some("gus"), crate::get_panic_message(&borrowed));
+        assert_eq!(None, crate::get_panic_message(&owned));
+        assert_eq!("owned 1", owned_message(&*owned));
+        assert_eq!(
+            Some(Cow::Owned("owned 1".to_string())),
+            crate::get_panic_message_cow(&owned)
+        );
+    }
+
+    #[test]
+    fn debug() {
+        #[derive(Debug)]
+        #[allow(dead_code)] // only read by `Debug`
+        struct Debugged(u32);
+        register_payload_debug::<Debugged>();
+
+        let debugged: Box<dyn Any + Send> = Box::new(Debugged(1));
+
+        assert_eq!(Some("Debugged(1)".to_string()), payload_debug(&debugged));
+        assert_eq!(
+            None,
+            payload_debug(&*Box::new(Unregistered) as &(dyn Any + Send))
+        );
+        // `Debug` and message formatters are registered separately
+        assert!(!is_registered(&*debugged));
+    }
+
+    #[test]
+    fn warns_once_per_type() {
+        #[derive(Debug)]
+        struct Named;
+        struct Unnamed;
+        register_payload_debug::<Named>();
+
+        let named = unknown_payload_warning(&Named).unwrap();
+        assert!(named.contains("`panic_message::registry::tests::warns_once_per_type::Named`"));
+        assert!(named.contains("register_panic_formatter"));
+        assert!(unknown_payload_warning(&Named).is_none());
+
+        assert!(unknown_payload_warning(&Unnamed)
+            .unwrap()
+            .contains("unregistered type"));
+        assert!(unknown_payload_warning(&"gus").is_none());
+    }
+}
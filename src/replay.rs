@@ -0,0 +1,110 @@
+//! Moving a panic to another thread and resuming it there.
+use std::any::Any;
+
+use crate::{PanicDetails, PanicKind};
+
+/// The payload of a panic resumed with [`PanicDetails::replay`], carrying the details of
+/// the original panic.
+///
+/// The functions that extract messages see through it, so code catching the resumed panic
+/// gets the original message, and [`PanicDetails::from_payload`] gets the original details
+/// back, with their location and thread.
+///
+/// ## Examples
+/// ```
+/// use std::panic::catch_unwind;
+/// use panic_message::{get_panic_message, PanicDetails};
+///
+/// let details = PanicDetails::new("gus").with_thread("worker");
+/// let payload = catch_unwind(|| details.replay()).unwrap_err();
+///
+/// assert_eq!(Some("gus"), get_panic_message(&payload));
+/// assert_eq!(Some("worker"), PanicDetails::from_payload(&payload).thread());
+/// ```
+#[derive(Debug, Clone)]
+pub struct ReplayedPanic {
+    details: PanicDetails,
+}
+
+impl ReplayedPanic {
+    /// The details of the original panic.
+    pub fn details(&self) -> &PanicDetails {
+        &self.details
+    }
+
+    /// Take the details of the original panic.
+    pub fn into_details(self) -> PanicDetails {
+        self.details
+    }
+
+    /// The original panic message, `None` if the original payload had none.
+    pub(crate) fn message(&self) -> Option<&str> {
+        match self.details.kind() {
+            PanicKind::NonString => None,
+            _ => Some(self.details.message()),
+        }
+    }
+}
+
+impl PanicDetails {
+    /// Turn these details into a panic payload, for [`std::panic::resume_unwind`].
+    ///
+    /// The payload is a [`ReplayedPanic`]. Its message is the original message, or none if
+    /// the original payload had no message, so it is classified as the same
+    /// [`PanicKind`].
+    pub fn into_payload(self) -> Box<dyn Any + Send> {
+        Box::new(ReplayedPanic { details: self })
+    }
+
+    /// Resume the panic these details describe on the current thread, for example to carry
+    /// a panic from a worker thread to the caller waiting on its result.
+    ///
+    /// Like [`std::panic::resume_unwind`], this doesn't run the panic hook again, so the
+    /// panic isn't reported twice.
+    pub fn replay(self) -> ! {
+        std::panic::resume_unwind(self.into_payload())
+    }
+}
+
+/// The details of a replayed panic, if `payload` is one.
+pub(crate) fn details(payload: &(dyn Any + Send)) -> Option<&PanicDetails> {
+    payload
+        .downcast_ref::<ReplayedPanic>()
+        .map(ReplayedPanic::details)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Location, PayloadKind};
+    use std::panic::catch_unwind;
+
+    #[test]
+    fn across_threads() {
+        let details = std::thread::spawn(|| {
+            let payload =
+                catch_unwind(|| panic!("index out of bounds: the len is 0 but the index is 0"))
+                    .unwrap_err();
+            PanicDetails::from_payload(payload).with_location(Location::new("src/lib.rs", 1, 2))
+        })
+        .join()
+        .unwrap();
+
+        let payload = catch_unwind(|| details.clone().replay()).unwrap_err();
+
+        assert_eq!(
+            PanicKind::IndexOutOfBounds,
+            crate::PanicPayloadExt::kind(&payload)
+        );
+        assert_eq!(PayloadKind::Replayed, crate::payload_kind(&payload));
+        assert_eq!(details, PanicDetails::from_payload(&payload));
+    }
+
+    #[test]
+    fn no_message_stays_without_one() {
+        let payload = PanicDetails::new("Box<dyn Any>").into_payload();
+
+        assert_eq!(None, crate::get_panic_message(&payload));
+        assert_eq!(PanicKind::NonString, crate::PanicPayloadExt::kind(&payload));
+    }
+}
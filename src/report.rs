@@ -0,0 +1,329 @@
+//! Reports assembled from named sections, so new kinds of data reach every format without
+//! each format knowing about them.
+use std::{
+    fmt,
+    sync::{Arc, RwLock},
+};
+
+use crate::{json, PanicDetails};
+
+type Provider = dyn Fn(&PanicDetails) -> Option<Section> + Send + Sync;
+
+static PROVIDERS: RwLock<Vec<Arc<Provider>>> = RwLock::new(Vec::new());
+
+/// A named part of a [`Report`], like its backtrace or the thread's breadcrumbs.
+///
+/// ## Examples
+/// ```
+/// use panic_message::{PanicDetails, Section};
+///
+/// let details = PanicDetails::new("gus")
+///     .with_section(Section::list("peers", vec!["10.0.0.1".to_string()]));
+///
+/// assert!(details.to_json().contains(r#""sections":{"peers":["10.0.0.1"]}"#));
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    pub(crate) name: String,
+    pub(crate) content: SectionContent,
+}
+
+/// What a [`Section`] holds.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum SectionContent {
+    /// Free text, possibly spanning several lines.
+    Text(String),
+    /// Items in order, like breadcrumbs.
+    List(Vec<String>),
+    /// Key-value pairs in order, like context fields.
+    Fields(Vec<(String, String)>),
+}
+
+impl Section {
+    /// A section holding text.
+    pub fn text(name: impl Into<String>, text: impl Into<String>) -> Self {
+        Section {
+            name: name.into(),
+            content: SectionContent::Text(text.into()),
+        }
+    }
+
+    /// A section holding a list of items.
+    pub fn list(name: impl Into<String>, items: Vec<String>) -> Self {
+        Section {
+            name: name.into(),
+            content: SectionContent::List(items),
+        }
+    }
+
+    /// A section holding key-value pairs.
+    pub fn fields(name: impl Into<String>, fields: Vec<(String, String)>) -> Self {
+        Section {
+            name: name.into(),
+            content: SectionContent::Fields(fields),
+        }
+    }
+
+    /// The section's name, which is its key in JSON formats.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// What the section holds.
+    pub fn content(&self) -> &SectionContent {
+        &self.content
+    }
+
+    /// Write the section as a member of `object`: text as a string, a list as an array
+    /// and fields as an object.
+    pub(crate) fn write_json(&self, object: &mut json::Object<'_>) -> fmt::Result {
+        match &self.content {
+            SectionContent::Text(text) => object.str(&self.name, text),
+            SectionContent::List(items) => object.array(&self.name, |array| {
+                for item in items {
+                    array.str(item)?;
+                }
+                Ok(())
+            }),
+            SectionContent::Fields(fields) => object.object(&self.name, |object| {
+                for (key, value) in fields {
+                    object.str(key, value)?;
+                }
+                Ok(())
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Section {
+    /// `name: text` for single-line text, otherwise the name on a line of its own and the
+    /// content indented below it.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.content {
+            SectionContent::Text(text) if !text.contains('\n') => {
+                write!(f, "{}: {}", self.name, text)
+            }
+            SectionContent::Text(text) => {
+                write!(f, "{}:", self.name)?;
+                for line in text.lines() {
+                    write!(f, "\n  {}", line)?;
+                }
+                Ok(())
+            }
+            SectionContent::List(items) => {
+                write!(f, "{}:", self.name)?;
+                for item in items {
+                    write!(f, "\n  - {}", item)?;
+                }
+                Ok(())
+            }
+            SectionContent::Fields(fields) => {
+                write!(f, "{}:", self.name)?;
+                for (key, value) in fields {
+                    write!(f, "\n  {}: {}", key, value)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Register `provider` to add a section to every report, like the hash of the active
+/// config or the peers that were connected. Providers run in the order they were
+/// registered, and return `None` to add nothing.
+///
+/// The hook [`PanicHookBuilder`][crate::PanicHookBuilder] installs runs them for each
+/// panic, before sending the report to its sinks, so every format renders their sections.
+/// It runs them on a helper thread, where a provider that panics is reported on stderr and
+/// skipped, so providers can't read the panicking thread's thread-locals; context like the
+/// id of the request it was handling goes in [`thread_context`][crate::thread_context],
+/// which the report carries in its [fields][PanicDetails::fields]. Other hooks can run them
+/// with [`PanicDetails::with_provided_sections`].
+///
+/// ## Examples
+/// ```
+/// use panic_message::{register_section_provider, PanicDetails, Section};
+///
+/// register_section_provider(|_: &PanicDetails| Some(Section::text("config", "a1b2")));
+///
+/// let details = PanicDetails::new("gus").with_provided_sections();
+/// assert_eq!("config", details.sections()[0].name());
+/// ```
+pub fn register_section_provider(
+    provider: impl Fn(&PanicDetails) -> Option<Section> + Send + Sync + 'static,
+) {
+    PROVIDERS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(Arc::new(provider));
+}
+
+/// Whether any section provider is registered.
+pub(crate) fn has_providers() -> bool {
+    !PROVIDERS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .is_empty()
+}
+
+impl PanicDetails {
+    /// Add the sections of every [registered provider][register_section_provider].
+    pub fn with_provided_sections(mut self) -> Self {
+        // cloned out of the lock, so providers can register providers
+        let providers = PROVIDERS.read().unwrap_or_else(|e| e.into_inner()).clone();
+        for provider in providers {
+            if let Some(Some(section)) =
+                crate::isolate::guarded("section provider", || provider(&self))
+            {
+                self.sections.push(section);
+            }
+        }
+        self
+    }
+}
+
+/// A panic report as a list of [`Section`]s, which renders as text or JSON by iterating
+/// them, whatever they are.
+///
+/// [`Report::from`] a [`PanicDetails`] starts with its `message`, `location`, `thread`,
+/// `context` (the [fields][PanicDetails::fields]) and `backtrace`, when present, followed by
+/// its own [sections][PanicDetails::sections].
+///
+/// ## Examples
+/// ```
+/// use panic_message::{Location, PanicDetails, Report, Section};
+///
+/// let details = PanicDetails::new("gus").with_location(Location::new("src/main.rs", 3, 5));
+/// let report = Report::from(&details).with_section(Section::text("config", "a1b2"));
+///
+/// assert_eq!(
+///     "message: gus\nlocation: src/main.rs:3:5\nconfig: a1b2",
+///     report.to_string()
+/// );
+/// assert_eq!(
+///     r#"{"message":"gus","location":"src/main.rs:3:5","config":"a1b2"}"#,
+///     report.to_json()
+/// );
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Report {
+    sections: Vec<Section>,
+}
+
+impl Report {
+    /// A report with no sections.
+    pub fn new() -> Self {
+        Report::default()
+    }
+
+    /// Add a section after the others.
+    pub fn with_section(mut self, section: Section) -> Self {
+        self.sections.push(section);
+        self
+    }
+
+    /// The sections, in order.
+    pub fn sections(&self) -> &[Section] {
+        &self.sections
+    }
+
+    /// The first section called `name`.
+    pub fn section(&self, name: &str) -> Option<&Section> {
+        self.sections.iter().find(|section| section.name == name)
+    }
+
+    /// Render the report as a single-line JSON object with a member per section.
+    pub fn to_json(&self) -> String {
+        let mut out = String::new();
+        self.write_json(&mut out)
+            .expect("writing to a String can't fail");
+        out
+    }
+
+    fn write_json(&self, out: &mut dyn fmt::Write) -> fmt::Result {
+        let mut object = json::Object::new(out)?;
+        for section in &self.sections {
+            section.write_json(&mut object)?;
+        }
+        object.finish()
+    }
+}
+
+impl From<&PanicDetails> for Report {
+    fn from(details: &PanicDetails) -> Self {
+        let mut report = Report::new().with_section(Section::text("message", details.message()));
+        if let Some(location) = details.location() {
+            report = report.with_section(Section::text("location", location.to_string()));
+        }
+        if let Some(thread) = details.thread() {
+            report = report.with_section(Section::text("thread", thread));
+        }
+        if !details.fields().is_empty() {
+            report = report.with_section(Section::fields("context", details.fields().to_vec()));
+        }
+        if let Some(backtrace) = details.backtrace() {
+            report = report.with_section(Section::text("backtrace", backtrace));
+        }
+        report.sections.extend(details.sections().iter().cloned());
+        report
+    }
+}
+
+impl fmt::Display for Report {
+    /// Each section, one after the other.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, section) in self.sections.iter().enumerate() {
+            if i > 0 {
+                f.write_str("\n")?;
+            }
+            write!(f, "{}", section)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn display() {
+        let report = Report::new()
+            .with_section(Section::text("backtrace", "0: main\n1: start"))
+            .with_section(Section::list(
+                "breadcrumbs",
+                vec!["a".to_string(), "b".to_string()],
+            ))
+            .with_section(Section::fields(
+                "context",
+                vec![("port".to_string(), "8080".to_string())],
+            ));
+
+        assert_eq!(
+            "backtrace:\n  0: main\n  1: start\nbreadcrumbs:\n  - a\n  - b\ncontext:\n  port: 8080",
+            report.to_string()
+        );
+        assert_eq!(
+            r#"{"backtrace":"0: main\n1: start","breadcrumbs":["a","b"],"context":{"port":"8080"}}"#,
+            report.to_json()
+        );
+    }
+
+    #[test]
+    fn from_details() {
+        let details = PanicDetails::new("gus")
+            .with_thread("main")
+            .with_field("port", "8080")
+            .with_section(Section::text("config", "a1b2"));
+
+        let report = Report::from(&details);
+
+        let names: Vec<_> = report.sections().iter().map(Section::name).collect();
+        assert_eq!(vec!["message", "thread", "context", "config"], names);
+        assert_eq!(
+            Some(&SectionContent::Text("a1b2".to_string())),
+            report.section("config").map(Section::content)
+        );
+    }
+}
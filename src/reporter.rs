@@ -0,0 +1,119 @@
+//! Crash services and other consumers of every report, registered for the whole process.
+use std::sync::{
+    atomic::{AtomicU64, Ordering},
+    Arc, RwLock,
+};
+
+use crate::PanicDetails;
+
+static NEXT_ID: AtomicU64 = AtomicU64::new(0);
+static REPORTERS: RwLock<Vec<(u64, Arc<dyn Reporter>)>> = RwLock::new(Vec::new());
+
+/// Something that receives every panic report, like a client for a crash service, an
+/// internal crash endpoint or a test's recorder.
+///
+/// Reporters are [registered][register_reporter] for the whole process, and the hook
+/// [`PanicHookBuilder`][crate::PanicHookBuilder] installs fans each report out to all of
+/// them, in registration order, after its [sinks][crate::sink::Sink]. Closures taking a
+/// `&PanicDetails` are reporters.
+///
+/// Sinks and reporters receive the same reports, on the same helper thread, and one that
+/// panics is reported on stderr and skipped either way. They differ in who adds them:
+///
+/// - A [sink][crate::sink::Sink] belongs to one hook, and is given to its builder by the
+///   binary that installs it. The hook reports the errors sinks return on stderr, and
+///   [flushes][crate::sink::Sink::flush] them before an [`ExitPolicy`][crate::ExitPolicy]
+///   exits.
+/// - A reporter is registered from anywhere, so a library, like a crash service's client,
+///   can receive reports without owning the hook or being passed to the code that builds
+///   it, and a test can register one and [unregister][ReporterHandle::unregister] it again.
+///   It returns nothing, so it deals with its own errors, and it isn't flushed.
+///
+/// So binaries configure sinks, and libraries and tests register reporters. A sink can
+/// still be registered, with a closure that emits to it and handles the error.
+///
+/// ## Examples
+/// ```
+/// use panic_message::{register_reporter, sink::{Sink, Stream}, PanicDetails, Reporter};
+///
+/// struct CrashService {
+///     endpoint: String,
+/// }
+///
+/// impl Reporter for CrashService {
+///     fn report(&self, report: &PanicDetails) {
+///         let body = report.to_json();
+///         // post `body` to `self.endpoint`
+///     }
+/// }
+///
+/// register_reporter(CrashService { endpoint: "https://crashes.example.com".to_string() });
+/// register_reporter(|report: &PanicDetails| eprintln!("reported: {}", report.message()));
+///
+/// let stderr = Stream::stderr();
+/// register_reporter(move |report: &PanicDetails| {
+///     if let Err(e) = stderr.emit(report) {
+///         eprintln!("failed to report panic: {}", e);
+///     }
+/// });
+/// ```
+pub trait Reporter: Send + Sync + 'static {
+    /// Handle one report.
+    fn report(&self, report: &PanicDetails);
+}
+
+impl<F> Reporter for F
+where
+    F: Fn(&PanicDetails) + Send + Sync + 'static,
+{
+    fn report(&self, report: &PanicDetails) {
+        self(report)
+    }
+}
+
+/// A registered reporter, see [`register_reporter`].
+///
+/// Dropping the handle leaves the reporter registered.
+#[derive(Debug)]
+pub struct ReporterHandle {
+    id: u64,
+}
+
+impl ReporterHandle {
+    /// Unregister the reporter, for example at the end of a test. Returns whether it was
+    /// still registered.
+    pub fn unregister(self) -> bool {
+        let mut reporters = REPORTERS.write().unwrap_or_else(|e| e.into_inner());
+        let before = reporters.len();
+        reporters.retain(|(id, _)| *id != self.id);
+        reporters.len() != before
+    }
+}
+
+/// Register `reporter` to receive every report, see [`Reporter`].
+pub fn register_reporter(reporter: impl Reporter) -> ReporterHandle {
+    let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+    REPORTERS
+        .write()
+        .unwrap_or_else(|e| e.into_inner())
+        .push((id, Arc::new(reporter)));
+    ReporterHandle { id }
+}
+
+/// Whether any reporter is registered.
+pub(crate) fn has_reporters() -> bool {
+    !REPORTERS
+        .read()
+        .unwrap_or_else(|e| e.into_inner())
+        .is_empty()
+}
+
+/// Send `report` to every registered reporter. Only for threads that aren't running a
+/// panic hook, as reporters may panic.
+pub(crate) fn report(report: &PanicDetails) {
+    // cloned out of the lock, so reporters can register and unregister reporters
+    let reporters = REPORTERS.read().unwrap_or_else(|e| e.into_inner()).clone();
+    for (_, reporter) in reporters {
+        crate::isolate::guarded("reporter", || reporter.report(report));
+    }
+}
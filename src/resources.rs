@@ -0,0 +1,269 @@
+//! Process resource usage, for reports about panics that happen when memory or file
+//! descriptors run out.
+use std::time::Duration;
+
+use crate::json;
+
+/// A snapshot of the process's resource usage, taken when it panicked.
+///
+/// Each number is `None` when the platform doesn't provide it. Capturing usage reads
+/// from `/proc` and calls `getrusage`, so it is opt-in, see
+/// [`PanicHookBuilder::with_resource_usage`][crate::PanicHookBuilder::with_resource_usage].
+///
+/// ## Examples
+/// ```
+/// use panic_message::{PanicDetails, ResourceUsage};
+///
+/// let details = PanicDetails::new("gus").with_resources(ResourceUsage::capture());
+/// # #[cfg(target_os = "linux")]
+/// assert!(details.resources().unwrap().rss_bytes.unwrap() > 0);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[cfg_attr(
+    feature = "schemars",
+    derive(schemars::JsonSchema),
+    schemars(
+        description = "The process's resource usage when it panicked. Absent numbers were not available."
+    )
+)]
+#[non_exhaustive]
+pub struct ResourceUsage {
+    /// The resident set size, in bytes.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub rss_bytes: Option<u64>,
+    /// The peak resident set size, in bytes.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub max_rss_bytes: Option<u64>,
+    /// How many file descriptors are open.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub open_fds: Option<u64>,
+    /// CPU time spent in user mode.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "user_time_us",
+            skip_serializing_if = "Option::is_none",
+            with = "crate::serde_impl::micros"
+        )
+    )]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<u64>"))]
+    pub user_time: Option<Duration>,
+    /// CPU time spent in the kernel.
+    #[cfg_attr(
+        feature = "serde",
+        serde(
+            rename = "system_time_us",
+            skip_serializing_if = "Option::is_none",
+            with = "crate::serde_impl::micros"
+        )
+    )]
+    #[cfg_attr(feature = "schemars", schemars(with = "Option<u64>"))]
+    pub system_time: Option<Duration>,
+    /// Page faults served without I/O.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub minor_faults: Option<u64>,
+    /// Page faults that needed I/O.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub major_faults: Option<u64>,
+    /// Context switches because the process waited for a resource.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub voluntary_context_switches: Option<u64>,
+    /// Context switches because the process was preempted.
+    #[cfg_attr(feature = "serde", serde(skip_serializing_if = "Option::is_none"))]
+    pub involuntary_context_switches: Option<u64>,
+}
+
+impl ResourceUsage {
+    /// Measure the current process.
+    pub fn capture() -> Self {
+        #[cfg_attr(not(unix), allow(unused_mut))]
+        let mut usage = ResourceUsage::default();
+        #[cfg(unix)]
+        unix::capture(&mut usage);
+        usage
+    }
+
+    pub(crate) fn write_json(&self, object: &mut json::Object<'_>) -> std::fmt::Result {
+        for (key, value) in self.fields() {
+            if let Some(value) = value {
+                object.u64(key, value)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Every field as an integer, with times in microseconds, keyed by its name in
+    /// the serialized formats.
+    pub(crate) fn fields(&self) -> [(&'static str, Option<u64>); 9] {
+        let micros = |time: Option<Duration>| time.map(|time| time.as_micros() as u64);
+        [
+            ("rss_bytes", self.rss_bytes),
+            ("max_rss_bytes", self.max_rss_bytes),
+            ("open_fds", self.open_fds),
+            ("user_time_us", micros(self.user_time)),
+            ("system_time_us", micros(self.system_time)),
+            ("minor_faults", self.minor_faults),
+            ("major_faults", self.major_faults),
+            (
+                "voluntary_context_switches",
+                self.voluntary_context_switches,
+            ),
+            (
+                "involuntary_context_switches",
+                self.involuntary_context_switches,
+            ),
+        ]
+    }
+
+    /// Set the field called `key` in the serialized formats, returning whether there is one.
+    pub(crate) fn set_field(&mut self, key: &str, value: u64) -> bool {
+        match key {
+            "rss_bytes" => self.rss_bytes = Some(value),
+            "max_rss_bytes" => self.max_rss_bytes = Some(value),
+            "open_fds" => self.open_fds = Some(value),
+            "user_time_us" => self.user_time = Some(Duration::from_micros(value)),
+            "system_time_us" => self.system_time = Some(Duration::from_micros(value)),
+            "minor_faults" => self.minor_faults = Some(value),
+            "major_faults" => self.major_faults = Some(value),
+            "voluntary_context_switches" => self.voluntary_context_switches = Some(value),
+            "involuntary_context_switches" => self.involuntary_context_switches = Some(value),
+            _ => return false,
+        }
+        true
+    }
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::{
+        convert::TryFrom,
+        os::raw::{c_int, c_long},
+        time::Duration,
+    };
+
+    use super::ResourceUsage;
+
+    const RUSAGE_SELF: c_int = 0;
+
+    #[cfg(target_vendor = "apple")]
+    type Microseconds = i32;
+    #[cfg(not(target_vendor = "apple"))]
+    type Microseconds = c_long;
+
+    #[repr(C)]
+    struct Timeval {
+        tv_sec: c_long,
+        tv_usec: Microseconds,
+    }
+
+    #[repr(C)]
+    struct Rusage {
+        ru_utime: Timeval,
+        ru_stime: Timeval,
+        ru_maxrss: c_long,
+        ru_ixrss: c_long,
+        ru_idrss: c_long,
+        ru_isrss: c_long,
+        ru_minflt: c_long,
+        ru_majflt: c_long,
+        ru_nswap: c_long,
+        ru_inblock: c_long,
+        ru_oublock: c_long,
+        ru_msgsnd: c_long,
+        ru_msgrcv: c_long,
+        ru_nsignals: c_long,
+        ru_nvcsw: c_long,
+        ru_nivcsw: c_long,
+    }
+
+    extern "C" {
+        fn getrusage(who: c_int, usage: *mut Rusage) -> c_int;
+    }
+
+    pub(super) fn capture(usage: &mut ResourceUsage) {
+        usage.rss_bytes = rss_bytes();
+        usage.open_fds = open_fds();
+
+        // SAFETY: `Rusage` is plain integers, for which all zeroes is valid.
+        let mut rusage: Rusage = unsafe { std::mem::zeroed() };
+        // SAFETY: `rusage` is a valid `struct rusage` to write to.
+        if unsafe { getrusage(RUSAGE_SELF, &mut rusage) } != 0 {
+            return;
+        }
+        let count = |value: c_long| u64::try_from(value).ok();
+        let time = |time: &Timeval| {
+            let secs = u64::try_from(time.tv_sec).ok()?;
+            let micros = u32::try_from(time.tv_usec).ok()?;
+            Some(Duration::from_secs(secs) + Duration::from_micros(micros.into()))
+        };
+        // macOS reports the peak in bytes, everything else in kilobytes
+        let max_rss_unit = if cfg!(target_vendor = "apple") {
+            1
+        } else {
+            1024
+        };
+        usage.max_rss_bytes = count(rusage.ru_maxrss).map(|max| max * max_rss_unit);
+        usage.user_time = time(&rusage.ru_utime);
+        usage.system_time = time(&rusage.ru_stime);
+        usage.minor_faults = count(rusage.ru_minflt);
+        usage.major_faults = count(rusage.ru_majflt);
+        usage.voluntary_context_switches = count(rusage.ru_nvcsw);
+        usage.involuntary_context_switches = count(rusage.ru_nivcsw);
+    }
+
+    /// The `VmRSS` line of `/proc/self/status`, where there is one.
+    fn rss_bytes() -> Option<u64> {
+        let status = std::fs::read_to_string("/proc/self/status").ok()?;
+        let line = status.lines().find(|line| line.starts_with("VmRSS:"))?;
+        let kilobytes = line["VmRSS:".len()..].trim().strip_suffix("kB")?;
+        Some(kilobytes.trim().parse::<u64>().ok()? * 1024)
+    }
+
+    fn open_fds() -> Option<u64> {
+        let entries = std::fs::read_dir("/proc/self/fd")
+            .or_else(|_| std::fs::read_dir("/dev/fd"))
+            .ok()?;
+        // don't count the descriptor that is reading the directory
+        Some((entries.count() as u64).saturating_sub(1))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn capture() {
+        let usage = ResourceUsage::capture();
+
+        assert!(usage.rss_bytes.unwrap() > 0);
+        assert!(usage.max_rss_bytes.unwrap() >= usage.rss_bytes.unwrap() / 2);
+        // at least stdin, stdout and stderr
+        assert!(usage.open_fds.unwrap() >= 3);
+        assert!(usage.user_time.is_some());
+        assert!(usage.minor_faults.unwrap() > 0);
+    }
+
+    #[test]
+    fn fields_round_trip() {
+        let usage = ResourceUsage {
+            rss_bytes: Some(1 << 20),
+            user_time: Some(Duration::from_millis(1500)),
+            involuntary_context_switches: Some(3),
+            ..ResourceUsage::default()
+        };
+
+        let mut decoded = ResourceUsage::default();
+        for (key, value) in usage.fields() {
+            if let Some(value) = value {
+                assert!(decoded.set_field(key, value));
+            }
+        }
+
+        assert_eq!(usage, decoded);
+        assert!(!decoded.set_field("gus", 1));
+    }
+}
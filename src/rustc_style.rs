@@ -0,0 +1,80 @@
+//! The panic line of rustc's default panic hook, for hooks that add to it.
+use std::{
+    fmt,
+    panic::{Location, PanicHookInfo as PanicInfo},
+};
+
+/// A [`PanicInfo`][std::panic::PanicInfo] displayed like the default panic hook prints it:
+/// `thread 'main' panicked at src/main.rs:3:5:` and the message on the next line, so a
+/// custom hook can keep the familiar format and print its own extras around it.
+///
+/// The thread is the current one, as a hook runs on the panicking thread, and the message is
+/// written like [`write_panic_message`][crate::write_panic_message] writes it. Unlike the
+/// [`Display`][fmt::Display] impl of [`PanicDetails`][crate::PanicDetails], it always uses
+/// rustc's English words rather than the [labels][crate::set_labels].
+///
+/// ## Examples
+/// ```
+/// use panic_message::RustcStyle;
+///
+/// std::panic::set_hook(Box::new(|pi| {
+///     eprintln!("{}", RustcStyle(pi));
+///     eprintln!("build: {}", env!("CARGO_PKG_VERSION"));
+/// }));
+/// # let _ = std::panic::take_hook();
+/// ```
+#[derive(Clone, Copy)]
+pub struct RustcStyle<'a>(pub &'a PanicInfo<'a>);
+
+impl fmt::Display for RustcStyle<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write_panic_line(
+            f,
+            std::thread::current().name(),
+            self.0.location(),
+            crate::imp::Message(self.0.payload()),
+        )
+    }
+}
+
+impl fmt::Debug for RustcStyle<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("RustcStyle")
+            .field(&self.to_string())
+            .finish()
+    }
+}
+
+fn write_panic_line(
+    out: &mut impl fmt::Write,
+    thread: Option<&str>,
+    location: Option<&Location<'_>>,
+    message: impl fmt::Display,
+) -> fmt::Result {
+    let thread = thread.unwrap_or("<unnamed>");
+    match location {
+        Some(location) => write!(
+            out,
+            "thread '{}' panicked at {}:\n{}",
+            thread, location, message
+        ),
+        None => write!(out, "thread '{}' panicked:\n{}", thread, message),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn panic_line() {
+        let location = Location::caller();
+        let mut out = String::new();
+        write_panic_line(&mut out, Some("main"), Some(location), "gus").unwrap();
+        assert_eq!(format!("thread 'main' panicked at {}:\ngus", location), out);
+
+        let mut out = String::new();
+        write_panic_line(&mut out, None, None, "gus").unwrap();
+        assert_eq!("thread '<unnamed>' panicked:\ngus", out);
+    }
+}
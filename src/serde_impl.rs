@@ -0,0 +1,292 @@
+//! [serde](https://serde.rs) support for [`PanicDetails`][crate::PanicDetails] and the
+//! types it holds, in the shape [`to_json`][crate::PanicDetails::to_json] writes, so
+//! reports serialized either way read back either way.
+use std::{
+    fmt,
+    marker::PhantomData,
+    time::{Duration, SystemTime},
+};
+
+use serde::{
+    de::{self, MapAccess, SeqAccess, Visitor},
+    Deserialize, Deserializer, Serialize, Serializer,
+};
+
+use crate::{trace::Trace, Section, SectionContent};
+
+pub(crate) fn is_zero(count: &u32) -> bool {
+    *count == 0
+}
+
+/// A backtrace as its rendered text.
+pub(crate) mod backtrace {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        trace: &Option<Trace>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        trace.as_ref().map(Trace::as_str).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Trace>, D::Error> {
+        Ok(Option::<String>::deserialize(deserializer)?.map(Trace::from))
+    }
+}
+
+/// A duration as a whole number of microseconds.
+pub(crate) mod micros {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        duration: &Option<Duration>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        duration
+            .map(|duration| duration.as_micros() as u64)
+            .serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<Duration>, D::Error> {
+        Ok(Option::<u64>::deserialize(deserializer)?.map(Duration::from_micros))
+    }
+}
+
+/// A time as microseconds since the Unix epoch.
+pub(crate) mod unix_micros {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        time: &Option<SystemTime>,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        time.map(crate::details::unix_micros).serialize(serializer)
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Option<SystemTime>, D::Error> {
+        match Option::<u64>::deserialize(deserializer)? {
+            Some(micros) => crate::details::from_unix_micros(micros)
+                .map(Some)
+                .ok_or_else(|| de::Error::custom("time_us out of range")),
+            None => Ok(None),
+        }
+    }
+}
+
+/// An address as a `0x`-prefixed hex string, because JSON numbers can't hold every one.
+pub(crate) mod hex {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        address: &u64,
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_str(&format_args!("{:#x}", address))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u64, D::Error> {
+        let text = String::deserialize(deserializer)?;
+        text.strip_prefix("0x")
+            .and_then(|digits| u64::from_str_radix(digits, 16).ok())
+            .ok_or_else(|| de::Error::custom("expected a hex address"))
+    }
+}
+
+/// Key-value pairs as an object, keeping their order.
+pub(crate) mod fields {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        fields: &[(String, String)],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(fields.iter().map(|(key, value)| (key, value)))
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<(String, String)>, D::Error> {
+        deserializer.deserialize_map(Pairs(PhantomData))
+    }
+}
+
+/// Sections as an object with a member per section, keyed by its name.
+pub(crate) mod sections {
+    use super::*;
+
+    pub(crate) fn serialize<S: Serializer>(
+        sections: &[Section],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(
+            sections
+                .iter()
+                .map(|section| (&section.name, &section.content)),
+        )
+    }
+
+    pub(crate) fn deserialize<'de, D: Deserializer<'de>>(
+        deserializer: D,
+    ) -> Result<Vec<Section>, D::Error> {
+        let pairs = deserializer.deserialize_map(Pairs(PhantomData))?;
+        Ok(pairs
+            .into_iter()
+            .map(|(name, content)| Section { name, content })
+            .collect())
+    }
+}
+
+/// The members of an object, in order.
+struct Pairs<V>(PhantomData<V>);
+
+impl<'de, V: Deserialize<'de>> Visitor<'de> for Pairs<V> {
+    type Value = Vec<(String, V)>;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("an object")
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, mut map: A) -> Result<Self::Value, A::Error> {
+        let mut pairs = Vec::new();
+        while let Some(pair) = map.next_entry()? {
+            pairs.push(pair);
+        }
+        Ok(pairs)
+    }
+}
+
+impl Serialize for SectionContent {
+    /// Text as a string, a list as an array of strings and fields as an object.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            SectionContent::Text(text) => serializer.serialize_str(text),
+            SectionContent::List(items) => items.serialize(serializer),
+            SectionContent::Fields(fields) => fields::serialize(fields, serializer),
+        }
+    }
+}
+
+impl<'de> Deserialize<'de> for SectionContent {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        deserializer.deserialize_any(ContentVisitor)
+    }
+}
+
+struct ContentVisitor;
+
+impl<'de> Visitor<'de> for ContentVisitor {
+    type Value = SectionContent;
+
+    fn expecting(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("a string, an array of strings or an object of strings")
+    }
+
+    fn visit_str<E: de::Error>(self, text: &str) -> Result<Self::Value, E> {
+        Ok(SectionContent::Text(text.to_string()))
+    }
+
+    fn visit_string<E: de::Error>(self, text: String) -> Result<Self::Value, E> {
+        Ok(SectionContent::Text(text))
+    }
+
+    fn visit_seq<A: SeqAccess<'de>>(self, mut seq: A) -> Result<Self::Value, A::Error> {
+        let mut items = Vec::new();
+        while let Some(item) = seq.next_element()? {
+            items.push(item);
+        }
+        Ok(SectionContent::List(items))
+    }
+
+    fn visit_map<A: MapAccess<'de>>(self, map: A) -> Result<Self::Value, A::Error> {
+        Pairs(PhantomData)
+            .visit_map(map)
+            .map(SectionContent::Fields)
+    }
+}
+
+#[cfg(feature = "schemars")]
+impl schemars::JsonSchema for SectionContent {
+    fn schema_name() -> std::borrow::Cow<'static, str> {
+        "SectionContent".into()
+    }
+
+    fn json_schema(generator: &mut schemars::SchemaGenerator) -> schemars::Schema {
+        schemars::json_schema!({
+            "description": "Text as a string, a list as an array of strings and fields as an object.",
+            "oneOf": [
+                generator.subschema_for::<String>(),
+                generator.subschema_for::<Vec<String>>(),
+                generator.subschema_for::<std::collections::BTreeMap<String, String>>(),
+            ]
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Location, PanicDetails, RawFrame, ResourceUsage, Section};
+    use std::time::{Duration, SystemTime};
+
+    #[test]
+    fn matches_to_json() {
+        let details = PanicDetails::new("gus")
+            .with_location(Location::new("src/main.rs", 3, 5))
+            .with_thread("main")
+            .with_thread_id(1)
+            .with_backtrace("0: main")
+            .with_resources(ResourceUsage::capture())
+            .with_uptime(Duration::from_micros(1_500_000))
+            .with_since_previous_panic(Duration::from_micros(7))
+            .with_time(SystemTime::now())
+            .with_abandoned_cleanups(1)
+            .with_field("port", "8080")
+            .with_field("host", "localhost")
+            .with_raw_frames(vec![RawFrame {
+                address: u64::MAX,
+                module: Some("libgus.so".to_string()),
+                offset: 0x1000,
+            }])
+            .with_section(Section::text("config", "a1b2"))
+            .with_section(Section::list("peers", vec!["10.0.0.1".to_string()]))
+            .with_section(Section::fields(
+                "limits",
+                vec![("fds".to_string(), "1024".to_string())],
+            ));
+
+        let json = serde_json::to_string(&details).unwrap();
+
+        assert_eq!(details.to_json(), json);
+        assert_eq!(
+            details,
+            serde_json::from_str::<PanicDetails>(&json).unwrap()
+        );
+        assert_eq!(
+            PanicDetails::new("gus"),
+            serde_json::from_str(r#"{"extra":[1],"message":"gus"}"#).unwrap()
+        );
+    }
+
+    #[test]
+    fn errors() {
+        let error = |json: &str| {
+            serde_json::from_str::<PanicDetails>(json)
+                .unwrap_err()
+                .to_string()
+        };
+
+        assert_eq!(
+            "missing field `message` at line 1 column 17",
+            error(r#"{"thread":"main"}"#)
+        );
+        assert_eq!(
+            "expected a hex address at line 1 column 46",
+            error(r#"{"message":"gus","raw_frames":[{"address":"5"}]}"#)
+        );
+    }
+}
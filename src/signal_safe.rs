@@ -0,0 +1,142 @@
+//! Output that is safe to produce from a signal handler (or a Windows exception filter):
+//! no allocation, no locks, only raw writes of buffers rendered ahead of time.
+#[cfg(unix)]
+use std::os::raw::{c_int, c_void};
+use std::{
+    cell::UnsafeCell,
+    fmt,
+    sync::atomic::{AtomicBool, AtomicUsize, Ordering},
+};
+
+#[cfg(unix)]
+extern "C" {
+    fn write(fd: c_int, buf: *const c_void, count: usize) -> isize;
+}
+
+/// Writes straight to a file descriptor with `write(2)`.
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct FdWriter(pub(crate) c_int);
+
+#[cfg(unix)]
+impl FdWriter {
+    pub(crate) const STDERR: FdWriter = FdWriter(2);
+
+    /// Write all of `bytes`, giving up silently on the first error.
+    pub(crate) fn write_all(self, mut bytes: &[u8]) {
+        while !bytes.is_empty() {
+            // SAFETY: `bytes` is valid for `bytes.len()` bytes.
+            let written = unsafe { write(self.0, bytes.as_ptr().cast(), bytes.len()) };
+            if written <= 0 {
+                return;
+            }
+            bytes = &bytes[written as usize..];
+        }
+    }
+}
+
+/// A fixed-size text buffer, rendered outside of signal handlers and read from inside them.
+///
+/// Access is guarded by a flag instead of a lock: writers wait for it, while a signal
+/// handler that finds the buffer busy (possibly because it interrupted a writer on its own
+/// thread) skips it instead of deadlocking.
+pub(crate) struct SignalBuffer<const N: usize> {
+    busy: AtomicBool,
+    len: AtomicUsize,
+    bytes: UnsafeCell<[u8; N]>,
+}
+
+// SAFETY: `bytes` is only accessed while holding `busy`.
+unsafe impl<const N: usize> Sync for SignalBuffer<N> {}
+
+impl<const N: usize> SignalBuffer<N> {
+    pub(crate) const fn new() -> Self {
+        SignalBuffer {
+            busy: AtomicBool::new(false),
+            len: AtomicUsize::new(0),
+            bytes: UnsafeCell::new([0; N]),
+        }
+    }
+
+    fn try_acquire(&self) -> bool {
+        self.busy
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .is_ok()
+    }
+
+    /// Replace the contents with whatever `render` writes, truncated to `N` bytes.
+    /// Must not be called from a signal handler.
+    pub(crate) fn set(&self, render: impl FnOnce(&mut dyn fmt::Write) -> fmt::Result) {
+        while !self.try_acquire() {
+            std::hint::spin_loop();
+        }
+        // SAFETY: we hold `busy`.
+        let bytes = unsafe { &mut *self.bytes.get() };
+        let mut cursor = Cursor { bytes, len: 0 };
+        // a fmt::Error here only means the output was truncated
+        let _ = render(&mut cursor);
+        self.len.store(cursor.len, Ordering::Relaxed);
+        self.busy.store(false, Ordering::Release);
+    }
+
+    /// Pass the contents to `read`, returning whether the buffer was available.
+    /// Safe to call from a signal handler, as long as `read` is.
+    pub(crate) fn read(&self, read: impl FnOnce(&[u8])) -> bool {
+        if !self.try_acquire() {
+            return false;
+        }
+        let len = self.len.load(Ordering::Relaxed);
+        // SAFETY: we hold `busy`, and `len` never exceeds `N`.
+        let bytes: &[u8; N] = unsafe { &*self.bytes.get() };
+        read(&bytes[..len]);
+        self.busy.store(false, Ordering::Release);
+        true
+    }
+}
+
+/// A `fmt::Write` into a fixed buffer, failing once it is full.
+struct Cursor<'a> {
+    bytes: &'a mut [u8],
+    len: usize,
+}
+
+impl fmt::Write for Cursor<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let available = self.bytes.len() - self.len;
+        let n = s.len().min(available);
+        self.bytes[self.len..self.len + n].copy_from_slice(&s.as_bytes()[..n]);
+        self.len += n;
+        if n < s.len() {
+            return Err(fmt::Error);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn truncates() {
+        let buffer = SignalBuffer::<4>::new();
+
+        buffer.set(|out| out.write_str("gus wynn"));
+
+        assert_eq!(4, buffer.len.load(Ordering::Relaxed));
+        assert_eq!(b"gus ", unsafe { &*buffer.bytes.get() });
+    }
+
+    #[test]
+    fn busy_buffers_are_skipped() {
+        let buffer = SignalBuffer::<4>::new();
+        buffer.set(|out| out.write_str("gus"));
+
+        assert!(buffer.try_acquire());
+        assert!(!buffer.read(|_| unreachable!()));
+        buffer.busy.store(false, Ordering::Release);
+        let mut read = Vec::new();
+        assert!(buffer.read(|bytes| read.extend_from_slice(bytes)));
+        assert_eq!(b"gus", read.as_slice());
+    }
+}
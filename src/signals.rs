@@ -0,0 +1,361 @@
+//! Recovering panic context when the process dies from a signal instead of a panic.
+//!
+//! A panic while panicking, or an explicit [`std::process::abort`], kills the process with
+//! `SIGABRT` without running any more panic hooks. [`install_abort_handler`] installs a
+//! handler for it that writes the last panic reported through a
+//! [`PanicHookBuilder`][crate::PanicHookBuilder] hook and the current thread's
+//! [breadcrumbs][crate::breadcrumb] to stderr (or the fd given to [`set_output_fd`]), then
+//! lets the process die as it would have.
+//!
+//! [`install_fatal_signal_handlers`] does the same for `SIGSEGV`, `SIGBUS` and `SIGILL`,
+//! crashes that often happen next to a caught panic, for example in FFI code that was
+//! left in a bad state by unwinding.
+//!
+//! On Windows, [`install_exception_filter`] instead registers an unhandled-exception
+//! filter that appends the same information to a report file.
+//!
+//! Everything the handlers print is rendered ahead of time into fixed-size buffers,
+//! so the handlers themselves only write out bytes. Breadcrumbs are only rendered once a
+//! handler is installed, so those a thread left before then show up with its next one.
+use std::{
+    collections::VecDeque,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+use crate::{signal_safe::SignalBuffer, PanicDetails};
+
+#[cfg(unix)]
+pub use unix::{install_abort_handler, install_fatal_signal_handlers, set_output_fd};
+#[cfg(windows)]
+pub use windows::install_exception_filter;
+
+static LAST_PANIC: SignalBuffer<4096> = SignalBuffer::new();
+static HAS_PANICKED: AtomicBool = AtomicBool::new(false);
+
+thread_local! {
+    static BREADCRUMBS: SignalBuffer<2048> = const { SignalBuffer::new() };
+}
+
+/// Whether a handler is installed, so breadcrumbs are only rendered for one once there is one.
+static ARMED: AtomicBool = AtomicBool::new(false);
+
+/// Note that a handler was installed, rendering the current thread's breadcrumbs for it.
+/// Other threads' breadcrumbs are rendered with their next one.
+fn arm() {
+    ARMED.store(true, Ordering::Relaxed);
+    crate::breadcrumbs::render_for_signals();
+}
+
+/// Whether a handler is installed.
+pub(crate) fn armed() -> bool {
+    ARMED.load(Ordering::Relaxed)
+}
+
+/// Record `details` as the last panic, for the signal handlers.
+pub(crate) fn record_last_panic(details: &PanicDetails) {
+    LAST_PANIC.set(|out| write!(out, "{}", details));
+    HAS_PANICKED.store(true, Ordering::Relaxed);
+}
+
+/// Render the current thread's breadcrumbs, for the signal handlers.
+pub(crate) fn render_breadcrumbs(trail: &VecDeque<String>) {
+    let _ = BREADCRUMBS.try_with(|breadcrumbs| {
+        breadcrumbs.set(|out| {
+            if trail.is_empty() {
+                return Ok(());
+            }
+            out.write_str("breadcrumbs (oldest first):\n")?;
+            for breadcrumb in trail {
+                writeln!(out, "  {}", breadcrumb)?;
+            }
+            Ok(())
+        })
+    });
+}
+
+/// Write everything recorded with `out`, after a header naming what killed the process.
+/// Async-signal-safe as long as `out` is.
+fn dump(out: impl Fn(&[u8]), what: &[&[u8]]) {
+    out(b"\npanic-message: received ");
+    for part in what {
+        out(part);
+    }
+    out(b"\n");
+    if HAS_PANICKED.load(Ordering::Relaxed) {
+        out(b"last panic: ");
+        if !LAST_PANIC.read(&out) {
+            out(b"<being recorded>");
+        }
+        out(b"\n");
+    }
+    let _ = BREADCRUMBS.try_with(|breadcrumbs| breadcrumbs.read(&out));
+}
+
+#[cfg(unix)]
+mod unix {
+    use std::{
+        io,
+        os::{raw::c_int, unix::io::RawFd},
+        ptr,
+        sync::{
+            atomic::{AtomicI32, Ordering},
+            Mutex,
+        },
+    };
+
+    use libc::{SIGABRT, SIGBUS, SIGILL, SIGSEGV};
+
+    use crate::signal_safe::FdWriter;
+
+    /// Size of the alternate signal stack given to a thread that has none.
+    const ALT_STACK_SIZE: usize = 64 * 1024;
+
+    static OUTPUT_FD: AtomicI32 = AtomicI32::new(FdWriter::STDERR.0);
+
+    /// The signals handled so far, so installing twice doesn't report twice.
+    static INSTALLED: Mutex<Vec<c_int>> = Mutex::new(Vec::new());
+
+    /// Make the signal handlers write to `fd` instead of stderr, for example a crash channel
+    /// a supervisor passes in as fd 3.
+    ///
+    /// `fd` must stay open for as long as the handlers are installed; the handlers never
+    /// close it.
+    pub fn set_output_fd(fd: RawFd) {
+        OUTPUT_FD.store(fd, Ordering::Relaxed);
+    }
+
+    /// Install a `SIGABRT` handler that prints the last panic and the current thread's
+    /// breadcrumbs to stderr before the process aborts.
+    ///
+    /// ## Examples
+    /// ```no_run
+    /// panic_message::PanicHookBuilder::new().install();
+    /// panic_message::signals::install_abort_handler().unwrap();
+    ///
+    /// panic_message::breadcrumb("about to abort");
+    /// std::process::abort();
+    /// ```
+    pub fn install_abort_handler() -> io::Result<()> {
+        install(SIGABRT)
+    }
+
+    /// Install handlers for `SIGSEGV`, `SIGBUS` and `SIGILL` that, after the handler that was
+    /// installed before, print the last panic and the current thread's breadcrumbs to stderr,
+    /// then let the signal take its default action, so the process still dies (and dumps
+    /// core) as it would have.
+    ///
+    /// The handlers run on the thread's alternate signal stack, so the standard library's
+    /// handler, which runs first, still reports stack overflows as "thread has overflowed its
+    /// stack" before aborting, which [`install_abort_handler`] reports. The standard library
+    /// gives every thread it starts an alternate stack; a thread calling this without one,
+    /// such as a foreign thread, gets one.
+    pub fn install_fatal_signal_handlers() -> io::Result<()> {
+        install(SIGSEGV)?;
+        install(SIGBUS)?;
+        install(SIGILL)
+    }
+
+    fn install(signum: c_int) -> io::Result<()> {
+        ensure_alt_stack()?;
+        let mut installed = INSTALLED.lock().unwrap_or_else(|e| e.into_inner());
+        if installed.contains(&signum) {
+            return Ok(());
+        }
+        // SAFETY: `on_fatal_signal` only does async-signal-safe work. `SIGSEGV` and `SIGILL`
+        // are only forbidden because a handler that returns would retry the faulting
+        // instruction, and it ends with the signal's default action instead.
+        unsafe {
+            signal_hook_registry::register_unchecked(signum, move |_| on_fatal_signal(signum))
+        }?;
+        run_on_alt_stack(signum)?;
+        installed.push(signum);
+        drop(installed);
+        super::arm();
+        Ok(())
+    }
+
+    /// Add `SA_ONSTACK` to the action `signal-hook` installed, which it doesn't set, so the
+    /// handlers also run when the thread has overflowed its stack.
+    fn run_on_alt_stack(signum: c_int) -> io::Result<()> {
+        // SAFETY: a zeroed `sigaction` is valid, and the action is only read and written back.
+        unsafe {
+            let mut action: libc::sigaction = std::mem::zeroed();
+            if libc::sigaction(signum, ptr::null(), &mut action) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            action.sa_flags |= libc::SA_ONSTACK;
+            if libc::sigaction(signum, &action, ptr::null_mut()) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    /// Give the calling thread an alternate signal stack if it has none. The stack is leaked,
+    /// as the thread may take a signal on it at any time until it exits.
+    fn ensure_alt_stack() -> io::Result<()> {
+        // SAFETY: a zeroed `stack_t` is valid, and the new stack lives forever.
+        unsafe {
+            let mut current: libc::stack_t = std::mem::zeroed();
+            if libc::sigaltstack(ptr::null(), &mut current) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            if current.ss_flags & libc::SS_DISABLE == 0 {
+                return Ok(());
+            }
+            let size = ALT_STACK_SIZE.max(libc::SIGSTKSZ);
+            let stack = libc::stack_t {
+                ss_sp: Box::leak(vec![0u8; size].into_boxed_slice())
+                    .as_mut_ptr()
+                    .cast(),
+                ss_flags: 0,
+                ss_size: size,
+            };
+            if libc::sigaltstack(&stack, ptr::null_mut()) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs after the previous handler, which `signal-hook` calls first.
+    fn on_fatal_signal(signum: c_int) {
+        let name: &[u8] = match signum {
+            SIGABRT => b"SIGABRT",
+            SIGSEGV => b"SIGSEGV",
+            SIGBUS => b"SIGBUS",
+            SIGILL => b"SIGILL",
+            _ => b"a fatal signal",
+        };
+        let out = FdWriter(OUTPUT_FD.load(Ordering::Relaxed));
+        super::dump(|bytes| out.write_all(bytes), &[name]);
+        let _ = signal_hook::low_level::emulate_default_handler(signum);
+    }
+}
+
+#[cfg(windows)]
+mod windows {
+    use std::{
+        ffi::c_void,
+        fs::OpenOptions,
+        io,
+        os::windows::io::IntoRawHandle,
+        path::Path,
+        sync::atomic::{AtomicPtr, AtomicUsize, Ordering},
+    };
+
+    type Filter = extern "system" fn(*const ExceptionPointers) -> i32;
+
+    /// The start of `EXCEPTION_POINTERS`.
+    #[repr(C)]
+    struct ExceptionPointers {
+        exception_record: *const ExceptionRecord,
+        context_record: *const c_void,
+    }
+
+    /// The start of `EXCEPTION_RECORD`.
+    #[repr(C)]
+    struct ExceptionRecord {
+        exception_code: u32,
+    }
+
+    const EXCEPTION_CONTINUE_SEARCH: i32 = 0;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn SetUnhandledExceptionFilter(filter: Option<Filter>) -> Option<Filter>;
+        fn WriteFile(
+            file: *mut c_void,
+            buffer: *const u8,
+            len: u32,
+            written: *mut u32,
+            overlapped: *mut c_void,
+        ) -> i32;
+        fn FlushFileBuffers(file: *mut c_void) -> i32;
+    }
+
+    /// The report file, opened ahead of time.
+    static REPORT_FILE: AtomicPtr<c_void> = AtomicPtr::new(std::ptr::null_mut());
+    /// The filter that was installed before ours, as a `usize` (0 for none).
+    static PREVIOUS_FILTER: AtomicUsize = AtomicUsize::new(0);
+
+    /// Register an unhandled-exception filter that appends the last panic and the current
+    /// thread's breadcrumbs to the file at `path` when the process is about to die from an
+    /// unhandled exception (an access violation, or a `std::process::abort` after a double
+    /// panic), then lets any previously registered filter and Windows Error Reporting run.
+    ///
+    /// The file is opened, and created if needed, when the filter is installed.
+    pub fn install_exception_filter(path: impl AsRef<Path>) -> io::Result<()> {
+        let file = OpenOptions::new()
+            .append(true)
+            .create(true)
+            .open(path)?
+            .into_raw_handle();
+        // installing twice leaks the first handle, instead of closing it while the filter
+        // might be using it
+        REPORT_FILE.store(file.cast(), Ordering::SeqCst);
+        // SAFETY: `on_unhandled_exception` only writes to the pre-opened file.
+        let previous = unsafe { SetUnhandledExceptionFilter(Some(on_unhandled_exception)) };
+        if let Some(previous) = previous {
+            if previous as usize != on_unhandled_exception as Filter as usize {
+                PREVIOUS_FILTER.store(previous as usize, Ordering::SeqCst);
+            }
+        }
+        super::arm();
+        Ok(())
+    }
+
+    extern "system" fn on_unhandled_exception(pointers: *const ExceptionPointers) -> i32 {
+        let file = REPORT_FILE.load(Ordering::SeqCst);
+        let write = |mut bytes: &[u8]| {
+            while !bytes.is_empty() {
+                let mut written = 0;
+                let len = bytes.len().min(u32::MAX as usize) as u32;
+                // SAFETY: `bytes` is valid for `len` bytes and `file` is an open handle.
+                let ok = unsafe {
+                    WriteFile(
+                        file,
+                        bytes.as_ptr(),
+                        len,
+                        &mut written,
+                        std::ptr::null_mut(),
+                    )
+                };
+                if ok == 0 || written == 0 {
+                    return;
+                }
+                bytes = &bytes[written as usize..];
+            }
+        };
+        // SAFETY: Windows passes valid exception pointers to the filter.
+        let code = unsafe {
+            match pointers.as_ref().and_then(|p| p.exception_record.as_ref()) {
+                Some(record) => record.exception_code,
+                None => 0,
+            }
+        };
+        if !file.is_null() {
+            super::dump(write, &[b"unhandled exception 0x", &hex(code)]);
+            // SAFETY: `file` is an open handle.
+            unsafe { FlushFileBuffers(file) };
+        }
+
+        match PREVIOUS_FILTER.load(Ordering::SeqCst) {
+            0 => EXCEPTION_CONTINUE_SEARCH,
+            previous => {
+                // SAFETY: only ever set from a `Filter`.
+                let previous: Filter = unsafe { std::mem::transmute(previous) };
+                previous(pointers)
+            }
+        }
+    }
+
+    /// `code` as 8 uppercase hex digits.
+    fn hex(code: u32) -> [u8; 8] {
+        let mut digits = [0; 8];
+        for (i, digit) in digits.iter_mut().enumerate() {
+            *digit = b"0123456789ABCDEF"[(code >> (28 - 4 * i)) as usize & 0xf];
+        }
+        digits
+    }
+}
@@ -0,0 +1,192 @@
+use std::{
+    io,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, SyncSender, TrySendError},
+        Arc, Condvar, Mutex,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+
+use crate::{sink::Sink, PanicDetails};
+
+/// How many reports a [`Background`] sink queues by default.
+const DEFAULT_CAPACITY: usize = 64;
+
+/// Runs another sink on a dedicated thread, so a slow sink (anything that touches the
+/// network) never blocks the panicking thread.
+///
+/// Reports are queued in a bounded channel; when it is full, new reports are dropped
+/// and counted instead of blocking. Because the process may exit right after a panic,
+/// call [`flush`][Background::flush] before exiting to give queued reports a chance
+/// to be delivered.
+///
+/// ## Examples
+/// ```
+/// use std::{io, sync::Arc, time::Duration};
+/// use panic_message::{sink::{Background, Sink}, PanicDetails};
+///
+/// struct Upload;
+///
+/// impl Sink for Upload {
+///     fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+///         // send `details` somewhere slow
+///         Ok(())
+///     }
+/// }
+///
+/// let sink = Arc::new(Background::new(Upload));
+/// sink.emit(&PanicDetails::new("gus")).unwrap();
+/// assert!(sink.flush(Duration::from_secs(1)));
+/// ```
+pub struct Background {
+    sender: SyncSender<PanicDetails>,
+    state: Arc<State>,
+}
+
+struct State {
+    queued: AtomicU64,
+    dropped: AtomicU64,
+    delivered: Mutex<u64>,
+    delivered_changed: Condvar,
+}
+
+impl Background {
+    /// Run `sink` on a new thread, with room for 64 queued reports.
+    pub fn new(sink: impl Sink) -> Self {
+        Background::with_capacity(sink, DEFAULT_CAPACITY)
+    }
+
+    /// Run `sink` on a new thread, with room for `capacity` queued reports.
+    pub fn with_capacity(sink: impl Sink, capacity: usize) -> Self {
+        let (sender, receiver) = mpsc::sync_channel::<PanicDetails>(capacity);
+        let state = Arc::new(State {
+            queued: AtomicU64::new(0),
+            dropped: AtomicU64::new(0),
+            delivered: Mutex::new(0),
+            delivered_changed: Condvar::new(),
+        });
+        let worker_state = Arc::clone(&state);
+        thread::Builder::new()
+            .name("panic-message-sink".to_string())
+            .spawn(move || {
+                // the hook ignores panics here, a panicking sink would report itself
+                crate::isolate::mark_helper_thread();
+                for details in receiver {
+                    let emitted =
+                        crate::isolate::guarded("background sink", || sink.emit(&details));
+                    if let Some(Err(e)) = emitted {
+                        eprintln!("panic-message: background sink failed: {}", e);
+                    }
+                    let mut delivered = worker_state.delivered.lock().unwrap();
+                    *delivered += 1;
+                    worker_state.delivered_changed.notify_all();
+                }
+            })
+            .expect("failed to spawn the background sink thread");
+        Background { sender, state }
+    }
+
+    /// How many reports were dropped because the queue was full.
+    pub fn dropped(&self) -> u64 {
+        self.state.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Wait up to `timeout` for every report queued so far to be handed to the inner sink.
+    /// Returns whether the queue drained in time.
+    pub fn flush(&self, timeout: Duration) -> bool {
+        let deadline = Instant::now() + timeout;
+        let queued = self.state.queued.load(Ordering::SeqCst);
+        let mut delivered = self.state.delivered.lock().unwrap();
+        while *delivered < queued {
+            let now = Instant::now();
+            if now >= deadline {
+                return false;
+            }
+            delivered = self
+                .state
+                .delivered_changed
+                .wait_timeout(delivered, deadline - now)
+                .unwrap()
+                .0;
+        }
+        true
+    }
+}
+
+impl Sink for Background {
+    fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+        match self.sender.try_send(details.clone()) {
+            Ok(()) => {
+                self.state.queued.fetch_add(1, Ordering::SeqCst);
+                Ok(())
+            }
+            Err(TrySendError::Full(_)) => {
+                self.state.dropped.fetch_add(1, Ordering::Relaxed);
+                Ok(())
+            }
+            Err(TrySendError::Disconnected(_)) => Err(io::Error::new(
+                io::ErrorKind::BrokenPipe,
+                "the background sink thread has exited",
+            )),
+        }
+    }
+
+    fn flush(&self, timeout: Duration) -> bool {
+        Background::flush(self, timeout)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Recorder(Arc<Mutex<Vec<String>>>);
+
+    impl Sink for Recorder {
+        fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+            self.0.lock().unwrap().push(details.message().to_string());
+            Ok(())
+        }
+    }
+
+    struct Blocked(Arc<Mutex<()>>);
+
+    impl Sink for Blocked {
+        fn emit(&self, _: &PanicDetails) -> io::Result<()> {
+            drop(self.0.lock().unwrap());
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn delivers_in_order() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = Background::new(Recorder(Arc::clone(&seen)));
+
+        sink.emit(&PanicDetails::new("a")).unwrap();
+        sink.emit(&PanicDetails::new("b")).unwrap();
+
+        assert!(sink.flush(Duration::from_secs(10)));
+        assert_eq!(vec!["a", "b"], *seen.lock().unwrap());
+        assert_eq!(0, sink.dropped());
+    }
+
+    #[test]
+    fn drops_when_full() {
+        let gate = Arc::new(Mutex::new(()));
+        let held = gate.lock().unwrap();
+        let sink = Background::with_capacity(Blocked(Arc::clone(&gate)), 1);
+
+        for _ in 0..5 {
+            sink.emit(&PanicDetails::new("gus")).unwrap();
+        }
+        // at most one report is in the worker and one in the queue
+        assert!(sink.dropped() >= 3);
+        assert!(!sink.flush(Duration::from_millis(10)));
+
+        drop(held);
+        assert!(sink.flush(Duration::from_secs(10)));
+    }
+}
@@ -0,0 +1,72 @@
+use std::{
+    io,
+    sync::mpsc::{Sender, SyncSender, TrySendError},
+};
+
+use crate::{sink::Sink, PanicDetails};
+
+/// Sends each report down the channel, so a supervising thread, an actor or a test can
+/// react to panics on other threads.
+///
+/// Fails with [`BrokenPipe`][io::ErrorKind::BrokenPipe] once the receiver is gone.
+///
+/// ## Examples
+/// ```
+/// use std::{sync::mpsc, thread};
+/// use panic_message::{PanicDetails, PanicHookBuilder};
+///
+/// let (sender, receiver) = mpsc::channel::<PanicDetails>();
+/// PanicHookBuilder::new().sink(sender).install();
+///
+/// let _ = thread::spawn(|| panic!("gus")).join();
+/// # let _ = std::panic::take_hook();
+///
+/// assert_eq!("gus", receiver.recv().unwrap().message());
+/// ```
+impl Sink for Sender<PanicDetails> {
+    fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+        self.send(details.clone()).map_err(|_| disconnected())
+    }
+}
+
+/// Sends each report down the channel without waiting for room, failing with
+/// [`WouldBlock`][io::ErrorKind::WouldBlock] when it is full rather than blocking the
+/// panicking thread, and with [`BrokenPipe`][io::ErrorKind::BrokenPipe] once the receiver
+/// is gone.
+impl Sink for SyncSender<PanicDetails> {
+    fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+        self.try_send(details.clone()).map_err(|e| match e {
+            TrySendError::Full(_) => {
+                io::Error::new(io::ErrorKind::WouldBlock, "panic report channel is full")
+            }
+            TrySendError::Disconnected(_) => disconnected(),
+        })
+    }
+}
+
+fn disconnected() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::BrokenPipe,
+        "panic report channel is disconnected",
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::mpsc;
+
+    #[test]
+    fn sync_channels_never_block() {
+        let (sender, receiver) = mpsc::sync_channel(1);
+
+        sender.emit(&PanicDetails::new("gus")).unwrap();
+        let full = sender.emit(&PanicDetails::new("wynn")).unwrap_err();
+        assert_eq!(io::ErrorKind::WouldBlock, full.kind());
+
+        assert_eq!("gus", receiver.recv().unwrap().message());
+        drop(receiver);
+        let gone = sender.emit(&PanicDetails::new("gus")).unwrap_err();
+        assert_eq!(io::ErrorKind::BrokenPipe, gone.kind());
+    }
+}
@@ -0,0 +1,38 @@
+use std::io;
+
+use wasm_bindgen::prelude::wasm_bindgen;
+
+use crate::{sink::Sink, PanicDetails, PanicHookBuilder};
+
+#[wasm_bindgen]
+extern "C" {
+    #[wasm_bindgen(js_namespace = console)]
+    fn error(message: &str);
+}
+
+/// A sink that writes each report to the browser or Node.js console with `console.error`,
+/// as it [displays][PanicDetails#impl-Display-for-PanicDetails], on
+/// `wasm32-unknown-unknown`, which has no stderr.
+///
+/// Reports there have no time or uptime, because the target has no clock.
+///
+/// ## Examples
+/// ```no_run
+/// use panic_message::{sink::Console, PanicHookBuilder};
+///
+/// PanicHookBuilder::new().sink(Console).install();
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Console;
+
+impl Sink for Console {
+    fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+        error(&details.to_string());
+        Ok(())
+    }
+}
+
+/// Install a panic hook that writes each panic to the console with a [`Console`] sink, in place of `console_error_panic_hook`, see [`PanicHookBuilder`] for more options.
+pub fn install_console_hook() {
+    PanicHookBuilder::new().sink(Console).install();
+}
@@ -0,0 +1,60 @@
+use std::{fmt, io};
+
+use crate::{sink::Sink, PanicDetails};
+
+/// A sink that calls `f` with each report, see [`from_fn`].
+pub struct FromFn<F>(F);
+
+/// A sink that calls `f` with each report, to hand panics to code that has no type
+/// implementing [`Sink`]. For the `log` crate, the `log` feature has a sink of its own.
+///
+/// ## Examples
+/// ```
+/// use std::sync::{Arc, Mutex};
+/// use panic_message::{sink::{self, Sink}, PanicDetails};
+///
+/// let messages = Arc::new(Mutex::new(Vec::new()));
+/// let seen = Arc::clone(&messages);
+/// let sink = sink::from_fn(move |details| {
+///     seen.lock().unwrap().push(details.message().to_string());
+///     Ok(())
+/// });
+///
+/// sink.emit(&PanicDetails::new("gus")).unwrap();
+/// assert_eq!(vec!["gus"], *messages.lock().unwrap());
+/// ```
+pub fn from_fn<F>(f: F) -> FromFn<F>
+where
+    F: Fn(&PanicDetails) -> io::Result<()> + Send + Sync + 'static,
+{
+    FromFn(f)
+}
+
+impl<F> Sink for FromFn<F>
+where
+    F: Fn(&PanicDetails) -> io::Result<()> + Send + Sync + 'static,
+{
+    fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+        (self.0)(details)
+    }
+}
+
+impl<F> fmt::Debug for FromFn<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FromFn").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn errors_are_returned() {
+        let sink = from_fn(|details| Err(io::Error::other(details.message().to_string())));
+
+        let error = sink.emit(&PanicDetails::new("gus")).unwrap_err();
+
+        assert_eq!("gus", error.to_string());
+    }
+}
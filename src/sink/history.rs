@@ -0,0 +1,145 @@
+use std::{
+    collections::{HashMap, VecDeque},
+    io,
+    sync::Mutex,
+};
+
+use crate::{sink::Sink, PanicDetails};
+
+/// Keeps the most recent reports in memory, for a service that survives its panics to
+/// show its last few on a health endpoint.
+///
+/// A history keeps up to its capacity of reports in total, or with
+/// [`per_thread`][History::per_thread] for each thread, so a thread that keeps panicking
+/// doesn't push out the others' reports. Reports of threads that have exited are kept
+/// until they are [cleared][History::clear].
+///
+/// ## Examples
+/// ```
+/// use std::{panic::catch_unwind, sync::Arc};
+/// use panic_message::{sink::History, PanicHookBuilder};
+///
+/// let history = Arc::new(History::new(2));
+/// PanicHookBuilder::new().sink(Arc::clone(&history)).install();
+///
+/// for message in ["a", "b", "c"] {
+///     let _ = catch_unwind(|| panic!("{}", message));
+/// }
+/// # let _ = std::panic::take_hook();
+///
+/// let messages: Vec<_> = history.snapshot().iter().map(|r| r.message().to_string()).collect();
+/// assert_eq!(vec!["b", "c"], messages);
+/// ```
+#[derive(Debug)]
+pub struct History {
+    capacity: usize,
+    per_thread: bool,
+    state: Mutex<State>,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    // reports numbered in the order they arrived, by thread id when kept per thread
+    reports: HashMap<Option<u64>, VecDeque<(u64, PanicDetails)>>,
+    next: u64,
+}
+
+impl History {
+    /// Keep the last `capacity` reports.
+    pub fn new(capacity: usize) -> Self {
+        History {
+            capacity,
+            per_thread: false,
+            state: Mutex::default(),
+        }
+    }
+
+    /// Keep the last `capacity` reports of each thread, by [thread id][PanicDetails::thread_id].
+    pub fn per_thread(capacity: usize) -> Self {
+        History {
+            per_thread: true,
+            ..History::new(capacity)
+        }
+    }
+
+    /// The reports kept, oldest first.
+    pub fn snapshot(&self) -> Vec<PanicDetails> {
+        let state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let mut reports: Vec<_> = state.reports.values().flatten().collect();
+        reports.sort_by_key(|(n, _)| *n);
+        reports
+            .into_iter()
+            .map(|(_, report)| report.clone())
+            .collect()
+    }
+
+    /// Forget every report kept.
+    pub fn clear(&self) {
+        self.state
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .reports
+            .clear();
+    }
+}
+
+impl Sink for History {
+    fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+        if self.capacity == 0 {
+            return Ok(());
+        }
+        let key = if self.per_thread {
+            details.thread_id()
+        } else {
+            None
+        };
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let n = state.next;
+        state.next += 1;
+        let reports = state.reports.entry(key).or_default();
+        if reports.len() == self.capacity {
+            reports.pop_front();
+        }
+        reports.push_back((n, details.clone()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn report(message: &str, thread_id: u64) -> PanicDetails {
+        PanicDetails::new(message).with_thread_id(thread_id)
+    }
+
+    fn messages(history: &History) -> Vec<String> {
+        history
+            .snapshot()
+            .iter()
+            .map(|report| report.message().to_string())
+            .collect()
+    }
+
+    #[test]
+    fn per_thread() {
+        let history = History::per_thread(1);
+
+        for (message, thread_id) in [("a", 1), ("b", 2), ("c", 1), ("d", 1)] {
+            history.emit(&report(message, thread_id)).unwrap();
+        }
+
+        assert_eq!(vec!["b", "d"], messages(&history));
+        history.clear();
+        assert!(history.snapshot().is_empty());
+    }
+
+    #[test]
+    fn empty() {
+        let history = History::new(0);
+
+        history.emit(&report("a", 1)).unwrap();
+
+        assert!(history.snapshot().is_empty());
+    }
+}
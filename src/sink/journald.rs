@@ -0,0 +1,144 @@
+use std::{
+    io,
+    os::unix::net::UnixDatagram,
+    path::{Path, PathBuf},
+};
+
+use crate::{sink::Sink, PanicDetails};
+
+/// Where journald listens for the native protocol.
+const SOCKET: &str = "/run/systemd/journal/socket";
+
+/// `LOG_ERR`.
+const PRIORITY: &str = "3";
+
+/// A sink that sends reports to the systemd journal with the
+/// [native protocol](https://systemd.io/JOURNAL_NATIVE_PROTOCOL/), as entries with
+/// structured fields instead of lines of stderr, so `journalctl` can filter them:
+///
+/// - `MESSAGE`: the report, as it [displays][PanicDetails#impl-Display-for-PanicDetails]
+/// - `PRIORITY`: `3`, an error
+/// - `CODE_FILE`, `CODE_LINE` and `CODE_COLUMN`: the panic's location, if known
+/// - `THREAD_NAME`: the panicking thread's name, if known
+/// - `PANIC_MESSAGE`: the panic message alone
+/// - `SYSLOG_IDENTIFIER`: the [identifier][Journald::with_identifier], if set
+///
+/// Reports are sent as single datagrams, so one larger than the socket's buffer fails with
+/// an error instead of being sent through a file descriptor, like `sd_journal_send` would.
+/// Trim them with [`PanicHookBuilder::with_size_budget`][crate::PanicHookBuilder::with_size_budget].
+///
+/// ## Examples
+/// ```no_run
+/// use panic_message::{sink::Journald, PanicHookBuilder};
+///
+/// PanicHookBuilder::new()
+///     .sink(Journald::new()?.with_identifier("my-daemon"))
+///     .install();
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct Journald {
+    socket: UnixDatagram,
+    path: PathBuf,
+    identifier: Option<String>,
+}
+
+impl Journald {
+    /// Send reports to the journal.
+    pub fn new() -> io::Result<Self> {
+        Journald::with_socket(SOCKET)
+    }
+
+    /// Send reports to the journal listening at `path`, for containers that mount its socket
+    /// somewhere else.
+    pub fn with_socket(path: impl AsRef<Path>) -> io::Result<Self> {
+        Ok(Journald {
+            socket: UnixDatagram::unbound()?,
+            path: path.as_ref().to_path_buf(),
+            identifier: None,
+        })
+    }
+
+    /// Set `SYSLOG_IDENTIFIER` to `identifier`, usually the program's name, which
+    /// `journalctl -t` filters on.
+    pub fn with_identifier(mut self, identifier: impl Into<String>) -> Self {
+        self.identifier = Some(identifier.into());
+        self
+    }
+}
+
+impl Sink for Journald {
+    fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+        let mut entry = Vec::new();
+        field(&mut entry, "MESSAGE", &details.to_string());
+        field(&mut entry, "PRIORITY", PRIORITY);
+        if let Some(location) = details.location() {
+            field(&mut entry, "CODE_FILE", location.file());
+            field(&mut entry, "CODE_LINE", &location.line().to_string());
+            field(&mut entry, "CODE_COLUMN", &location.column().to_string());
+        }
+        if let Some(thread) = details.thread() {
+            field(&mut entry, "THREAD_NAME", thread);
+        }
+        field(&mut entry, "PANIC_MESSAGE", details.message());
+        if let Some(identifier) = &self.identifier {
+            field(&mut entry, "SYSLOG_IDENTIFIER", identifier);
+        }
+        self.socket.send_to(&entry, &self.path)?;
+        Ok(())
+    }
+}
+
+/// Append a field: `NAME=value` on a line, or for a value spanning lines, the name on a
+/// line followed by the value's length as a little-endian `u64`, the value and a newline.
+fn field(entry: &mut Vec<u8>, name: &str, value: &str) {
+    entry.extend_from_slice(name.as_bytes());
+    if value.contains('\n') {
+        entry.push(b'\n');
+        entry.extend_from_slice(&(value.len() as u64).to_le_bytes());
+    } else {
+        entry.push(b'=');
+    }
+    entry.extend_from_slice(value.as_bytes());
+    entry.push(b'\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Location;
+
+    #[test]
+    fn sends_fields() {
+        let path =
+            std::env::temp_dir().join(format!("panic-message-{}-journald", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let journal = UnixDatagram::bind(&path).unwrap();
+
+        let details = PanicDetails::new("gus")
+            .with_location(Location::new("src/main.rs", 3, 5))
+            .with_thread("main");
+        Journald::with_socket(&path)
+            .unwrap()
+            .with_identifier("daemon")
+            .emit(&details)
+            .unwrap();
+
+        let mut buffer = [0; 1024];
+        let len = journal.recv(&mut buffer).unwrap();
+        let _ = std::fs::remove_file(&path);
+        let message = "thread 'main' panicked at src/main.rs:3:5:\ngus";
+        let mut expected = b"MESSAGE\n".to_vec();
+        expected.extend_from_slice(&(message.len() as u64).to_le_bytes());
+        expected.extend_from_slice(message.as_bytes());
+        expected.push(b'\n');
+        expected.extend_from_slice(
+            b"PRIORITY=3\nCODE_FILE=src/main.rs\nCODE_LINE=3\nCODE_COLUMN=5\nTHREAD_NAME=main\n\
+              PANIC_MESSAGE=gus\nSYSLOG_IDENTIFIER=daemon\n",
+        );
+        assert_eq!(
+            String::from_utf8_lossy(&expected),
+            String::from_utf8_lossy(&buffer[..len])
+        );
+    }
+}
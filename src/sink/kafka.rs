@@ -0,0 +1,134 @@
+use std::{io, sync::Mutex, time::Duration};
+
+use rdkafka::{
+    config::ClientConfig,
+    error::KafkaError,
+    producer::{BaseProducer, BaseRecord, DeliveryResult, Producer, ProducerContext},
+    ClientContext,
+};
+
+use crate::{sink::Sink, PanicDetails};
+
+/// A sink that publishes each report to a Kafka topic, with
+/// [rdkafka](https://docs.rs/rdkafka).
+///
+/// The record's value is [`PanicDetails::to_json`], and its key the
+/// [fingerprint][PanicDetails::fingerprint] in hex, so panics from the same place land in the
+/// same partition. `emit` waits until the broker acknowledges the record, up to the
+/// [timeout][Kafka::with_timeout], and fails if it doesn't. librdkafka retries the send in
+/// that time.
+///
+/// Waiting for a broker doesn't belong on the panicking thread, so wrap this sink in a
+/// [`Background`][crate::sink::Background], which queues reports for it and drops them
+/// when the queue is full. [`Background::flush`][crate::sink::Background::flush] then
+/// waits for queued reports to be acknowledged.
+///
+/// ## Examples
+/// ```no_run
+/// use panic_message::{sink::{Background, Kafka}, PanicHookBuilder};
+///
+/// PanicHookBuilder::new()
+///     .sink(Background::new(Kafka::new("kafka-1:9092,kafka-2:9092", "panics")?))
+///     .install();
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct Kafka {
+    producer: BaseProducer<Deliveries>,
+    topic: String,
+    timeout: Duration,
+}
+
+/// Keeps the error of the last record that failed to be delivered.
+#[derive(Default)]
+struct Deliveries {
+    failed: Mutex<Option<KafkaError>>,
+}
+
+impl ClientContext for Deliveries {}
+
+impl ProducerContext for Deliveries {
+    type DeliveryOpaque = ();
+
+    fn delivery(&self, result: &DeliveryResult<'_>, _: ()) {
+        if let Err((e, _)) = result {
+            *self.failed.lock().unwrap_or_else(|e| e.into_inner()) = Some(e.clone());
+        }
+    }
+}
+
+impl Kafka {
+    /// Publish to `topic` on the cluster reached through `bootstrap_servers`, a
+    /// comma-separated list of `host:port`s.
+    pub fn new(bootstrap_servers: &str, topic: impl Into<String>) -> io::Result<Self> {
+        let mut config = ClientConfig::new();
+        config.set("bootstrap.servers", bootstrap_servers);
+        Kafka::with_config(&config, topic)
+    }
+
+    /// Publish to `topic` with a producer created from `config`, for TLS, SASL and the
+    /// other settings of librdkafka.
+    pub fn with_config(config: &ClientConfig, topic: impl Into<String>) -> io::Result<Self> {
+        let producer = config
+            .create_with_context(Deliveries::default())
+            .map_err(io::Error::other)?;
+        Ok(Kafka {
+            producer,
+            topic: topic.into(),
+            timeout: Duration::from_secs(5),
+        })
+    }
+
+    /// How long `emit` waits for the broker to acknowledge a report. Defaults to 5 seconds.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+}
+
+impl Sink for Kafka {
+    fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+        let key = format!("{:016x}", details.fingerprint());
+        let report = details.to_json();
+        self.producer
+            .send(BaseRecord::to(&self.topic).key(&key).payload(&report))
+            .map_err(|(e, _)| io::Error::other(e))?;
+        self.producer.flush(self.timeout).map_err(|e| {
+            io::Error::new(
+                io::ErrorKind::TimedOut,
+                format!("kafka: report not acknowledged: {}", e),
+            )
+        })?;
+        let failed = self
+            .producer
+            .context()
+            .failed
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take();
+        match failed {
+            Some(e) => Err(io::Error::other(format!("kafka: {}", e))),
+            None => Ok(()),
+        }
+    }
+
+    fn flush(&self, timeout: Duration) -> bool {
+        self.producer.flush(timeout).is_ok()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unreachable_broker() {
+        let sink = Kafka::new("127.0.0.1:1", "panics")
+            .unwrap()
+            .with_timeout(Duration::from_millis(100));
+
+        let error = sink.emit(&PanicDetails::new("gus")).unwrap_err();
+
+        assert_eq!(io::ErrorKind::TimedOut, error.kind());
+        assert!(!sink.flush(Duration::from_millis(10)));
+    }
+}
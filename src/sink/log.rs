@@ -0,0 +1,155 @@
+use std::io;
+
+use log::{kv::Key, Level, Record};
+
+use crate::{sink::Sink, PanicDetails, PanicHookBuilder};
+
+/// A sink that emits each report as a [`log`](https://docs.rs/log) record, for binaries that
+/// already route `log` to files or syslog.
+///
+/// The record is an error, with the report as it
+/// [displays][PanicDetails#impl-Display-for-PanicDetails] as its message, the panic's file
+/// and line as its own, and these key-values:
+///
+/// - `message`: the panic message alone
+/// - `thread`: the panicking thread's name, if known
+///
+/// The target is `"panic"`, unless [set][Log::with_target] otherwise.
+///
+/// ## Examples
+/// ```
+/// use panic_message::{sink, PanicHookBuilder};
+///
+/// PanicHookBuilder::new()
+///     .sink(sink::Log::new().with_target("my_daemon::panic"))
+///     .install();
+/// # let _ = std::panic::take_hook();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Log {
+    target: String,
+}
+
+impl Log {
+    /// Emit records with the target `"panic"`.
+    pub fn new() -> Self {
+        Log {
+            target: "panic".to_string(),
+        }
+    }
+
+    /// Emit records with the target `target`, for loggers that filter by target.
+    pub fn with_target(mut self, target: impl Into<String>) -> Self {
+        self.target = target.into();
+        self
+    }
+}
+
+impl Default for Log {
+    fn default() -> Self {
+        Log::new()
+    }
+}
+
+impl Sink for Log {
+    fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+        let mut fields = vec![(Key::from("message"), details.message())];
+        if let Some(thread) = details.thread() {
+            fields.push((Key::from("thread"), thread));
+        }
+        let fields = fields.as_slice();
+        log::logger().log(
+            &Record::builder()
+                .args(format_args!("{}", details))
+                .level(Level::Error)
+                .target(&self.target)
+                .file(details.location().map(|location| location.file()))
+                .line(details.location().map(|location| location.line()))
+                .key_values(&fields)
+                .build(),
+        );
+        Ok(())
+    }
+
+    fn flush(&self, _: std::time::Duration) -> bool {
+        log::logger().flush();
+        true
+    }
+}
+
+/// Install a panic hook that emits each panic as a `log::error!` record with a [`Log`] sink,
+/// see [`PanicHookBuilder`] for more options.
+pub fn install_log_hook() {
+    PanicHookBuilder::new().sink(Log::new()).install();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Mutex;
+
+    use log::Metadata;
+
+    use super::*;
+    use crate::Location;
+
+    /// The records it was sent, as their target, file, line, message and key-values.
+    struct Recorder(Mutex<Vec<String>>);
+
+    impl log::Log for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn log(&self, record: &Record<'_>) {
+            let field = |key: &str| {
+                record
+                    .key_values()
+                    .get(Key::from(key))
+                    .map(|value| value.to_string())
+            };
+            self.0.lock().unwrap().push(format!(
+                "{} {} {:?}:{:?} {:?} {:?} {:?}",
+                record.level(),
+                record.target(),
+                record.file(),
+                record.line(),
+                record.args().to_string(),
+                field("message"),
+                field("thread"),
+            ));
+        }
+
+        fn flush(&self) {}
+    }
+
+    static RECORDER: Recorder = Recorder(Mutex::new(Vec::new()));
+
+    #[test]
+    fn emits_records() {
+        log::set_logger(&RECORDER).unwrap();
+        log::set_max_level(log::LevelFilter::Error);
+
+        Log::new()
+            .emit(
+                &PanicDetails::new("gus")
+                    .with_location(Location::new("src/main.rs", 3, 5))
+                    .with_thread("main"),
+            )
+            .unwrap();
+        Log::new()
+            .with_target("daemon")
+            .emit(&PanicDetails::new("wynn"))
+            .unwrap();
+
+        assert_eq!(
+            vec![
+                "ERROR panic Some(\"src/main.rs\"):Some(3) \
+                 \"thread 'main' panicked at src/main.rs:3:5:\\ngus\" \
+                 Some(\"gus\") Some(\"main\")",
+                "ERROR daemon None:None \"thread '<unnamed>' panicked:\\nwynn\" \
+                 Some(\"wynn\") None",
+            ],
+            *RECORDER.0.lock().unwrap()
+        );
+    }
+}
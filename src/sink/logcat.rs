@@ -0,0 +1,137 @@
+use std::{
+    ffi::CString,
+    io,
+    os::raw::{c_char, c_int},
+};
+
+use crate::{sink::Sink, PanicDetails};
+
+/// `ANDROID_LOG_ERROR` from `<android/log.h>`.
+const ERROR: c_int = 6;
+
+/// The most text logcat keeps of one entry; longer entries are cut, so reports are split
+/// into entries of at most this many bytes.
+const MAX_ENTRY: usize = 4000;
+
+#[link(name = "log")]
+extern "C" {
+    fn __android_log_write(priority: c_int, tag: *const c_char, text: *const c_char) -> c_int;
+}
+
+/// A sink that writes reports to logcat at the error priority, so panics in a library
+/// loaded by an Android app show up in `adb logcat` instead of going to a stderr nobody
+/// reads.
+///
+/// Each report is written as it [displays][PanicDetails#impl-Display-for-PanicDetails],
+/// split at line boundaries into entries logcat doesn't truncate.
+///
+/// This links against the NDK's `liblog`.
+///
+/// ## Examples
+/// ```no_run
+/// use panic_message::{sink::Logcat, PanicHookBuilder};
+///
+/// PanicHookBuilder::new()
+///     .sink(Logcat::new().with_tag("my-library"))
+///     .install();
+/// ```
+#[derive(Debug, Clone)]
+pub struct Logcat {
+    tag: CString,
+}
+
+impl Logcat {
+    /// A sink logging under the tag `panic`.
+    pub fn new() -> Self {
+        Logcat {
+            tag: CString::new("panic").expect("no NUL in the tag"),
+        }
+    }
+
+    /// Log under `tag` instead, usually the app's or library's name. NUL bytes in it are
+    /// dropped.
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = c_string(tag.into());
+        self
+    }
+}
+
+impl Default for Logcat {
+    fn default() -> Self {
+        Logcat::new()
+    }
+}
+
+impl Sink for Logcat {
+    fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+        for entry in entries(&details.to_string()) {
+            let text = c_string(entry.to_string());
+            // SAFETY: both strings are NUL-terminated and outlive the call.
+            let written = unsafe { __android_log_write(ERROR, self.tag.as_ptr(), text.as_ptr()) };
+            if written < 0 {
+                return Err(io::Error::from_raw_os_error(-written));
+            }
+        }
+        Ok(())
+    }
+}
+
+fn c_string(mut text: String) -> CString {
+    text.retain(|c| c != '\0');
+    CString::new(text).expect("NUL bytes were removed")
+}
+
+/// Split `text` into entries of at most [`MAX_ENTRY`] bytes, at the last line break that
+/// fits, or at a character boundary within a longer line.
+fn entries(text: &str) -> impl Iterator<Item = &str> {
+    let mut rest = text;
+    std::iter::from_fn(move || {
+        if rest.is_empty() {
+            return None;
+        }
+        if rest.len() <= MAX_ENTRY {
+            return Some(std::mem::take(&mut rest));
+        }
+        let mut end = MAX_ENTRY;
+        while !rest.is_char_boundary(end) {
+            end -= 1;
+        }
+        let (entry, next) = match rest[..end].rfind('\n') {
+            Some(newline) => (&rest[..newline], &rest[newline + 1..]),
+            None => rest.split_at(end),
+        };
+        rest = next;
+        Some(entry)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn splits_long_reports() {
+        let line = "x".repeat(1500);
+        let text = [line.as_str(); 4].join("\n");
+
+        let split: Vec<_> = entries(&text).collect();
+
+        assert_eq!(
+            vec![[line.as_str(); 2].join("\n"), [line.as_str(); 2].join("\n")],
+            split
+        );
+        assert_eq!(vec!["gus"], entries("gus").collect::<Vec<_>>());
+
+        let long = "é".repeat(MAX_ENTRY);
+        let split: Vec<_> = entries(&long).collect();
+        assert_eq!(long, split.concat());
+        assert!(split.iter().all(|entry| entry.len() <= MAX_ENTRY));
+    }
+
+    #[test]
+    fn tag_without_nul() {
+        let logcat = Logcat::new().with_tag("my\0library");
+
+        assert_eq!("mylibrary", logcat.tag.to_str().unwrap());
+    }
+}
@@ -0,0 +1,102 @@
+//! Destinations for panic reports, fed by the hook [`PanicHookBuilder`][crate::PanicHookBuilder]
+//! installs.
+//!
+//! Sinks are called from inside the panic hook, which waits for them, so they should be
+//! quick. Sinks that talk to the network should be wrapped in a [`Background`] sink.
+//! [`from_fn`] makes a sink out of a closure, and the `Sender` and `SyncSender` of a channel
+//! of `PanicDetails` forward reports to its receiver. With the `log` feature, `Log` emits
+//! reports as `log` records, and with the `tracing` feature, `Tracing` as `tracing` events.
+//! On wasm, the `wasm` feature's `Console` writes reports with `console.error`. [`ReportFile`] writes each report to a file for users to send
+//! in. On Windows, `DebugOutput` and `EventLog` send reports to the debugger and the Windows
+//! Event Log, for GUI applications without a visible stderr.
+use std::{io, sync::Arc, time::Duration};
+
+use crate::PanicDetails;
+
+mod background;
+mod channel;
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+mod console;
+mod from_fn;
+mod history;
+#[cfg(all(target_os = "linux", feature = "journald"))]
+mod journald;
+#[cfg(feature = "kafka")]
+mod kafka;
+#[cfg(feature = "log")]
+mod log;
+#[cfg(all(target_os = "android", feature = "android"))]
+mod logcat;
+#[cfg(feature = "cbor")]
+mod pipe;
+#[cfg(feature = "redis")]
+mod redis;
+mod report_file;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+mod stream;
+#[cfg(feature = "tracing")]
+mod tracing;
+#[cfg(windows)]
+mod windows;
+
+#[cfg(feature = "log")]
+pub use self::log::{install_log_hook, Log};
+#[cfg(feature = "tracing")]
+pub use self::tracing::{install_tracing_hook, Tracing};
+pub use background::Background;
+#[cfg(all(target_arch = "wasm32", feature = "wasm"))]
+pub use console::{install_console_hook, Console};
+pub use from_fn::{from_fn, FromFn};
+pub use history::History;
+#[cfg(all(target_os = "linux", feature = "journald"))]
+pub use journald::Journald;
+#[cfg(feature = "kafka")]
+pub use kafka::Kafka;
+#[cfg(all(target_os = "android", feature = "android"))]
+pub use logcat::Logcat;
+#[cfg(feature = "cbor")]
+pub use pipe::Pipe;
+#[cfg(feature = "redis")]
+pub use redis::RedisStream;
+pub use report_file::{ReportFile, ReportFileFormat};
+#[cfg(feature = "sqlite")]
+pub use sqlite::{SqliteSink, StoredPanic};
+pub use stream::Stream;
+#[cfg(windows)]
+pub use windows::{DebugOutput, EventLog};
+
+/// Somewhere to send [`PanicDetails`].
+pub trait Sink: Send + Sync + 'static {
+    /// Send one report. Errors are reported on stderr by the hook and otherwise ignored,
+    /// there is nobody else to report them to.
+    fn emit(&self, details: &PanicDetails) -> io::Result<()>;
+
+    /// Wait up to `timeout` for the reports this sink accepted to be delivered, returning
+    /// whether they were. Sinks that deliver reports before `emit` returns don't need to
+    /// override this.
+    fn flush(&self, timeout: Duration) -> bool {
+        let _ = timeout;
+        true
+    }
+}
+
+impl<S: Sink + ?Sized> Sink for Box<S> {
+    fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+        (**self).emit(details)
+    }
+
+    fn flush(&self, timeout: Duration) -> bool {
+        (**self).flush(timeout)
+    }
+}
+
+impl<S: Sink + ?Sized> Sink for Arc<S> {
+    fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+        (**self).emit(details)
+    }
+
+    fn flush(&self, timeout: Duration) -> bool {
+        (**self).flush(timeout)
+    }
+}
@@ -0,0 +1,95 @@
+use std::{fs::File, io};
+
+use crate::{sink::Sink, PanicDetails};
+
+/// Writes each report as a [CBOR frame][PanicDetails::write_framed] to a pipe or file, so a
+/// parent process can read the panics of a subprocess with [`PanicDetails::read_framed`],
+/// and log them or [replay][PanicDetails::replay] them as its own.
+///
+/// ## Examples
+/// In the subprocess, report panics down fd 3:
+/// ```no_run
+/// # #[cfg(unix)] {
+/// use std::os::unix::io::FromRawFd;
+/// use panic_message::{sink::Pipe, PanicHookBuilder};
+///
+/// // SAFETY: the parent opened fd 3 for us, and nothing else uses it
+/// let pipe = unsafe { Pipe::from_raw_fd(3) };
+/// PanicHookBuilder::new().sink(pipe).install();
+/// # }
+/// ```
+///
+/// In the parent, re-raise the first panic the subprocess reported:
+/// ```no_run
+/// use std::process::{Command, Stdio};
+/// use panic_message::PanicDetails;
+///
+/// let mut child = Command::new("sandboxed").stderr(Stdio::piped()).spawn().unwrap();
+/// if let Some(details) = PanicDetails::read_framed(child.stderr.take().unwrap()).unwrap() {
+///     details.replay();
+/// }
+/// ```
+#[derive(Debug)]
+pub struct Pipe {
+    file: File,
+}
+
+impl From<File> for Pipe {
+    /// Write reports to `file`.
+    fn from(file: File) -> Self {
+        Pipe { file }
+    }
+}
+
+#[cfg(unix)]
+impl From<std::os::unix::io::OwnedFd> for Pipe {
+    /// Write reports to `fd`.
+    fn from(fd: std::os::unix::io::OwnedFd) -> Self {
+        Pipe::from(File::from(fd))
+    }
+}
+
+#[cfg(unix)]
+impl std::os::unix::io::FromRawFd for Pipe {
+    /// Write reports to `fd`, which the pipe takes ownership of and closes when dropped.
+    unsafe fn from_raw_fd(fd: std::os::unix::io::RawFd) -> Self {
+        // SAFETY: forwarded to the caller.
+        Pipe::from(unsafe { File::from_raw_fd(fd) })
+    }
+}
+
+impl Sink for Pipe {
+    fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+        details.write_framed(&self.file)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_round_trip() {
+        let path = std::env::temp_dir().join(format!("panic-message-{}-pipe", std::process::id()));
+        let sink = Pipe::from(File::create(&path).unwrap());
+        let details = PanicDetails::new("gus").with_thread("main");
+
+        sink.emit(&details).unwrap();
+        sink.emit(&PanicDetails::new("wynn")).unwrap();
+
+        let mut written = File::open(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(
+            Some(details),
+            PanicDetails::read_framed(&mut written).unwrap()
+        );
+        assert_eq!(
+            "wynn",
+            PanicDetails::read_framed(&mut written)
+                .unwrap()
+                .unwrap()
+                .message()
+        );
+        assert_eq!(None, PanicDetails::read_framed(&mut written).unwrap());
+    }
+}
@@ -0,0 +1,220 @@
+use std::{
+    io::{self, BufRead, BufReader, Read, Write},
+    net::TcpStream,
+    sync::Mutex,
+    time::Duration,
+};
+
+use crate::{sink::Sink, PanicDetails};
+
+/// A sink that appends each report to a [Redis stream](https://redis.io/docs/data-types/streams/)
+/// with `XADD`, trimming the stream to roughly `max_len` entries.
+///
+/// Each entry has a `message` field, a `kind` field (see [`PanicKind`][crate::PanicKind])
+/// and a `report` field holding [`PanicDetails::to_json`].
+///
+/// The connection is made on the first report and reused; after an error it is dropped
+/// and made again for the next report. Wrap this sink in a
+/// [`Background`][crate::sink::Background] to keep network round trips off the panicking thread.
+///
+/// ## Examples
+/// ```no_run
+/// use panic_message::{sink::{Background, RedisStream}, PanicHookBuilder};
+///
+/// PanicHookBuilder::new()
+///     .sink(Background::new(
+///         RedisStream::new("127.0.0.1:6379", "panics").with_max_len(10_000),
+///     ))
+///     .install();
+/// ```
+pub struct RedisStream {
+    addr: String,
+    key: String,
+    max_len: usize,
+    password: Option<String>,
+    timeout: Duration,
+    connection: Mutex<Option<BufReader<TcpStream>>>,
+}
+
+impl RedisStream {
+    /// Add reports to the stream `key` on the Redis server at `addr` (`host:port`).
+    pub fn new(addr: impl Into<String>, key: impl Into<String>) -> Self {
+        RedisStream {
+            addr: addr.into(),
+            key: key.into(),
+            max_len: 1000,
+            password: None,
+            timeout: Duration::from_secs(5),
+            connection: Mutex::new(None),
+        }
+    }
+
+    /// Trim the stream to about `max_len` entries (`MAXLEN ~`). Defaults to 1000.
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = max_len;
+        self
+    }
+
+    /// Authenticate with `AUTH` after connecting.
+    pub fn with_password(mut self, password: impl Into<String>) -> Self {
+        self.password = Some(password.into());
+        self
+    }
+
+    /// The connect, read and write timeout. Defaults to 5 seconds.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    fn connect(&self) -> io::Result<BufReader<TcpStream>> {
+        let mut last_error = None;
+        for addr in std::net::ToSocketAddrs::to_socket_addrs(self.addr.as_str())? {
+            match TcpStream::connect_timeout(&addr, self.timeout) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(self.timeout))?;
+                    stream.set_write_timeout(Some(self.timeout))?;
+                    let mut connection = BufReader::new(stream);
+                    if let Some(password) = &self.password {
+                        command(&mut connection, &["AUTH", password])?;
+                    }
+                    return Ok(connection);
+                }
+                Err(e) => last_error = Some(e),
+            }
+        }
+        Err(last_error.unwrap_or_else(|| {
+            io::Error::new(io::ErrorKind::InvalidInput, "address resolved to nothing")
+        }))
+    }
+}
+
+impl Sink for RedisStream {
+    fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+        let max_len = self.max_len.to_string();
+        let report = details.to_json();
+        let args = [
+            "XADD",
+            &self.key,
+            "MAXLEN",
+            "~",
+            &max_len,
+            "*",
+            "message",
+            details.message(),
+            "kind",
+            details.kind().as_str(),
+            "report",
+            &report,
+        ];
+
+        let mut connection = self.connection.lock().unwrap_or_else(|e| e.into_inner());
+        if connection.is_none() {
+            *connection = Some(self.connect()?);
+        }
+        let result = command(connection.as_mut().unwrap(), &args);
+        if result.is_err() {
+            *connection = None;
+        }
+        result
+    }
+}
+
+/// Send one command and read its reply, failing on error replies.
+fn command(connection: &mut BufReader<TcpStream>, args: &[&str]) -> io::Result<()> {
+    let mut request = format!("*{}\r\n", args.len()).into_bytes();
+    for arg in args {
+        request.extend_from_slice(format!("${}\r\n", arg.len()).as_bytes());
+        request.extend_from_slice(arg.as_bytes());
+        request.extend_from_slice(b"\r\n");
+    }
+    connection.get_mut().write_all(&request)?;
+
+    let mut line = String::new();
+    connection.read_line(&mut line)?;
+    let line = line.trim_end();
+    match line.as_bytes().first() {
+        Some(b'+') | Some(b':') => Ok(()),
+        Some(b'$') => {
+            // a bulk string, the id of the new entry
+            let len: i64 = line[1..].parse().map_err(|_| protocol_error(line))?;
+            if len >= 0 {
+                io::copy(
+                    &mut connection.by_ref().take(len as u64 + 2),
+                    &mut io::sink(),
+                )?;
+            }
+            Ok(())
+        }
+        Some(b'-') => Err(io::Error::other(format!("redis: {}", &line[1..]))),
+        _ => Err(protocol_error(line)),
+    }
+}
+
+fn protocol_error(line: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("unexpected redis reply {:?}", line),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{net::TcpListener, thread};
+
+    /// A fake server that answers each command with the next of `replies` and returns what it read.
+    fn server(replies: &'static [&'static str]) -> (String, thread::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap().to_string();
+        let handle = thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut received = String::new();
+            for reply in replies {
+                // read one command: the array header, then a length and value line per arg
+                let mut header = String::new();
+                reader.read_line(&mut header).unwrap();
+                received.push_str(&header);
+                let args: usize = header.trim_end()[1..].parse().unwrap();
+                for _ in 0..args * 2 {
+                    reader.read_line(&mut received).unwrap();
+                }
+                reader.get_mut().write_all(reply.as_bytes()).unwrap();
+            }
+            received
+        });
+        (addr, handle)
+    }
+
+    #[test]
+    fn xadd() {
+        let (addr, server) = server(&["$3\r\n1-0\r\n"]);
+        let sink = RedisStream::new(addr, "panics").with_max_len(5);
+
+        sink.emit(&PanicDetails::new("gus")).unwrap();
+
+        assert_eq!(
+            concat!(
+                "*12\r\n$4\r\nXADD\r\n$6\r\npanics\r\n$6\r\nMAXLEN\r\n$1\r\n~\r\n$1\r\n5\r\n",
+                "$1\r\n*\r\n$7\r\nmessage\r\n$3\r\ngus\r\n$4\r\nkind\r\n$8\r\nexplicit\r\n",
+                "$6\r\nreport\r\n$17\r\n{\"message\":\"gus\"}\r\n"
+            ),
+            server.join().unwrap()
+        );
+    }
+
+    #[test]
+    fn auth_and_errors() {
+        let (addr, server) = server(&["+OK\r\n", "-ERR no such key\r\n"]);
+        let sink = RedisStream::new(addr, "panics").with_password("hunter2");
+
+        let error = sink.emit(&PanicDetails::new("gus")).unwrap_err();
+
+        assert_eq!("redis: ERR no such key", error.to_string());
+        assert!(server
+            .join()
+            .unwrap()
+            .starts_with("*2\r\n$4\r\nAUTH\r\n$7\r\nhunter2\r\n"));
+    }
+}
@@ -0,0 +1,334 @@
+use std::{
+    fmt,
+    fs::{self, OpenOptions},
+    io::{self, Write},
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicU64, Ordering},
+    time::UNIX_EPOCH,
+};
+
+use crate::{json, sink::Sink, time::Rfc3339, PanicDetails, SectionContent};
+
+/// The format of the files [`ReportFile`] writes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ReportFileFormat {
+    /// A TOML document, which users can read and paste into a bug report.
+    #[default]
+    Toml,
+    /// A JSON object, with the report as [`PanicDetails::to_json`] writes it in `panic`.
+    Json,
+}
+
+/// Writes each report to a new file in a directory, with the application's name and
+/// version and the operating system, and prints a short notice on stderr pointing at it,
+/// for command-line tools whose users should send in the file rather than a backtrace.
+///
+/// The directory is created if needed. Files are called `report-<time>-<pid>-<n>.toml`,
+/// or `.json`, so reports never overwrite each other. The notice reads
+/// `the program panicked: <message>` and `A crash report was written to <path>`, with the
+/// [labels][crate::set_labels] currently set.
+///
+/// ## Examples
+/// ```
+/// use panic_message::{sink::ReportFile, PanicHookBuilder};
+///
+/// PanicHookBuilder::new()
+///     .sink(
+///         ReportFile::new(std::env::temp_dir().join("my-tool"))
+///             .with_app(env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+///     )
+///     .install();
+/// # let _ = std::panic::take_hook();
+/// ```
+#[derive(Debug)]
+pub struct ReportFile {
+    dir: PathBuf,
+    format: ReportFileFormat,
+    app: Option<(String, String)>,
+    notice: bool,
+    next: AtomicU64,
+}
+
+impl ReportFile {
+    /// Write TOML reports to `dir`.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        ReportFile {
+            dir: dir.into(),
+            format: ReportFileFormat::Toml,
+            app: None,
+            notice: true,
+            next: AtomicU64::new(0),
+        }
+    }
+
+    /// Write reports in `format`.
+    pub fn with_format(mut self, format: ReportFileFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    /// Name the application and its version in every report, usually
+    /// `env!("CARGO_PKG_NAME")` and `env!("CARGO_PKG_VERSION")`.
+    pub fn with_app(mut self, name: impl Into<String>, version: impl Into<String>) -> Self {
+        self.app = Some((name.into(), version.into()));
+        self
+    }
+
+    /// Whether to print the notice on stderr, `true` by default.
+    pub fn with_notice(mut self, notice: bool) -> Self {
+        self.notice = notice;
+        self
+    }
+
+    /// The directory reports are written to.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    fn render(&self, details: &PanicDetails) -> String {
+        let mut out = String::new();
+        let written = match self.format {
+            ReportFileFormat::Toml => self.write_toml(details, &mut out),
+            ReportFileFormat::Json => self.write_json(details, &mut out),
+        };
+        written.expect("writing to a String can't fail");
+        out
+    }
+
+    fn write_toml(&self, details: &PanicDetails, out: &mut dyn fmt::Write) -> fmt::Result {
+        if let Some((name, version)) = &self.app {
+            toml_pair(out, "name", name)?;
+            toml_pair(out, "version", version)?;
+        }
+        toml_pair(out, "operating_system", &operating_system())?;
+        toml_pair(out, "message", details.message())?;
+        if let Some(location) = details.location() {
+            toml_pair(out, "location", &location.to_string())?;
+        }
+        if let Some(thread) = details.thread() {
+            toml_pair(out, "thread", thread)?;
+        }
+        if let Some(time) = details.time() {
+            toml_pair(out, "time", &Rfc3339(time).to_string())?;
+        }
+        if let Some(backtrace) = details.backtrace() {
+            toml_pair(out, "backtrace", backtrace)?;
+        }
+        if !details.fields().is_empty() {
+            out.write_str("\n[context]\n")?;
+            for (key, value) in details.fields() {
+                toml_pair(out, key, value)?;
+            }
+        }
+        if !details.sections().is_empty() {
+            out.write_str("\n[sections]\n")?;
+            for section in details.sections() {
+                toml_key(out, section.name())?;
+                out.write_str(" = ")?;
+                match section.content() {
+                    SectionContent::Text(text) => toml_str(out, text)?,
+                    SectionContent::List(items) => {
+                        out.write_str("[")?;
+                        for (i, item) in items.iter().enumerate() {
+                            if i > 0 {
+                                out.write_str(", ")?;
+                            }
+                            toml_basic_str(out, item)?;
+                        }
+                        out.write_str("]")?;
+                    }
+                    SectionContent::Fields(fields) => {
+                        out.write_str("{ ")?;
+                        for (i, (key, value)) in fields.iter().enumerate() {
+                            if i > 0 {
+                                out.write_str(", ")?;
+                            }
+                            toml_key(out, key)?;
+                            out.write_str(" = ")?;
+                            toml_basic_str(out, value)?;
+                        }
+                        out.write_str(" }")?;
+                    }
+                }
+                out.write_str("\n")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn write_json(&self, details: &PanicDetails, out: &mut dyn fmt::Write) -> fmt::Result {
+        let mut object = json::Object::new(out)?;
+        if let Some((name, version)) = &self.app {
+            object.str("name", name)?;
+            object.str("version", version)?;
+        }
+        object.str("operating_system", &operating_system())?;
+        object.raw("panic", &details.to_json())?;
+        object.finish()
+    }
+
+    fn path(&self) -> PathBuf {
+        let secs = crate::time::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let extension = match self.format {
+            ReportFileFormat::Toml => "toml",
+            ReportFileFormat::Json => "json",
+        };
+        self.dir.join(format!(
+            "report-{}-{}-{}.{}",
+            secs,
+            std::process::id(),
+            self.next.fetch_add(1, Ordering::Relaxed),
+            extension
+        ))
+    }
+}
+
+impl Sink for ReportFile {
+    fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+        let report = self.render(details);
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path();
+        let mut file = OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)?;
+        file.write_all(report.as_bytes())?;
+        if self.notice {
+            let labels = crate::labels();
+            let notice = format!(
+                "{}: {}\n{} {}\n",
+                labels.program_panicked,
+                details.message(),
+                labels.report_file_prompt,
+                path.display()
+            );
+            // one write, so the notice isn't interleaved with concurrent output
+            let _ = io::stderr().lock().write_all(notice.as_bytes());
+        }
+        Ok(())
+    }
+}
+
+fn operating_system() -> String {
+    format!("{} {}", std::env::consts::OS, std::env::consts::ARCH)
+}
+
+fn toml_pair(out: &mut dyn fmt::Write, key: &str, value: &str) -> fmt::Result {
+    toml_key(out, key)?;
+    out.write_str(" = ")?;
+    toml_str(out, value)?;
+    out.write_str("\n")
+}
+
+/// A bare key if it can be one, otherwise a quoted one.
+fn toml_key(out: &mut dyn fmt::Write, key: &str) -> fmt::Result {
+    let bare = !key.is_empty()
+        && key
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+    if bare {
+        out.write_str(key)
+    } else {
+        toml_basic_str(out, key)
+    }
+}
+
+/// Multi-line text as a literal string, which shows it as it is, where it can be one.
+fn toml_str(out: &mut dyn fmt::Write, value: &str) -> fmt::Result {
+    let literal = value.contains('\n')
+        && !value.contains("'''")
+        && !value.ends_with('\'')
+        && !value
+            .chars()
+            .any(|c| c.is_control() && c != '\n' && c != '\t');
+    if literal {
+        write!(out, "'''\n{}'''", value)
+    } else {
+        toml_basic_str(out, value)
+    }
+}
+
+/// A basic string, whose escapes are the same as JSON's.
+fn toml_basic_str(out: &mut dyn fmt::Write, value: &str) -> fmt::Result {
+    json::write_str(out, value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Location, Section};
+
+    #[test]
+    fn toml() {
+        let details = PanicDetails::new("gus \"wynn\"")
+            .with_location(Location::new("src/main.rs", 3, 5))
+            .with_thread("main")
+            .with_backtrace("   0: server::handle\n   1: server::main")
+            .with_field("request id", "7")
+            .with_section(Section::list("breadcrumbs", vec!["a".to_string()]))
+            .with_section(Section::fields(
+                "config",
+                vec![("port".to_string(), "8080".to_string())],
+            ));
+        let sink = ReportFile::new("reports").with_app("tool", "1.2.0");
+
+        assert_eq!(
+            format!(
+                "name = \"tool\"\n\
+                 version = \"1.2.0\"\n\
+                 operating_system = \"{}\"\n\
+                 message = \"gus \\\"wynn\\\"\"\n\
+                 location = \"src/main.rs:3:5\"\n\
+                 thread = \"main\"\n\
+                 backtrace = '''\n   0: server::handle\n   1: server::main'''\n\
+                 \n[context]\n\
+                 \"request id\" = \"7\"\n\
+                 \n[sections]\n\
+                 breadcrumbs = [\"a\"]\n\
+                 config = {{ port = \"8080\" }}\n",
+                operating_system()
+            ),
+            sink.render(&details)
+        );
+    }
+
+    #[test]
+    fn json() {
+        let sink = ReportFile::new("reports").with_format(ReportFileFormat::Json);
+
+        assert_eq!(
+            format!(
+                r#"{{"operating_system":"{}","panic":{{"message":"gus"}}}}"#,
+                operating_system()
+            ),
+            sink.render(&PanicDetails::new("gus"))
+        );
+    }
+
+    #[test]
+    fn writes_new_files() {
+        let dir =
+            std::env::temp_dir().join(format!("panic-message-{}-reports", std::process::id()));
+        let sink = ReportFile::new(&dir).with_notice(false);
+
+        sink.emit(&PanicDetails::new("gus")).unwrap();
+        sink.emit(&PanicDetails::new("wynn")).unwrap();
+
+        let mut reports: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| fs::read_to_string(entry.unwrap().path()).unwrap())
+            .collect();
+        let _ = fs::remove_dir_all(&dir);
+        reports.sort();
+        assert_eq!(2, reports.len());
+        assert!(reports[0].contains("message = \"gus\"\n"), "{}", reports[0]);
+        assert!(
+            reports[1].contains("message = \"wynn\"\n"),
+            "{}",
+            reports[1]
+        );
+    }
+}
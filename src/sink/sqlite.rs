@@ -0,0 +1,308 @@
+use std::{
+    convert::TryFrom,
+    io,
+    path::Path,
+    sync::Mutex,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use crate::{sink::Sink, PanicDetails};
+
+/// The schema version stored in `PRAGMA user_version`.
+const SCHEMA_VERSION: i64 = 1;
+
+const SCHEMA: &str = "
+    CREATE TABLE IF NOT EXISTS panics (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        time_ms INTEGER NOT NULL,
+        report TEXT NOT NULL
+    );
+    CREATE INDEX IF NOT EXISTS panics_time ON panics (time_ms);
+";
+
+/// A sink that appends reports to a local [SQLite](https://sqlite.org) database, for a
+/// durable crash history that can be queried later, for example on the next start.
+///
+/// The crate owns the schema (a single `panics` table, holding each report as
+/// [JSON][PanicDetails::to_json]) and opens the database in WAL mode. The oldest reports
+/// are deleted once the stored reports take more than
+/// [`with_max_bytes`][SqliteSink::with_max_bytes].
+///
+/// SQLite is built into the crate, so no system library is needed.
+///
+/// ## Examples
+/// ```no_run
+/// use std::sync::Arc;
+/// use panic_message::{sink::SqliteSink, PanicHookBuilder};
+///
+/// let crashes = Arc::new(SqliteSink::open("crashes.db")?.with_max_bytes(1 << 20));
+/// for crash in crashes.recent(10)? {
+///     println!("last run panicked: {}", crash.details().message());
+/// }
+/// PanicHookBuilder::new().sink(crashes).install();
+/// # Ok::<(), std::io::Error>(())
+/// ```
+pub struct SqliteSink {
+    db: Mutex<Connection>,
+    max_bytes: u64,
+}
+
+/// A report read back from a [`SqliteSink`] database.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StoredPanic {
+    id: i64,
+    time: SystemTime,
+    details: PanicDetails,
+}
+
+impl StoredPanic {
+    /// The row id, increasing with every report.
+    pub fn id(&self) -> i64 {
+        self.id
+    }
+
+    /// When the report was stored.
+    pub fn time(&self) -> SystemTime {
+        self.time
+    }
+
+    /// The stored details.
+    pub fn details(&self) -> &PanicDetails {
+        &self.details
+    }
+
+    /// Take the stored details.
+    pub fn into_details(self) -> PanicDetails {
+        self.details
+    }
+}
+
+impl SqliteSink {
+    /// Open or create the database at `path`, creating or checking the schema.
+    pub fn open(path: impl AsRef<Path>) -> io::Result<Self> {
+        let db = Connection::open(path).map_err(io::Error::other)?;
+        db.pragma_update(None, "journal_mode", "WAL")
+            .map_err(io::Error::other)?;
+        db.busy_timeout(Duration::from_secs(1))
+            .map_err(io::Error::other)?;
+        let version: i64 = db
+            .pragma_query_value(None, "user_version", |row| row.get(0))
+            .map_err(io::Error::other)?;
+        if version > SCHEMA_VERSION {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "crash database schema version {} is newer than the supported version {}",
+                    version, SCHEMA_VERSION
+                ),
+            ));
+        }
+        db.execute_batch(SCHEMA).map_err(io::Error::other)?;
+        db.pragma_update(None, "user_version", SCHEMA_VERSION)
+            .map_err(io::Error::other)?;
+        Ok(SqliteSink {
+            db: Mutex::new(db),
+            max_bytes: 10 << 20,
+        })
+    }
+
+    /// Keep the stored reports to at most `max_bytes` of JSON, deleting the oldest; the
+    /// newest report is always kept. Defaults to 10 MiB.
+    pub fn with_max_bytes(mut self, max_bytes: u64) -> Self {
+        self.max_bytes = max_bytes;
+        self
+    }
+
+    /// The number of stored reports.
+    pub fn count(&self) -> io::Result<u64> {
+        let db = self.db.lock().unwrap_or_else(|e| e.into_inner());
+        db.query_row("SELECT COUNT(*) FROM panics", [], |row| row.get(0))
+            .map_err(io::Error::other)
+    }
+
+    /// The size of the stored reports, in bytes of JSON.
+    pub fn size(&self) -> io::Result<u64> {
+        let db = self.db.lock().unwrap_or_else(|e| e.into_inner());
+        db.query_row(
+            "SELECT COALESCE(SUM(LENGTH(CAST(report AS BLOB))), 0) FROM panics",
+            [],
+            |row| row.get(0),
+        )
+        .map_err(io::Error::other)
+    }
+
+    /// The newest `limit` reports, newest first.
+    pub fn recent(&self, limit: usize) -> io::Result<Vec<StoredPanic>> {
+        let db = self.db.lock().unwrap_or_else(|e| e.into_inner());
+        let mut statement = db
+            .prepare("SELECT id, time_ms, report FROM panics ORDER BY id DESC LIMIT ?1")
+            .map_err(io::Error::other)?;
+        let rows = statement
+            .query_map([i64::try_from(limit).unwrap_or(i64::MAX)], stored)
+            .map_err(io::Error::other)?;
+        rows.map(|row| row.map_err(io::Error::other)?).collect()
+    }
+
+    /// Reports stored at or after `time`, oldest first.
+    pub fn since(&self, time: SystemTime) -> io::Result<Vec<StoredPanic>> {
+        let db = self.db.lock().unwrap_or_else(|e| e.into_inner());
+        let mut statement = db
+            .prepare("SELECT id, time_ms, report FROM panics WHERE time_ms >= ?1 ORDER BY id")
+            .map_err(io::Error::other)?;
+        let rows = statement
+            .query_map([millis(time)], stored)
+            .map_err(io::Error::other)?;
+        rows.map(|row| row.map_err(io::Error::other)?).collect()
+    }
+
+    /// Delete every stored report.
+    pub fn clear(&self) -> io::Result<()> {
+        let db = self.db.lock().unwrap_or_else(|e| e.into_inner());
+        db.execute("DELETE FROM panics", [])
+            .map(drop)
+            .map_err(io::Error::other)
+    }
+}
+
+impl Sink for SqliteSink {
+    fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+        let db = self.db.lock().unwrap_or_else(|e| e.into_inner());
+        db.execute(
+            "INSERT INTO panics (time_ms, report) VALUES (?1, ?2)",
+            params![millis(SystemTime::now()), details.to_json()],
+        )
+        .map_err(io::Error::other)?;
+
+        // the newest row whose report, with the newer ones, takes more than the cap, if any
+        let oldest_kept: Option<i64> = db
+            .query_row(
+                "SELECT id FROM (
+                     SELECT id, SUM(LENGTH(CAST(report AS BLOB))) OVER (ORDER BY id DESC) AS total
+                     FROM panics
+                 )
+                 WHERE total > ?1 AND id < (SELECT MAX(id) FROM panics)
+                 ORDER BY id DESC LIMIT 1",
+                [i64::try_from(self.max_bytes).unwrap_or(i64::MAX)],
+                |row| row.get(0),
+            )
+            .optional()
+            .map_err(io::Error::other)?;
+        if let Some(id) = oldest_kept {
+            db.execute("DELETE FROM panics WHERE id <= ?1", [id])
+                .map_err(io::Error::other)?;
+        }
+        Ok(())
+    }
+}
+
+/// Read a row selecting `id, time_ms, report`.
+fn stored(row: &Row<'_>) -> rusqlite::Result<io::Result<StoredPanic>> {
+    let id = row.get(0)?;
+    let time_ms: i64 = row.get(1)?;
+    let report: String = row.get(2)?;
+    Ok(PanicDetails::from_json(&report)
+        .map_err(io::Error::from)
+        .map(|details| StoredPanic {
+            id,
+            time: UNIX_EPOCH + Duration::from_millis(time_ms.max(0) as u64),
+            details,
+        }))
+}
+
+fn millis(time: SystemTime) -> i64 {
+    time.duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_millis() as i64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Location, Section};
+    use std::{
+        path::PathBuf,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    fn temp_db() -> PathBuf {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let path = std::env::temp_dir().join(format!(
+            "panic-message-{}-{}.db",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        remove(&path);
+        path
+    }
+
+    fn remove(path: &Path) {
+        for suffix in &["", "-wal", "-shm"] {
+            let mut file = path.as_os_str().to_owned();
+            file.push(suffix);
+            let _ = std::fs::remove_file(file);
+        }
+    }
+
+    #[test]
+    fn store_and_query() {
+        let path = temp_db();
+        let sink = SqliteSink::open(&path).unwrap();
+        let details = PanicDetails::new("gus")
+            .with_location(Location::new("src/main.rs", 3, 5))
+            .with_thread("main")
+            .with_time(UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+            .with_backtrace("0: main")
+            .with_field("request_id", "7")
+            .with_section(Section::text("config", "a1b2"));
+        let before = UNIX_EPOCH + Duration::from_millis(millis(SystemTime::now()) as u64);
+
+        sink.emit(&details).unwrap();
+        sink.emit(&PanicDetails::new("second")).unwrap();
+
+        assert_eq!(2, sink.count().unwrap());
+        let recent = sink.recent(10).unwrap();
+        assert_eq!("second", recent[0].details().message());
+        assert_eq!(&details, recent[1].details());
+        assert!(recent[0].id() > recent[1].id());
+        assert_eq!(2, sink.since(before).unwrap().len());
+
+        // reopening keeps the history
+        drop(sink);
+        let sink = SqliteSink::open(&path).unwrap();
+        assert_eq!(2, sink.count().unwrap());
+        sink.clear().unwrap();
+        assert_eq!(0, sink.count().unwrap());
+        drop(sink);
+        remove(&path);
+    }
+
+    #[test]
+    fn capped() {
+        let path = temp_db();
+        let report = PanicDetails::new("0").to_json().len() as u64;
+        let sink = SqliteSink::open(&path)
+            .unwrap()
+            .with_max_bytes(3 * report + 1);
+
+        for i in 0..10 {
+            sink.emit(&PanicDetails::new(i.to_string())).unwrap();
+        }
+        assert_eq!(3 * report, sink.size().unwrap());
+
+        let messages: Vec<_> = sink
+            .recent(10)
+            .unwrap()
+            .into_iter()
+            .map(|stored| stored.into_details().message().to_string())
+            .collect();
+        assert_eq!(vec!["9", "8", "7"], messages);
+
+        // a report larger than the cap is still kept, alone
+        sink.emit(&PanicDetails::new("gus".repeat(10))).unwrap();
+        assert_eq!(1, sink.count().unwrap());
+        drop(sink);
+        remove(&path);
+    }
+}
@@ -0,0 +1,216 @@
+use std::{
+    fmt,
+    fs::File,
+    io::{self, Write},
+    sync::Mutex,
+};
+
+use crate::{format::Format, sink::Sink, PanicDetails};
+
+/// Writes each report as text to stderr, or to another file, file descriptor or writer,
+/// such as a crash channel a supervisor passes in as fd 3.
+///
+/// Reports are written the way the default hook prints them, or with a
+/// [`Format`][crate::format::Format], one report per line.
+///
+/// ## Examples
+/// ```
+/// use panic_message::{format::Ecs, sink::Stream, PanicHookBuilder};
+///
+/// PanicHookBuilder::new()
+///     .sink(Stream::stderr().with_format(Ecs))
+///     .install();
+/// # let _ = std::panic::take_hook();
+/// ```
+///
+/// On unix, reports can go to a file descriptor the process inherited:
+/// ```no_run
+/// # #[cfg(unix)] {
+/// use std::os::unix::io::FromRawFd;
+/// use panic_message::sink::Stream;
+///
+/// // SAFETY: the supervisor opened fd 3 for us, and nothing else uses it
+/// let crashes = unsafe { Stream::from_raw_fd(3) };
+/// # }
+/// ```
+pub struct Stream {
+    target: Target,
+    format: Option<Box<dyn Format>>,
+}
+
+enum Target {
+    Stderr,
+    File(File),
+    Writer(Mutex<Box<dyn Write + Send>>),
+}
+
+impl Stream {
+    /// Write reports to stderr.
+    pub fn stderr() -> Self {
+        Stream {
+            target: Target::Stderr,
+            format: None,
+        }
+    }
+
+    /// Write reports to `writer`, like a socket to a log shipper or a pipe.
+    pub fn writer(writer: impl Write + Send + 'static) -> Self {
+        Stream {
+            target: Target::Writer(Mutex::new(Box::new(writer))),
+            format: None,
+        }
+    }
+
+    /// Write reports with `format` instead of the default hook's layout.
+    pub fn with_format(mut self, format: impl Format + 'static) -> Self {
+        self.format = Some(Box::new(format));
+        self
+    }
+
+    fn render(&self, details: &PanicDetails) -> String {
+        let mut out = match &self.format {
+            Some(format) => format.render(details),
+            None => details.to_string(),
+        };
+        out.push('\n');
+        out
+    }
+}
+
+impl From<File> for Stream {
+    /// Write reports to `file`.
+    fn from(file: File) -> Self {
+        Stream {
+            target: Target::File(file),
+            format: None,
+        }
+    }
+}
+
+#[cfg(unix)]
+impl From<std::os::unix::io::OwnedFd> for Stream {
+    /// Write reports to `fd`.
+    fn from(fd: std::os::unix::io::OwnedFd) -> Self {
+        Stream::from(File::from(fd))
+    }
+}
+
+#[cfg(unix)]
+impl std::os::unix::io::FromRawFd for Stream {
+    /// Write reports to `fd`, which the stream takes ownership of and closes when dropped.
+    unsafe fn from_raw_fd(fd: std::os::unix::io::RawFd) -> Self {
+        // SAFETY: forwarded to the caller.
+        Stream::from(unsafe { File::from_raw_fd(fd) })
+    }
+}
+
+impl fmt::Debug for Stream {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let target: &dyn fmt::Debug = match &self.target {
+            Target::Stderr => &"stderr",
+            Target::File(file) => file,
+            Target::Writer(_) => &"writer",
+        };
+        f.debug_struct("Stream")
+            .field("target", target)
+            .field("formatted", &self.format.is_some())
+            .finish()
+    }
+}
+
+impl Sink for Stream {
+    fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+        let report = self.render(details);
+        // one write per report, so reports from concurrent panics don't interleave
+        match &self.target {
+            Target::Stderr => io::stderr().lock().write_all(report.as_bytes()),
+            Target::File(file) => (&*file).write_all(report.as_bytes()),
+            Target::Writer(writer) => {
+                let mut writer = writer.lock().unwrap_or_else(|e| e.into_inner());
+                writer.write_all(report.as_bytes())?;
+                writer.flush()
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{format::Ecs, Location};
+
+    fn temp_file(name: &str) -> std::path::PathBuf {
+        std::env::temp_dir().join(format!("panic-message-{}-{}", std::process::id(), name))
+    }
+
+    #[test]
+    fn writes_to_file() {
+        let path = temp_file("stream");
+        let sink = Stream::from(File::create(&path).unwrap());
+        let details = PanicDetails::new("gus")
+            .with_location(Location::new("src/main.rs", 3, 5))
+            .with_thread("main");
+
+        sink.emit(&details).unwrap();
+        sink.emit(&PanicDetails::new("wynn")).unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(
+            "thread 'main' panicked at src/main.rs:3:5:\ngus\n\
+             thread '<unnamed>' panicked:\nwynn\n",
+            written
+        );
+    }
+
+    #[test]
+    fn with_format() {
+        let sink = Stream::stderr().with_format(Ecs);
+
+        let rendered = sink.render(&PanicDetails::new("gus"));
+
+        assert!(rendered.starts_with('{'), "{}", rendered);
+        assert!(rendered.ends_with("}\n"), "{}", rendered);
+    }
+
+    #[test]
+    fn writer() {
+        #[derive(Clone, Default)]
+        struct Shared(std::sync::Arc<Mutex<Vec<u8>>>);
+
+        impl Write for Shared {
+            fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+                self.0.lock().unwrap().write(buf)
+            }
+
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
+        }
+
+        let written = Shared::default();
+        let sink = Stream::writer(written.clone()).with_format(crate::format::Json);
+
+        sink.emit(&PanicDetails::new("gus")).unwrap();
+
+        let written = String::from_utf8(written.0.lock().unwrap().clone()).unwrap();
+        assert!(
+            written.starts_with(r#"{"message":"gus","time_us":"#),
+            "{}",
+            written
+        );
+        assert!(written.ends_with("}\n"), "{}", written);
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn from_owned_fd() {
+        let path = temp_file("stream-fd");
+        let fd = std::os::unix::io::OwnedFd::from(File::create(&path).unwrap());
+        Stream::from(fd).emit(&PanicDetails::new("gus")).unwrap();
+
+        let written = std::fs::read_to_string(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert!(written.ends_with("gus\n"), "{}", written);
+    }
+}
@@ -0,0 +1,169 @@
+use std::io;
+
+use crate::{sink::Sink, PanicDetails, PanicHookBuilder};
+
+/// A sink that emits each report as a [`tracing`](https://docs.rs/tracing) error event, with
+/// the target `"panic"` and these fields:
+///
+/// - `message`: the panic message
+/// - `panic.file`, `panic.line` and `panic.column`: the panic's location, if known
+/// - `thread.name`: the panicking thread's name, if known
+///
+/// The hook [`PanicHookBuilder`] installs takes the span the panic happened in, and the
+/// subscriber, from the panicking thread, so the event belongs to that span although the
+/// sink runs on a helper thread.
+///
+/// ## Examples
+/// ```
+/// use panic_message::{sink::Tracing, PanicHookBuilder};
+///
+/// PanicHookBuilder::new().sink(Tracing).install();
+/// # let _ = std::panic::take_hook();
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Tracing;
+
+impl Sink for Tracing {
+    fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+        let location = details.location();
+        tracing::error!(
+            target: "panic",
+            message = details.message(),
+            panic.file = location.map(|location| location.file()),
+            panic.line = location.map(|location| location.line()),
+            panic.column = location.map(|location| location.column()),
+            thread.name = details.thread(),
+        );
+        Ok(())
+    }
+}
+
+/// Install a panic hook that emits each panic as a `tracing::error!` event in the current
+/// span with a [`Tracing`] sink, see [`PanicHookBuilder`] for more options.
+pub fn install_tracing_hook() {
+    PanicHookBuilder::new().sink(Tracing).install();
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        fmt,
+        sync::{
+            atomic::{AtomicU64, Ordering},
+            Arc, Mutex,
+        },
+    };
+
+    use tracing::{
+        field::{Field, Visit},
+        span, Event, Metadata, Subscriber,
+    };
+
+    use super::*;
+    use crate::Location;
+
+    /// Records each event as its target, the span it was emitted in and its fields.
+    #[derive(Clone, Default)]
+    struct Recorder {
+        entered: Arc<AtomicU64>,
+        span: Arc<Mutex<Option<&'static Metadata<'static>>>>,
+        events: Arc<Mutex<Vec<String>>>,
+    }
+
+    struct Fields(String);
+
+    impl Visit for Fields {
+        fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+            self.0.push_str(&format!(" {}={:?}", field.name(), value));
+        }
+    }
+
+    impl Subscriber for Recorder {
+        fn enabled(&self, _: &Metadata<'_>) -> bool {
+            true
+        }
+
+        fn new_span(&self, span: &span::Attributes<'_>) -> span::Id {
+            *self.span.lock().unwrap() = Some(span.metadata());
+            span::Id::from_u64(7)
+        }
+
+        fn record(&self, _: &span::Id, _: &span::Record<'_>) {}
+
+        fn record_follows_from(&self, _: &span::Id, _: &span::Id) {}
+
+        fn event(&self, event: &Event<'_>) {
+            let mut fields = Fields(format!(
+                "{} in {}:",
+                event.metadata().target(),
+                self.entered.load(Ordering::SeqCst)
+            ));
+            event.record(&mut fields);
+            self.events.lock().unwrap().push(fields.0);
+        }
+
+        fn enter(&self, span: &span::Id) {
+            self.entered.store(span.into_u64(), Ordering::SeqCst);
+        }
+
+        fn exit(&self, _: &span::Id) {
+            self.entered.store(0, Ordering::SeqCst);
+        }
+
+        fn current_span(&self) -> tracing_core::span::Current {
+            match (
+                self.entered.load(Ordering::SeqCst),
+                *self.span.lock().unwrap(),
+            ) {
+                (0, _) | (_, None) => tracing_core::span::Current::none(),
+                (id, Some(metadata)) => {
+                    tracing_core::span::Current::new(span::Id::from_u64(id), metadata)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn emits_events_in_the_current_span() {
+        let recorder = Recorder::default();
+
+        tracing::subscriber::with_default(recorder.clone(), || {
+            let _span = tracing::error_span!("request").entered();
+            Tracing
+                .emit(
+                    &PanicDetails::new("gus")
+                        .with_location(Location::new("src/main.rs", 3, 5))
+                        .with_thread("main"),
+                )
+                .unwrap();
+        });
+
+        assert_eq!(
+            vec![
+                "panic in 7: message=\"gus\" panic.file=\"src/main.rs\" panic.line=3 \
+                 panic.column=5 thread.name=\"main\""
+            ],
+            *recorder.events.lock().unwrap()
+        );
+    }
+
+    #[test]
+    fn emits_events_in_the_captured_span() {
+        let recorder = Recorder::default();
+
+        let captured = tracing::subscriber::with_default(recorder.clone(), || {
+            let _span = tracing::error_span!("request").entered();
+            crate::context::Captured::capture()
+        });
+        std::thread::spawn(move || {
+            captured.enter(|| Tracing.emit(&PanicDetails::new("gus")).unwrap())
+        })
+        .join()
+        .unwrap();
+
+        assert_eq!(
+            vec!["panic in 7: message=\"gus\""],
+            *recorder.events.lock().unwrap()
+        );
+    }
+}
@@ -0,0 +1,163 @@
+use std::{ffi::c_void, io, ptr};
+
+use crate::{sink::Sink, PanicDetails};
+
+/// `EVENTLOG_ERROR_TYPE`.
+const EVENTLOG_ERROR_TYPE: u16 = 0x0001;
+
+/// The longest string an event can hold, in UTF-16 units, with room for the NUL.
+const MAX_EVENT_STRING: usize = 31_839;
+
+#[link(name = "kernel32")]
+extern "system" {
+    fn OutputDebugStringW(text: *const u16);
+}
+
+#[link(name = "advapi32")]
+extern "system" {
+    fn RegisterEventSourceW(server: *const u16, source: *const u16) -> *mut c_void;
+    fn ReportEventW(
+        log: *mut c_void,
+        kind: u16,
+        category: u16,
+        event_id: u32,
+        user: *mut c_void,
+        strings_len: u16,
+        data_len: u32,
+        strings: *const *const u16,
+        data: *mut c_void,
+    ) -> i32;
+    fn DeregisterEventSource(log: *mut c_void) -> i32;
+}
+
+/// A sink that sends reports to the debugger with `OutputDebugStringW`, for GUI
+/// applications, whose stderr goes nowhere. The reports show up in the debugger's output
+/// window, or in a viewer like Sysinternals' DebugView when no debugger is attached.
+///
+/// Each report is sent as it [displays][PanicDetails#impl-Display-for-PanicDetails].
+///
+/// ## Examples
+/// ```no_run
+/// use panic_message::{sink::DebugOutput, PanicHookBuilder};
+///
+/// PanicHookBuilder::new().sink(DebugOutput).install();
+/// ```
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DebugOutput;
+
+impl Sink for DebugOutput {
+    fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+        let text = wide(&format!("{}\n", details), usize::MAX);
+        // SAFETY: `text` is NUL-terminated.
+        unsafe { OutputDebugStringW(text.as_ptr()) };
+        Ok(())
+    }
+}
+
+/// A sink that writes reports to the Windows Event Log as errors of an event source, so
+/// they can be found in Event Viewer under "Windows Logs > Application".
+///
+/// Without a message file registered for the source, Event Viewer prefixes the report
+/// with a note that the description for the event can't be found, but still shows it.
+/// Reports longer than an event can hold are cut.
+///
+/// ## Examples
+/// ```no_run
+/// use panic_message::{sink::{DebugOutput, EventLog}, PanicHookBuilder};
+///
+/// PanicHookBuilder::new()
+///     .sink(DebugOutput)
+///     .sink(EventLog::register("My Application")?)
+///     .install();
+/// # Ok::<(), std::io::Error>(())
+/// ```
+#[derive(Debug)]
+pub struct EventLog {
+    log: *mut c_void,
+}
+
+// SAFETY: event log handles can be used from any thread.
+unsafe impl Send for EventLog {}
+// SAFETY: `ReportEventW` can be called concurrently.
+unsafe impl Sync for EventLog {}
+
+impl EventLog {
+    /// Open the event source called `source` on the local machine.
+    pub fn register(source: &str) -> io::Result<Self> {
+        let source = wide(source, usize::MAX);
+        // SAFETY: `source` is NUL-terminated, and a null server is the local machine.
+        let log = unsafe { RegisterEventSourceW(ptr::null(), source.as_ptr()) };
+        if log.is_null() {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(EventLog { log })
+    }
+}
+
+impl Sink for EventLog {
+    fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+        let text = wide(&details.to_string(), MAX_EVENT_STRING);
+        let strings = [text.as_ptr()];
+        // SAFETY: `self.log` is an open event source and `strings` holds one
+        // NUL-terminated string.
+        let reported = unsafe {
+            ReportEventW(
+                self.log,
+                EVENTLOG_ERROR_TYPE,
+                0,
+                0,
+                ptr::null_mut(),
+                1,
+                0,
+                strings.as_ptr(),
+                ptr::null_mut(),
+            )
+        };
+        if reported == 0 {
+            return Err(io::Error::last_os_error());
+        }
+        Ok(())
+    }
+}
+
+impl Drop for EventLog {
+    fn drop(&mut self) {
+        // SAFETY: `self.log` is an open event source, closed only here.
+        unsafe { DeregisterEventSource(self.log) };
+    }
+}
+
+/// `text` as a NUL-terminated UTF-16 string of at most `max` units, NUL included, without
+/// an unpaired surrogate where it was cut. NULs in `text` are dropped.
+fn wide(text: &str, max: usize) -> Vec<u16> {
+    let mut wide: Vec<u16> = text.encode_utf16().filter(|&unit| unit != 0).collect();
+    if wide.len() >= max {
+        wide.truncate(max - 1);
+        if wide
+            .last()
+            .is_some_and(|unit| (0xD800..0xDC00).contains(unit))
+        {
+            wide.pop();
+        }
+    }
+    wide.push(0);
+    wide
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wide_strings() {
+        assert_eq!(vec![0x67, 0x75, 0x73, 0], wide("g\0us", usize::MAX));
+        assert_eq!(vec![0x67, 0x75, 0], wide("gus", 3));
+        // the emoji is a surrogate pair, which isn't split
+        assert_eq!(vec![0x67, 0], wide("g😀", 3));
+    }
+
+    #[test]
+    fn debug_output() {
+        DebugOutput.emit(&PanicDetails::new("gus")).unwrap();
+    }
+}
@@ -0,0 +1,125 @@
+//! The source line a panic happened on, for reports read during development.
+use std::{fs::File, io::Read};
+
+use crate::{Location, PanicDetails, Section};
+
+/// Source files larger than this aren't read.
+const MAX_FILE_BYTES: u64 = 1 << 20;
+/// Lines longer than this, in chars, are cut.
+const MAX_LINE_CHARS: usize = 160;
+
+impl PanicDetails {
+    /// Read the source file at the [location][PanicDetails::location] and add the line the
+    /// panic happened on, with a caret under its column, as a `source`
+    /// [section][PanicDetails::sections], which the `Display` impl prints after the message:
+    ///
+    /// ```text
+    /// thread 'main' panicked at src/main.rs:2:5:
+    /// gus
+    /// source:
+    ///   2 |     panic!("gus");
+    ///     |     ^
+    /// ```
+    ///
+    /// The file is opened at the location's path, which is relative to the package root the
+    /// code was compiled from, so this is for running where it was built, as with
+    /// `cargo run` or `cargo test`. Nothing is added when there is no location, or the file
+    /// can't be read, is larger than 1 MiB or doesn't have the line.
+    pub fn with_source_snippet(self) -> Self {
+        let snippet = self.location().and_then(|location| {
+            let file = File::open(location.file()).ok()?;
+            let mut source = String::new();
+            file.take(MAX_FILE_BYTES + 1)
+                .read_to_string(&mut source)
+                .ok()?;
+            if source.len() as u64 > MAX_FILE_BYTES {
+                return None;
+            }
+            snippet(&source, location)
+        });
+        match snippet {
+            Some(snippet) => self.with_section(Section::text("source", snippet)),
+            None => self,
+        }
+    }
+}
+
+/// The location's line in `source`, and a caret under its column.
+fn snippet(source: &str, location: &Location) -> Option<String> {
+    let offset = location.byte_offset(source)?;
+    let start = source[..offset].rfind('\n').map_or(0, |i| i + 1);
+    let line = source[start..].lines().next().unwrap_or("");
+    let before = &source[start..offset];
+    if before.chars().count() >= MAX_LINE_CHARS {
+        return None;
+    }
+    let line = match line.char_indices().nth(MAX_LINE_CHARS) {
+        Some((cut, _)) => format!("{}…", &line[..cut]),
+        None => line.to_string(),
+    };
+    // tabs are kept, so the caret lines up however wide they are shown
+    let indent: String = before
+        .chars()
+        .map(|c| if c == '\t' { '\t' } else { ' ' })
+        .collect();
+    let number = location.line().to_string();
+    Some(format!(
+        "{} | {}\n{:width$} | {}^",
+        number,
+        line.trim_end(),
+        "",
+        indent,
+        width = number.len()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caret_under_the_column() {
+        let source = "fn main() {\n\tlet é = 1; panic!(\"gus\");\n}\n";
+
+        assert_eq!(
+            Some("2 | \tlet é = 1; panic!(\"gus\");\n  | \t           ^".to_string()),
+            snippet(source, &Location::new("src/main.rs", 2, 13))
+        );
+        assert_eq!(None, snippet(source, &Location::new("src/main.rs", 9, 1)));
+    }
+
+    #[test]
+    fn long_lines_are_cut() {
+        let source = "x".repeat(500);
+
+        let cut = snippet(&source, &Location::new("src/main.rs", 1, 3)).unwrap();
+        assert!(cut.starts_with(&format!("1 | {}…\n", "x".repeat(MAX_LINE_CHARS))));
+        assert_eq!(
+            None,
+            snippet(&source, &Location::new("src/main.rs", 1, 400))
+        );
+    }
+
+    #[test]
+    fn reads_the_file() {
+        let line = line!();
+        let details = PanicDetails::new("gus")
+            .with_location(Location::new(file!(), line, 5))
+            .with_source_snippet();
+
+        let section = &details.sections()[0];
+        assert_eq!("source", section.name());
+        assert_eq!(
+            &crate::SectionContent::Text(format!(
+                "{} |         let line = line!();\n    |     ^",
+                line
+            )),
+            section.content()
+        );
+
+        let missing = PanicDetails::new("gus")
+            .with_location(Location::new("src/missing.rs", 1, 1))
+            .with_source_snippet();
+        assert!(missing.sections().is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+//! Counts of the panics the hook reported, for supervisors tracking how flaky workers are,
+//! and for the `metrics` crate's recorder, with the `metrics` feature.
+use std::{
+    collections::BTreeMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Mutex,
+    },
+};
+
+static TOTAL: AtomicU64 = AtomicU64::new(0);
+static BY_THREAD: Mutex<BTreeMap<String, u64>> = Mutex::new(BTreeMap::new());
+
+/// What unnamed threads are counted as.
+const UNNAMED: &str = "<unnamed>";
+
+/// A snapshot of the panics reported so far, see [`panic_stats`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct PanicStats {
+    total: u64,
+    by_thread: BTreeMap<String, u64>,
+}
+
+impl PanicStats {
+    /// How many panics were reported.
+    pub fn total(&self) -> u64 {
+        self.total
+    }
+
+    /// How many panics were reported on each thread name, with unnamed threads counted as
+    /// `<unnamed>`.
+    pub fn by_thread(&self) -> &BTreeMap<String, u64> {
+        &self.by_thread
+    }
+
+    /// How many panics were reported on threads called `name`.
+    pub fn for_thread(&self, name: &str) -> u64 {
+        self.by_thread.get(name).copied().unwrap_or(0)
+    }
+
+    /// How many panics were reported after the `earlier` snapshot was taken.
+    pub fn since(&self, earlier: &PanicStats) -> u64 {
+        self.total.saturating_sub(earlier.total)
+    }
+
+    /// Render the counts in the Prometheus
+    /// [text exposition format](https://prometheus.io/docs/instrumenting/exposition_formats/),
+    /// as a `panics_total` counter with a `thread` label, for a metrics endpoint.
+    ///
+    /// ## Examples
+    /// ```
+    /// let metrics = panic_message::panic_stats().to_prometheus();
+    ///
+    /// assert!(metrics.contains("# TYPE panics_total counter\n"));
+    /// ```
+    pub fn to_prometheus(&self) -> String {
+        let mut out = String::from(
+            "# HELP panics_total Panics reported by the panic hook.\n\
+             # TYPE panics_total counter\n",
+        );
+        for (thread, count) in &self.by_thread {
+            out.push_str("panics_total{thread=\"");
+            for c in thread.chars() {
+                match c {
+                    '\\' => out.push_str("\\\\"),
+                    '"' => out.push_str("\\\""),
+                    '\n' => out.push_str("\\n"),
+                    c => out.push(c),
+                }
+            }
+            out.push_str(&format!("\"}} {}\n", count));
+        }
+        out
+    }
+}
+
+/// Counts of the panics every [`PanicHookBuilder`][crate::PanicHookBuilder] hook has
+/// reported so far in this process. Panics a hook [filters out][crate::PanicHookBuilder::include_path]
+/// aren't counted.
+///
+/// ## Examples
+/// ```
+/// use std::{panic::catch_unwind, thread};
+/// use panic_message::{panic_stats, PanicHookBuilder};
+///
+/// PanicHookBuilder::new().install();
+/// let watermark = panic_stats();
+///
+/// let worker = thread::Builder::new().name("worker".to_string());
+/// let _ = worker.spawn(|| panic!("gus")).unwrap().join();
+/// # let _ = std::panic::take_hook();
+///
+/// let stats = panic_stats();
+/// assert_eq!(1, stats.since(&watermark));
+/// assert_eq!(1, stats.for_thread("worker"));
+/// ```
+pub fn panic_stats() -> PanicStats {
+    let by_thread = BY_THREAD.lock().unwrap_or_else(|e| e.into_inner());
+    PanicStats {
+        total: TOTAL.load(Ordering::Relaxed),
+        by_thread: by_thread.clone(),
+    }
+}
+
+/// Count a panic on the thread called `thread`.
+pub(crate) fn record(thread: Option<&str>) {
+    let mut by_thread = BY_THREAD.lock().unwrap_or_else(|e| e.into_inner());
+    // under the lock, so snapshots never count a panic in one place and not the other
+    TOTAL.fetch_add(1, Ordering::Relaxed);
+    *by_thread
+        .entry(thread.unwrap_or(UNNAMED).to_string())
+        .or_insert(0) += 1;
+}
+
+/// Increment the `metrics` counter `panics_total` for a panic on the thread called `thread`,
+/// labeled with the thread and the panic's [fingerprint][crate::PanicDetails::fingerprint].
+#[cfg(feature = "metrics")]
+pub(crate) fn record_metric(thread: Option<&str>, fingerprint: u64) {
+    metrics::counter!(
+        "panics_total",
+        "thread" => thread.unwrap_or(UNNAMED).to_string(),
+        "fingerprint" => format!("{:016x}", fingerprint),
+    )
+    .increment(1);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn since() {
+        let earlier = PanicStats {
+            total: 2,
+            by_thread: BTreeMap::new(),
+        };
+        let later = PanicStats {
+            total: 5,
+            by_thread: BTreeMap::from([("main".to_string(), 5)]),
+        };
+
+        assert_eq!(3, later.since(&earlier));
+        assert_eq!(0, earlier.since(&later));
+        assert_eq!(5, later.for_thread("main"));
+        assert_eq!(0, later.for_thread("worker"));
+    }
+
+    #[test]
+    fn prometheus() {
+        let stats = PanicStats {
+            total: 3,
+            by_thread: BTreeMap::from([("main".to_string(), 1), ("say \"hi\"\\\n".to_string(), 2)]),
+        };
+
+        assert_eq!(
+            concat!(
+                "# HELP panics_total Panics reported by the panic hook.\n",
+                "# TYPE panics_total counter\n",
+                "panics_total{thread=\"main\"} 1\n",
+                "panics_total{thread=\"say \\\"hi\\\"\\\\\\n\"} 2\n",
+            ),
+            stats.to_prometheus()
+        );
+    }
+}
@@ -0,0 +1,220 @@
+//! Panics that carry key-value fields along with their message.
+use std::{any::Any, fmt};
+
+use crate::AsPanicPayload;
+
+/// The payload [`panic_with_context!`] panics with: a message and the fields it was given,
+/// in order.
+///
+/// It displays as its message. Panicking with one directly, through
+/// [`std::panic::panic_any`], works the same as using the macro.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StructuredPanic {
+    /// The panic message.
+    pub message: String,
+    /// The key-value fields, formatted.
+    pub fields: Vec<(String, String)>,
+}
+
+impl fmt::Display for StructuredPanic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.message)
+    }
+}
+
+/// Recover the message and fields of a [`panic_with_context!`] panic, for example to
+/// forward the fields to structured logging.
+///
+/// ## Examples
+/// ```
+/// use std::panic::catch_unwind;
+/// use panic_message::{get_structured_panic, panic_with_context};
+///
+/// let payload = catch_unwind(|| panic_with_context!("gus", port = 8080)).unwrap_err();
+///
+/// let structured = get_structured_panic(&payload).unwrap();
+/// assert_eq!("gus", structured.message);
+/// assert_eq!(vec![("port".to_string(), "8080".to_string())], structured.fields);
+/// ```
+///
+/// In a [`std::panic::set_hook`] hook:
+/// ```
+/// std::panic::set_hook(Box::new(|pi| {
+///     if let Some(structured) = panic_message::get_structured_panic(pi.payload()) {
+///         for (key, value) in &structured.fields {
+///             eprintln!("{}={}", key, value);
+///         }
+///     }
+/// }));
+/// # let _ = std::panic::take_hook();
+/// ```
+pub fn get_structured_panic<P>(payload: &P) -> Option<&StructuredPanic>
+where
+    P: AsPanicPayload + ?Sized,
+{
+    crate::try_downcast_payload(payload)
+}
+
+/// The payload's fields, empty if it isn't a [`StructuredPanic`].
+pub(crate) fn fields(payload: &(dyn Any + Send)) -> Vec<(String, String)> {
+    match payload.downcast_ref::<StructuredPanic>() {
+        Some(structured) => structured.fields.clone(),
+        None => Vec::new(),
+    }
+}
+
+/// Panic with a message and key-value fields, which [`PanicDetails`][crate::PanicDetails]
+/// and every report format built on it record along with the message.
+///
+/// The message is a format string, which can capture variables inline. Fields are
+/// `key = value`, formatted with `Debug`; `key = %value` formats with `Display` instead,
+/// and `key = ?value` is accepted for `Debug` too.
+///
+/// The payload is not a string, so the standard library's default hook prints it as
+/// `Box<dyn Any>`; this crate's functions and hooks see the message.
+///
+/// ## Examples
+/// ```
+/// use std::panic::catch_unwind;
+/// use panic_message::{panic_with_context, PanicDetails};
+///
+/// let port = 8080;
+/// let addr = std::net::Ipv4Addr::LOCALHOST;
+/// let payload = catch_unwind(|| {
+///     panic_with_context!("failed to bind {addr}", port = port, addr = %addr);
+/// }).unwrap_err();
+///
+/// let details = PanicDetails::from_payload(&payload);
+/// assert_eq!("failed to bind 127.0.0.1", details.message());
+/// assert_eq!(
+///     [("port".to_string(), "8080".to_string()), ("addr".to_string(), "127.0.0.1".to_string())],
+///     details.fields()
+/// );
+/// ```
+#[macro_export]
+macro_rules! panic_with_context {
+    ($message:literal $(, $($fields:tt)*)?) => {{
+        #[allow(unused_mut)] // without fields
+        let mut fields: ::std::vec::Vec<(::std::string::String, ::std::string::String)> =
+            ::std::vec::Vec::new();
+        $crate::__panic_fields!(fields; $($($fields)*)?);
+        ::std::panic::panic_any($crate::StructuredPanic {
+            message: ::std::format!($message),
+            fields,
+        })
+    }};
+}
+
+/// Check an invariant, and [`panic_with_context!`] with the message and fields if it
+/// doesn't hold.
+///
+/// ## Examples
+/// ```
+/// use std::panic::catch_unwind;
+/// use panic_message::{ensure_or_panic, get_structured_panic};
+///
+/// let id = 7;
+/// let state = "closed";
+/// let payload = catch_unwind(|| {
+///     ensure_or_panic!(state == "open", "invariant broken: {state:?}", id = id);
+/// }).unwrap_err();
+///
+/// let structured = get_structured_panic(&payload).unwrap();
+/// assert_eq!("invariant broken: \"closed\"", structured.message);
+/// assert_eq!(vec![("id".to_string(), "7".to_string())], structured.fields);
+/// ```
+#[macro_export]
+macro_rules! ensure_or_panic {
+    ($condition:expr, $message:literal $(, $($fields:tt)*)?) => {
+        if !$condition {
+            $crate::panic_with_context!($message $(, $($fields)*)?);
+        }
+    };
+}
+
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __panic_fields {
+    ($fields:ident;) => {};
+    ($fields:ident; $key:ident = %$value:expr $(, $($rest:tt)*)?) => {
+        $fields.push((
+            ::std::string::ToString::to_string(::std::stringify!($key)),
+            ::std::format!("{}", $value),
+        ));
+        $crate::__panic_fields!($fields; $($($rest)*)?);
+    };
+    ($fields:ident; $key:ident = ?$value:expr $(, $($rest:tt)*)?) => {
+        $crate::__panic_fields!($fields; $key = $value $(, $($rest)*)?);
+    };
+    ($fields:ident; $key:ident = $value:expr $(, $($rest:tt)*)?) => {
+        $fields.push((
+            ::std::string::ToString::to_string(::std::stringify!($key)),
+            ::std::format!("{:?}", $value),
+        ));
+        $crate::__panic_fields!($fields; $($($rest)*)?);
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::catch_unwind;
+
+    #[test]
+    fn macro_fields() {
+        let name = "gus";
+        let payload = catch_unwind(|| {
+            panic_with_context!("no {name}", name = name, display = %name, debug = ?1,);
+        })
+        .unwrap_err();
+
+        assert_eq!("no gus", crate::panic_message(&payload));
+        assert_eq!(
+            vec![
+                ("name".to_string(), "\"gus\"".to_string()),
+                ("display".to_string(), "gus".to_string()),
+                ("debug".to_string(), "1".to_string()),
+            ],
+            fields(&*payload)
+        );
+    }
+
+    #[test]
+    fn no_fields() {
+        let payload = catch_unwind(|| panic_with_context!("gus")).unwrap_err();
+
+        assert_eq!("gus", crate::panic_message(&payload));
+        assert!(fields(&*payload).is_empty());
+    }
+
+    #[test]
+    fn ensure() {
+        let value = 3;
+        ensure_or_panic!(value > 2, "too small");
+
+        let payload =
+            catch_unwind(|| ensure_or_panic!(value > 5, "{value} too small", min = 5)).unwrap_err();
+
+        assert_eq!("3 too small", crate::panic_message(&payload));
+        assert_eq!(
+            vec![("min".to_string(), "5".to_string())],
+            fields(&*payload)
+        );
+    }
+
+    #[test]
+    fn get() {
+        let payload = catch_unwind(|| panic_with_context!("gus", id = 1)).unwrap_err();
+        let string: Box<dyn Any + Send> = Box::new("gus".to_string());
+
+        assert_eq!(
+            Some(&StructuredPanic {
+                message: "gus".to_string(),
+                fields: vec![("id".to_string(), "1".to_string())],
+            }),
+            get_structured_panic(&payload)
+        );
+        assert_eq!("gus", get_structured_panic(&*payload).unwrap().to_string());
+        assert_eq!(None, get_structured_panic(&string));
+    }
+}
@@ -0,0 +1,407 @@
+//! Raw frame addresses, so reports from stripped binaries can be symbolized later, on a
+//! host that has their symbol files.
+use std::{
+    collections::HashMap,
+    ffi::OsString,
+    fmt::Write as _,
+    io,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use crate::{trace::Trace, PanicDetails};
+
+/// An unresolved stack frame: an instruction address and the module it is in, see
+/// [`capture_raw_frames`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "schemars", derive(schemars::JsonSchema))]
+pub struct RawFrame {
+    /// The address in the process that panicked.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_impl::hex"))]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub address: u64,
+    /// The path of the executable or shared library the address is in, if it is known.
+    #[cfg_attr(
+        feature = "serde",
+        serde(default, skip_serializing_if = "Option::is_none")
+    )]
+    pub module: Option<String>,
+    /// The address as the module's file sees it, with where the module was loaded
+    /// subtracted. This is what symbolizers look up.
+    #[cfg_attr(feature = "serde", serde(with = "crate::serde_impl::hex"))]
+    #[cfg_attr(feature = "schemars", schemars(with = "String"))]
+    pub offset: u64,
+}
+
+/// Capture the addresses of the current stack's frames, without symbolizing them, which is
+/// cheap and works in stripped binaries. Return addresses are recorded one byte back, so
+/// they point into the call instruction and symbolize to the call site.
+///
+/// Frames are only captured on 64-bit Linux with glibc so far; elsewhere this returns no
+/// frames.
+///
+/// ## Examples
+/// ```
+/// use panic_message::{capture_raw_frames, PanicDetails};
+///
+/// let details = PanicDetails::new("gus").with_raw_frames(capture_raw_frames());
+/// # #[cfg(all(target_os = "linux", target_env = "gnu", target_pointer_width = "64"))]
+/// assert!(!details.raw_frames().is_empty());
+/// ```
+pub fn capture_raw_frames() -> Vec<RawFrame> {
+    #[cfg(all(target_os = "linux", target_env = "gnu", target_pointer_width = "64"))]
+    return linux::capture();
+    #[cfg(not(all(target_os = "linux", target_env = "gnu", target_pointer_width = "64")))]
+    Vec::new()
+}
+
+/// Where [`resolve_report`] finds the symbol files of the modules in a report, and which
+/// program it symbolizes them with.
+///
+/// A module's symbol file is, in order: the file given for its file name with
+/// [`with_file`][SymbolFiles::with_file]; the first match in a
+/// [`with_dir`][SymbolFiles::with_dir] directory; or the module itself, if it exists at the
+/// same path on this host.
+#[derive(Debug, Clone)]
+pub struct SymbolFiles {
+    program: OsString,
+    files: HashMap<String, PathBuf>,
+    dirs: Vec<PathBuf>,
+}
+
+impl Default for SymbolFiles {
+    fn default() -> Self {
+        SymbolFiles {
+            program: "addr2line".into(),
+            files: HashMap::new(),
+            dirs: Vec::new(),
+        }
+    }
+}
+
+impl SymbolFiles {
+    /// Symbolize with `addr2line`, using the modules themselves as their symbol files.
+    pub fn new() -> Self {
+        SymbolFiles::default()
+    }
+
+    /// Symbolize with `program` instead of `addr2line`. It is run as
+    /// `program -f -C -e <symbol file> <offset>...`, and must print the function and the
+    /// `file:line` of each offset on two lines, like binutils' `addr2line` does. For PDB
+    /// files and dSYM bundles, use `llvm-addr2line`.
+    pub fn with_program(mut self, program: impl Into<OsString>) -> Self {
+        self.program = program.into();
+        self
+    }
+
+    /// Use `path` as the symbol file of the module whose file name is `module`, like
+    /// `"server"` or `"libfoo.so"`.
+    pub fn with_file(mut self, module: impl Into<String>, path: impl Into<PathBuf>) -> Self {
+        self.files.insert(module.into(), path.into());
+        self
+    }
+
+    /// Look for symbol files in `dir`, under the module's file name, with `.debug` added,
+    /// as a `.pdb` file, or in a `.dSYM` bundle.
+    pub fn with_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        self.dirs.push(dir.into());
+        self
+    }
+
+    fn find(&self, module: &str) -> Option<PathBuf> {
+        let path = Path::new(module);
+        let name = path.file_name()?.to_str()?;
+        if let Some(file) = self.files.get(name) {
+            return Some(file.clone());
+        }
+        let stem = path.file_stem()?.to_str()?;
+        for dir in &self.dirs {
+            let candidates = [
+                dir.join(name),
+                dir.join(format!("{}.debug", name)),
+                dir.join(format!("{}.pdb", stem)),
+                dir.join(format!("{}.dSYM/Contents/Resources/DWARF/{}", name, name)),
+            ];
+            if let Some(found) = candidates.iter().find(|candidate| candidate.is_file()) {
+                return Some(found.clone());
+            }
+        }
+        Some(path.to_path_buf()).filter(|path| path.is_file())
+    }
+}
+
+/// A frame as the symbolizer resolved it.
+#[derive(Debug, PartialEq, Eq)]
+struct Symbol {
+    function: String,
+    location: Option<String>,
+}
+
+/// Symbolize the [raw frames][PanicDetails::raw_frames] of `report` with `symbols`, and
+/// replace its backtrace with the result. Frames in modules without a symbol file, or that
+/// the symbolizer doesn't know, are rendered as their module and offset.
+///
+/// Reports without raw frames are left alone.
+///
+/// ## Examples
+/// ```no_run
+/// use panic_message::{capture_raw_frames, resolve_report, PanicDetails, SymbolFiles};
+///
+/// let mut report = PanicDetails::new("gus").with_raw_frames(capture_raw_frames());
+///
+/// // later, on the host that has the symbol files
+/// resolve_report(&mut report, &SymbolFiles::new().with_dir("/srv/symbols")).unwrap();
+/// println!("{}", report.backtrace().unwrap());
+/// ```
+pub fn resolve_report(report: &mut PanicDetails, symbols: &SymbolFiles) -> io::Result<()> {
+    let frames = report.raw_frames();
+    if frames.is_empty() {
+        return Ok(());
+    }
+    let mut by_module: HashMap<&str, Vec<usize>> = HashMap::new();
+    for (i, frame) in frames.iter().enumerate() {
+        if let Some(module) = &frame.module {
+            by_module.entry(module).or_default().push(i);
+        }
+    }
+    let mut resolved: Vec<Option<Symbol>> = frames.iter().map(|_| None).collect();
+    for (module, indices) in by_module {
+        let file = match symbols.find(module) {
+            Some(file) => file,
+            None => continue,
+        };
+        let output = Command::new(&symbols.program)
+            .args(["-f", "-C", "-e"])
+            .arg(&file)
+            .args(indices.iter().map(|&i| format!("{:#x}", frames[i].offset)))
+            .output()?;
+        if !output.status.success() {
+            return Err(io::Error::other(format!(
+                "symbolizing with {} failed: {}",
+                file.display(),
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        let mut lines = stdout.lines();
+        for i in indices {
+            match (lines.next(), lines.next()) {
+                (Some(function), Some(location)) => resolved[i] = parse(function, location),
+                _ => break,
+            }
+        }
+    }
+    let rendered = render(frames, &resolved);
+    report.backtrace = Some(Trace::from(rendered));
+    Ok(())
+}
+
+/// Parse the two lines the symbolizer printed for one address, `None` if it didn't know it.
+fn parse(function: &str, location: &str) -> Option<Symbol> {
+    let location = Some(location.trim())
+        .filter(|location| !location.starts_with("??"))
+        .map(|location| {
+            // binutils appends a discriminator, like `lib.rs:10 (discriminator 2)`
+            location
+                .split(" (discriminator")
+                .next()
+                .unwrap_or(location)
+                .to_string()
+        });
+    match function.trim() {
+        "??" if location.is_none() => None,
+        function => Some(Symbol {
+            function: function.to_string(),
+            location,
+        }),
+    }
+}
+
+/// Render resolved frames the way [`std::backtrace::Backtrace`] does.
+fn render(frames: &[RawFrame], resolved: &[Option<Symbol>]) -> String {
+    let mut out = String::new();
+    for (i, (frame, symbol)) in frames.iter().zip(resolved).enumerate() {
+        let _ = match symbol {
+            Some(symbol) => writeln!(out, "{:>4}: {}", i, symbol.function),
+            None => match &frame.module {
+                Some(module) => {
+                    let name = Path::new(module)
+                        .file_name()
+                        .map_or(module.into(), |name| name.to_string_lossy());
+                    writeln!(out, "{:>4}: <unknown> ({}+{:#x})", i, name, frame.offset)
+                }
+                None => writeln!(out, "{:>4}: <unknown> ({:#x})", i, frame.address),
+            },
+        };
+        if let Some(location) = symbol.as_ref().and_then(|symbol| symbol.location.as_ref()) {
+            let _ = writeln!(out, "             at {}", location);
+        }
+    }
+    out
+}
+
+#[cfg(all(target_os = "linux", target_env = "gnu", target_pointer_width = "64"))]
+mod linux {
+    use std::{
+        ffi::CStr,
+        os::raw::{c_char, c_int, c_void},
+    };
+
+    use super::RawFrame;
+
+    const MAX_FRAMES: usize = 256;
+    const PT_LOAD: u32 = 1;
+
+    #[repr(C)]
+    struct DlInfo {
+        dli_fname: *const c_char,
+        dli_fbase: *mut c_void,
+        dli_sname: *const c_char,
+        dli_saddr: *mut c_void,
+    }
+
+    extern "C" {
+        fn backtrace(buffer: *mut *mut c_void, size: c_int) -> c_int;
+        fn dladdr(address: *const c_void, info: *mut DlInfo) -> c_int;
+    }
+
+    pub(super) fn capture() -> Vec<RawFrame> {
+        let mut buffer = [std::ptr::null_mut(); MAX_FRAMES];
+        // SAFETY: `buffer` has room for `MAX_FRAMES` addresses.
+        let len = unsafe { backtrace(buffer.as_mut_ptr(), MAX_FRAMES as c_int) };
+        buffer[..len.max(0) as usize]
+            .iter()
+            .enumerate()
+            // the first address is in `backtrace` itself, the rest are return addresses
+            .map(|(i, &ip)| frame((ip as u64).saturating_sub((i > 0).into())))
+            .collect()
+    }
+
+    fn frame(address: u64) -> RawFrame {
+        // SAFETY: `Dl_info` is pointers, for which all zeroes (null) is valid.
+        let mut info: DlInfo = unsafe { std::mem::zeroed() };
+        // SAFETY: `info` is a valid `Dl_info` to write to; `dladdr` only looks the
+        // address up, it doesn't dereference it.
+        if unsafe { dladdr(address as *const c_void, &mut info) } == 0 || info.dli_fbase.is_null() {
+            return RawFrame {
+                address,
+                module: None,
+                offset: address,
+            };
+        }
+        let module = Some(info.dli_fname)
+            .filter(|name| !name.is_null())
+            // SAFETY: `dladdr` sets a non-null name to a C string that lives as long as
+            // the module is loaded, and it is copied right away.
+            .map(|name| {
+                unsafe { CStr::from_ptr(name) }
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .filter(|name| !name.is_empty())
+            // the main executable may have an empty name
+            .or_else(|| Some(std::env::current_exe().ok()?.to_str()?.to_string()));
+        // SAFETY: `dli_fbase` is where `dladdr` found the module mapped, starting with
+        // its ELF header.
+        let bias = unsafe { load_bias(info.dli_fbase as *const u8) };
+        RawFrame {
+            address,
+            module,
+            offset: address.wrapping_sub(bias),
+        }
+    }
+
+    /// How far from the addresses in its file the ELF image mapped at `base` was loaded:
+    /// `base` itself for position-independent images, 0 for executables that aren't.
+    ///
+    /// # Safety
+    ///
+    /// `base` must point to a mapped ELF header, followed by its program headers.
+    unsafe fn load_bias(base: *const u8) -> u64 {
+        if base.cast::<[u8; 4]>().read() != *b"\x7fELF" {
+            return base as u64;
+        }
+        // `Elf64_Ehdr`: `e_phoff` at 32, `e_phentsize` at 54, `e_phnum` at 56
+        let phoff = base.add(32).cast::<u64>().read_unaligned() as usize;
+        let phentsize = usize::from(base.add(54).cast::<u16>().read_unaligned());
+        let phnum = usize::from(base.add(56).cast::<u16>().read_unaligned());
+        for i in 0..phnum {
+            let phdr = base.add(phoff + i * phentsize);
+            // `Elf64_Phdr`: `p_type` at 0, `p_offset` at 8, `p_vaddr` at 16; the first
+            // loaded segment is the one that maps the header
+            if phdr.cast::<u32>().read_unaligned() == PT_LOAD {
+                let offset = phdr.add(8).cast::<u64>().read_unaligned();
+                let vaddr = phdr.add(16).cast::<u64>().read_unaligned();
+                return (base as u64).wrapping_sub(vaddr.wrapping_sub(offset));
+            }
+        }
+        base as u64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_symbolizer_output() {
+        assert_eq!(
+            Some(Symbol {
+                function: "gus::main".to_string(),
+                location: Some("/src/main.rs:3".to_string()),
+            }),
+            parse("gus::main", "/src/main.rs:3 (discriminator 1)")
+        );
+        assert_eq!(
+            Some(Symbol {
+                function: "gus::main".to_string(),
+                location: None,
+            }),
+            parse("gus::main", "??:?")
+        );
+        assert_eq!(None, parse("??", "??:0"));
+    }
+
+    #[test]
+    fn render_unresolved() {
+        let frames = [
+            RawFrame {
+                address: 0x5000,
+                module: Some("/srv/libgus.so".to_string()),
+                offset: 0x1000,
+            },
+            RawFrame {
+                address: 0x6000,
+                module: None,
+                offset: 0x6000,
+            },
+        ];
+
+        assert_eq!(
+            "   0: <unknown> (libgus.so+0x1000)\n   1: <unknown> (0x6000)\n",
+            render(&frames, &[None, None])
+        );
+    }
+
+    #[cfg(all(target_os = "linux", target_env = "gnu", target_pointer_width = "64"))]
+    #[test]
+    fn resolves_this_test() {
+        let mut report = PanicDetails::new("gus").with_raw_frames(capture_raw_frames());
+        assert!(report
+            .raw_frames()
+            .iter()
+            .any(|frame| frame.module.is_some()));
+
+        match resolve_report(&mut report, &SymbolFiles::new()) {
+            Ok(()) => assert!(
+                report.backtrace().unwrap().contains("resolves_this_test"),
+                "{}",
+                report.backtrace().unwrap()
+            ),
+            // no `addr2line` on this host
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {}
+            Err(e) => panic!("{}", e),
+        }
+    }
+}
@@ -0,0 +1,82 @@
+//! Returning caught panics from `main`.
+use std::{
+    panic::{catch_unwind, UnwindSafe},
+    process::{ExitCode, Termination},
+};
+
+use crate::{CaughtPanic, PanicPayloadExt};
+
+/// What `main` returns to report a caught panic and exit with a failure code.
+///
+/// When it holds a [`CaughtPanic`], the message (and the backtrace, if one was attached) is
+/// printed to stderr and the process exits with code `101`, the code of an uncaught panic.
+/// Otherwise the value is reported as `main` would report it.
+///
+/// ## Examples
+/// ```no_run
+/// use panic_message::PanicExit;
+///
+/// fn main() -> PanicExit {
+///     PanicExit::catch(|| {
+///         // ...
+///     })
+/// }
+/// ```
+#[derive(Debug)]
+pub struct PanicExit<T = ()>(Result<T, CaughtPanic>);
+
+impl<T> PanicExit<T> {
+    /// Run `f`, catching a panic.
+    pub fn catch(f: impl FnOnce() -> T + UnwindSafe) -> Self {
+        PanicExit(catch_unwind(f).map_err(PanicPayloadExt::into_caught_panic))
+    }
+
+    /// The value, or the caught panic.
+    pub fn into_result(self) -> Result<T, CaughtPanic> {
+        self.0
+    }
+}
+
+impl<T> From<Result<T, CaughtPanic>> for PanicExit<T> {
+    fn from(result: Result<T, CaughtPanic>) -> Self {
+        PanicExit(result)
+    }
+}
+
+impl<T> From<CaughtPanic> for PanicExit<T> {
+    fn from(caught: CaughtPanic) -> Self {
+        PanicExit(Err(caught))
+    }
+}
+
+impl<T: Termination> Termination for PanicExit<T> {
+    fn report(self) -> ExitCode {
+        match self.0 {
+            Ok(value) => value.report(),
+            Err(caught) => {
+                eprintln!("Error: {}: {}", crate::labels().program_panicked, caught);
+                if let Some(backtrace) = caught.backtrace() {
+                    eprintln!("{}", backtrace);
+                }
+                ExitCode::from(101)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn catch() {
+        assert_eq!(3, PanicExit::catch(|| 3).into_result().unwrap());
+
+        let caught = PanicExit::<()>::catch(|| panic!("gus"))
+            .into_result()
+            .unwrap_err();
+        assert_eq!("gus", caught.message());
+
+        assert_eq!(ExitCode::from(101), PanicExit::<()>::from(caught).report());
+    }
+}
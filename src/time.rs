@@ -0,0 +1,76 @@
+//! Timestamp rendering, without pulling in a date library.
+use std::{
+    fmt,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Whether the target has a clock. On `wasm32-unknown-unknown` reading one panics, which in
+/// a panic hook aborts the process.
+pub(crate) const HAS_CLOCK: bool = !cfg!(all(target_family = "wasm", target_os = "unknown"));
+
+/// The current time, or the epoch on targets without a clock.
+pub(crate) fn now() -> SystemTime {
+    if HAS_CLOCK {
+        SystemTime::now()
+    } else {
+        UNIX_EPOCH
+    }
+}
+
+/// Displays a [`SystemTime`] as an RFC 3339 UTC timestamp with millisecond precision,
+/// e.g. `2021-06-01T12:30:00.000Z`. Times before the epoch are clamped to it.
+pub(crate) struct Rfc3339(pub(crate) SystemTime);
+
+impl fmt::Display for Rfc3339 {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let since_epoch = self.0.duration_since(UNIX_EPOCH).unwrap_or_default();
+        let secs = since_epoch.as_secs();
+        let (year, month, day) = civil_from_days((secs / 86_400) as i64);
+        let secs_of_day = secs % 86_400;
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:03}Z",
+            year,
+            month,
+            day,
+            secs_of_day / 3600,
+            secs_of_day / 60 % 60,
+            secs_of_day % 60,
+            since_epoch.subsec_millis()
+        )
+    }
+}
+
+/// Convert days since 1970-01-01 to a proleptic Gregorian (year, month, day).
+///
+/// See <http://howardhinnant.github.io/date_algorithms.html#civil_from_days>.
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + (month <= 2) as i64;
+    (year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn rfc3339() {
+        let at = |secs, millis| {
+            Rfc3339(UNIX_EPOCH + Duration::from_secs(secs) + Duration::from_millis(millis))
+                .to_string()
+        };
+
+        assert_eq!("1970-01-01T00:00:00.000Z", at(0, 0));
+        assert_eq!("2000-02-29T23:59:59.999Z", at(951_868_799, 999));
+        assert_eq!("2021-06-01T12:30:00.000Z", at(1_622_550_600, 0));
+    }
+}
@@ -0,0 +1,362 @@
+//! Backtraces that are only symbolized when first rendered.
+use std::{
+    backtrace::Backtrace,
+    borrow::Cow,
+    fmt::{self, Write},
+    sync::{Arc, OnceLock},
+};
+
+use crate::PanicDetails;
+
+/// The frames of the panic and unwinding machinery, which [`BacktraceFilter`] hides. This
+/// crate's own frames are only hidden where it runs as that machinery, capturing a
+/// backtrace in a hook, so a panic in the rest of it keeps its frames.
+const MACHINERY: &[&str] = &[
+    "std::panicking::",
+    "core::panicking::",
+    "std::sys::backtrace::",
+    "std::sys_common::backtrace::",
+    "std::backtrace::",
+    "std::backtrace_rs::",
+    "rust_begin_unwind",
+    "__rustc::rust_begin_unwind",
+    "rust_panic",
+    "__rust_start_panic",
+    "__rust_try",
+    "panic_unwind::",
+    "_Unwind_",
+    "panic_message::hook::",
+    "panic_message::isolate::",
+    "panic_message::catch::install_hook::",
+    "panic_message::details::PanicDetails::capture",
+    "panic_message::details::PanicDetails::from_panic_info",
+    "panic_message::trace::BacktraceMode::capture",
+];
+
+/// When a panic hook captures a backtrace, see
+/// [`PanicHookBuilder::with_backtrace`][crate::PanicHookBuilder::with_backtrace].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum BacktraceMode {
+    /// Never.
+    Disabled,
+    /// When `RUST_BACKTRACE` or `RUST_LIB_BACKTRACE` asks for one, like
+    /// [`Backtrace::capture`] and the default hook.
+    #[default]
+    Env,
+    /// Always, like [`Backtrace::force_capture`].
+    Forced,
+}
+
+impl BacktraceMode {
+    pub(crate) fn capture(self) -> Option<Trace> {
+        let backtrace = match self {
+            BacktraceMode::Disabled => return None,
+            BacktraceMode::Env => Backtrace::capture(),
+            BacktraceMode::Forced => Backtrace::force_capture(),
+        };
+        match backtrace.status() {
+            std::backtrace::BacktraceStatus::Captured => Some(Trace::captured(backtrace)),
+            _ => None,
+        }
+    }
+}
+
+/// The backtrace of a [`PanicDetails`][crate::PanicDetails]: already rendered, or
+/// captured and rendered (which symbolizes it) the first time it is needed.
+#[derive(Clone)]
+pub(crate) enum Trace {
+    Rendered(String),
+    Captured {
+        backtrace: Arc<Backtrace>,
+        rendered: OnceLock<String>,
+    },
+}
+
+impl Trace {
+    pub(crate) fn captured(backtrace: Backtrace) -> Self {
+        Trace::Captured {
+            backtrace: Arc::new(backtrace),
+            rendered: OnceLock::new(),
+        }
+    }
+
+    pub(crate) fn as_str(&self) -> &str {
+        match self {
+            Trace::Rendered(rendered) => rendered,
+            Trace::Captured {
+                backtrace,
+                rendered,
+            } => rendered.get_or_init(|| backtrace.to_string()),
+        }
+    }
+
+    pub(crate) fn backtrace(&self) -> Option<&Backtrace> {
+        match self {
+            Trace::Rendered(_) => None,
+            Trace::Captured { backtrace, .. } => Some(backtrace),
+        }
+    }
+
+    pub(crate) fn is_resolved(&self) -> bool {
+        match self {
+            Trace::Rendered(_) => true,
+            Trace::Captured { rendered, .. } => rendered.get().is_some(),
+        }
+    }
+}
+
+impl From<String> for Trace {
+    fn from(rendered: String) -> Self {
+        Trace::Rendered(rendered)
+    }
+}
+
+impl PartialEq for Trace {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl Eq for Trace {}
+
+impl fmt::Debug for Trace {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+/// Hides the frames of a rendered backtrace that only show how the panic was raised and
+/// reported, so it starts at the code that panicked, like the short backtraces of
+/// `RUST_BACKTRACE=1`.
+///
+/// It drops the frames up to the end of the panic machinery and from the start of the
+/// runtime's, which the standard library marks with `__rust_end_short_backtrace` and
+/// `__rust_begin_short_backtrace`, then any frame whose function starts with one of its
+/// prefixes: by default `std::panicking::`, `core::panicking::`, the unwinding runtime's
+/// and this crate's. The frames left are numbered from 0 again.
+///
+/// ## Examples
+/// ```
+/// use panic_message::{BacktraceFilter, PanicDetails};
+///
+/// let details = PanicDetails::new("gus")
+///     .with_backtrace(
+///         "   0: std::panicking::begin_panic_handler\n\
+///          \x20  1: core::panicking::panic_fmt\n\
+///          \x20  2: tokio::runtime::task::poll\n\
+///          \x20  3: server::handle\n\
+///          \x20            at ./src/main.rs:3:5",
+///     )
+///     .with_backtrace_filter(&BacktraceFilter::new().hide_prefix("tokio::"));
+///
+/// assert_eq!(
+///     "   0: server::handle\n             at ./src/main.rs:3:5",
+///     details.backtrace().unwrap()
+/// );
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BacktraceFilter {
+    prefixes: Vec<Cow<'static, str>>,
+}
+
+impl BacktraceFilter {
+    /// A filter hiding the panic and unwinding machinery.
+    pub fn new() -> Self {
+        BacktraceFilter {
+            prefixes: MACHINERY.iter().map(|&prefix| prefix.into()).collect(),
+        }
+    }
+
+    /// Also hide the frames of functions starting with `prefix`, like `tokio::runtime::`
+    /// or a framework's dispatch code.
+    pub fn hide_prefix(mut self, prefix: impl Into<Cow<'static, str>>) -> Self {
+        self.prefixes.push(prefix.into());
+        self
+    }
+
+    /// Filter a backtrace rendered like [`Backtrace`]'s `Display` impl renders one, keeping
+    /// lines that aren't part of a frame.
+    pub fn filter(&self, backtrace: &str) -> String {
+        let frames = frames(backtrace);
+        let start = frames
+            .iter()
+            .rposition(|frame| frame.function().contains("__rust_end_short_backtrace"))
+            .map_or(0, |end| end + 1);
+        let end = frames
+            .iter()
+            .skip(start)
+            .position(|frame| frame.function().contains("__rust_begin_short_backtrace"))
+            .map_or(frames.len(), |begin| start + begin);
+
+        let mut out = String::new();
+        let mut number = 0;
+        for (i, frame) in frames.iter().enumerate() {
+            let function = match frame.number {
+                Some(_) => frame.function(),
+                None => {
+                    // not a frame, like a line the backtrace starts with
+                    out.push_str(frame.lines);
+                    continue;
+                }
+            };
+            if i < start
+                || i >= end
+                || self
+                    .prefixes
+                    .iter()
+                    .any(|prefix| function.starts_with(&**prefix))
+            {
+                continue;
+            }
+            if !out.is_empty() && !out.ends_with('\n') {
+                out.push('\n');
+            }
+            let _ = write!(
+                out,
+                "{:4}: {}",
+                number,
+                frame.lines[frame.header_len..].trim_end()
+            );
+            number += 1;
+        }
+        out.truncate(out.trim_end().len());
+        out
+    }
+}
+
+impl Default for BacktraceFilter {
+    fn default() -> Self {
+        BacktraceFilter::new()
+    }
+}
+
+/// A frame of a rendered backtrace: its `N: function` line and the `at file:line` lines
+/// after it, or a line that isn't part of a frame.
+struct Frame<'a> {
+    number: Option<usize>,
+    /// The frame's lines, with the line break that ends them.
+    lines: &'a str,
+    /// How long the `   N: ` before the function is.
+    header_len: usize,
+}
+
+impl Frame<'_> {
+    fn function(&self) -> &str {
+        let rest = &self.lines[self.header_len..];
+        rest.lines().next().unwrap_or("").trim()
+    }
+}
+
+fn frames(backtrace: &str) -> Vec<Frame<'_>> {
+    let mut frames: Vec<Frame<'_>> = Vec::new();
+    let mut offset = 0;
+    for line in backtrace.split_inclusive('\n') {
+        let start = offset;
+        offset += line.len();
+        match header(line) {
+            Some((number, header_len)) => frames.push(Frame {
+                number: Some(number),
+                lines: &backtrace[start..offset],
+                header_len,
+            }),
+            None => match frames.last_mut() {
+                // an `at` line, or one wrapped from the function's
+                Some(frame) if frame.number.is_some() => {
+                    let begin = frame.lines.as_ptr() as usize - backtrace.as_ptr() as usize;
+                    frame.lines = &backtrace[begin..offset];
+                }
+                _ => frames.push(Frame {
+                    number: None,
+                    lines: line,
+                    header_len: 0,
+                }),
+            },
+        }
+    }
+    frames
+}
+
+/// The frame number of a `   N: function` line, and how long the part before the function
+/// is.
+fn header(line: &str) -> Option<(usize, usize)> {
+    let digits = line.trim_start();
+    let indent = line.len() - digits.len();
+    let colon = digits.find(": ")?;
+    let number = digits[..colon].parse().ok()?;
+    Some((number, indent + colon + 2))
+}
+
+impl PanicDetails {
+    /// Hide the backtrace's machinery frames with `filter`, rendering the backtrace if it
+    /// hasn't been yet.
+    pub fn with_backtrace_filter(mut self, filter: &BacktraceFilter) -> Self {
+        if let Some(backtrace) = self.backtrace() {
+            self.backtrace = Some(Trace::Rendered(filter.filter(backtrace)));
+        }
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BACKTRACE: &str = "   0: panic_message::trace::BacktraceMode::capture
+             at ./src/trace.rs:27:29
+   1: std::panicking::rust_panic_with_hook
+   2: std::sys::backtrace::__rust_end_short_backtrace
+   3: __rustc::rust_begin_unwind
+   4: core::panicking::panic_fmt
+   5: server::handle
+             at ./src/main.rs:3:5
+   6: server::main
+             at ./src/main.rs:9:5
+   7: core::ops::function::FnOnce::call_once
+   8: std::sys::backtrace::__rust_begin_short_backtrace
+   9: std::rt::lang_start
+  10: main
+";
+
+    #[test]
+    fn short_backtrace() {
+        assert_eq!(
+            "   0: server::handle
+             at ./src/main.rs:3:5
+   1: server::main
+             at ./src/main.rs:9:5
+   2: core::ops::function::FnOnce::call_once",
+            BacktraceFilter::new().filter(BACKTRACE)
+        );
+    }
+
+    #[test]
+    fn without_markers() {
+        let backtrace = "disabled backtrace\n   0: std::panicking::begin_panic\n   1: gus\n";
+
+        assert_eq!(
+            "disabled backtrace\n   0: gus",
+            BacktraceFilter::new().filter(backtrace)
+        );
+        assert_eq!(
+            "disabled backtrace",
+            BacktraceFilter::new().hide_prefix("gus").filter(backtrace)
+        );
+    }
+
+    #[test]
+    fn keeps_other_frames_of_this_crate() {
+        let backtrace = "   0: panic_message::hook::PanicHook::report
+   1: panic_message::isolate::isolated::{{closure}}
+   2: panic_message::registry::owned_message
+             at ./src/registry.rs:40:5
+   3: server::main
+";
+
+        assert_eq!(
+            "   0: panic_message::registry::owned_message
+             at ./src/registry.rs:40:5
+   1: server::main",
+            BacktraceFilter::new().filter(backtrace)
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! How long the process has been running, and how long ago it last panicked.
+use std::{
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+/// When the crate was first asked for the uptime, for platforms that don't say when
+/// the process started.
+static FIRST_USE: OnceLock<Instant> = OnceLock::new();
+static LAST_PANIC: Mutex<Option<Instant>> = Mutex::new(None);
+
+/// Start measuring uptime, for platforms where it can't be read from the OS.
+pub(crate) fn start() {
+    if crate::time::HAS_CLOCK {
+        FIRST_USE.get_or_init(Instant::now);
+    }
+}
+
+/// How long the process has been running. Where the OS doesn't say, this is the time
+/// since [`start`] was first called, usually when a panic hook was built. `None` on
+/// targets without a clock.
+pub(crate) fn uptime() -> Option<Duration> {
+    #[cfg(any(target_os = "linux", target_os = "android"))]
+    if let Some(uptime) = linux::process_age() {
+        return Some(uptime);
+    }
+    if !crate::time::HAS_CLOCK {
+        return None;
+    }
+    Some(truncate(FIRST_USE.get_or_init(Instant::now).elapsed()))
+}
+
+/// Record a panic now, returning how long ago the previous one was recorded.
+pub(crate) fn record_panic() -> Option<Duration> {
+    if !crate::time::HAS_CLOCK {
+        return None;
+    }
+    let now = Instant::now();
+    let mut last = LAST_PANIC.lock().unwrap_or_else(|e| e.into_inner());
+    let previous = last.replace(now)?;
+    Some(truncate(now - previous))
+}
+
+/// Round down to whole microseconds, the resolution of the serialized formats, so
+/// reports survive a round trip through them unchanged.
+fn truncate(duration: Duration) -> Duration {
+    Duration::from_micros(duration.as_micros() as u64)
+}
+
+#[cfg(any(target_os = "linux", target_os = "android"))]
+mod linux {
+    use std::{os::raw::c_long, time::Duration};
+
+    const SC_CLK_TCK: i32 = 2;
+
+    extern "C" {
+        fn sysconf(name: i32) -> c_long;
+    }
+
+    /// The time since boot, minus the time the process started after boot.
+    pub(super) fn process_age() -> Option<Duration> {
+        let uptime = std::fs::read_to_string("/proc/uptime").ok()?;
+        let since_boot: f64 = uptime.split_whitespace().next()?.parse().ok()?;
+
+        let stat = std::fs::read_to_string("/proc/self/stat").ok()?;
+        // the command name in parentheses may contain spaces, so skip past it; `starttime`
+        // is field 22, the 20th after the name
+        let fields = &stat[stat.rfind(')')? + 1..];
+        let start_ticks: u64 = fields.split_whitespace().nth(19)?.parse().ok()?;
+        // SAFETY: `sysconf` has no preconditions.
+        let ticks_per_second = unsafe { sysconf(SC_CLK_TCK) };
+        if ticks_per_second <= 0 {
+            return None;
+        }
+
+        let started = start_ticks as f64 / ticks_per_second as f64;
+        Some(super::truncate(Duration::from_secs_f64(
+            (since_boot - started).max(0.0),
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn uptime_increases() {
+        start();
+        let first = uptime().unwrap();
+        std::thread::sleep(Duration::from_millis(50));
+
+        assert!(uptime().unwrap() > first);
+        assert!(first < Duration::from_secs(24 * 60 * 60));
+    }
+
+    #[test]
+    fn time_since_previous_panic() {
+        record_panic();
+
+        assert!(record_panic().unwrap() < Duration::from_secs(60));
+    }
+
+    #[test]
+    fn truncates_to_micros() {
+        assert_eq!(
+            Duration::from_micros(1),
+            truncate(Duration::from_nanos(1999))
+        );
+    }
+}
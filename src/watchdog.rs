@@ -0,0 +1,169 @@
+//! Detecting crash loops with a marker file that is only cleared on clean shutdown.
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+/// A marker file that counts how many runs in a row ended without a clean shutdown, so an
+/// application that keeps panicking on startup can notice and fall back to a safe mode.
+///
+/// [`start`][CrashWatchdog::start] marks the file as running; [`shutdown`][CrashWatchdog::shutdown]
+/// marks it clean again. If the next run finds it still marked as running, the previous run
+/// crashed (or was killed), and [`consecutive_crashes`][CrashWatchdog::consecutive_crashes]
+/// counts one more crash.
+///
+/// ## Examples
+/// ```
+/// use panic_message::CrashWatchdog;
+///
+/// # let path = std::env::temp_dir().join(format!("panic-message-doc-{}", std::process::id()));
+/// let watchdog = CrashWatchdog::start(&path).unwrap();
+/// if watchdog.consecutive_crashes() >= 3 {
+///     // start without plugins, the last three runs crashed
+/// }
+///
+/// // ...
+///
+/// watchdog.shutdown().unwrap();
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct CrashWatchdog {
+    path: PathBuf,
+    consecutive_crashes: u32,
+}
+
+/// What the marker file last recorded.
+#[derive(Debug, PartialEq, Eq)]
+enum Marker {
+    /// The last run shut down cleanly.
+    Clean,
+    /// A run started, after this many crashed runs, and hasn't shut down.
+    Running { previous_crashes: u32 },
+}
+
+impl CrashWatchdog {
+    /// Read the marker at `path`, then mark it as running.
+    ///
+    /// A missing marker, or one with contents it doesn't recognize, counts as a clean shutdown.
+    pub fn start(path: impl AsRef<Path>) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let consecutive_crashes = match fs::read_to_string(&path) {
+            Ok(contents) => match Marker::parse(&contents) {
+                Marker::Clean => 0,
+                Marker::Running { previous_crashes } => previous_crashes.saturating_add(1),
+            },
+            Err(e) if e.kind() == io::ErrorKind::NotFound => 0,
+            Err(e) => return Err(e),
+        };
+        write(
+            &path,
+            &Marker::Running {
+                previous_crashes: consecutive_crashes,
+            },
+        )?;
+        Ok(CrashWatchdog {
+            path,
+            consecutive_crashes,
+        })
+    }
+
+    /// How many runs in a row before this one ended without calling
+    /// [`shutdown`][CrashWatchdog::shutdown].
+    pub fn consecutive_crashes(&self) -> u32 {
+        self.consecutive_crashes
+    }
+
+    /// Mark the marker clean, so the next run starts with no crashes counted.
+    ///
+    /// There is deliberately no `Drop` impl doing this: unwinding out of `main` after a
+    /// panic drops values too, and must still count as a crash.
+    pub fn shutdown(self) -> io::Result<()> {
+        write(&self.path, &Marker::Clean)
+    }
+}
+
+impl Marker {
+    fn parse(contents: &str) -> Self {
+        let mut words = contents.split_whitespace();
+        match (words.next(), words.next().map(str::parse)) {
+            (Some("running"), Some(Ok(previous_crashes))) => Marker::Running { previous_crashes },
+            // a marker cut short by a crash while writing it
+            (Some("running"), _) => Marker::Running {
+                previous_crashes: 0,
+            },
+            _ => Marker::Clean,
+        }
+    }
+
+    fn render(&self) -> String {
+        match self {
+            Marker::Clean => "clean\n".to_string(),
+            Marker::Running { previous_crashes } => format!("running {}\n", previous_crashes),
+        }
+    }
+}
+
+/// Replace the marker by renaming a new file over it, so it is never half-written.
+fn write(path: &Path, marker: &Marker) -> io::Result<()> {
+    let mut temp = path.as_os_str().to_owned();
+    temp.push(".tmp");
+    fs::write(&temp, marker.render())?;
+    fs::rename(&temp, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_marker(name: &str) -> PathBuf {
+        let path = std::env::temp_dir().join(format!(
+            "panic-message-watchdog-{}-{}",
+            std::process::id(),
+            name
+        ));
+        let _ = fs::remove_file(&path);
+        path
+    }
+
+    #[test]
+    fn counts_runs_without_shutdown() {
+        let path = temp_marker("counts");
+
+        assert_eq!(
+            0,
+            CrashWatchdog::start(&path).unwrap().consecutive_crashes()
+        );
+        assert_eq!(
+            1,
+            CrashWatchdog::start(&path).unwrap().consecutive_crashes()
+        );
+        let watchdog = CrashWatchdog::start(&path).unwrap();
+        assert_eq!(2, watchdog.consecutive_crashes());
+        watchdog.shutdown().unwrap();
+        assert_eq!(
+            0,
+            CrashWatchdog::start(&path).unwrap().consecutive_crashes()
+        );
+
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn parse() {
+        assert_eq!(Marker::Clean, Marker::parse("clean\n"));
+        assert_eq!(Marker::Clean, Marker::parse(""));
+        assert_eq!(
+            Marker::Running {
+                previous_crashes: 4
+            },
+            Marker::parse("running 4\n")
+        );
+        assert_eq!(
+            Marker::Running {
+                previous_crashes: 0
+            },
+            Marker::parse("running")
+        );
+    }
+}
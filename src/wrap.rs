@@ -0,0 +1,178 @@
+//! Payloads wrapping a value that isn't a string, rendered so the message can be extracted.
+use std::{any::Any, borrow::Cow, fmt};
+
+use crate::PanicMessageProvider;
+
+/// A panic payload holding a value and its `Display` rendering, the message every
+/// function in this crate reports for it. See [`panic_any_display`].
+///
+/// The message is rendered when the wrapper is created, so it can be returned as a `&str`
+/// and later changes to the value through interior mutability don't show.
+pub struct DisplayPanic<T> {
+    value: T,
+    message: String,
+}
+
+impl<T: fmt::Display + Send + 'static> DisplayPanic<T> {
+    /// Wrap `value`, rendering its message.
+    pub fn new(value: T) -> Self {
+        DisplayPanic {
+            message: value.to_string(),
+            value,
+        }
+    }
+}
+
+impl<T> DisplayPanic<T> {
+    /// The value panicked with.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Take the value back.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    /// The value's rendering.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl<T: Send + 'static> PanicMessageProvider for DisplayPanic<T> {
+    fn panic_message(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.message)
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for DisplayPanic<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DisplayPanic").field(&self.value).finish()
+    }
+}
+
+/// A panic payload holding a value and its `Debug` rendering, the message every function
+/// in this crate reports for it. See [`panic_any_debug!`].
+///
+/// Like [`DisplayPanic`], the message is rendered when the wrapper is created.
+pub struct DebugPanic<T> {
+    value: T,
+    message: String,
+}
+
+impl<T: fmt::Debug + Send + 'static> DebugPanic<T> {
+    /// Wrap `value`, rendering its message with `{:?}`.
+    pub fn new(value: T) -> Self {
+        DebugPanic {
+            message: format!("{:?}", value),
+            value,
+        }
+    }
+}
+
+impl<T> DebugPanic<T> {
+    /// The value panicked with.
+    pub fn value(&self) -> &T {
+        &self.value
+    }
+
+    /// Take the value back.
+    pub fn into_inner(self) -> T {
+        self.value
+    }
+
+    /// The value's rendering.
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl<T: Send + 'static> PanicMessageProvider for DebugPanic<T> {
+    fn panic_message(&self) -> Cow<'_, str> {
+        Cow::Borrowed(&self.message)
+    }
+}
+
+impl<T> fmt::Debug for DebugPanic<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("DebugPanic").field(&self.message).finish()
+    }
+}
+
+/// Panic with a value as the payload, wrapped in a [`DebugPanic`] so the message is its
+/// `Debug` rendering instead of `"Box<dyn Any>"`.
+///
+/// ## Examples
+/// ```
+/// use std::panic::catch_unwind;
+/// use panic_message::{panic_any_debug, panic_message, DebugPanic};
+///
+/// #[derive(Debug)]
+/// struct Fatal {
+///     code: u32,
+/// }
+///
+/// let payload = catch_unwind(|| panic_any_debug!(Fatal { code: 3 })).unwrap_err();
+///
+/// assert_eq!("Fatal { code: 3 }", panic_message(&payload));
+/// assert_eq!(3, payload.downcast_ref::<DebugPanic<Fatal>>().unwrap().value().code);
+/// ```
+#[macro_export]
+macro_rules! panic_any_debug {
+    ($value:expr $(,)?) => {
+        $crate::PanicMessageProvider::panic($crate::DebugPanic::new($value))
+    };
+}
+
+/// Panic with `value` as the payload, wrapped in a [`DisplayPanic`] so the message is its
+/// `Display` rendering instead of `"Box<dyn Any>"`, which is all a `dyn Any` payload
+/// allows otherwise.
+///
+/// ## Examples
+/// ```
+/// use std::{net::Ipv4Addr, panic::catch_unwind};
+/// use panic_message::{panic_any_display, panic_message, DisplayPanic};
+///
+/// let payload = catch_unwind(|| panic_any_display(Ipv4Addr::LOCALHOST)).unwrap_err();
+///
+/// assert_eq!("127.0.0.1", panic_message(&payload));
+/// let wrapped = payload.downcast_ref::<DisplayPanic<Ipv4Addr>>().unwrap();
+/// assert!(wrapped.value().is_loopback());
+/// ```
+#[track_caller]
+pub fn panic_any_display<T: fmt::Display + Any + Send>(value: T) -> ! {
+    DisplayPanic::new(value).panic()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::panic::catch_unwind;
+
+    #[test]
+    fn display() {
+        let payload = catch_unwind(|| panic_any_display(3_u32)).unwrap_err();
+
+        assert_eq!("3", crate::panic_message(&payload));
+        assert_eq!("3", crate::PanicDetails::from_payload(&payload).message());
+        let wrapped = payload.downcast::<DisplayPanic<u32>>().unwrap();
+        assert_eq!(3, wrapped.into_inner());
+    }
+
+    #[test]
+    fn debug() {
+        let payload = catch_unwind(|| panic_any_debug!(Some("gus"))).unwrap_err();
+
+        assert_eq!(r#"Some("gus")"#, crate::panic_message(&payload));
+        let wrapped = payload.downcast_ref::<DebugPanic<Option<&str>>>().unwrap();
+        assert_eq!(r#"DebugPanic("Some(\"gus\")")"#, format!("{:?}", wrapped));
+    }
+
+    #[test]
+    fn location_is_the_caller() {
+        let report = crate::catch_unwind_report(|| panic_any_display(3_u32)).unwrap_err();
+
+        assert!(report.location().unwrap().file().ends_with("wrap.rs"));
+    }
+}
@@ -0,0 +1,96 @@
+//! Needs the `macros` feature.
+#![cfg(feature = "macros")]
+use std::fmt;
+
+use panic_message::{catch_panic, CaughtPanic};
+
+#[catch_panic]
+fn double(value: u32) -> u32 {
+    if value == 0 {
+        panic!("zero");
+    }
+    value * 2
+}
+
+#[catch_panic]
+fn nothing() {
+    panic!("gus");
+}
+
+#[derive(Debug)]
+enum Error {
+    Odd,
+    Panicked(String),
+}
+
+impl From<CaughtPanic> for Error {
+    fn from(caught: CaughtPanic) -> Self {
+        Error::Panicked(caught.to_string())
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Odd => f.write_str("odd"),
+            Error::Panicked(message) => write!(f, "panicked: {}", message),
+        }
+    }
+}
+
+#[catch_panic]
+fn halve<T>(value: T) -> Result<u32, Error>
+where
+    T: Into<u32>,
+{
+    let value = value.into();
+    if value % 2 == 1 {
+        return Err(Error::Odd);
+    }
+    Ok(100 / value * 2)
+}
+
+#[catch_panic]
+fn run<F: Fn() -> u8>(f: F) -> u32 {
+    u32::from(f())
+}
+
+const BODY_LINE: u32 = line!() + 3;
+#[catch_panic]
+fn body_line() -> u32 {
+    line!()
+}
+
+struct Counter(u32);
+
+impl Counter {
+    #[catch_panic]
+    pub fn bump(&mut self) -> u32 {
+        self.0 += 1;
+        assert!(self.0 < 2, "too many");
+        self.0
+    }
+}
+
+#[test]
+fn catch_panic() {
+    assert_eq!(4, double(2).unwrap());
+    assert_eq!("zero", double(0).unwrap_err().message());
+    assert_eq!("gus", nothing().unwrap_err().message());
+
+    assert_eq!(50, halve(4_u8).unwrap());
+    assert!(matches!(halve(3_u8), Err(Error::Odd)));
+    assert_eq!(
+        "panicked: attempt to divide by zero",
+        halve(0_u8).unwrap_err().to_string()
+    );
+
+    assert_eq!(7, run(|| 7).unwrap());
+    assert_eq!("gus", run(|| panic!("gus")).unwrap_err().message());
+    // the body keeps its spans
+    assert_eq!(BODY_LINE, body_line().unwrap());
+
+    let mut counter = Counter(0);
+    assert_eq!(1, counter.bump().unwrap());
+    assert_eq!("too many", counter.bump().unwrap_err().message());
+}
@@ -0,0 +1,41 @@
+//! `catch_unwind_report` wraps the process-global panic hook, so this runs on its own.
+use std::panic::{catch_unwind, resume_unwind, AssertUnwindSafe};
+use std::sync::atomic::{AtomicU32, Ordering::SeqCst};
+
+use panic_message::catch_unwind_report;
+
+#[test]
+fn reports_carry_the_location() {
+    static PREVIOUS_HOOK_CALLS: AtomicU32 = AtomicU32::new(0);
+    std::panic::set_hook(Box::new(|_| {
+        PREVIOUS_HOOK_CALLS.fetch_add(1, SeqCst);
+    }));
+
+    let line = line!() + 1;
+    let report = catch_unwind_report(|| panic!("gus")).unwrap_err();
+    assert_eq!("gus", report.message());
+    let location = report.location().unwrap();
+    assert!(location.file().ends_with("catch_unwind_report.rs"));
+    assert_eq!(line, location.line());
+    assert_eq!(Some("reports_carry_the_location"), report.thread());
+    assert_eq!(1, PREVIOUS_HOOK_CALLS.load(SeqCst));
+
+    // a panic caught inside doesn't stand in for the one that got out
+    let line = line!() + 3;
+    let report = catch_unwind_report(|| {
+        catch_unwind(|| panic!("inner")).unwrap_err();
+        panic!("outer")
+    })
+    .unwrap_err();
+    assert_eq!("outer", report.message());
+    assert_eq!(line, report.location().unwrap().line());
+
+    // and a resumed payload has no location to match
+    let payload = catch_unwind(|| panic!("earlier")).unwrap_err();
+    let report = catch_unwind_report(AssertUnwindSafe(move || -> u32 { resume_unwind(payload) }))
+        .unwrap_err();
+    assert_eq!("earlier", report.message());
+    assert_eq!(None, report.location());
+
+    assert_eq!(Ok(3), catch_unwind_report(|| 3).map_err(|_| ()));
+}
@@ -0,0 +1,24 @@
+//! `catch_unwind_report_with` wraps the process-global panic hook, so this runs on its own.
+use panic_message::{catch_unwind_report_with, BacktraceMode};
+
+#[test]
+fn backtrace_modes() {
+    std::panic::set_hook(Box::new(|_| {}));
+
+    let report = catch_unwind_report_with(BacktraceMode::Forced, || panic!("gus")).unwrap_err();
+    assert!(report.std_backtrace().is_some());
+    assert!(report.backtrace().unwrap().contains("backtrace_modes"));
+
+    let report = catch_unwind_report_with(BacktraceMode::Disabled, || panic!("gus")).unwrap_err();
+    assert_eq!(None, report.backtrace());
+
+    // the innermost call decides
+    let report = catch_unwind_report_with(BacktraceMode::Disabled, || {
+        let inner = catch_unwind_report_with(BacktraceMode::Forced, || panic!("inner"));
+        assert!(inner.unwrap_err().backtrace().is_some());
+        panic!("outer")
+    })
+    .unwrap_err();
+    assert_eq!("outer", report.message());
+    assert!(report.std_backtrace().is_none());
+}
@@ -0,0 +1,46 @@
+//! Installs panic hooks, which are process-global, so it runs on its own
+//!
+use std::{
+    panic::catch_unwind,
+    sync::{Arc, Mutex},
+};
+
+use panic_message::PanicHookBuilder;
+
+mod common;
+use common::Log;
+
+#[test]
+fn previous_hooks_are_called() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let previous = Arc::clone(&seen);
+    std::panic::set_hook(Box::new(move |pi| {
+        let message = panic_message::panic_info_message(pi);
+        previous
+            .lock()
+            .unwrap()
+            .push(format!("previous: {}", message));
+    }));
+    PanicHookBuilder::new()
+        .sink(Log(Arc::clone(&seen), "sink"))
+        .chain_previous(true)
+        .install();
+
+    catch_unwind(|| panic!("gus")).unwrap_err();
+    assert_eq!(vec!["sink: gus", "previous: gus"], *seen.lock().unwrap());
+
+    seen.lock().unwrap().clear();
+    PanicHookBuilder::new()
+        .sink(Log(Arc::clone(&seen), "sink"))
+        .chain_previous_first(true)
+        .install();
+
+    catch_unwind(|| panic!("wynn")).unwrap_err();
+    let _ = std::panic::take_hook();
+
+    // the first hook from this crate is now the previous one, and chains the first hook
+    assert_eq!(
+        vec!["sink: wynn", "previous: wynn", "sink: wynn"],
+        *seen.lock().unwrap()
+    );
+}
@@ -0,0 +1,33 @@
+//! Cleanup callbacks and the hook are process-global, so this runs on its own
+//!
+use std::{
+    panic::catch_unwind,
+    sync::{Arc, Mutex},
+};
+
+use panic_message::PanicHookBuilder;
+
+mod common;
+use common::Log;
+
+#[test]
+fn cleanups_run_before_every_report() {
+    let events = Arc::new(Mutex::new(Vec::new()));
+    PanicHookBuilder::new()
+        .sink(Log(Arc::clone(&events), "report"))
+        .install();
+    let cleanup_events = Arc::clone(&events);
+    panic_message::register_cleanup(move || {
+        cleanup_events.lock().unwrap().push("cleanup".to_string())
+    });
+    panic_message::register_cleanup(|| panic!("cleanup failed"));
+
+    catch_unwind(|| panic!("gus")).unwrap_err();
+    catch_unwind(|| panic!("wynn")).unwrap_err();
+    let _ = std::panic::take_hook();
+
+    assert_eq!(
+        vec!["cleanup", "report: gus", "cleanup", "report: wynn"],
+        *events.lock().unwrap()
+    );
+}
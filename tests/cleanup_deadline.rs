@@ -0,0 +1,31 @@
+//! Cleanup callbacks and the hook are process-global, so this runs on its own
+//!
+use std::{
+    panic::catch_unwind,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+use panic_message::PanicHookBuilder;
+
+mod common;
+use common::Recorder;
+
+#[test]
+fn hung_cleanups_are_abandoned_and_reported() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    PanicHookBuilder::new()
+        .sink(Recorder(Arc::clone(&seen)))
+        .with_cleanup_deadline(Duration::from_millis(50))
+        .install();
+    panic_message::register_cleanup(|| std::thread::sleep(Duration::from_secs(60)));
+
+    let start = Instant::now();
+    catch_unwind(|| panic!("gus")).unwrap_err();
+    let _ = std::panic::take_hook();
+
+    assert!(start.elapsed() < Duration::from_secs(30));
+    let seen = seen.lock().unwrap();
+    assert_eq!(1, seen[0].abandoned_cleanups());
+    assert!(seen[0].to_json().contains(r#""abandoned_cleanups":1"#));
+}
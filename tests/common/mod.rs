@@ -0,0 +1,33 @@
+//! Sinks shared by the integration tests. Each test file only uses some of them.
+#![allow(dead_code)]
+
+use std::{
+    io,
+    sync::{Arc, Mutex},
+};
+
+use panic_message::{sink::Sink, PanicDetails};
+
+/// Keeps every report it's sent.
+pub struct Recorder(pub Arc<Mutex<Vec<PanicDetails>>>);
+
+impl Sink for Recorder {
+    fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+        self.0.lock().unwrap().push(details.clone());
+        Ok(())
+    }
+}
+
+/// Adds `"<prefix>: <message>"` to a log of events for every report, to check that it's
+/// sent in the right order with them.
+pub struct Log(pub Arc<Mutex<Vec<String>>>, pub &'static str);
+
+impl Sink for Log {
+    fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("{}: {}", self.1, details.message()));
+        Ok(())
+    }
+}
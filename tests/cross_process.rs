@@ -0,0 +1,53 @@
+//! Runs this test binary again as a subprocess that panics, and installs a
+//! `PanicHookBuilder` hook there, which is process-global.
+#![cfg(all(unix, feature = "cbor"))]
+use std::{
+    io,
+    os::unix::io::AsFd,
+    panic::catch_unwind,
+    process::{Command, Stdio},
+};
+
+use panic_message::{get_panic_message, sink::Pipe, PanicDetails, PanicHookBuilder};
+
+const CHILD: &str = "PANIC_MESSAGE_CROSS_PROCESS_CHILD";
+
+#[test]
+fn subprocess_panics_replay_in_the_parent() {
+    if std::env::var_os(CHILD).is_some() {
+        let stderr = io::stderr().as_fd().try_clone_to_owned().unwrap();
+        PanicHookBuilder::new().sink(Pipe::from(stderr)).install();
+        let port = 8080;
+        panic!("sandboxed job failed on port {}", port);
+    }
+
+    let mut child = Command::new(std::env::current_exe().unwrap())
+        .args([
+            "--exact",
+            "subprocess_panics_replay_in_the_parent",
+            "--quiet",
+        ])
+        .env(CHILD, "1")
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn()
+        .unwrap();
+    let details = PanicDetails::read_framed(child.stderr.take().unwrap())
+        .unwrap()
+        .expect("the subprocess reported its panic");
+    assert!(!child.wait().unwrap().success());
+
+    assert_eq!("sandboxed job failed on port 8080", details.message());
+    assert!(details
+        .location()
+        .unwrap()
+        .file()
+        .ends_with("cross_process.rs"));
+
+    let payload = catch_unwind(|| details.clone().replay()).unwrap_err();
+    assert_eq!(
+        Some("sandboxed job failed on port 8080"),
+        get_panic_message(&payload)
+    );
+    assert_eq!(details, PanicDetails::from_payload(&payload));
+}
@@ -0,0 +1,50 @@
+//! Installing a `PanicHookBuilder` hook is process-global, so this runs on its own.
+use std::{
+    panic::catch_unwind,
+    sync::{Arc, Mutex},
+    thread,
+    time::Duration,
+};
+
+use panic_message::{register_reporter, PanicDetails, PanicHookBuilder};
+
+#[test]
+fn repeats_are_suppressed_and_counted() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let reports = Arc::clone(&seen);
+    register_reporter(move |report: &PanicDetails| {
+        let repeats = report
+            .fields()
+            .iter()
+            .find(|(key, _)| key == "suppressed_repeats")
+            .map(|(_, value)| value.clone());
+        reports
+            .lock()
+            .unwrap()
+            .push((report.message().to_string(), repeats))
+    });
+
+    PanicHookBuilder::new()
+        .with_dedup_window(Duration::from_millis(200))
+        .install();
+    for round in 0..2 {
+        for i in 0..5 {
+            catch_unwind(|| panic!("retry {}", i)).unwrap_err();
+        }
+        catch_unwind(|| panic!("elsewhere")).unwrap_err();
+        if round == 0 {
+            thread::sleep(Duration::from_millis(300));
+        }
+    }
+    let _ = std::panic::take_hook();
+
+    assert_eq!(
+        vec![
+            ("retry 0".to_string(), None),
+            ("elsewhere".to_string(), None),
+            ("retry 0".to_string(), Some("4".to_string())),
+            ("elsewhere".to_string(), None),
+        ],
+        *seen.lock().unwrap()
+    );
+}
@@ -0,0 +1,54 @@
+//! Needs the `macros` feature.
+#![cfg(feature = "macros")]
+use std::{borrow::Cow, collections::HashMap, fmt, panic::catch_unwind};
+
+use panic_message::{PanicDetails, PanicMessage, PanicMessageProvider};
+
+#[derive(PanicMessage)]
+struct Named {
+    _code: u32,
+    _lookup: HashMap<String, u32>,
+    #[panic_message]
+    reason: String,
+}
+
+#[derive(PanicMessage)]
+struct Tuple(#[allow(dead_code)] u32, #[panic_message] &'static str);
+
+#[derive(PanicMessage)]
+enum Displayed {
+    Gus,
+}
+
+impl fmt::Display for Displayed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Displayed::Gus => f.write_str("displayed gus"),
+        }
+    }
+}
+
+#[test]
+fn derive() {
+    let named = Named {
+        _code: 1,
+        _lookup: HashMap::new(),
+        reason: "named gus".to_string(),
+    };
+    assert!(matches!(named.panic_message(), Cow::Borrowed("named gus")));
+    assert_eq!("tuple gus", Tuple(1, "tuple gus").panic_message());
+    assert_eq!("displayed gus", Displayed::Gus.panic_message());
+
+    let payload = catch_unwind(|| Displayed::Gus.panic()).unwrap_err();
+    assert_eq!(
+        "displayed gus",
+        PanicDetails::from_payload(payload).message()
+    );
+
+    // registered at startup, without going through `panic`
+    #[cfg(any(target_os = "linux", target_vendor = "apple", windows))]
+    {
+        let payload = catch_unwind(|| std::panic::panic_any(Tuple(1, "tuple gus"))).unwrap_err();
+        assert_eq!("tuple gus", panic_message::panic_message(&payload));
+    }
+}
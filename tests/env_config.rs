@@ -0,0 +1,32 @@
+//! Sets environment variables and installs a `PanicHookBuilder` hook, which are
+//! process-global, so it runs on its own
+//!
+use std::{
+    panic::catch_unwind,
+    sync::{Arc, Mutex},
+};
+
+use panic_message::{BacktraceMode, PanicHookBuilder};
+
+mod common;
+use common::Recorder;
+
+#[test]
+fn env_config() {
+    std::env::set_var("PANIC_MESSAGE_BACKTRACE", "0");
+    std::env::set_var("PANIC_MESSAGE_FORMAT", "compact");
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    PanicHookBuilder::new()
+        .sink(Recorder(Arc::clone(&seen)))
+        .with_backtrace(BacktraceMode::Forced)
+        .with_env_config()
+        .install();
+
+    catch_unwind(|| panic!("gus")).unwrap_err();
+    let _ = std::panic::take_hook();
+
+    let seen = seen.lock().unwrap();
+    assert_eq!("gus", seen[0].message());
+    assert_eq!(None, seen[0].backtrace());
+}
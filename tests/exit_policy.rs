@@ -0,0 +1,79 @@
+//! The exit policy hook exits the process, so each test re-runs itself as a child process
+//!
+use std::{
+    io,
+    process::{Command, Output},
+    sync::atomic::{AtomicBool, Ordering},
+    time::Duration,
+};
+
+use panic_message::{
+    sink::{Background, Sink, Stream},
+    ExitPolicy, PanicDetails, PanicHookBuilder, PanicKind,
+};
+
+const CHILD_ENV: &str = "PANIC_MESSAGE_EXIT_CHILD";
+
+fn is_child() -> bool {
+    std::env::var_os(CHILD_ENV).is_some()
+}
+
+/// Run the test called `name` in a child process.
+fn run_child(name: &str) -> Output {
+    Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", name, "--nocapture"])
+        .env(CHILD_ENV, "1")
+        .output()
+        .unwrap()
+}
+
+/// Prints "delivered" to stderr, slowly.
+struct Slow;
+
+impl Sink for Slow {
+    fn emit(&self, _: &PanicDetails) -> io::Result<()> {
+        static ONCE: AtomicBool = AtomicBool::new(false);
+        assert!(!ONCE.swap(true, Ordering::SeqCst));
+        std::thread::sleep(Duration::from_millis(100));
+        eprintln!("delivered");
+        Ok(())
+    }
+}
+
+#[test]
+fn exits_with_the_code_for_the_kind() {
+    if is_child() {
+        PanicHookBuilder::new()
+            .sink(Stream::stderr())
+            .sink(Background::new(Slow))
+            .with_exit_policy(ExitPolicy::new().with_code(PanicKind::Assertion, 3))
+            .install();
+        let _ = std::panic::catch_unwind(|| assert_eq!(1, 2));
+        unreachable!("the hook did not exit");
+    }
+
+    let output = run_child("exits_with_the_code_for_the_kind");
+
+    assert_eq!(Some(3), output.status.code());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("assertion `left == right` failed"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("delivered"), "{}", stderr);
+}
+
+#[test]
+fn other_kinds_exit_with_the_default_code() {
+    if is_child() {
+        PanicHookBuilder::new()
+            .with_exit_policy(ExitPolicy::new().with_code(PanicKind::Assertion, 3))
+            .install();
+        panic!("gus");
+    }
+
+    let output = run_child("other_kinds_exit_with_the_default_code");
+
+    assert_eq!(Some(101), output.status.code());
+}
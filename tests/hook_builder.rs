@@ -0,0 +1,34 @@
+//! Installs a `PanicHookBuilder` hook, which is process-global, so it runs on its own
+//!
+use std::{
+    panic::catch_unwind,
+    sync::{Arc, Mutex},
+};
+
+use panic_message::PanicHookBuilder;
+
+mod common;
+use common::Recorder;
+
+#[test]
+fn sinks_see_every_panic() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    PanicHookBuilder::new()
+        .sink(Recorder(Arc::clone(&seen)))
+        .install();
+
+    let line = line!() + 1;
+    catch_unwind(|| panic!("gus")).unwrap_err();
+    let _ = std::panic::take_hook();
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(1, seen.len());
+    assert_eq!("gus", seen[0].message());
+    let location = seen[0].location().unwrap();
+    assert!(location.file().ends_with("hook_builder.rs"));
+    assert_eq!(line, location.line());
+    assert_eq!(Some("sinks_see_every_panic"), seen[0].thread());
+    assert!(seen[0].uptime().is_some());
+    assert!(seen[0].thread_id().is_some());
+    assert!(seen[0].time().unwrap() <= std::time::SystemTime::now());
+}
@@ -0,0 +1,35 @@
+//! Installs a `PanicHookBuilder` hook, which is process-global, so it runs on its own
+//!
+use std::{
+    panic::catch_unwind,
+    sync::{Arc, Mutex},
+};
+
+use panic_message::{BacktraceMode, PanicHookBuilder};
+
+mod common;
+use common::Recorder;
+
+#[test]
+fn options_leave_out_what_is_off() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    PanicHookBuilder::new()
+        .sink(Recorder(Arc::clone(&seen)))
+        .with_location(false)
+        .with_thread(false)
+        .with_backtrace(BacktraceMode::Forced)
+        .install();
+
+    catch_unwind(|| panic!("gus")).unwrap_err();
+    let _ = std::panic::take_hook();
+
+    let seen = seen.lock().unwrap();
+    assert_eq!("gus", seen[0].message());
+    assert_eq!(None, seen[0].location());
+    assert_eq!(None, seen[0].thread());
+    assert_eq!(None, seen[0].thread_id());
+    assert!(seen[0]
+        .backtrace()
+        .unwrap()
+        .contains("options_leave_out_what_is_off"));
+}
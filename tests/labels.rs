@@ -0,0 +1,30 @@
+//! This test is an integration test because labels are process-global, and the other
+//! tests expect the defaults.
+use panic_message::{labels, set_labels, Labels, PanicDetails};
+
+#[test]
+fn labels_apply_to_display() {
+    let mut french = Labels::default();
+    french.fallback_message = "message inconnu".into();
+    french.thread = "le fil".into();
+    french.unnamed_thread = "<sans nom>".into();
+    french.panicked = "a paniqué".into();
+    set_labels(french.clone());
+
+    assert_eq!(french, *labels());
+    assert_eq!(
+        "le fil '<sans nom>' a paniqué:\nmessage inconnu",
+        PanicDetails::new("Box<dyn Any>").to_string()
+    );
+    // machine-readable output keeps the original
+    assert_eq!(
+        r#"{"message":"Box<dyn Any>"}"#,
+        PanicDetails::new("Box<dyn Any>").to_json()
+    );
+
+    set_labels(Labels::default());
+    assert_eq!(
+        "thread '<unnamed>' panicked:\nBox<dyn Any>",
+        PanicDetails::new("Box<dyn Any>").to_string()
+    );
+}
@@ -0,0 +1,76 @@
+//! Installs a `PanicHookBuilder` hook and a `metrics` recorder, both process-global, so this
+//! runs on its own.
+#![cfg(feature = "metrics")]
+use std::{
+    panic::catch_unwind,
+    sync::{Arc, Mutex},
+};
+
+use metrics::{Counter, CounterFn, Gauge, Histogram, Key, KeyName, Metadata, SharedString, Unit};
+use panic_message::PanicHookBuilder;
+
+/// Keeps every increment of a counter, as its name and labels.
+#[derive(Clone, Default)]
+struct Recorder(Arc<Mutex<Vec<String>>>);
+
+struct Increments(Arc<Mutex<Vec<String>>>, String);
+
+impl CounterFn for Increments {
+    fn increment(&self, value: u64) {
+        self.0
+            .lock()
+            .unwrap()
+            .push(format!("{} += {}", self.1, value));
+    }
+
+    fn absolute(&self, _: u64) {}
+}
+
+impl metrics::Recorder for Recorder {
+    fn describe_counter(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+    fn describe_gauge(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+    fn describe_histogram(&self, _: KeyName, _: Option<Unit>, _: SharedString) {}
+
+    fn register_counter(&self, key: &Key, _: &Metadata<'_>) -> Counter {
+        let labels: Vec<_> = key
+            .labels()
+            .map(|label| format!("{}={}", label.key(), label.value()))
+            .collect();
+        let name = format!("{}{{{}}}", key.name(), labels.join(","));
+        Counter::from_arc(Arc::new(Increments(Arc::clone(&self.0), name)))
+    }
+
+    fn register_gauge(&self, _: &Key, _: &Metadata<'_>) -> Gauge {
+        Gauge::noop()
+    }
+
+    fn register_histogram(&self, _: &Key, _: &Metadata<'_>) -> Histogram {
+        Histogram::noop()
+    }
+}
+
+#[test]
+fn panics_are_counted() {
+    let recorder = Recorder::default();
+    metrics::set_global_recorder(recorder.clone()).unwrap();
+    PanicHookBuilder::new().install();
+
+    std::thread::Builder::new()
+        .name("worker".to_string())
+        .spawn(|| catch_unwind(|| panic!("gus")).unwrap_err())
+        .unwrap()
+        .join()
+        .unwrap();
+    let _ = std::panic::take_hook();
+
+    let increments = recorder.0.lock().unwrap();
+    assert_eq!(1, increments.len());
+    assert!(
+        increments[0].starts_with("panics_total{thread=worker,fingerprint="),
+        "{:?}",
+        increments
+    );
+    assert!(increments[0].ends_with("} += 1"), "{:?}", increments);
+}
@@ -0,0 +1,32 @@
+//! This test is an integration test because it installs a `set_hook` handler,
+//! which can race with other tests, so it must be run on its own
+//!
+use std::panic::{catch_unwind, set_hook};
+use std::sync::Mutex;
+
+use panic_message::{Location, PanicEvent};
+
+#[test]
+fn panic_event_from_hook_info() {
+    static SEEN: Mutex<Option<(String, Option<Location>, bool)>> = Mutex::new(None);
+
+    set_hook(Box::new(|pi| {
+        let event = PanicEvent::from(pi);
+        *SEEN.lock().unwrap() = Some((
+            event.message().to_string(),
+            event.location(),
+            event.is_owned(),
+        ));
+    }));
+
+    let line = line!() + 1;
+    catch_unwind(|| panic!("gus")).unwrap_err();
+    let _ = std::panic::take_hook();
+
+    let (message, location, owned) = SEEN.lock().unwrap().take().unwrap();
+    assert_eq!("gus", message);
+    let location = location.unwrap();
+    assert!(location.file().ends_with("panic_event.rs"));
+    assert_eq!(line, location.line());
+    assert!(!owned);
+}
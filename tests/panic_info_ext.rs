@@ -0,0 +1,32 @@
+//! This test is an integration test because it installs a `set_hook` handler,
+//! which can race with other tests, so it must be run on its own
+//!
+use std::panic::{catch_unwind, set_hook};
+use std::sync::atomic::{AtomicBool, AtomicU32, Ordering::SeqCst};
+
+use panic_message::PanicInfoExt;
+
+#[test]
+fn panic_info_ext() {
+    static CALLED: AtomicBool = AtomicBool::new(false);
+    static LINE: AtomicU32 = AtomicU32::new(0);
+
+    set_hook(Box::new(|pi| {
+        // assert's here will SIGILL or abort the process if they fail
+        assert_eq!(Some("gus"), pi.message_str());
+        assert_eq!("gus", pi.message_or_default());
+        let details = pi.details();
+        assert_eq!("gus", details.message());
+        assert_eq!(LINE.load(SeqCst), details.location().unwrap().line());
+        CALLED.store(true, SeqCst);
+    }));
+
+    catch_unwind(|| {
+        LINE.store(line!() + 1, SeqCst);
+        panic!("gus");
+    })
+    .unwrap_err();
+
+    // Ensure we actually entered the hook
+    assert!(CALLED.load(SeqCst));
+}
@@ -0,0 +1,58 @@
+//! Installs a `PanicHookBuilder` hook and registers section providers and a formatter, all
+//! process-global, so this runs on its own.
+use std::{
+    io,
+    panic::{catch_unwind, panic_any},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use panic_message::{
+    register_panic_formatter, register_section_provider,
+    sink::{Background, Sink},
+    PanicDetails, PanicHookBuilder, Section,
+};
+
+mod common;
+use common::Recorder;
+
+struct Panicking;
+
+impl Sink for Panicking {
+    fn emit(&self, details: &PanicDetails) -> io::Result<()> {
+        panic!("sink failed on {}", details.message());
+    }
+
+    fn flush(&self, _: Duration) -> bool {
+        panic!("flush failed");
+    }
+}
+
+struct Fatal;
+
+#[test]
+fn panicking_application_code_is_skipped() {
+    register_section_provider(|_: &PanicDetails| panic!("provider failed"));
+    register_section_provider(|_: &PanicDetails| Some(Section::text("config", "a1b2")));
+    register_panic_formatter(|_: &Fatal| panic!("formatter failed"));
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let background = Arc::new(Background::new(Panicking));
+    PanicHookBuilder::new()
+        .sink(Panicking)
+        .sink(Arc::clone(&background))
+        .sink(Recorder(Arc::clone(&seen)))
+        .install();
+
+    let payload = catch_unwind(|| panic!("gus")).unwrap_err();
+    assert!(background.flush(Duration::from_secs(5)));
+    catch_unwind(|| panic!("again")).unwrap_err();
+    catch_unwind(|| panic_any(Fatal)).unwrap_err();
+    let _ = std::panic::take_hook();
+
+    assert_eq!("gus", panic_message::panic_message(&payload));
+    let seen = seen.lock().unwrap();
+    let messages: Vec<_> = seen.iter().map(PanicDetails::message).collect();
+    assert_eq!(vec!["gus", "again", "Box<dyn Any>"], messages);
+    assert_eq!(&[Section::text("config", "a1b2")], seen[0].sections());
+}
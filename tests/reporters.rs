@@ -0,0 +1,32 @@
+//! Reporters are process-global, and installing a `PanicHookBuilder` hook is too, so this
+//! runs on its own.
+use std::{
+    panic::catch_unwind,
+    sync::{Arc, Mutex},
+};
+
+use panic_message::{register_reporter, PanicDetails, PanicHookBuilder};
+
+#[test]
+fn hook_fans_out_to_reporters() {
+    let first = Arc::new(Mutex::new(Vec::new()));
+    let second = Arc::new(Mutex::new(Vec::new()));
+    let seen = Arc::clone(&first);
+    let handle = register_reporter(move |report: &PanicDetails| {
+        seen.lock().unwrap().push(report.message().to_string())
+    });
+    register_reporter(|_: &PanicDetails| panic!("reporter"));
+    let seen = Arc::clone(&second);
+    register_reporter(move |report: &PanicDetails| {
+        seen.lock().unwrap().push(report.message().to_string())
+    });
+
+    PanicHookBuilder::new().install();
+    catch_unwind(|| panic!("gus")).unwrap_err();
+    assert!(handle.unregister());
+    catch_unwind(|| panic!("wynn")).unwrap_err();
+    let _ = std::panic::take_hook();
+
+    assert_eq!(vec!["gus"], *first.lock().unwrap());
+    assert_eq!(vec!["gus", "wynn"], *second.lock().unwrap());
+}
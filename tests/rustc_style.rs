@@ -0,0 +1,42 @@
+//! This test is an integration test because it installs a `set_hook` handler,
+//! which can race with other tests, so it must be run on its own
+//!
+use std::panic::{catch_unwind, set_hook};
+use std::sync::{
+    atomic::{AtomicU32, Ordering::SeqCst},
+    Mutex,
+};
+
+use panic_message::RustcStyle;
+
+#[test]
+fn rustc_style() {
+    static LINE: AtomicU32 = AtomicU32::new(0);
+    static OUTPUT: Mutex<String> = Mutex::new(String::new());
+
+    set_hook(Box::new(|pi| {
+        *OUTPUT.lock().unwrap() = RustcStyle(pi).to_string();
+    }));
+
+    std::thread::Builder::new()
+        .name("worker".to_string())
+        .spawn(|| {
+            catch_unwind(|| {
+                LINE.store(line!() + 1, SeqCst);
+                panic!("gus {}", 1);
+            })
+            .unwrap_err();
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+
+    assert_eq!(
+        format!(
+            "thread 'worker' panicked at {}:{}:17:\ngus 1",
+            file!(),
+            LINE.load(SeqCst)
+        ),
+        *OUTPUT.lock().unwrap()
+    );
+}
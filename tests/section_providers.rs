@@ -0,0 +1,59 @@
+//! Section providers are process-global, and installing a `PanicHookBuilder` hook is too,
+//! so this runs on its own.
+use std::{
+    panic::catch_unwind,
+    sync::{Arc, Mutex},
+};
+
+use panic_message::{
+    register_section_provider, thread_context, PanicDetails, PanicHookBuilder, Section,
+};
+
+mod common;
+use common::Recorder;
+
+#[test]
+fn providers_add_sections_to_hook_reports() {
+    register_section_provider(|_: &PanicDetails| Some(Section::text("config", "a1b2")));
+    register_section_provider(|_: &PanicDetails| None);
+    register_section_provider(|details: &PanicDetails| {
+        details
+            .thread()
+            .map(|thread| Section::list("peers", vec![thread.to_string()]))
+    });
+    register_section_provider(|details: &PanicDetails| {
+        details
+            .fields()
+            .iter()
+            .find(|(key, _)| key == "request_id")
+            .map(|(_, id)| Section::text("request_id", id.as_str()))
+    });
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    PanicHookBuilder::new()
+        .sink(Recorder(Arc::clone(&seen)))
+        .install();
+    std::thread::Builder::new()
+        .name("worker".to_string())
+        .spawn(|| {
+            let _request = thread_context("request_id", "7");
+            catch_unwind(|| panic!("gus")).unwrap_err()
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+    let _ = std::panic::take_hook();
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(
+        &[
+            Section::text("config", "a1b2"),
+            Section::list("peers", vec!["worker".to_string()]),
+            Section::text("request_id", "7"),
+        ],
+        seen[0].sections()
+    );
+    assert!(seen[0]
+        .to_json()
+        .ends_with(r#""sections":{"config":"a1b2","peers":["worker"],"request_id":"7"}}"#));
+}
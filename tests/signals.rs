@@ -0,0 +1,185 @@
+//! The signal handlers kill the process, so each test re-runs itself as a child process
+//!
+#![cfg(all(unix, feature = "signals"))]
+use std::{
+    os::{raw::c_int, unix::process::ExitStatusExt},
+    panic::catch_unwind,
+    process::{Command, Output},
+};
+
+const CHILD_ENV: &str = "PANIC_MESSAGE_SIGNALS_CHILD";
+
+extern "C" {
+    fn raise(signum: c_int) -> c_int;
+    fn signal(signum: c_int, handler: usize) -> usize;
+    fn write(fd: c_int, buf: *const std::ffi::c_void, count: usize) -> isize;
+}
+
+fn is_child() -> bool {
+    std::env::var_os(CHILD_ENV).is_some()
+}
+
+/// Run the test called `name` in a child process.
+fn run_child(name: &str) -> Output {
+    Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", name, "--nocapture"])
+        .env(CHILD_ENV, "1")
+        .output()
+        .unwrap()
+}
+
+#[test]
+fn abort_prints_last_panic_and_breadcrumbs() {
+    if is_child() {
+        panic_message::PanicHookBuilder::new().install();
+        panic_message::signals::install_abort_handler().unwrap();
+        panic_message::breadcrumb("step 1");
+        catch_unwind(|| panic!("gus")).unwrap_err();
+        panic_message::breadcrumb("step 2");
+        std::process::abort();
+    }
+
+    let output = run_child("abort_prints_last_panic_and_breadcrumbs");
+
+    assert_eq!(Some(6), output.status.signal());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("panic-message: received SIGABRT"),
+        "{}",
+        stderr
+    );
+    assert!(
+        stderr.contains("last panic: thread 'abort_prints_last_panic_and_breadcrumbs' panicked at"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("gus"), "{}", stderr);
+    assert!(
+        stderr.contains("breadcrumbs (oldest first):\n  step 1\n  step 2\n"),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn sigill_prints_breadcrumbs_and_reraises() {
+    if is_child() {
+        panic_message::signals::install_fatal_signal_handlers().unwrap();
+        panic_message::breadcrumb("jumping");
+        unsafe { raise(4) };
+        unreachable!("SIGILL was not re-raised");
+    }
+
+    let output = run_child("sigill_prints_breadcrumbs_and_reraises");
+
+    assert_eq!(Some(4), output.status.signal());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        stderr.contains("panic-message: received SIGILL"),
+        "{}",
+        stderr
+    );
+    assert!(!stderr.contains("last panic"), "{}", stderr);
+    assert!(stderr.contains("  jumping\n"), "{}", stderr);
+}
+
+#[test]
+fn stack_overflow_is_still_reported() {
+    #[allow(unconditional_recursion)]
+    fn recurse(depth: u64) -> u64 {
+        let frame = std::hint::black_box([depth; 64]);
+        recurse(depth + 1) + frame[0]
+    }
+
+    if is_child() {
+        panic_message::signals::install_fatal_signal_handlers().unwrap();
+        panic_message::signals::install_abort_handler().unwrap();
+        std::thread::spawn(|| {
+            panic_message::breadcrumb("recursing");
+            recurse(0)
+        })
+        .join()
+        .unwrap();
+        unreachable!("the stack did not overflow");
+    }
+
+    let output = run_child("stack_overflow_is_still_reported");
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    // the standard library's handler runs first, and aborts
+    let overflowed = stderr.find("has overflowed its stack").unwrap();
+    assert!(
+        stderr[overflowed..].contains("panic-message: received SIGABRT"),
+        "{}",
+        stderr
+    );
+    assert!(stderr.contains("  recursing\n"), "{}", stderr);
+    assert_eq!(Some(6), output.status.signal());
+}
+
+#[test]
+fn previous_handlers_are_called() {
+    extern "C" fn previous(_: c_int) {
+        let message = b"previous handler\n";
+        unsafe { write(2, message.as_ptr().cast(), message.len()) };
+    }
+
+    if is_child() {
+        unsafe { signal(4, previous as extern "C" fn(c_int) as usize) };
+        panic_message::signals::install_fatal_signal_handlers().unwrap();
+        // installing twice doesn't report twice
+        panic_message::signals::install_fatal_signal_handlers().unwrap();
+        unsafe { raise(4) };
+        unreachable!("SIGILL was not re-raised");
+    }
+
+    let output = run_child("previous_handlers_are_called");
+
+    assert_eq!(Some(4), output.status.signal());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let previous = stderr.find("previous handler\n").unwrap();
+    assert_eq!(
+        1,
+        stderr[previous..]
+            .matches("panic-message: received SIGILL")
+            .count(),
+        "{}",
+        stderr
+    );
+}
+
+#[test]
+fn output_fd_replaces_stderr() {
+    if is_child() {
+        use std::os::unix::io::IntoRawFd;
+
+        let path = std::env::var_os("PANIC_MESSAGE_SIGNALS_OUTPUT").unwrap();
+        let fd = std::fs::File::create(path).unwrap().into_raw_fd();
+        panic_message::signals::set_output_fd(fd);
+        // left before the handler is installed
+        panic_message::breadcrumb("to a file");
+        panic_message::signals::install_abort_handler().unwrap();
+        std::process::abort();
+    }
+
+    let path =
+        std::env::temp_dir().join(format!("panic-message-signals-{}.txt", std::process::id()));
+    let output = Command::new(std::env::current_exe().unwrap())
+        .args(["--exact", "output_fd_replaces_stderr", "--nocapture"])
+        .env(CHILD_ENV, "1")
+        .env("PANIC_MESSAGE_SIGNALS_OUTPUT", &path)
+        .output()
+        .unwrap();
+
+    assert_eq!(Some(6), output.status.signal());
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(!stderr.contains("panic-message: received"), "{}", stderr);
+    let written = std::fs::read_to_string(&path).unwrap();
+    let _ = std::fs::remove_file(&path);
+    assert!(
+        written.contains("panic-message: received SIGABRT"),
+        "{}",
+        written
+    );
+    assert!(written.contains("  to a file\n"), "{}", written);
+}
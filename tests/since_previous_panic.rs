@@ -0,0 +1,32 @@
+//! Installs panic hooks, which are process-global, so it runs on its own
+//!
+use std::{
+    panic::catch_unwind,
+    sync::{Arc, Mutex},
+};
+
+use panic_message::{PanicDetails, PanicHookBuilder};
+
+mod common;
+use common::Recorder;
+
+#[test]
+fn only_the_installed_hook_counts_panics() {
+    std::panic::set_hook(Box::new(|pi| {
+        let details = PanicDetails::from_panic_info(pi);
+        assert_eq!(None, details.since_previous_panic());
+    }));
+    catch_unwind(|| panic!("in another hook")).unwrap_err();
+
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    PanicHookBuilder::new()
+        .sink(Recorder(Arc::clone(&seen)))
+        .install();
+    catch_unwind(|| panic!("gus")).unwrap_err();
+    catch_unwind(|| panic!("again")).unwrap_err();
+    let _ = std::panic::take_hook();
+
+    let seen = seen.lock().unwrap();
+    assert_eq!(None, seen[0].since_previous_panic());
+    assert!(seen[1].since_previous_panic().is_some());
+}
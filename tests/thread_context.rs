@@ -0,0 +1,45 @@
+//! Installs a `PanicHookBuilder` hook, which is process-global, so it runs on its own.
+use std::{
+    cell::Cell,
+    panic::catch_unwind,
+    sync::{Arc, Mutex},
+};
+
+use panic_message::{sink, thread_context, PanicHookBuilder};
+
+thread_local! {
+    static REQUEST_ID: Cell<u64> = const { Cell::new(0) };
+}
+
+#[test]
+fn sinks_get_the_panicking_threads_context() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let sink = Arc::clone(&seen);
+    PanicHookBuilder::new()
+        .sink(sink::from_fn(move |details| {
+            sink.lock().unwrap().push(format!(
+                "{} {:?} in request {}",
+                details.message(),
+                details.fields(),
+                REQUEST_ID.with(Cell::get)
+            ));
+            Ok(())
+        }))
+        .install();
+
+    REQUEST_ID.with(|id| id.set(7));
+    let request = thread_context("request_id", "7");
+    catch_unwind(|| panic!("gus")).unwrap_err();
+    drop(request);
+    catch_unwind(|| panic!("again")).unwrap_err();
+    let _ = std::panic::take_hook();
+
+    // the sink runs on a helper thread, which has thread-locals of its own
+    assert_eq!(
+        vec![
+            r#"gus [("request_id", "7")] in request 0"#,
+            "again [] in request 0"
+        ],
+        *seen.lock().unwrap()
+    );
+}
@@ -0,0 +1,27 @@
+//! This test is an integration test because the unknown payload policy is process-global.
+use std::{
+    any::TypeId,
+    panic::{catch_unwind, panic_any},
+    sync::{Arc, Mutex},
+};
+
+use panic_message::{set_unknown_payload_policy, PanicDetails, UnknownPayloadPolicy};
+
+#[test]
+fn callback_sees_unknown_payloads() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let callback_seen = Arc::clone(&seen);
+    set_unknown_payload_policy(UnknownPayloadPolicy::callback(move |payload_type| {
+        callback_seen.lock().unwrap().push(payload_type)
+    }));
+
+    let unknown = catch_unwind(|| panic_any(1_u8)).unwrap_err();
+    let string = catch_unwind(|| panic!("gus")).unwrap_err();
+
+    assert_eq!(
+        "Box<dyn Any>",
+        PanicDetails::from_payload(&unknown).message()
+    );
+    assert_eq!("gus", PanicDetails::from_payload(&string).message());
+    assert_eq!(vec![TypeId::of::<u8>()], *seen.lock().unwrap());
+}
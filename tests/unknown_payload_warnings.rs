@@ -0,0 +1,37 @@
+//! Installs a `PanicHookBuilder` hook, which is process-global, so it runs on its own
+//!
+use std::{
+    panic::{catch_unwind, panic_any},
+    sync::{Arc, Mutex},
+};
+
+use panic_message::PanicHookBuilder;
+
+mod common;
+use common::Recorder;
+
+#[test]
+fn first_unknown_payload_is_flagged() {
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    PanicHookBuilder::new()
+        .sink(Recorder(Arc::clone(&seen)))
+        .with_unknown_payload_warnings()
+        .install();
+
+    catch_unwind(|| panic_any(1_u8)).unwrap_err();
+    catch_unwind(|| panic_any(2_u8)).unwrap_err();
+    catch_unwind(|| panic!("gus")).unwrap_err();
+    let _ = std::panic::take_hook();
+
+    let seen = seen.lock().unwrap();
+    let warnings: Vec<_> = seen
+        .iter()
+        .map(|details| {
+            details
+                .fields()
+                .iter()
+                .any(|(key, _)| key == "payload_warning")
+        })
+        .collect();
+    assert_eq!(vec![true, false, false], warnings);
+}